@@ -1,51 +1,193 @@
 use std::fs;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{NamedParsedQuery, NamedQuery, NamedRawQuery};
+use crate::{IRPass, NamedParsedQuery, NamedQuery, NamedRawQuery, NodePathFormat};
 use crate::ir::generator::IRGenerator;
+use crate::ir::passes::run_passes;
 use crate::ir::Query;
-use crate::targets::{BindingsGenerator, TargetCodeGenerator, TargetCodeLibGenerator,
-                     TargetCodeStandaloneProgGenerator};
+use crate::syntax::ParsedQuery;
+use crate::targets::{Bindings, BindingsGenerator, InputFormat, ResultsDelivery, TargetCodeGenerator,
+                     TargetCodeLibGenerator, TargetCodeStandaloneProgGenerator};
+
+/// Default maximum number of procedures generated for a query, see
+/// [`StandaloneProgGeneratingCompiler::with_max_procedures`].
+pub const DEFAULT_MAX_PROCEDURES: usize = 1024;
 
 #[derive(Error, Debug)]
 pub enum CompilationError {
     #[error(transparent)]
     IoError(#[from] std::io::Error),
+    #[error("invalid query at position {position}: {message}")]
+    ParseError { message: String, position: usize },
+    #[error("invalid function expression: {0}")]
+    InvalidFunctionExpression(String),
+    #[error("ill-typed function expression: {0}")]
+    IllTypedFunctionExpression(String),
+    #[error("invalid I-Regexp pattern `{pattern}`: {message}")]
+    InvalidIRegexp { pattern: String, message: String },
+    /// A valid query which uses a feature that cannot be compiled. `fragment` is the part of
+    /// the query using the feature and `span` is its position in the query text.
+    #[error("query `{query_name}` uses an unsupported feature, {feature}, in `{fragment}` at {}..{}", span.start, span.end)]
+    UnsupportedFeature {
+        query_name: String,
+        feature: String,
+        fragment: String,
+        span: Range<usize>,
+    },
+    #[error("query `{query_name}` needs more than {max_procedures} procedures, which is supported only for queries without filters not executed in a single pass")]
+    ProceduresLimitExceeded { query_name: String, max_procedures: usize },
+    #[error("line {line_number} of the queries file is not a query name followed by a query")]
+    InvalidQueriesFileLine { line_number: usize },
+    #[error("invalid intermediate query code: {0}")]
+    InvalidIR(String),
+    #[error("queries with filters cannot be executed in a single pass with other queries")]
+    FiltersInSinglePass,
+    #[error("the target does not support generating bindings")]
+    BindingsNotSupported,
+    #[error("a symbol prefix is given, but no bindings are generated")]
+    SymbolPrefixWithoutBindings,
+    #[error("`{0}` is not a valid path of a C header file")]
+    InvalidCHeaderPath(String),
+    #[error("the target does not support executing queries in a single pass")]
+    SinglePassNotSupported,
+    #[error("the target does not support returning results as spans of the input")]
+    ResultSpansNotSupported,
+    #[error("results of queries executed in a single pass cannot be returned as spans of the input")]
+    ResultSpansInSinglePass,
+    #[error("the target does not support passing results to a callback")]
+    ResultCallbacksNotSupported,
+    #[error("results of queries executed in a single pass cannot be passed to a callback")]
+    ResultCallbacksInSinglePass,
+    #[error("the target does not support returning paths of the selected nodes")]
+    NodePathsNotSupported,
+    #[error("paths of the selected nodes cannot be returned for queries executed in a single pass")]
+    NodePathsInSinglePass,
+    #[error("paths of the selected nodes cannot be returned with results as spans of the input")]
+    NodePathsWithResultSpans,
+    #[error("the target does not support NDJSON input")]
+    NdjsonInputNotSupported,
+    #[error("queries executed in a single pass cannot be executed on NDJSON input")]
+    NdjsonInputInSinglePass,
+    #[error("results of queries executed on NDJSON input cannot be returned as spans of the input")]
+    NdjsonInputWithResultSpans,
+    #[error("results of queries executed on NDJSON input cannot be passed to a callback")]
+    NdjsonInputWithResultCallbacks,
+    #[error("the target does not support visiting array elements out of the document order")]
+    RandomAccessNotSupported,
     #[error(transparent)]
-    ParseError(#[from] rsonpath_syntax::error::ParseError),
-    #[error("multiple compilation errors")]
+    TemplateError(#[from] askama::Error),
+    #[error("multiple compilation errors:{}", format_errors(.0))]
     MultipleErrors(Vec<(String, CompilationError)>),
 }
 
-struct CompilerHelper {}
+impl CompilationError {
+    fn in_query(self, name: &str) -> CompilationError {
+        match self {
+            CompilationError::UnsupportedFeature { feature, fragment, span, .. } => {
+                CompilationError::UnsupportedFeature {
+                    query_name: name.to_string(),
+                    feature,
+                    fragment,
+                    span,
+                }
+            }
+            CompilationError::ProceduresLimitExceeded { max_procedures, .. } => {
+                CompilationError::ProceduresLimitExceeded {
+                    query_name: name.to_string(),
+                    max_procedures,
+                }
+            }
+            err => err,
+        }
+    }
+}
+
+impl From<serde_json::Error> for CompilationError {
+    fn from(err: serde_json::Error) -> CompilationError {
+        CompilationError::InvalidIR(err.to_string())
+    }
+}
+
+type QueryFileErrors = Vec<(String, CompilationError)>;
+
+fn format_errors(errors: &[(String, CompilationError)]) -> String {
+    errors
+        .iter()
+        .map(|(name, err)| format!("\n  {name}: {err}"))
+        .collect()
+}
+
+pub(crate) struct CompilerHelper {}
+
+#[derive(Serialize)]
+struct NamedQueryIR<'a> {
+    name: &'a str,
+    query: &'a Query,
+}
+
+#[derive(Deserialize)]
+struct OwnedNamedQueryIR {
+    name: String,
+    query: Query,
+}
 
 impl CompilerHelper {
-    fn parse(
+    pub(crate) fn parse(
+        name: &str,
         query: &str
-    ) -> Result<rsonpath_syntax::JsonPathQuery, CompilationError> {
-        rsonpath_syntax::parse(query).map_err(|err| CompilationError::ParseError(err))
+    ) -> Result<ParsedQuery, CompilationError> {
+        ParsedQuery::parse(query).map_err(|err| err.in_query(name))
     }
 
-    fn generate_ir(parsed_query: &rsonpath_syntax::JsonPathQuery) -> Query {
-        let ir_generator = IRGenerator::new(&parsed_query);
-        ir_generator.generate()
+    pub(crate) fn generate_ir(
+        name: &str,
+        parsed_query: &ParsedQuery,
+        max_procedures: Option<usize>,
+        is_random_access_supported: bool,
+        ir_passes: &[IRPass],
+    ) -> Result<Query, CompilationError> {
+        let ir_generator =
+            IRGenerator::new(parsed_query, max_procedures, is_random_access_supported);
+        let mut query_ir = ir_generator.generate().map_err(|err| err.in_query(name))?;
+        run_passes(&mut query_ir, ir_passes);
+        Ok(query_ir)
     }
 
     fn generate_query_ir_output(query_ir: &Query) -> String {
-        format!("{:#?}\n", query_ir)
+        format!("{}\n", query_ir.to_text())
     }
 
-    fn generate_queries_irs_output(queries_irs: &Vec<NamedQuery>) -> String {
-        let mut output = String::new();
-        for (name, query_ir) in queries_irs {
-            output.push_str(&format!("{name}:\n"));
-            output.push_str(&Self::generate_query_ir_output(query_ir));
-        }
-        output
+    /// The IR of the queries of a library is written as a JSON list of objects with the name
+    /// of a query and its IR, in the format of [`Query::to_text`].
+    fn generate_queries_irs_output(queries_irs: &[NamedQuery]) -> String {
+        let named_queries: Vec<NamedQueryIR> = queries_irs
+            .iter()
+            .map(|(name, query)| NamedQueryIR { name, query })
+            .collect();
+        format!("{}\n", serde_json::to_string_pretty(&named_queries).unwrap())
+    }
+
+    fn read_query_ir(file_path: &str) -> Result<Query, CompilationError> {
+        Query::from_text(&fs::read_to_string(file_path)?)
+    }
+
+    fn read_queries_irs(file_path: &str) -> Result<Vec<NamedQuery>, CompilationError> {
+        let named_queries: Vec<OwnedNamedQueryIR> =
+            serde_json::from_str(&fs::read_to_string(file_path)?)?;
+        named_queries
+            .into_iter()
+            .map(|named_query| {
+                named_query.query.validate()?;
+                Ok((named_query.name, named_query.query))
+            })
+            .collect()
     }
 
     fn write_to_file(file_path: &str, content: String) -> Result<(), CompilationError> {
@@ -62,6 +204,10 @@ pub struct StandaloneProgGeneratingCompiler {
     mmap: bool,
     eager_filter_evaluation: bool,
     ir_output_file_path: Option<String>,
+    ir_passes: Vec<IRPass>,
+    max_procedures: Option<usize>,
+    node_paths: Option<NodePathFormat>,
+    input_format: InputFormat,
 }
 
 impl StandaloneProgGeneratingCompiler {
@@ -71,6 +217,10 @@ impl StandaloneProgGeneratingCompiler {
             mmap: false,
             eager_filter_evaluation: false,
             ir_output_file_path: None,
+            ir_passes: IRPass::value_variants().to_vec(),
+            max_procedures: None,
+            node_paths: None,
+            input_format: InputFormat::Json,
         }
     }
 
@@ -102,13 +252,91 @@ impl StandaloneProgGeneratingCompiler {
         }
     }
 
+    /// Disables an optimization pass run on the IR of the queries.
+    pub fn without_ir_pass(self, pass: IRPass) -> StandaloneProgGeneratingCompiler {
+        StandaloneProgGeneratingCompiler {
+            ir_passes: self.ir_passes.into_iter().filter(|&p| p != pass).collect(),
+            ..self
+        }
+    }
+
+    pub fn without_ir_passes(self) -> StandaloneProgGeneratingCompiler {
+        StandaloneProgGeneratingCompiler {
+            ir_passes: Vec::new(),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of procedures generated for a query, [`DEFAULT_MAX_PROCEDURES`]
+    /// by default, beyond which the active segments are passed at runtime. The compilation of
+    /// queries with filters, slices with negative steps and merged queries fails beyond a limit
+    /// set this way.
+    pub fn with_max_procedures(self, max_procedures: usize) -> StandaloneProgGeneratingCompiler {
+        StandaloneProgGeneratingCompiler {
+            max_procedures: Some(max_procedures),
+            ..self
+        }
+    }
+
+    /// Outputs every selected node as an object with its path in the given format and the
+    /// node, `{"path": ..., "value": ...}`, instead of the node alone.
+    pub fn with_node_paths(self, format: NodePathFormat) -> StandaloneProgGeneratingCompiler {
+        StandaloneProgGeneratingCompiler {
+            node_paths: Some(format),
+            ..self
+        }
+    }
+
+    /// Sets the format of the input of the program, see [`InputFormat`].
+    pub fn with_input_format(self, input_format: InputFormat) -> StandaloneProgGeneratingCompiler {
+        StandaloneProgGeneratingCompiler {
+            input_format,
+            ..self
+        }
+    }
+
     pub fn compile<T: TargetCodeStandaloneProgGenerator>(
         self,
         query: &str,
         output_file_path: &str,
     ) -> Result<(), CompilationError> {
-        let parsed_query = CompilerHelper::parse(query)?;
-        let query_ir = CompilerHelper::generate_ir(&parsed_query);
+        let parsed_query = CompilerHelper::parse(query, query)?;
+        let query_ir = CompilerHelper::generate_ir(
+            query,
+            &parsed_query,
+            self.max_procedures,
+            T::is_random_access_supported(),
+            &self.ir_passes,
+        )?;
+        self.generate_code::<T>(query_ir, output_file_path)
+    }
+
+    /// Generates the code from the intermediate query code in the given file, as written by
+    /// [`write_ir_to_file`](Self::write_ir_to_file), running the enabled passes on it.
+    pub fn compile_ir<T: TargetCodeStandaloneProgGenerator>(
+        self,
+        ir_file_path: &str,
+        output_file_path: &str,
+    ) -> Result<(), CompilationError> {
+        let mut query_ir = CompilerHelper::read_query_ir(ir_file_path)?;
+        run_passes(&mut query_ir, &self.ir_passes);
+        self.generate_code::<T>(query_ir, output_file_path)
+    }
+
+    fn generate_code<T: TargetCodeStandaloneProgGenerator>(
+        self,
+        query_ir: Query,
+        output_file_path: &str,
+    ) -> Result<(), CompilationError> {
+        if self.node_paths.is_some() && !T::are_node_paths_supported() {
+            return Err(CompilationError::NodePathsNotSupported);
+        }
+        if self.input_format != InputFormat::Json && !T::is_ndjson_input_supported() {
+            return Err(CompilationError::NdjsonInputNotSupported);
+        }
+        if query_ir.is_random_access_needed() && !T::is_random_access_supported() {
+            return Err(CompilationError::RandomAccessNotSupported);
+        }
         if let Some(ir_output_file_path) = self.ir_output_file_path {
             let ir_output = CompilerHelper::generate_query_ir_output(&query_ir);
             CompilerHelper::write_to_file(&ir_output_file_path, ir_output)?;
@@ -118,8 +346,10 @@ impl StandaloneProgGeneratingCompiler {
             self.logging,
             self.mmap,
             self.eager_filter_evaluation,
+            self.node_paths,
+            self.input_format,
         );
-        let target_code = target_code_generator.generate();
+        let target_code = target_code_generator.generate()?;
         CompilerHelper::write_to_file(output_file_path, target_code)
     }
 }
@@ -128,8 +358,16 @@ impl StandaloneProgGeneratingCompiler {
 pub struct LibGeneratingCompiler {
     logging: bool,
     eager_filter_evaluation: bool,
-    bindings_generator: Option<Box<dyn BindingsGenerator>>,
+    bindings_generators: Vec<Box<dyn BindingsGenerator>>,
+    symbol_prefix: String,
     ir_output_file_path: Option<String>,
+    ir_passes: Vec<IRPass>,
+    max_procedures: Option<usize>,
+    single_pass_query_name: Option<String>,
+    result_spans: bool,
+    result_callbacks: bool,
+    node_paths: Option<NodePathFormat>,
+    input_format: InputFormat,
 }
 
 impl LibGeneratingCompiler {
@@ -137,14 +375,30 @@ impl LibGeneratingCompiler {
         LibGeneratingCompiler {
             logging: false,
             eager_filter_evaluation: false,
-            bindings_generator: None,
+            bindings_generators: Vec::new(),
+            symbol_prefix: String::new(),
             ir_output_file_path: None,
+            ir_passes: IRPass::value_variants().to_vec(),
+            max_procedures: None,
+            single_pass_query_name: None,
+            result_spans: false,
+            result_callbacks: false,
+            node_paths: None,
+            input_format: InputFormat::Json,
         }
     }
 
-    pub fn add_bindings_generator(self, bindings_generator: impl BindingsGenerator + 'static) -> LibGeneratingCompiler {
+    pub fn add_bindings_generator(mut self, bindings_generator: impl BindingsGenerator + 'static) -> LibGeneratingCompiler {
+        self.bindings_generators.push(Box::new(bindings_generator));
+        self
+    }
+
+    /// Prefixes the names of the `extern "C"` functions called through the bindings with the
+    /// given string. The functions freeing the results are defined by every library, so linking
+    /// several libraries together requires a different prefix for each of them.
+    pub fn with_symbol_prefix(self, symbol_prefix: &str) -> LibGeneratingCompiler {
         LibGeneratingCompiler {
-            bindings_generator: Some(Box::new(bindings_generator)),
+            symbol_prefix: symbol_prefix.to_string(),
             ..self
         }
     }
@@ -170,82 +424,288 @@ impl LibGeneratingCompiler {
         }
     }
 
+    /// Disables an optimization pass run on the IR of the queries.
+    pub fn without_ir_pass(self, pass: IRPass) -> LibGeneratingCompiler {
+        LibGeneratingCompiler {
+            ir_passes: self.ir_passes.into_iter().filter(|&p| p != pass).collect(),
+            ..self
+        }
+    }
+
+    pub fn without_ir_passes(self) -> LibGeneratingCompiler {
+        LibGeneratingCompiler {
+            ir_passes: Vec::new(),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of procedures generated for a query, see
+    /// [`StandaloneProgGeneratingCompiler::with_max_procedures`].
+    pub fn with_max_procedures(self, max_procedures: usize) -> LibGeneratingCompiler {
+        LibGeneratingCompiler {
+            max_procedures: Some(max_procedures),
+            ..self
+        }
+    }
+
+    /// Merges the queries without filters into a function with the given name, which executes
+    /// them in a single pass and returns their results grouped by their names.
+    pub fn with_single_pass(self, query_name: &str) -> LibGeneratingCompiler {
+        LibGeneratingCompiler {
+            single_pass_query_name: Some(query_name.to_string()),
+            ..self
+        }
+    }
+
+    /// Generates additional functions returning the results as (offset, length) spans of the
+    /// padded input instead of copies of the selected nodes.
+    pub fn with_result_spans(self) -> LibGeneratingCompiler {
+        LibGeneratingCompiler {
+            result_spans: true,
+            ..self
+        }
+    }
+
+    /// Generates additional functions passing each result to a callback as soon as it is
+    /// selected, in document order.
+    pub fn with_result_callbacks(self) -> LibGeneratingCompiler {
+        LibGeneratingCompiler {
+            result_callbacks: true,
+            ..self
+        }
+    }
+
+    /// Returns every selected node as `{"path": ..., "value": ...}`, with its path in the given
+    /// format.
+    pub fn with_node_paths(self, format: NodePathFormat) -> LibGeneratingCompiler {
+        LibGeneratingCompiler {
+            node_paths: Some(format),
+            ..self
+        }
+    }
+
+    /// Sets the format of the input of the functions executing the queries, see
+    /// [`InputFormat`].
+    pub fn with_input_format(self, input_format: InputFormat) -> LibGeneratingCompiler {
+        LibGeneratingCompiler {
+            input_format,
+            ..self
+        }
+    }
+
     pub fn compile<T: TargetCodeLibGenerator>(
         self,
         queries: QueriesSource,
         output_file_path: &str,
     ) -> Result<(), CompilationError> {
-        let queries = match queries {
-            QueriesSource::Immediate { queries } => queries,
-            QueriesSource::File { file_path } => Self::read_queries_from_file(&file_path)?
+        let (queries, errors) = match queries {
+            QueriesSource::Immediate { queries } => (queries, Vec::new()),
+            QueriesSource::File { file_path } => Self::read_queries_from_file(&file_path)?,
+            QueriesSource::IRFile { file_path } => {
+                let mut queries_irs = CompilerHelper::read_queries_irs(&file_path)?;
+                for (_, query_ir) in &mut queries_irs {
+                    run_passes(query_ir, &self.ir_passes);
+                }
+                return self.generate_code::<T>(queries_irs, output_file_path);
+            }
         };
-        let parsed_queries = Self::parse_queries(&queries)?;
-        let queries_irs = Self::generate_ir(&parsed_queries);
+        let (mut parsed_queries, mut errors) = Self::parse_queries(&queries, errors);
+        if let Some(query_name) = &self.single_pass_query_name {
+            match ParsedQuery::merge(&parsed_queries) {
+                Ok(merged_query) => {
+                    parsed_queries = vec![(query_name.to_string(), merged_query)];
+                }
+                Err(CompilationError::MultipleErrors(merge_errors)) => {
+                    errors.extend(merge_errors);
+                    parsed_queries.clear();
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        let queries_irs = Self::generate_ir(
+            &parsed_queries,
+            errors,
+            self.max_procedures,
+            T::is_random_access_supported(),
+            &self.ir_passes,
+        )?;
+        self.generate_code::<T>(queries_irs, output_file_path)
+    }
+
+    fn generate_code<T: TargetCodeLibGenerator>(
+        self,
+        queries_irs: Vec<NamedQuery>,
+        output_file_path: &str,
+    ) -> Result<(), CompilationError> {
+        let are_queries_merged = queries_irs
+            .iter()
+            .any(|(_, query_ir)| !query_ir.merged_queries.is_empty());
+        if !self.bindings_generators.is_empty() && !T::are_bindings_supported() {
+            return Err(CompilationError::BindingsNotSupported);
+        }
+        if !self.symbol_prefix.is_empty() && self.bindings_generators.is_empty() {
+            return Err(CompilationError::SymbolPrefixWithoutBindings);
+        }
+        if are_queries_merged && !T::is_single_pass_supported() {
+            return Err(CompilationError::SinglePassNotSupported);
+        }
+        if self.result_spans && !T::are_result_spans_supported() {
+            return Err(CompilationError::ResultSpansNotSupported);
+        }
+        if self.result_spans && are_queries_merged {
+            return Err(CompilationError::ResultSpansInSinglePass);
+        }
+        if self.result_callbacks && !T::are_result_callbacks_supported() {
+            return Err(CompilationError::ResultCallbacksNotSupported);
+        }
+        if self.result_callbacks && are_queries_merged {
+            return Err(CompilationError::ResultCallbacksInSinglePass);
+        }
+        if self.node_paths.is_some() && !T::are_node_paths_supported() {
+            return Err(CompilationError::NodePathsNotSupported);
+        }
+        if self.node_paths.is_some() && are_queries_merged {
+            return Err(CompilationError::NodePathsInSinglePass);
+        }
+        if self.node_paths.is_some() && self.result_spans {
+            return Err(CompilationError::NodePathsWithResultSpans);
+        }
+        if self.input_format != InputFormat::Json {
+            if !T::is_ndjson_input_supported() {
+                return Err(CompilationError::NdjsonInputNotSupported);
+            }
+            if are_queries_merged {
+                return Err(CompilationError::NdjsonInputInSinglePass);
+            }
+            if self.result_spans {
+                return Err(CompilationError::NdjsonInputWithResultSpans);
+            }
+            if self.result_callbacks {
+                return Err(CompilationError::NdjsonInputWithResultCallbacks);
+            }
+        }
+        let is_random_access_needed = queries_irs
+            .iter()
+            .any(|(_, query_ir)| query_ir.is_random_access_needed());
+        if is_random_access_needed && !T::is_random_access_supported() {
+            return Err(CompilationError::RandomAccessNotSupported);
+        }
         if let Some(ir_output_file_path) = self.ir_output_file_path {
             let ir_output = CompilerHelper::generate_queries_irs_output(&queries_irs);
             CompilerHelper::write_to_file(&ir_output_file_path, ir_output)?;
         }
         let filename = Path::new(output_file_path).file_name().unwrap().to_str().unwrap()
             .to_string();
-        let bindings = self.bindings_generator.is_some();
-        if let Some(bindings_generator) = self.bindings_generator {
-            bindings_generator.generate(&queries_irs)
-                .map_err(|err| CompilationError::IoError(err))?;
+        let bindings = (!self.bindings_generators.is_empty())
+            .then(|| Bindings { symbol_prefix: self.symbol_prefix.clone() });
+        let results_delivery = ResultsDelivery {
+            spans: self.result_spans,
+            callbacks: self.result_callbacks,
+            node_paths: self.node_paths,
         };
+        for bindings_generator in &self.bindings_generators {
+            bindings_generator.generate(
+                &queries_irs,
+                results_delivery,
+                T::are_execution_contexts_supported(),
+                &self.symbol_prefix,
+            )?;
+        }
         let target_code_generator = T::new(
             queries_irs,
             filename,
             self.logging,
             bindings,
             self.eager_filter_evaluation,
+            results_delivery,
+            self.input_format,
         );
-        let target_code = target_code_generator.generate();
+        let target_code = target_code_generator.generate()?;
         CompilerHelper::write_to_file(output_file_path, target_code)?;
         Ok(())
     }
 
-    fn read_queries_from_file(file_path: &str) -> Result<Vec<NamedRawQuery>, CompilationError> {
+    /// Reads the queries from a file with a query name and a query in each line, returning the
+    /// other lines as errors.
+    fn read_queries_from_file(
+        file_path: &str,
+    ) -> Result<(Vec<NamedRawQuery>, QueryFileErrors), CompilationError> {
         let mut queries = Vec::new();
+        let mut errors = Vec::new();
         let query_file = File::open(file_path)?;
-        for line in BufReader::new(query_file).lines().flatten() {
-            let (name, query) = line.split_once(" ").unwrap();
-            queries.push((name.to_string(), query.to_string()));
+        for (i, line) in BufReader::new(query_file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.split_once(" ") {
+                Some((name, query)) => queries.push((name.to_string(), query.to_string())),
+                None => errors.push((
+                    line.to_string(),
+                    CompilationError::InvalidQueriesFileLine { line_number: i + 1 },
+                )),
+            }
         }
-        Ok(queries)
+        Ok((queries, errors))
     }
 
+    /// Parses the queries, returning the ones that failed to parse as errors, added to the given
+    /// ones.
     fn parse_queries(
         queries: &Vec<NamedRawQuery>,
-    ) -> Result<Vec<NamedParsedQuery>, CompilationError> {
-        let parse_results: Vec<(String, Result<rsonpath_syntax::JsonPathQuery, CompilationError>)> =
+        mut errors: QueryFileErrors,
+    ) -> (Vec<NamedParsedQuery>, QueryFileErrors) {
+        let parse_results: Vec<(String, Result<ParsedQuery, CompilationError>)> =
             queries.iter()
-                .map(|(name, query)| (name.to_string(), CompilerHelper::parse(query)))
+                .map(|(name, query)| (name.to_string(), CompilerHelper::parse(name, query)))
                 .collect();
-        let mut parse_errors = Vec::new();
         let mut parsed_queries = Vec::new();
         for (name, parse_result) in parse_results {
             match parse_result {
                 Ok(query_syntax) => parsed_queries.push((name.to_string(), query_syntax)),
                 Err(err) => {
-                    parse_errors.push((name.to_string(), err))
+                    errors.push((name.to_string(), err))
                 }
             }
         }
-        return if !parse_errors.is_empty() {
-            Err(CompilationError::MultipleErrors(parse_errors))
-        } else {
-            Ok(parsed_queries)
-        };
+        (parsed_queries, errors)
     }
 
-    fn generate_ir(queries: &Vec<NamedParsedQuery>) -> Vec<NamedQuery> {
-        queries.iter()
-            .map(|(name, query)| (name.to_string(), CompilerHelper::generate_ir(query)))
-            .collect()
+    /// Generates the intermediate code of the queries, failing with the errors of the earlier
+    /// stages together with the ones of the queries for which it could not be generated.
+    fn generate_ir(
+        queries: &Vec<NamedParsedQuery>,
+        mut errors: QueryFileErrors,
+        max_procedures: Option<usize>,
+        is_random_access_supported: bool,
+        ir_passes: &[IRPass],
+    ) -> Result<Vec<NamedQuery>, CompilationError> {
+        let mut queries_irs = Vec::new();
+        for (name, query) in queries {
+            let ir_result = CompilerHelper::generate_ir(
+                name,
+                query,
+                max_procedures,
+                is_random_access_supported,
+                ir_passes,
+            );
+            match ir_result {
+                Ok(query_ir) => queries_irs.push((name.to_string(), query_ir)),
+                Err(err) => errors.push((name.to_string(), err)),
+            }
+        }
+        if !errors.is_empty() {
+            Err(CompilationError::MultipleErrors(errors))
+        } else {
+            Ok(queries_irs)
+        }
     }
 }
 
 pub enum QueriesSource {
     File { file_path: String },
     Immediate { queries: Vec<NamedRawQuery> },
+    /// File with the intermediate code of the queries, as written by
+    /// [`LibGeneratingCompiler::write_ir_to_file`].
+    IRFile { file_path: String },
 }
\ No newline at end of file
@@ -0,0 +1,844 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use serde_json::Value;
+
+use crate::{IRPass, NodePathFormat};
+use crate::compiler::{CompilationError, CompilerHelper};
+use crate::ir::{
+    Comparable, ComparisonOp, FilterExpression, FilterId, FilterSubquery,
+    FilterSubquerySelector, Instruction, LiteralValue, LogicalFunction, Procedure, Query, Regex,
+    RegexState, SelectionCondition, Slice, ValueFunction,
+};
+
+/// Executes the IR of a query against a parsed JSON document, selecting the nodes in the same
+/// order as the code generated for DOM-based targets.
+pub struct Interpreter {
+    query: Query,
+}
+
+impl Interpreter {
+    /// Creates an interpreter of the query, with its IR optimized by all passes, as by default
+    /// in the compilers.
+    pub fn new(query: &str) -> Result<Interpreter, CompilationError> {
+        Self::generate(query, None)
+    }
+
+    /// Creates an interpreter of the query, with the given maximum number of procedures
+    /// generated for it, as in the compilers.
+    pub fn with_max_procedures(
+        query: &str,
+        max_procedures: usize,
+    ) -> Result<Interpreter, CompilationError> {
+        Self::generate(query, Some(max_procedures))
+    }
+
+    fn generate(query: &str, max_procedures: Option<usize>) -> Result<Interpreter, CompilationError> {
+        let parsed_query = CompilerHelper::parse(query, query)?;
+        Ok(Interpreter {
+            query: CompilerHelper::generate_ir(
+                query,
+                &parsed_query,
+                max_procedures,
+                true,
+                IRPass::value_variants(),
+            )?,
+        })
+    }
+
+    /// Creates an interpreter of intermediate query code in the textual format in which the
+    /// compilers write it to a file.
+    pub fn from_ir(ir: &str) -> Result<Interpreter, CompilationError> {
+        Ok(Interpreter {
+            query: Query::from_text(ir)?,
+        })
+    }
+
+    /// Returns the nodes of the document selected by the query.
+    pub fn run<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        self.execute(document).results
+    }
+
+    /// Returns the nodes of the document selected by every query merged into the query, grouped
+    /// by the names of the queries, in their order.
+    pub fn run_merged<'a>(&self, document: &'a Value) -> Vec<(&str, Vec<&'a Value>)> {
+        let execution = self.execute(document);
+        let mut results: Vec<(&str, Vec<&'a Value>)> = self
+            .query
+            .merged_queries
+            .iter()
+            .map(|merged_query| (merged_query.name.as_str(), Vec::new()))
+            .collect();
+        for (result, query_index) in execution.results.into_iter().zip(execution.result_queries) {
+            if let Some(query_index) = query_index {
+                results[query_index].1.push(result);
+            }
+        }
+        results
+    }
+
+    /// Returns the nodes of the document selected by the query, together with their paths in
+    /// the given format.
+    pub fn run_with_node_paths<'a>(
+        &self,
+        document: &'a Value,
+        format: NodePathFormat,
+    ) -> Vec<(String, &'a Value)> {
+        let execution = self.execute(document);
+        execution
+            .result_node_paths
+            .iter()
+            .map(|node_path| format_node_path(node_path, format))
+            .zip(execution.results)
+            .collect()
+    }
+
+    fn execute<'a>(&self, document: &'a Value) -> Execution<'_, 'a> {
+        let mut execution = Execution::new(&self.query, document);
+        for (query_index, merged_query) in self.query.merged_queries.iter().enumerate() {
+            if merged_query.segments_count == 0 {
+                execution.save_result(document, Some(query_index));
+            }
+        }
+        if self.query.has_root_procedure() {
+            let segment_conditions = vec![true; self.query.segments_count];
+            let root_procedure_name = self.query.root_procedure_name();
+            execution.execute_procedure(&root_procedure_name, document, &segment_conditions);
+        }
+        execution
+    }
+}
+
+/// Position of the current node in its parent, needed by the conditions on indices and names.
+#[derive(Clone, Copy)]
+enum Position<'a> {
+    Root,
+    Element { index: usize, array_length: usize },
+    Member { name: &'a str },
+}
+
+/// Whether the remaining instructions for the current child should be executed, or skipped
+/// because of `Continue`.
+#[derive(PartialEq)]
+enum Flow {
+    Next,
+    Continue,
+}
+
+struct Execution<'q, 'a> {
+    query: &'q Query,
+    procedures: HashMap<String, &'q Procedure>,
+    root: &'a Value,
+    results: Vec<&'a Value>,
+    /// Positions of the nodes on the path from the root to the current node.
+    node_path: Vec<Position<'a>>,
+    result_node_paths: Vec<Vec<Position<'a>>>,
+    /// Indices of the merged queries selecting the results.
+    result_queries: Vec<Option<usize>>,
+    child_active_segments: Vec<bool>,
+    is_child_selected: bool,
+}
+
+impl<'q, 'a> Execution<'q, 'a> {
+    fn new(query: &'q Query, root: &'a Value) -> Execution<'q, 'a> {
+        Execution {
+            query,
+            procedures: query
+                .procedures
+                .iter()
+                .map(|procedure| (procedure.name.to_lowercase(), procedure))
+                .collect(),
+            root,
+            results: Vec::new(),
+            node_path: Vec::new(),
+            result_node_paths: Vec::new(),
+            result_queries: Vec::new(),
+            child_active_segments: Vec::new(),
+            is_child_selected: false,
+        }
+    }
+
+    fn are_any_filters(&self) -> bool {
+        !self.query.filter_procedures.is_empty()
+    }
+
+    fn execute_procedure(&mut self, name: &str, node: &'a Value, segment_conditions: &[bool]) {
+        let procedure = *self
+            .procedures
+            .get(&name.to_lowercase())
+            .unwrap_or_else(|| panic!("procedure {name} is not defined"));
+        let mut filter_values = HashMap::new();
+        self.execute_instructions(
+            &procedure.instructions,
+            node,
+            Position::Root,
+            segment_conditions,
+            &mut filter_values,
+        );
+    }
+
+    fn execute_instructions(
+        &mut self,
+        instructions: &'q [Instruction],
+        node: &'a Value,
+        position: Position<'a>,
+        segment_conditions: &[bool],
+        filter_values: &mut HashMap<&'q FilterId, bool>,
+    ) -> Flow {
+        for instruction in instructions {
+            let flow = self.execute_instruction(
+                instruction,
+                node,
+                position,
+                segment_conditions,
+                filter_values,
+            );
+            if flow == Flow::Continue {
+                return Flow::Continue;
+            }
+        }
+        Flow::Next
+    }
+
+    fn execute_instruction(
+        &mut self,
+        instruction: &'q Instruction,
+        node: &'a Value,
+        position: Position<'a>,
+        segment_conditions: &[bool],
+        filter_values: &mut HashMap<&'q FilterId, bool>,
+    ) -> Flow {
+        match instruction {
+            Instruction::ForEachElement { instructions } => {
+                if let Value::Array(array) = node {
+                    for (index, element) in array.iter().enumerate() {
+                        let position = Position::Element { index, array_length: array.len() };
+                        self.node_path.push(position);
+                        self.execute_instructions(
+                            instructions,
+                            element,
+                            position,
+                            segment_conditions,
+                            &mut HashMap::new(),
+                        );
+                        self.node_path.pop();
+                    }
+                }
+                Flow::Next
+            }
+            Instruction::ForEachElementInSlices { slices, instructions } => {
+                if let Value::Array(array) = node {
+                    for slice in slices {
+                        for index in slice_indices(slice, array.len() as i64) {
+                            let position = Position::Element { index, array_length: array.len() };
+                            self.node_path.push(position);
+                            self.execute_instructions(
+                                instructions,
+                                &array[index],
+                                position,
+                                segment_conditions,
+                                &mut HashMap::new(),
+                            );
+                            self.node_path.pop();
+                        }
+                    }
+                }
+                Flow::Next
+            }
+            Instruction::ForEachElementInReverseOrder { instructions, .. } => {
+                if let Value::Array(array) = node {
+                    for (index, element) in array.iter().enumerate().rev() {
+                        let position = Position::Element { index, array_length: array.len() };
+                        self.node_path.push(position);
+                        self.execute_instructions(
+                            instructions,
+                            element,
+                            position,
+                            segment_conditions,
+                            &mut HashMap::new(),
+                        );
+                        self.node_path.pop();
+                    }
+                }
+                Flow::Next
+            }
+            Instruction::ForEachMember { instructions } => {
+                if let Value::Object(object) = node {
+                    for (name, value) in object {
+                        self.node_path.push(Position::Member { name });
+                        self.execute_instructions(
+                            instructions,
+                            value,
+                            Position::Member { name },
+                            segment_conditions,
+                            &mut HashMap::new(),
+                        );
+                        self.node_path.pop();
+                    }
+                }
+                Flow::Next
+            }
+            Instruction::IfCurrentIndexEquals { index, instructions } => {
+                let is_selected = matches!(
+                    position,
+                    Position::Element { index: current_index, .. } if current_index as u64 == *index
+                );
+                self.execute_instructions_if(
+                    is_selected,
+                    instructions,
+                    node,
+                    position,
+                    segment_conditions,
+                    filter_values,
+                )
+            }
+            Instruction::IfCurrentIndexFromEndEquals { index, instructions } => {
+                let is_selected = matches!(
+                    position,
+                    Position::Element { index: current_index, array_length }
+                        if (current_index as u64) + index == array_length as u64
+                );
+                self.execute_instructions_if(
+                    is_selected,
+                    instructions,
+                    node,
+                    position,
+                    segment_conditions,
+                    filter_values,
+                )
+            }
+            Instruction::IfCurrentIndexInSlice { slice, instructions } => {
+                let is_selected = matches!(
+                    position,
+                    Position::Element { index, array_length }
+                        if is_in_slice(slice, index as i64, array_length as i64)
+                );
+                self.execute_instructions_if(
+                    is_selected,
+                    instructions,
+                    node,
+                    position,
+                    segment_conditions,
+                    filter_values,
+                )
+            }
+            Instruction::IfCurrentMemberNameEquals { name, instructions } => {
+                let is_selected = matches!(
+                    position,
+                    Position::Member { name: current_name } if current_name == name
+                );
+                self.execute_instructions_if(
+                    is_selected,
+                    instructions,
+                    node,
+                    position,
+                    segment_conditions,
+                    filter_values,
+                )
+            }
+            Instruction::IfActiveFilterInstance { .. } => Flow::Next,
+            Instruction::IfSegmentActive { segment_index, instructions } => {
+                self.execute_instructions_if(
+                    segment_conditions[*segment_index],
+                    instructions,
+                    node,
+                    position,
+                    segment_conditions,
+                    filter_values,
+                )
+            }
+            Instruction::ExecuteProcedureOnChild { conditions, segments, name } => {
+                if !self.are_any_filters() {
+                    self.execute_procedure(name, node, segment_conditions);
+                    return Flow::Next;
+                }
+                let mut new_segment_conditions = segment_conditions.to_vec();
+                for (i, condition) in conditions.iter().enumerate() {
+                    new_segment_conditions[i] = match condition {
+                        Some(condition) => {
+                            Self::is_condition_met(condition, segment_conditions, filter_values)
+                        }
+                        None => segment_conditions[i],
+                    } && (i == 0 || segment_conditions[i - 1]);
+                }
+                if segments.is_empty()
+                    || segments.iter().any(|&segment| new_segment_conditions[segment])
+                {
+                    self.execute_procedure(name, node, &new_segment_conditions);
+                }
+                Flow::Next
+            }
+            Instruction::SaveCurrentNodeDuringTraversal { condition, instruction, query_index } => {
+                let is_selected = match condition {
+                    Some(condition) if self.are_any_filters() => {
+                        Self::is_condition_met(condition, segment_conditions, filter_values)
+                    }
+                    _ => true,
+                };
+                if is_selected {
+                    self.save_result(node, *query_index);
+                }
+                self.execute_instruction(
+                    instruction,
+                    node,
+                    position,
+                    segment_conditions,
+                    filter_values,
+                )
+            }
+            Instruction::Continue => Flow::Continue,
+            Instruction::TraverseCurrentNodeSubtree => Flow::Next,
+            Instruction::StartFilterExecution { filter_id } => {
+                let value = self.evaluate_filter(filter_id, node);
+                filter_values.insert(filter_id, value);
+                Flow::Next
+            }
+            Instruction::EndFiltersExecution | Instruction::UpdateSubqueriesState => Flow::Next,
+            Instruction::ExecuteProcedureOnChildWithActiveSegments {
+                name,
+                segments_count,
+                instructions,
+            } => {
+                self.child_active_segments = vec![false; *segments_count];
+                self.is_child_selected = false;
+                self.execute_instructions(
+                    instructions,
+                    node,
+                    position,
+                    segment_conditions,
+                    filter_values,
+                );
+                if self.is_child_selected {
+                    self.save_result(node, None);
+                }
+                // The active segments are passed in place of the segment conditions, which
+                // are used only in queries with filters.
+                let active_segments = std::mem::take(&mut self.child_active_segments);
+                if active_segments.contains(&true) {
+                    self.execute_procedure(name, node, &active_segments);
+                }
+                Flow::Next
+            }
+            Instruction::ActivateSegmentInChild { segment_index } => {
+                self.child_active_segments[*segment_index] = true;
+                Flow::Next
+            }
+            Instruction::SelectChild => {
+                self.is_child_selected = true;
+                Flow::Next
+            }
+        }
+    }
+
+    fn save_result(&mut self, node: &'a Value, query_index: Option<usize>) {
+        self.results.push(node);
+        self.result_node_paths.push(self.node_path.clone());
+        self.result_queries.push(query_index);
+    }
+
+    fn execute_instructions_if(
+        &mut self,
+        condition: bool,
+        instructions: &'q [Instruction],
+        node: &'a Value,
+        position: Position<'a>,
+        segment_conditions: &[bool],
+        filter_values: &mut HashMap<&'q FilterId, bool>,
+    ) -> Flow {
+        if condition {
+            self.execute_instructions(
+                instructions,
+                node,
+                position,
+                segment_conditions,
+                filter_values,
+            )
+        } else {
+            Flow::Next
+        }
+    }
+
+    fn is_condition_met(
+        condition: &SelectionCondition,
+        segment_conditions: &[bool],
+        filter_values: &HashMap<&FilterId, bool>,
+    ) -> bool {
+        match condition {
+            SelectionCondition::Filter { id } => *filter_values
+                .get(id)
+                .unwrap_or_else(|| panic!("filter {id} is used before its execution")),
+            SelectionCondition::RuntimeSegmentCondition { segment_index } => {
+                segment_conditions[*segment_index]
+            }
+            SelectionCondition::And { lhs, rhs } => {
+                Self::is_condition_met(lhs, segment_conditions, filter_values)
+                    && Self::is_condition_met(rhs, segment_conditions, filter_values)
+            }
+            SelectionCondition::Or { lhs, rhs } => {
+                Self::is_condition_met(lhs, segment_conditions, filter_values)
+                    || Self::is_condition_met(rhs, segment_conditions, filter_values)
+            }
+        }
+    }
+
+    fn evaluate_filter(&self, filter_id: &FilterId, node: &'a Value) -> bool {
+        let params: Vec<SubqueryResult> = self.query.filter_subqueries[filter_id]
+            .iter()
+            .map(|subquery| {
+                let node = if subquery.is_absolute { self.root } else { node };
+                self.evaluate_subquery(subquery, node)
+            })
+            .collect();
+        let filter_procedure = &self.query.filter_procedures[filter_id];
+        Self::evaluate_expression(&filter_procedure.expression, &filter_procedure.regexes, &params)
+    }
+
+    fn evaluate_expression(
+        expression: &FilterExpression,
+        regexes: &[Regex],
+        params: &[SubqueryResult<'a>],
+    ) -> bool {
+        match expression {
+            FilterExpression::Or { lhs, rhs } => {
+                Self::evaluate_expression(lhs, regexes, params)
+                    || Self::evaluate_expression(rhs, regexes, params)
+            }
+            FilterExpression::And { lhs, rhs } => {
+                Self::evaluate_expression(lhs, regexes, params)
+                    && Self::evaluate_expression(rhs, regexes, params)
+            }
+            FilterExpression::Not { expr } => !Self::evaluate_expression(expr, regexes, params),
+            FilterExpression::Comparison { lhs, rhs, op } => {
+                let lhs = Self::evaluate_comparable(lhs, params);
+                let rhs = Self::evaluate_comparable(rhs, params);
+                match op {
+                    ComparisonOp::EqualTo => lhs.equal(&rhs),
+                    ComparisonOp::NotEqualTo => !lhs.equal(&rhs),
+                    ComparisonOp::LessOrEqualTo => lhs.less(&rhs) || lhs.equal(&rhs),
+                    ComparisonOp::GreaterOrEqualTo => rhs.less(&lhs) || lhs.equal(&rhs),
+                    ComparisonOp::LessThan => lhs.less(&rhs),
+                    ComparisonOp::GreaterThan => rhs.less(&lhs),
+                }
+            }
+            FilterExpression::ExistenceTest { param_id } => params[*param_id].exists,
+            FilterExpression::FunctionCall { function } => match function {
+                LogicalFunction::Match { arg, regex_id, is_full_match } => {
+                    let arg = Self::evaluate_comparable(arg, params);
+                    match (regex_id, arg) {
+                        (Some(regex_id), FilterValue::Node(Value::String(str))) => {
+                            regex_matches(&regexes[*regex_id], str, *is_full_match)
+                        }
+                        (Some(regex_id), FilterValue::Literal(Value::String(str))) => {
+                            regex_matches(&regexes[*regex_id], &str, *is_full_match)
+                        }
+                        _ => false,
+                    }
+                }
+            },
+        }
+    }
+
+    fn evaluate_comparable(comparable: &Comparable, params: &[SubqueryResult<'a>]) -> FilterValue<'a> {
+        match comparable {
+            Comparable::Param { id } => params[*id].value.clone(),
+            Comparable::Literal { value } => FilterValue::Literal(match value {
+                LiteralValue::String(str) => Value::from(str.as_str()),
+                LiteralValue::Int(number) => Value::from(*number),
+                LiteralValue::Float(number) => Value::from(*number),
+                LiteralValue::Bool(value) => Value::from(*value),
+                LiteralValue::Null => Value::Null,
+            }),
+            Comparable::FunctionCall { function } => match function {
+                ValueFunction::Length { arg } => {
+                    let length = match Self::evaluate_comparable(arg, params).value() {
+                        Some(Value::String(str)) => str.chars().count(),
+                        Some(Value::Array(array)) => array.len(),
+                        Some(Value::Object(object)) => object.len(),
+                        _ => return FilterValue::Nothing,
+                    };
+                    FilterValue::Literal(Value::from(length))
+                }
+                ValueFunction::Count { param_id } => {
+                    FilterValue::Literal(Value::from(params[*param_id].count))
+                }
+                ValueFunction::Value { param_id } => params[*param_id].value.clone(),
+            },
+        }
+    }
+
+    fn evaluate_subquery(&self, subquery: &FilterSubquery, node: &'a Value) -> SubqueryResult<'a> {
+        let mut nodes = Vec::new();
+        self.select_nodes(subquery, 0, node, &mut nodes);
+        SubqueryResult {
+            value: if nodes.len() == 1 { FilterValue::Node(nodes[0]) } else { FilterValue::Nothing },
+            exists: !nodes.is_empty(),
+            count: nodes.len(),
+        }
+    }
+
+    fn select_nodes(
+        &self,
+        subquery: &FilterSubquery,
+        segment_index: usize,
+        node: &'a Value,
+        nodes: &mut Vec<&'a Value>,
+    ) {
+        if segment_index == subquery.segments.len() {
+            nodes.push(node);
+            return;
+        }
+        self.select_children(subquery, segment_index, node, nodes);
+        if subquery.segments[segment_index].is_descendant {
+            for child in children(node) {
+                self.select_nodes(subquery, segment_index, child, nodes);
+            }
+        }
+    }
+
+    fn select_children(
+        &self,
+        subquery: &FilterSubquery,
+        segment_index: usize,
+        node: &'a Value,
+        nodes: &mut Vec<&'a Value>,
+    ) {
+        for selector in &subquery.segments[segment_index].selectors {
+            match (selector, node) {
+                (FilterSubquerySelector::Name(name), Value::Object(object)) => {
+                    if let Some(child) = object.get(name) {
+                        self.select_nodes(subquery, segment_index + 1, child, nodes);
+                    }
+                }
+                (FilterSubquerySelector::Index(index), Value::Array(array)) => {
+                    let index = if *index >= 0 { *index } else { array.len() as i64 + index };
+                    if let Some(child) = usize::try_from(index).ok().and_then(|index| array.get(index)) {
+                        self.select_nodes(subquery, segment_index + 1, child, nodes);
+                    }
+                }
+                (FilterSubquerySelector::Wildcard, _) => {
+                    for child in children(node) {
+                        self.select_nodes(subquery, segment_index + 1, child, nodes);
+                    }
+                }
+                (FilterSubquerySelector::Slice(slice), Value::Array(array)) => {
+                    let length = array.len() as i64;
+                    for i in 0..length {
+                        let index = if slice.step > 0 { i } else { length - 1 - i };
+                        if is_in_slice(slice, index, length) {
+                            self.select_nodes(subquery, segment_index + 1, &array[index as usize], nodes);
+                        }
+                    }
+                }
+                (FilterSubquerySelector::Filter(filter_id), _) => {
+                    for child in children(node) {
+                        if self.evaluate_filter(filter_id, child) {
+                            self.select_nodes(subquery, segment_index + 1, child, nodes);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Value of a comparable in a filter expression. Values which are not nodes of the document
+/// are the results of literals and functions.
+#[derive(Clone)]
+enum FilterValue<'a> {
+    Nothing,
+    Node(&'a Value),
+    Literal(Value),
+}
+
+impl FilterValue<'_> {
+    fn value(&self) -> Option<&Value> {
+        match self {
+            FilterValue::Nothing => None,
+            FilterValue::Node(node) => Some(node),
+            FilterValue::Literal(value) => Some(value),
+        }
+    }
+
+    fn equal(&self, other: &FilterValue) -> bool {
+        match (self.value(), other.value()) {
+            (None, None) => true,
+            (Some(lhs), Some(rhs)) => values_equal(lhs, rhs),
+            _ => false,
+        }
+    }
+
+    fn less(&self, other: &FilterValue) -> bool {
+        match (self.value(), other.value()) {
+            (Some(Value::String(lhs)), Some(Value::String(rhs))) => lhs < rhs,
+            (Some(lhs), Some(rhs)) => compare_numbers(lhs, rhs) == Some(Ordering::Less),
+            _ => false,
+        }
+    }
+}
+
+struct SubqueryResult<'a> {
+    value: FilterValue<'a>,
+    exists: bool,
+    count: usize,
+}
+
+/// Structural equality of values, under which numbers are equal if their values are,
+/// regardless of how they are written.
+fn values_equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Array(lhs), Value::Array(rhs)) => {
+            lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| values_equal(lhs, rhs))
+        }
+        (Value::Object(lhs), Value::Object(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs.iter().all(|(name, lhs)| rhs.get(name).is_some_and(|rhs| values_equal(lhs, rhs)))
+        }
+        (Value::Number(_), Value::Number(_)) => compare_numbers(lhs, rhs) == Some(Ordering::Equal),
+        _ => lhs == rhs,
+    }
+}
+
+fn compare_numbers(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    let (Value::Number(lhs), Value::Number(rhs)) = (lhs, rhs) else {
+        return None;
+    };
+    match (lhs.as_i64(), rhs.as_i64()) {
+        (Some(lhs), Some(rhs)) => Some(lhs.cmp(&rhs)),
+        _ => lhs.as_f64()?.partial_cmp(&rhs.as_f64()?),
+    }
+}
+
+fn children(node: &Value) -> Box<dyn Iterator<Item=&Value> + '_> {
+    match node {
+        Value::Array(array) => Box::new(array.iter()),
+        Value::Object(object) => Box::new(object.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// Formats the path of a node, given by its positions below the root, as a Normalized Path
+/// (RFC 9535) or a JSON Pointer (RFC 6901).
+fn format_node_path(node_path: &[Position], format: NodePathFormat) -> String {
+    let mut formatted = match format {
+        NodePathFormat::NormalizedPath => "$".to_string(),
+        NodePathFormat::JsonPointer => String::new(),
+    };
+    for position in node_path {
+        match (position, format) {
+            (Position::Root, _) => {}
+            (Position::Element { index, .. }, NodePathFormat::NormalizedPath) => {
+                formatted += &format!("[{index}]");
+            }
+            (Position::Element { index, .. }, NodePathFormat::JsonPointer) => {
+                formatted += &format!("/{index}");
+            }
+            (Position::Member { name }, NodePathFormat::NormalizedPath) => {
+                formatted += "['";
+                for c in name.chars() {
+                    match c {
+                        '\u{8}' => formatted += "\\b",
+                        '\u{c}' => formatted += "\\f",
+                        '\n' => formatted += "\\n",
+                        '\r' => formatted += "\\r",
+                        '\t' => formatted += "\\t",
+                        '\'' => formatted += "\\'",
+                        '\\' => formatted += "\\\\",
+                        c if c < ' ' => formatted += &format!("\\u{:04x}", c as u32),
+                        c => formatted.push(c),
+                    }
+                }
+                formatted += "']";
+            }
+            (Position::Member { name }, NodePathFormat::JsonPointer) => {
+                formatted += &format!("/{}", name.replace('~', "~0").replace('/', "~1"));
+            }
+        }
+    }
+    formatted
+}
+
+/// Checks whether the element at `index` of an array of the given length is in the slice.
+fn is_in_slice(slice: &Slice, index: i64, length: i64) -> bool {
+    let bound = |index: i64| if index >= 0 { index } else { length + index };
+    if slice.step > 0 {
+        let lower = bound(slice.start).max(0);
+        if index < lower || slice.end.is_some_and(|end| index >= bound(end)) {
+            return false;
+        }
+        (index - lower) % slice.step == 0
+    } else if slice.step < 0 {
+        let upper = bound(slice.start).min(length - 1);
+        if index > upper || slice.end.is_some_and(|end| index <= bound(end)) {
+            return false;
+        }
+        (upper - index) % -slice.step == 0
+    } else {
+        false
+    }
+}
+
+/// Returns the indices of the elements in the slice, in the order in which they are selected.
+fn slice_indices(slice: &Slice, length: i64) -> Vec<usize> {
+    let bound = |index: i64| if index >= 0 { index } else { length + index };
+    let mut indices = Vec::new();
+    if slice.step > 0 {
+        let end = slice.end.map_or(length, |end| bound(end).clamp(0, length));
+        let mut index = bound(slice.start).clamp(0, length);
+        while index < end {
+            indices.push(index as usize);
+            index += slice.step;
+        }
+    } else if slice.step < 0 {
+        let end = slice.end.map_or(-1, |end| bound(end).clamp(-1, length - 1));
+        let mut index = bound(slice.start).clamp(-1, length - 1);
+        while index > end {
+            indices.push(index as usize);
+            index += slice.step;
+        }
+    }
+    indices
+}
+
+/// Simulates the automaton of the regex on the string. A full match has to start at the
+/// beginning and end at the end of the string, while a search can match any substring.
+fn regex_matches(regex: &Regex, str: &str, is_full_match: bool) -> bool {
+    let mut current_states = Vec::new();
+    let mut pos = 0;
+    loop {
+        if pos == 0 || !is_full_match {
+            add_regex_state(regex, &mut current_states, regex.start);
+        }
+        if current_states.iter().any(|&state| {
+            matches!(regex.states[state], RegexState::Match) && (!is_full_match || pos == str.len())
+        }) {
+            return true;
+        }
+        if pos == str.len() || (is_full_match && current_states.is_empty()) {
+            return false;
+        }
+        let c = str[pos..].chars().next().unwrap();
+        pos += c.len_utf8();
+        let mut next_states = Vec::new();
+        for &state in &current_states {
+            if let RegexState::Chars { ranges, next } = &regex.states[state] {
+                if ranges.iter().any(|&(start, end)| (start..=end).contains(&(c as u32))) {
+                    add_regex_state(regex, &mut next_states, *next);
+                }
+            }
+        }
+        current_states = next_states;
+    }
+}
+
+fn add_regex_state(regex: &Regex, states: &mut Vec<usize>, state: usize) {
+    if states.contains(&state) {
+        return;
+    }
+    states.push(state);
+    if let RegexState::Split { next, alt } = regex.states[state] {
+        add_regex_state(regex, states, next);
+        add_regex_state(regex, states, alt);
+    }
+}
@@ -1,25 +1,134 @@
 use std::collections::HashMap;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::compiler::CompilationError;
+use crate::IRPass;
+use crate::ir::procedure_segments::ProcedureSegmentsData;
 
 mod filter_generator;
 pub mod generator;
+pub mod passes;
 mod procedure_segments;
+mod regex_generator;
+mod serialization;
+mod validation;
+
+/// Name of the procedure executed on the root of the document.
+const ROOT_PROCEDURE_NAME: &str = "Selectors_0";
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Query {
     pub procedures: Vec<Procedure>,
+    #[serde(with = "serialization::sorted_entries")]
     pub filter_procedures: HashMap<FilterId, FilterProcedure>,
+    #[serde(with = "serialization::sorted_entries")]
     pub filter_subqueries: HashMap<FilterId, Vec<FilterSubquery>>,
     pub segments_count: usize,
+    /// Reports of the optimization passes run on the query, in the order in which they were run.
+    #[serde(default)]
+    pub passes: Vec<PassReport>,
+    /// Queries merged into this one to be executed in a single pass, in the order of their
+    /// segments. Empty for a single query.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merged_queries: Vec<MergedQuery>,
+}
+
+impl Query {
+    /// Serializes the query to JSON, which can be read back with [`Query::from_text`]. The same
+    /// query is always written in the same way.
+    pub fn to_text(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Reads the query from its textual form and validates it with [`Query::validate`].
+    pub fn from_text(text: &str) -> Result<Query, CompilationError> {
+        let query: Query = serde_json::from_str(text)?;
+        query.validate()?;
+        Ok(query)
+    }
+
+    /// Checks that the procedures and filters to which the query refers are defined, and that
+    /// the results of the filters are used only after their execution.
+    pub fn validate(&self) -> Result<(), CompilationError> {
+        validation::validate(self).map_err(CompilationError::InvalidIR)
+    }
+
+    /// Whether the array elements are visited out of the document order, which only the
+    /// targets with random access to them support.
+    pub fn is_random_access_needed(&self) -> bool {
+        let mut is_needed = false;
+        for procedure in &self.procedures {
+            passes::for_each_instruction(&procedure.instructions, &mut |instruction| {
+                is_needed |= matches!(instruction, Instruction::ForEachElementInSlices { .. });
+            });
+        }
+        is_needed
+    }
+
+    /// Whether a procedure is executed on the root of the document. It is not for merged queries
+    /// without any segments, which only select the root.
+    pub fn has_root_procedure(&self) -> bool {
+        self.merged_queries.is_empty() || self.segments_count > 0
+    }
+
+    /// Name of the procedure executed on the root of the document. For merged queries, it is
+    /// the procedure for the first segments of the queries.
+    pub fn root_procedure_name(&self) -> String {
+        if self.merged_queries.is_empty() {
+            return ROOT_PROCEDURE_NAME.to_string();
+        }
+        let first_segments = first_segments_of_merged_queries(
+            self.merged_queries.iter().map(|merged_query| merged_query.segments_count),
+        );
+        ProcedureSegmentsData::new(first_segments).name()
+    }
+}
+
+/// A query executed in a single pass with the other queries merged into the same [`Query`].
+/// The segments of the merged queries are numbered consecutively, in the order of the queries.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MergedQuery {
+    pub name: String,
+    pub segments_count: usize,
 }
 
-#[derive(Debug)]
+/// Indices of the first segments of the merged queries with the given numbers of segments,
+/// skipping the queries without segments.
+pub fn first_segments_of_merged_queries(
+    segments_counts: impl IntoIterator<Item = usize>,
+) -> Vec<SegmentIndex> {
+    let mut first_segments = Vec::new();
+    let mut first_segment = 0;
+    for segments_count in segments_counts {
+        if segments_count > 0 {
+            first_segments.push(first_segment);
+        }
+        first_segment += segments_count;
+    }
+    first_segments
+}
+
+/// Result of an optimization pass, with the number of procedures, conditions or instructions
+/// it removed, merged or folded.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PassReport {
+    pub pass: IRPass,
+    pub changes: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Procedure {
     pub name: String,
     pub instructions: Vec<Instruction>,
+    /// Whether the segments active at the node are passed to the procedure at runtime, instead
+    /// of being fixed for the procedure by the generator.
+    #[serde(default)]
+    pub are_active_segments_passed: bool,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum Instruction {
     ForEachElement {
         instructions: Vec<Instruction>,
@@ -27,6 +136,21 @@ pub enum Instruction {
     ForEachMember {
         instructions: Vec<Instruction>,
     },
+    /// Executes the instructions on the elements in the slices, in their order. Generated only
+    /// for the targets with random access to the elements.
+    ForEachElementInSlices {
+        slices: Vec<Slice>,
+        instructions: Vec<Instruction>,
+    },
+    /// Executes the instructions on the elements in the reverse order, in which a slice with a
+    /// negative step selects them. The targets without random access to the elements visit them
+    /// in the document order and reverse the order of their results afterwards, which in a query
+    /// with merged queries are the results of the merged query with index `query_index`.
+    ForEachElementInReverseOrder {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        query_index: Option<usize>,
+        instructions: Vec<Instruction>,
+    },
     IfCurrentIndexEquals {
         index: u64,
         instructions: Vec<Instruction>,
@@ -35,6 +159,10 @@ pub enum Instruction {
         index: u64,
         instructions: Vec<Instruction>,
     },
+    IfCurrentIndexInSlice {
+        slice: Slice,
+        instructions: Vec<Instruction>,
+    },
     IfCurrentMemberNameEquals {
         name: String,
         instructions: Vec<Instruction>,
@@ -42,14 +170,24 @@ pub enum Instruction {
     IfActiveFilterInstance {
         instructions: Vec<Instruction>
     },
+    /// Executes the instructions if the segment is active at the current node. Used only in
+    /// the procedures to which the active segments are passed.
+    IfSegmentActive {
+        segment_index: SegmentIndex,
+        instructions: Vec<Instruction>,
+    },
     ExecuteProcedureOnChild {
         conditions: Vec<Option<SelectionCondition>>,
         segments: Vec<SegmentIndex>,
         name: String,
     },
+    /// Saves the current node while executing the instruction. In a query with merged queries,
+    /// `query_index` is the index of the merged query selecting the node.
     SaveCurrentNodeDuringTraversal {
         condition: Option<SelectionCondition>,
         instruction: Box<Instruction>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        query_index: Option<usize>,
     },
     Continue,
     TraverseCurrentNodeSubtree,
@@ -58,6 +196,17 @@ pub enum Instruction {
     },
     EndFiltersExecution,
     UpdateSubqueriesState,
+    /// Executes the procedure on the child with the active segments determined by the
+    /// instructions, which also determine whether the child is selected.
+    ExecuteProcedureOnChildWithActiveSegments {
+        name: String,
+        segments_count: usize,
+        instructions: Vec<Instruction>,
+    },
+    ActivateSegmentInChild {
+        segment_index: SegmentIndex,
+    },
+    SelectChild,
 }
 
 impl Instruction {
@@ -72,21 +221,78 @@ impl Instruction {
     pub fn is_array_element_iteration(&self) -> bool {
         match self {
             Instruction::ForEachElement { .. }
+            | Instruction::ForEachElementInSlices { .. }
+            | Instruction::ForEachElementInReverseOrder { .. }
             | Instruction::SaveCurrentNodeDuringTraversal { .. } => true,
             _ => false,
         }
     }
 }
 
-#[derive(Debug)]
+/// Array slice with bounds and step as written in the query, with negative bounds counted
+/// from the end of the array. The targets select its elements following the slice semantics
+/// from RFC 9535, using the length of the array only when a bound is relative to its end or the
+/// step is negative.
+#[derive(Eq, PartialEq, Hash, Clone, Ord, PartialOrd, Serialize, Deserialize, Debug)]
+pub struct Slice {
+    pub start: i64,
+    pub end: Option<i64>,
+    pub step: i64,
+}
+
+impl Slice {
+    pub fn from_syntax(slice: &rsonpath_syntax::Slice) -> Slice {
+        Slice {
+            start: index_to_i64(slice.start()),
+            end: slice.end().map(index_to_i64),
+            step: match slice.step() {
+                rsonpath_syntax::Step::Forward(num) => num.as_u64() as i64,
+                rsonpath_syntax::Step::Backward(num) => -(num.as_u64() as i64),
+            },
+        }
+    }
+
+    /// Slice with the single element at the index.
+    pub fn from_index(index: i64) -> Slice {
+        Slice {
+            start: index,
+            end: if index == -1 { None } else { Some(index + 1) },
+            step: 1,
+        }
+    }
+
+    /// Slice with all the elements, as selected by a wildcard.
+    pub fn all() -> Slice {
+        Slice {
+            start: 0,
+            end: None,
+            step: 1,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.step == 0
+    }
+}
+
+/// Converts an index to a signed offset, negative for indices counted from the end.
+pub fn index_to_i64(index: rsonpath_syntax::Index) -> i64 {
+    match index {
+        rsonpath_syntax::Index::FromStart(num) => num.as_u64() as i64,
+        rsonpath_syntax::Index::FromEnd(num) => -(num.as_u64() as i64),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct FilterProcedure {
     pub name: String,
     pub filter_id: FilterId,
     pub arity: usize,
     pub expression: FilterExpression,
+    pub regexes: Vec<Regex>,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum FilterExpression {
     Or {
         lhs: Box<FilterExpression>,
@@ -107,15 +313,43 @@ pub enum FilterExpression {
     ExistenceTest {
         param_id: usize,
     },
+    FunctionCall {
+        function: LogicalFunction,
+    },
 }
 
-#[derive(Debug)]
+/// Function extension returning a logical value (RFC 9535, section 2.4.1).
+#[derive(Serialize, Deserialize, Debug)]
+pub enum LogicalFunction {
+    /// `match()` and `search()`. `regex_id` indexes the regexes of the filter procedure and is
+    /// `None` when the pattern is not a string, in which case the result is always false.
+    Match {
+        arg: Comparable,
+        regex_id: Option<usize>,
+        is_full_match: bool,
+    },
+}
+
+/// Expression evaluating to a value or to nothing (RFC 9535, section 2.4.1).
+#[derive(Serialize, Deserialize, Debug)]
 pub enum Comparable {
     Param { id: usize },
     Literal { value: LiteralValue },
+    FunctionCall { function: ValueFunction },
+}
+
+/// Function extension returning a value (RFC 9535, section 2.4.1).
+#[derive(Serialize, Deserialize, Debug)]
+pub enum ValueFunction {
+    Length { arg: Box<Comparable> },
+    /// `count()`. The subquery with the given id counts the nodes it selects.
+    Count { param_id: usize },
+    /// `value()`. The subquery with the given id keeps the value of the only node it selects,
+    /// or nothing if it selects more than one.
+    Value { param_id: usize },
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum LiteralValue {
     String(String),
     Int(i64),
@@ -124,7 +358,7 @@ pub enum LiteralValue {
     Null,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum ComparisonOp {
     EqualTo,
     NotEqualTo,
@@ -134,7 +368,7 @@ pub enum ComparisonOp {
     GreaterThan,
 }
 
-#[derive(Eq, PartialEq, Hash, Clone, Ord, PartialOrd, Debug)]
+#[derive(Eq, PartialEq, Hash, Clone, Ord, PartialOrd, Serialize, Deserialize, Debug)]
 pub enum SelectionCondition {
     Filter {
         id: FilterId,
@@ -227,10 +461,13 @@ impl SelectionCondition {
     }
 }
 
-#[derive(Eq, PartialEq, Hash, Clone, Ord, PartialOrd, Debug)]
+#[derive(Eq, PartialEq, Hash, Clone, Ord, PartialOrd, Serialize, Deserialize, Debug)]
 pub struct FilterId {
     pub segment_index: SegmentIndex,
     pub selector_index: SelectorIndex,
+    /// Indices of the subquery, segment and selector at every level of nesting of a filter in
+    /// a subquery. Empty for the filters of the query itself.
+    pub nested_path: Vec<(usize, SegmentIndex, SelectorIndex)>,
 }
 
 impl FilterId {
@@ -238,21 +475,95 @@ impl FilterId {
         FilterId {
             segment_index,
             selector_index,
+            nested_path: Vec::new(),
         }
     }
+
+    pub fn nested(
+        &self,
+        subquery_index: usize,
+        segment_index: SegmentIndex,
+        selector_index: SelectorIndex,
+    ) -> FilterId {
+        let mut nested_path = self.nested_path.clone();
+        nested_path.push((subquery_index, segment_index, selector_index));
+        FilterId {
+            segment_index: self.segment_index,
+            selector_index: self.selector_index,
+            nested_path,
+        }
+    }
+
+    pub fn is_nested(&self) -> bool {
+        !self.nested_path.is_empty()
+    }
+}
+
+impl Display for FilterId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_{}", self.segment_index, self.selector_index)?;
+        for (subquery_index, segment_index, selector_index) in &self.nested_path {
+            write!(f, "_{subquery_index}_{segment_index}_{selector_index}")?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct FilterSubquery {
     pub is_absolute: bool,
     pub is_existence_test: bool,
+    pub is_length_needed: bool,
+    pub is_count_needed: bool,
+    /// The subquery is the argument of a function accepting only strings, so a complex value
+    /// it selects is not read.
+    pub is_string_needed: bool,
+    /// The subquery is the argument of `value()`, so it is not singular and its result is
+    /// nothing if it selects more than one node.
+    pub is_value_needed: bool,
     pub segments: Vec<FilterSubquerySegment>,
 }
 
-#[derive(Debug)]
-pub enum FilterSubquerySegment {
+impl FilterSubquery {
+    pub fn has_filters(&self) -> bool {
+        self.segments.iter().any(|segment| {
+            segment
+                .selectors
+                .iter()
+                .any(|selector| matches!(selector, FilterSubquerySelector::Filter(_)))
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FilterSubquerySegment {
+    pub is_descendant: bool,
+    pub selectors: Vec<FilterSubquerySelector>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum FilterSubquerySelector {
     Name(String),
     Index(i64),
+    Wildcard,
+    Slice(Slice),
+    /// Filter nested in the subquery. Its own subqueries and procedure are stored under the
+    /// given id, like the ones of the filters in the query.
+    Filter(FilterId),
+}
+
+/// Nondeterministic finite automaton compiled from an I-Regexp pattern.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Regex {
+    pub start: usize,
+    pub states: Vec<RegexState>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum RegexState {
+    Chars { ranges: Vec<(u32, u32)>, next: usize },
+    Split { next: usize, alt: usize },
+    Match,
 }
 
 pub type SegmentIndex = usize;
@@ -1,24 +1,27 @@
 use std::collections::HashMap;
 
 use itertools::chain;
-use rsonpath_syntax::Comparable::{AbsoluteSingularQuery, RelativeSingularQuery};
-use rsonpath_syntax::TestExpr::{Absolute, Relative};
 
+use crate::ir;
 use crate::ir::{
     Comparable, ComparisonOp, FilterExpression, FilterId, FilterProcedure, FilterSubquery,
-    LiteralValue, SegmentIndex,
+    FilterSubquerySegment, index_to_i64, LiteralValue, LogicalFunction, Regex, SegmentIndex,
+    ValueFunction,
 };
 use crate::ir::Comparable::{Literal, Param};
 use crate::ir::FilterExpression::{And, Comparison, ExistenceTest, Not, Or};
-use crate::ir::FilterSubquerySegment::{Index, Name};
+use crate::ir::FilterSubquerySelector::{Filter, Index, Name, Slice, Wildcard};
 use crate::ir::LiteralValue::{Bool, Float, Int, Null};
+use crate::ir::regex_generator::RegexGenerator;
+use crate::syntax;
+use crate::syntax::{FilterQuery, FunctionArgument, FunctionCall, JsonPathQuery, LogicalExpr, TestExpr};
 
 pub struct FilterUtils {}
 
 impl FilterUtils {
     pub fn get_all_filters(
-        query_syntax: &rsonpath_syntax::JsonPathQuery,
-    ) -> Vec<(&rsonpath_syntax::LogicalExpr, FilterId)> {
+        query_syntax: &JsonPathQuery,
+    ) -> Vec<(&LogicalExpr, FilterId)> {
         let mut filters = Vec::new();
         for i in 0..query_syntax.segments().len() {
             filters.append(&mut Self::get_filters_in_segment(query_syntax, i));
@@ -27,168 +30,282 @@ impl FilterUtils {
     }
 
     pub fn get_filters_in_segment(
-        query_syntax: &rsonpath_syntax::JsonPathQuery,
+        query_syntax: &JsonPathQuery,
         segment_index: SegmentIndex,
-    ) -> Vec<(&rsonpath_syntax::LogicalExpr, FilterId)> {
+    ) -> Vec<(&LogicalExpr, FilterId)> {
         let mut filters = Vec::new();
         let selectors = query_syntax.segments()[segment_index].selectors();
         for (selector_index, selector) in selectors.iter().enumerate() {
-            if let rsonpath_syntax::Selector::Filter(filter_expression) = selector {
-                filters.push((
-                    filter_expression,
-                    FilterId {
-                        segment_index,
-                        selector_index,
-                    },
-                ));
+            if let syntax::Selector::Filter(filter_expression) = selector {
+                filters.push((filter_expression, FilterId::new(segment_index, selector_index)));
+            }
+        }
+        filters
+    }
+
+    /// Returns the filters in the segments of a subquery of the filter with the given id.
+    pub fn get_nested_filters<'a>(
+        subquery: &'a JsonPathQuery,
+        filter_id: &FilterId,
+        subquery_index: usize,
+    ) -> Vec<(&'a LogicalExpr, FilterId)> {
+        let mut filters = Vec::new();
+        for (segment_index, segment) in subquery.segments().iter().enumerate() {
+            for (selector_index, selector) in segment.selectors().iter().enumerate() {
+                if let syntax::Selector::Filter(filter_expression) = selector {
+                    filters.push((
+                        filter_expression,
+                        filter_id.nested(subquery_index, segment_index, selector_index),
+                    ));
+                }
             }
         }
         filters
     }
 }
 
-pub struct FilterSubqueryFinder {}
+pub struct FilterSubqueryFinder<'a> {
+    filter_id: FilterId,
+    subquery_count: usize,
+    remaining_filters: Vec<(&'a LogicalExpr, FilterId)>,
+}
 
-impl FilterSubqueryFinder {
+impl<'a> FilterSubqueryFinder<'a> {
+    pub fn new() -> FilterSubqueryFinder<'a> {
+        FilterSubqueryFinder {
+            filter_id: FilterId::new(0, 0),
+            subquery_count: 0,
+            remaining_filters: Vec::new(),
+        }
+    }
+
+    /// Returns the subqueries of every filter in the query, including the filters nested in
+    /// the subqueries of other filters.
     pub fn get_all_subqueries(
-        query_syntax: &rsonpath_syntax::JsonPathQuery,
+        &mut self,
+        query_syntax: &'a JsonPathQuery,
     ) -> HashMap<FilterId, Vec<FilterSubquery>> {
         let mut result = HashMap::new();
-        for (filter_expr, filter_id) in FilterUtils::get_all_filters(query_syntax) {
-            result.insert(filter_id, Self::get_subqueries_in_filter(filter_expr));
+        self.remaining_filters = FilterUtils::get_all_filters(query_syntax);
+        while let Some((filter_expr, filter_id)) = self.remaining_filters.pop() {
+            let subqueries = self.get_subqueries_in_filter(filter_expr, &filter_id);
+            result.insert(filter_id, subqueries);
         }
         result
     }
 
     pub fn get_subqueries_in_filter(
-        filter_expr: &rsonpath_syntax::LogicalExpr,
+        &mut self,
+        filter_expr: &'a LogicalExpr,
+        filter_id: &FilterId,
     ) -> Vec<FilterSubquery> {
-        Self::get_all_subqueries_paths_in_expr(filter_expr)
+        self.filter_id = filter_id.clone();
+        self.subquery_count = 0;
+        self.get_all_subqueries_paths_in_expr(filter_expr)
     }
 
     fn get_all_subqueries_paths_in_expr(
-        filter_expr: &rsonpath_syntax::LogicalExpr,
-        //filter_id: &FilterId,
+        &mut self,
+        filter_expr: &'a LogicalExpr,
     ) -> Vec<FilterSubquery> {
         match filter_expr {
-            rsonpath_syntax::LogicalExpr::Or(lhs, rhs) => chain![
-                Self::get_all_subqueries_paths_in_expr(lhs),
-                Self::get_all_subqueries_paths_in_expr(rhs)
+            LogicalExpr::Or(lhs, rhs) => chain![
+                self.get_all_subqueries_paths_in_expr(lhs),
+                self.get_all_subqueries_paths_in_expr(rhs)
             ]
                 .collect(),
-            rsonpath_syntax::LogicalExpr::And(lhs, rhs) => chain![
-                Self::get_all_subqueries_paths_in_expr(lhs),
-                Self::get_all_subqueries_paths_in_expr(rhs)
+            LogicalExpr::And(lhs, rhs) => chain![
+                self.get_all_subqueries_paths_in_expr(lhs),
+                self.get_all_subqueries_paths_in_expr(rhs)
             ]
                 .collect(),
-            rsonpath_syntax::LogicalExpr::Not(expr) => Self::get_all_subqueries_paths_in_expr(expr),
-            rsonpath_syntax::LogicalExpr::Comparison(comparison_expr) => vec![
-                Self::get_subquery_from_comparable(comparison_expr.lhs()),
-                Self::get_subquery_from_comparable(comparison_expr.rhs()),
+            LogicalExpr::Not(expr) => self.get_all_subqueries_paths_in_expr(expr),
+            LogicalExpr::Comparison(comparison_expr) => chain![
+                self.get_subqueries_in_comparable(&comparison_expr.lhs),
+                self.get_subqueries_in_comparable(&comparison_expr.rhs)
             ]
-                .into_iter()
-                .filter(|subquery| subquery.is_some())
-                .map(|subquery| subquery.unwrap())
                 .collect(),
-            rsonpath_syntax::LogicalExpr::Test(test_expr) => {
-                let subquery = match test_expr {
-                    Relative(subquery) => Self::convert_subquery(subquery, false, true),
-                    Absolute(subquery) => Self::convert_subquery(subquery, true, true),
-                };
-                vec![subquery]
+            LogicalExpr::Test(TestExpr::Query(subquery)) => {
+                vec![self.convert_filter_query(subquery, true)]
+            }
+            LogicalExpr::Test(TestExpr::FunctionExpr(function_call)) => {
+                self.get_subqueries_in_function_call(function_call)
             }
         }
     }
 
-    fn get_subquery_from_comparable(
-        comparison_expr: &rsonpath_syntax::Comparable,
-    ) -> Option<FilterSubquery> {
-        let subquery = match comparison_expr {
-            RelativeSingularQuery(subquery) => {
-                Some(Self::convert_singular_subquery(subquery, false, false))
+    fn get_subqueries_in_comparable(
+        &mut self,
+        comparable: &'a syntax::Comparable,
+    ) -> Vec<FilterSubquery> {
+        match comparable {
+            syntax::Comparable::RelativeSingularQuery(subquery) => {
+                vec![self.convert_singular_subquery(subquery, false, false)]
             }
-            AbsoluteSingularQuery(subquery) => {
-                Some(Self::convert_singular_subquery(subquery, true, false))
+            syntax::Comparable::AbsoluteSingularQuery(subquery) => {
+                vec![self.convert_singular_subquery(subquery, true, false)]
             }
-            _ => return None,
-        };
-        subquery
+            syntax::Comparable::FunctionExpr(function_call) => {
+                self.get_subqueries_in_function_call(function_call)
+            }
+            syntax::Comparable::Literal(_) => vec![],
+        }
+    }
+
+    fn get_subqueries_in_function_call(
+        &mut self,
+        function_call: &'a FunctionCall,
+    ) -> Vec<FilterSubquery> {
+        let mut subqueries = Vec::new();
+        for arg in function_call.args.iter() {
+            match arg {
+                FunctionArgument::Value(comparable) => {
+                    let mut arg_subqueries = self.get_subqueries_in_comparable(comparable);
+                    let is_node_value = match comparable {
+                        syntax::Comparable::FunctionExpr(arg_function_call) => {
+                            arg_function_call.name == "value"
+                        }
+                        _ => true,
+                    };
+                    if is_node_value {
+                        for subquery in arg_subqueries.iter_mut() {
+                            subquery.is_length_needed = function_call.name == "length";
+                            subquery.is_string_needed = matches!(function_call.name, "match" | "search");
+                        }
+                    }
+                    subqueries.append(&mut arg_subqueries);
+                }
+                FunctionArgument::Nodes(filter_query) => {
+                    let mut subquery = self.convert_filter_query(filter_query, false);
+                    subquery.is_count_needed = function_call.name == "count";
+                    subquery.is_value_needed = function_call.name == "value";
+                    subqueries.push(subquery);
+                }
+            }
+        }
+        subqueries
+    }
+
+    fn convert_filter_query(
+        &mut self,
+        filter_query: &'a FilterQuery,
+        is_existence_test: bool,
+    ) -> FilterSubquery {
+        match filter_query {
+            FilterQuery::Relative(subquery) => self.convert_subquery(subquery, false, is_existence_test),
+            FilterQuery::Absolute(subquery) => self.convert_subquery(subquery, true, is_existence_test),
+        }
     }
 
     fn convert_subquery(
-        subquery: &rsonpath_syntax::JsonPathQuery,
+        &mut self,
+        subquery: &'a JsonPathQuery,
         is_absolute: bool,
         is_existence_test: bool,
     ) -> FilterSubquery {
+        let subquery_index = self.next_subquery_index();
+        self.remaining_filters.append(&mut FilterUtils::get_nested_filters(
+            subquery,
+            &self.filter_id,
+            subquery_index,
+        ));
         let mut result = FilterSubquery {
             is_absolute,
             is_existence_test,
+            is_length_needed: false,
+            is_count_needed: false,
+            is_string_needed: false,
+            is_value_needed: false,
             segments: Vec::new(),
         };
-        for segment in subquery.segments() {
-            if segment.is_descendant() {
-                panic!()
-            }
-            for selector in segment.selectors().iter() {
-                match selector {
-                    rsonpath_syntax::Selector::Name(name) => {
-                        result.segments.push(Name(name.unquoted().to_string()));
-                    }
-                    rsonpath_syntax::Selector::Index(index) => {
-                        result.segments.push(Index(match index {
-                            rsonpath_syntax::Index::FromStart(index) => index.as_u64() as i64,
-                            rsonpath_syntax::Index::FromEnd(index) => -(index.as_u64() as i64),
-                        }));
-                    }
-                    _ => panic!(),
-                }
-            }
+        for (segment_index, segment) in subquery.segments().iter().enumerate() {
+            let selectors = segment
+                .selectors()
+                .iter()
+                .enumerate()
+                .map(|(selector_index, selector)| match selector {
+                    syntax::Selector::Name(name) => Name(name.unquoted().to_string()),
+                    syntax::Selector::Index(index) => Index(index_to_i64(*index)),
+                    syntax::Selector::Wildcard => Wildcard,
+                    syntax::Selector::Slice(slice) => Slice(ir::Slice::from_syntax(slice)),
+                    syntax::Selector::Filter(_) => Filter(self.filter_id.nested(
+                        subquery_index,
+                        segment_index,
+                        selector_index,
+                    )),
+                })
+                .collect();
+            result.segments.push(FilterSubquerySegment {
+                is_descendant: segment.is_descendant(),
+                selectors,
+            });
         }
         result
     }
 
     fn convert_singular_subquery(
+        &mut self,
         subquery: &rsonpath_syntax::SingularJsonPathQuery,
         is_absolute: bool,
         is_existence_test: bool
     ) -> FilterSubquery {
+        self.next_subquery_index();
         let mut result = FilterSubquery {
             is_absolute,
             is_existence_test,
+            is_length_needed: false,
+            is_count_needed: false,
+            is_string_needed: false,
+            is_value_needed: false,
             segments: Vec::new(),
         };
         for segment in subquery.segments() {
-            match segment {
-                rsonpath_syntax::SingularSegment::Name(name) => {
-                    result.segments.push(Name(name.unquoted().to_string()));
-                }
-                rsonpath_syntax::SingularSegment::Index(index) => {
-                    result.segments.push(Index(match index {
-                        rsonpath_syntax::Index::FromStart(index) => index.as_u64() as i64,
-                        rsonpath_syntax::Index::FromEnd(index) => -(index.as_u64() as i64),
-                    }));
-                }
-            }
+            let selector = match segment {
+                rsonpath_syntax::SingularSegment::Name(name) => Name(name.unquoted().to_string()),
+                rsonpath_syntax::SingularSegment::Index(index) => Index(index_to_i64(*index)),
+            };
+            result.segments.push(FilterSubquerySegment {
+                is_descendant: false,
+                selectors: vec![selector],
+            });
         }
         result
     }
+
+    /// Returns the index of the next subquery, in the order in which `FilterGenerator` assigns
+    /// the ids of parameters.
+    fn next_subquery_index(&mut self) -> usize {
+        self.subquery_count += 1;
+        self.subquery_count - 1
+    }
 }
 
-pub struct FilterGenerator {
+pub struct FilterGenerator<'a> {
+    filter_id: FilterId,
     subquery_count: usize,
+    regexes: Vec<Regex>,
+    remaining_filters: Vec<(&'a LogicalExpr, FilterId)>,
 }
 
-impl FilterGenerator {
-    pub fn new() -> FilterGenerator {
-        FilterGenerator { subquery_count: 0 }
+impl<'a> FilterGenerator<'a> {
+    pub fn new() -> FilterGenerator<'a> {
+        FilterGenerator {
+            filter_id: FilterId::new(0, 0),
+            subquery_count: 0,
+            regexes: Vec::new(),
+            remaining_filters: Vec::new(),
+        }
     }
 
+    /// Generates the procedures of every filter in the query, including the filters nested in
+    /// the subqueries of other filters.
     pub fn generate_filter_procedures(
         &mut self,
-        query_syntax: &rsonpath_syntax::JsonPathQuery,
+        query_syntax: &'a JsonPathQuery,
     ) -> HashMap<FilterId, FilterProcedure> {
         let mut filter_procedures = HashMap::new();
-        for (filter_expression, filter_id) in FilterUtils::get_all_filters(query_syntax) {
+        self.remaining_filters = FilterUtils::get_all_filters(query_syntax);
+        while let Some((filter_expression, filter_id)) = self.remaining_filters.pop() {
             filter_procedures.insert(
                 filter_id.clone(),
                 self.generate_filter_procedure(filter_expression, filter_id),
@@ -199,65 +316,125 @@ impl FilterGenerator {
 
     fn generate_filter_procedure(
         &mut self,
-        filter_expression: &rsonpath_syntax::LogicalExpr,
+        filter_expression: &'a LogicalExpr,
         id: FilterId,
     ) -> FilterProcedure {
+        self.filter_id = id.clone();
         self.subquery_count = 0;
         let expression = self.generate_filter_expr(filter_expression);
         FilterProcedure {
-            name: format!("Filter_{}_{}", id.segment_index, id.selector_index),
+            name: format!("Filter_{id}"),
             filter_id: id,
             arity: self.subquery_count,
             expression,
+            regexes: std::mem::take(&mut self.regexes),
         }
     }
 
     fn generate_filter_expr(
         &mut self,
-        filter_expr: &rsonpath_syntax::LogicalExpr,
+        filter_expr: &'a LogicalExpr,
     ) -> FilterExpression {
         match filter_expr {
-            rsonpath_syntax::LogicalExpr::Or(lhs, rhs) => Or {
+            LogicalExpr::Or(lhs, rhs) => Or {
                 lhs: Box::new(self.generate_filter_expr(lhs)),
                 rhs: Box::new(self.generate_filter_expr(rhs)),
             },
-            rsonpath_syntax::LogicalExpr::And(lhs, rhs) => And {
+            LogicalExpr::And(lhs, rhs) => And {
                 lhs: Box::new(self.generate_filter_expr(lhs)),
                 rhs: Box::new(self.generate_filter_expr(rhs)),
             },
-            rsonpath_syntax::LogicalExpr::Not(expr) => Not {
+            LogicalExpr::Not(expr) => Not {
                 expr: Box::new(self.generate_filter_expr(expr)),
             },
-            rsonpath_syntax::LogicalExpr::Comparison(comparison_expr) => Comparison {
-                lhs: self.generate_comparable(comparison_expr.lhs()),
-                rhs: self.generate_comparable(comparison_expr.rhs()),
-                op: self.generate_comparison_op(comparison_expr.op()),
+            LogicalExpr::Comparison(comparison_expr) => Comparison {
+                lhs: self.generate_comparable(&comparison_expr.lhs),
+                rhs: self.generate_comparable(&comparison_expr.rhs),
+                op: self.generate_comparison_op(comparison_expr.op),
+            },
+            LogicalExpr::Test(TestExpr::Query(subquery)) => ExistenceTest {
+                param_id: self.next_nodes_param_id(subquery),
             },
-            rsonpath_syntax::LogicalExpr::Test { .. } => {
-                let param = ExistenceTest {
-                    param_id: self.subquery_count,
-                };
-                self.subquery_count += 1;
-                param
+            LogicalExpr::Test(TestExpr::FunctionExpr(function_call)) => {
+                FilterExpression::FunctionCall {
+                    function: self.generate_logical_function_call(function_call),
+                }
             }
         }
     }
 
-    fn generate_comparable(&mut self, comparable: &rsonpath_syntax::Comparable) -> Comparable {
+    fn generate_comparable(&mut self, comparable: &'a syntax::Comparable) -> Comparable {
         match comparable {
-            rsonpath_syntax::Comparable::Literal(literal) => Literal {
+            syntax::Comparable::Literal(literal) => Literal {
                 value: self.generate_literal(literal),
             },
-            AbsoluteSingularQuery { .. } | RelativeSingularQuery { .. } => {
-                let param = Param {
-                    id: self.subquery_count,
-                };
-                self.subquery_count += 1;
-                param
+            syntax::Comparable::AbsoluteSingularQuery { .. }
+            | syntax::Comparable::RelativeSingularQuery { .. } => Param {
+                id: self.next_param_id(),
+            },
+            syntax::Comparable::FunctionExpr(function_call) => {
+                self.generate_function_call(function_call)
+            }
+        }
+    }
+
+    fn generate_function_call(&mut self, function_call: &'a FunctionCall) -> Comparable {
+        let function = match (function_call.name, function_call.args.as_slice()) {
+            ("length", [FunctionArgument::Value(arg)]) => ValueFunction::Length {
+                arg: Box::new(self.generate_comparable(arg)),
+            },
+            ("count", [FunctionArgument::Nodes(arg)]) => ValueFunction::Count {
+                param_id: self.next_nodes_param_id(arg),
+            },
+            ("value", [FunctionArgument::Nodes(arg)]) => ValueFunction::Value {
+                param_id: self.next_nodes_param_id(arg),
+            },
+            _ => unreachable!("function `{}` is not supported", function_call.name),
+        };
+        Comparable::FunctionCall { function }
+    }
+
+    fn generate_logical_function_call(
+        &mut self,
+        function_call: &'a FunctionCall,
+    ) -> LogicalFunction {
+        match (function_call.name, function_call.args.as_slice()) {
+            (name @ ("match" | "search"), [FunctionArgument::Value(arg), _]) => {
+                let arg = self.generate_comparable(arg);
+                let regex_id = function_call.regexp.as_ref().map(|regexp| {
+                    self.regexes.push(RegexGenerator::generate(regexp));
+                    self.regexes.len() - 1
+                });
+                LogicalFunction::Match {
+                    arg,
+                    regex_id,
+                    is_full_match: name == "match",
+                }
             }
+            _ => unreachable!("function `{}` is not supported", function_call.name),
         }
     }
 
+    /// Returns the id of the next subquery, in the order in which `FilterSubqueryFinder` finds
+    /// them.
+    fn next_param_id(&mut self) -> usize {
+        self.subquery_count += 1;
+        self.subquery_count - 1
+    }
+
+    /// Returns the id of the next subquery, which is not singular, and schedules the generation
+    /// of procedures for the filters nested in it.
+    fn next_nodes_param_id(&mut self, subquery: &'a FilterQuery) -> usize {
+        let param_id = self.next_param_id();
+        let (FilterQuery::Relative(subquery) | FilterQuery::Absolute(subquery)) = subquery;
+        self.remaining_filters.append(&mut FilterUtils::get_nested_filters(
+            subquery,
+            &self.filter_id,
+            param_id,
+        ));
+        param_id
+    }
+
     fn generate_literal(&self, literal_syntax: &rsonpath_syntax::Literal) -> LiteralValue {
         match literal_syntax {
             rsonpath_syntax::Literal::String(json_str) => {
@@ -2,74 +2,151 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use itertools::Itertools;
 
-use crate::ir::{Instruction, Procedure, Query, SelectionCondition};
+use crate::compiler::{CompilationError, DEFAULT_MAX_PROCEDURES};
+use crate::ir::{first_segments_of_merged_queries, index_to_i64, FilterId, Instruction, MergedQuery, Procedure, Query, SegmentIndex, SelectionCondition, Slice};
 use crate::ir::filter_generator::{FilterGenerator, FilterSubqueryFinder, FilterUtils};
-use crate::ir::Instruction::{Continue, EndFiltersExecution, ExecuteProcedureOnChild, ForEachElement, ForEachMember, IfActiveFilterInstance, IfCurrentIndexEquals, IfCurrentIndexFromEndEquals, IfCurrentMemberNameEquals, SaveCurrentNodeDuringTraversal, StartFilterExecution, TraverseCurrentNodeSubtree, UpdateSubqueriesState};
+use crate::ir::Instruction::{ActivateSegmentInChild, Continue, EndFiltersExecution, ExecuteProcedureOnChild, ExecuteProcedureOnChildWithActiveSegments, ForEachElement, ForEachElementInReverseOrder, ForEachElementInSlices, ForEachMember, IfActiveFilterInstance, IfCurrentIndexEquals, IfCurrentIndexFromEndEquals, IfCurrentIndexInSlice, IfCurrentMemberNameEquals, IfSegmentActive, SaveCurrentNodeDuringTraversal, SelectChild, StartFilterExecution, TraverseCurrentNodeSubtree, UpdateSubqueriesState};
 use crate::ir::procedure_segments::{ProcedureSegments, ProcedureSegmentsData};
+use crate::syntax::{JsonPathQuery, ParsedQuery, Selector};
+
+/// Name of the procedure to which the active segments are passed at runtime.
+const ACTIVE_SEGMENTS_PROCEDURE_NAME: &str = "Selectors_active";
+
+/// Maximum number of groups of array selectors whose combinations are tested in nested
+/// conditions, beyond which the selectors matching an element are collected at runtime.
+const MAX_NESTED_ARRAY_CONDITION_GROUPS: usize = 3;
 
 pub struct IRGenerator<'a> {
-    query_syntax: &'a rsonpath_syntax::JsonPathQuery,
+    parsed_query: &'a ParsedQuery,
+    query_syntax: &'a JsonPathQuery,
     generated_procedures: HashMap<ProcedureSegmentsData, Procedure>,
     procedures_to_generate: HashSet<ProcedureSegmentsData>,
     procedure_queue: VecDeque<ProcedureSegmentsData>,
-    filter_generator: FilterGenerator,
+    filter_generator: FilterGenerator<'a>,
     are_filters_in_query: bool,
+    are_negative_step_slices_in_query: bool,
+    max_procedures: Option<usize>,
+    is_active_segments_procedure_used: bool,
+    is_procedures_limit_exceeded: bool,
+    is_random_access_supported: bool,
 }
 
 impl IRGenerator<'_> {
-    pub fn new(query_syntax: &rsonpath_syntax::JsonPathQuery) -> IRGenerator {
+    /// Creates a generator of the IR of the query with at most `max_procedures` procedures for
+    /// sets of active segments, [`DEFAULT_MAX_PROCEDURES`] if not given. Only a given limit is
+    /// enforced for queries with filters, slices with negative steps and merged queries. Array
+    /// elements are visited in the order of the selectors if the target allows it.
+    pub fn new(
+        parsed_query: &ParsedQuery,
+        max_procedures: Option<usize>,
+        is_random_access_supported: bool,
+    ) -> IRGenerator {
+        let query_syntax = &parsed_query.syntax;
         IRGenerator {
+            parsed_query,
             query_syntax,
             generated_procedures: HashMap::new(),
             procedures_to_generate: HashSet::new(),
             procedure_queue: VecDeque::new(),
             filter_generator: FilterGenerator::new(),
             are_filters_in_query: !FilterUtils::get_all_filters(query_syntax).is_empty(),
+            are_negative_step_slices_in_query: query_syntax.segments().iter().any(|segment| {
+                segment.selectors().iter().any(|selector| {
+                    matches!(selector, Selector::Slice(slice) if Slice::from_syntax(slice).step < 0)
+                })
+            }),
+            max_procedures,
+            is_active_segments_procedure_used: false,
+            is_procedures_limit_exceeded: false,
+            is_random_access_supported,
         }
     }
 
-    pub fn generate(mut self) -> Query {
-        let first_segment = ProcedureSegments::new(self.query_syntax, vec![0]);
+    pub fn generate(mut self) -> Result<Query, CompilationError> {
+        let first_segment = ProcedureSegments::new(self.parsed_query, self.first_segments());
         let first_segment_procedure_name = first_segment.name();
-        if self.query_syntax.segments().is_empty() {
-            Query {
+        if self.query_syntax.segments().is_empty() && !self.parsed_query.is_merged() {
+            Ok(Query {
                 procedures: vec![Procedure {
                     name: first_segment_procedure_name,
                     instructions: vec![SaveCurrentNodeDuringTraversal {
                         instruction: Box::new(TraverseCurrentNodeSubtree),
                         condition: None,
+                        query_index: None,
                     }],
+                    are_active_segments_passed: false,
                 }],
                 filter_procedures: HashMap::new(),
                 filter_subqueries: HashMap::new(),
                 segments_count: 0,
-            }
+                passes: Vec::new(),
+                merged_queries: Vec::new(),
+            })
         } else {
             let segments_data = first_segment.segments_data();
-            self.procedure_queue.push_back(segments_data.clone());
-            self.procedures_to_generate.insert(segments_data);
+            if !first_segment.is_empty() {
+                self.procedure_queue.push_back(segments_data.clone());
+                self.procedures_to_generate.insert(segments_data);
+            }
             while let Some(procedure_segments) = self.procedure_queue.pop_front() {
                 self.generate_procedure(&ProcedureSegments::new(
-                    self.query_syntax,
+                    self.parsed_query,
                     procedure_segments.segments(),
                 ));
+                if self.is_procedures_limit_exceeded {
+                    return Err(CompilationError::ProceduresLimitExceeded {
+                        query_name: String::new(),
+                        max_procedures: self.max_procedures.unwrap_or(DEFAULT_MAX_PROCEDURES),
+                    });
+                }
             }
+            let active_segments_procedure = if self.is_active_segments_procedure_used {
+                Some(self.generate_active_segments_procedure())
+            } else {
+                None
+            };
             let procedures: Vec<Procedure> = self
                 .generated_procedures
                 .into_values()
+                .chain(active_segments_procedure)
                 .sorted_by(|a, b| a.name.cmp(&b.name))
                 .collect();
-            Query {
+            Ok(Query {
                 procedures,
                 filter_procedures: self
                     .filter_generator
                     .generate_filter_procedures(self.query_syntax),
-                filter_subqueries: FilterSubqueryFinder::get_all_subqueries(self.query_syntax),
+                filter_subqueries: FilterSubqueryFinder::new()
+                    .get_all_subqueries(self.query_syntax),
                 segments_count: self.query_syntax.segments().len(),
-            }
+                passes: Vec::new(),
+                merged_queries: self
+                    .parsed_query
+                    .merged_queries
+                    .iter()
+                    .map(|(name, segments_count)| MergedQuery {
+                        name: name.to_string(),
+                        segments_count: *segments_count,
+                    })
+                    .collect(),
+            })
         }
     }
 
+    /// Segments active at the root of the document, which are the first segments of the merged
+    /// queries with any segments.
+    fn first_segments(&self) -> Vec<SegmentIndex> {
+        if !self.parsed_query.is_merged() {
+            return vec![0];
+        }
+        first_segments_of_merged_queries(
+            self.parsed_query
+                .merged_queries
+                .iter()
+                .map(|(_, segments_count)| *segments_count),
+        )
+    }
+
     fn generate_procedure(&mut self, segments: &ProcedureSegments) {
         let mut instructions = Vec::new();
         if self.are_filters_in_query {
@@ -84,10 +161,112 @@ impl IRGenerator<'_> {
             Procedure {
                 name: segments.name(),
                 instructions,
+                are_active_segments_passed: false,
             },
         );
     }
 
+    /// Generates the procedure to which the active segments are passed, activating the segments
+    /// at the children as the procedures for fixed sets of segments do.
+    fn generate_active_segments_procedure(&self) -> Procedure {
+        let mut member_instructions = Vec::new();
+        let mut element_instructions = Vec::new();
+        for segment_index in 0..self.query_syntax.segments().len() {
+            let (segment_member_instructions, segment_element_instructions) =
+                self.generate_active_segment_instructions(segment_index);
+            for (instructions, segment_instructions) in [
+                (&mut member_instructions, segment_member_instructions),
+                (&mut element_instructions, segment_element_instructions),
+            ] {
+                if !segment_instructions.is_empty() {
+                    instructions.push(IfSegmentActive {
+                        segment_index,
+                        instructions: segment_instructions,
+                    });
+                }
+            }
+        }
+        Procedure {
+            name: ACTIVE_SEGMENTS_PROCEDURE_NAME.to_string(),
+            instructions: vec![
+                ForEachMember {
+                    instructions: vec![self.active_segments_procedure_execution(member_instructions)],
+                },
+                ForEachElement {
+                    instructions: vec![self.active_segments_procedure_execution(element_instructions)],
+                },
+            ],
+            are_active_segments_passed: true,
+        }
+    }
+
+    /// Generates the instructions for the members and for the elements activating, at the
+    /// children matched by the selectors of the segment, the next segment, or selecting them
+    /// if it is the last one.
+    fn generate_active_segment_instructions(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> (Vec<Instruction>, Vec<Instruction>) {
+        let segments = self.query_syntax.segments();
+        let segment = &segments[segment_index];
+        let on_match = if segment_index + 1 < segments.len() {
+            ActivateSegmentInChild { segment_index: segment_index + 1 }
+        } else {
+            SelectChild
+        };
+        let mut member_instructions = Vec::new();
+        let mut element_instructions = Vec::new();
+        for selector in segment.selectors().iter() {
+            match selector {
+                Selector::Name(name) => {
+                    member_instructions.push(IfCurrentMemberNameEquals {
+                        name: name.unquoted().to_string(),
+                        instructions: vec![on_match.clone()],
+                    });
+                }
+                Selector::Wildcard => {
+                    member_instructions.push(on_match.clone());
+                    element_instructions.push(on_match.clone());
+                }
+                Selector::Index(index) => {
+                    let condition = match index {
+                        rsonpath_syntax::Index::FromStart(index) => {
+                            ArrayElementCondition::Index(index.as_u64())
+                        }
+                        rsonpath_syntax::Index::FromEnd(index) => {
+                            ArrayElementCondition::IndexFromEnd(index.as_u64())
+                        }
+                    };
+                    element_instructions.push(condition.into_instruction(vec![on_match.clone()]));
+                }
+                Selector::Slice(slice) => {
+                    let slice = Slice::from_syntax(slice);
+                    if !slice.is_empty() {
+                        element_instructions.push(
+                            ArrayElementCondition::InSlice(slice)
+                                .into_instruction(vec![on_match.clone()]),
+                        );
+                    }
+                }
+                // Not used for queries with filters.
+                Selector::Filter(_) => {}
+            }
+        }
+        if segment.is_descendant() {
+            member_instructions.push(ActivateSegmentInChild { segment_index });
+            element_instructions.push(ActivateSegmentInChild { segment_index });
+        }
+        (member_instructions, element_instructions)
+    }
+
+    fn active_segments_procedure_execution(&self, instructions: Vec<Instruction>) -> Instruction {
+        ExecuteProcedureOnChildWithActiveSegments {
+            name: ACTIVE_SEGMENTS_PROCEDURE_NAME.to_string(),
+            segments_count: self.query_syntax.segments().len(),
+            instructions,
+        }
+    }
+
     fn generate_start_filters_execution(
         &mut self,
         segments: &ProcedureSegments,
@@ -219,10 +398,8 @@ impl IRGenerator<'_> {
         let mut instructions = Vec::new();
         self.generate_start_filters_execution(segments, &mut instructions);
         for (name, occurrences) in segments.name_selectors() {
-            let node_selected = !occurrences.finals().is_empty()
-                || !wildcards_segments.finals().is_empty() || !filters_segments.finals().is_empty();
             let successors_segments_excluding_filters = ProcedureSegments::merge(
-                self.query_syntax,
+                self.parsed_query,
                 vec![
                     wildcards_segments.successors(),
                     occurrences.successors(),
@@ -233,15 +410,16 @@ impl IRGenerator<'_> {
                 &segments.descendants(),
                 &filters_segments,
             );
+            let final_segments = ProcedureSegments::merge(self.parsed_query, vec![
+                occurrences.finals(),
+                wildcards_segments.finals(),
+            ]);
             let selection_condition = self.get_selection_condition_with_filters(
-                &ProcedureSegments::merge(self.query_syntax, vec![
-                    occurrences.finals(),
-                    wildcards_segments.finals(),
-                ]),
+                &final_segments,
                 &filters_segments.finals(),
             );
             let procedure_segments = ProcedureSegments::merge(
-                self.query_syntax,
+                self.parsed_query,
                 vec![
                     successors_segments_excluding_filters,
                     segments.descendants(),
@@ -251,7 +429,7 @@ impl IRGenerator<'_> {
             let inner_instructions = self.generate_procedure_execution(
                 segments,
                 &procedure_segments,
-                node_selected,
+                &final_segments.merge_with(&filters_segments.finals()),
                 selection_condition,
                 successors_segments_conditions,
             );
@@ -266,103 +444,292 @@ impl IRGenerator<'_> {
     }
 
     fn generate_array_selectors(&mut self, segments: &ProcedureSegments) -> Vec<Instruction> {
-        let wildcards_segments = segments.wildcards();
-        let filters_segments = segments.filters();
+        if let Some(selectors) = self.get_array_selectors_in_order(segments) {
+            return self.generate_array_selectors_in_order(segments, selectors);
+        }
         let mut instructions = Vec::new();
         self.generate_start_filters_execution(segments, &mut instructions);
-        for (index, occurrences) in segments.non_negative_index_selectors() {
-            let node_selected = !occurrences.finals().is_empty()
-                || !wildcards_segments.finals().is_empty() || !filters_segments.finals().is_empty();
-            let successors_segments_excluding_filters = ProcedureSegments::merge(
-                self.query_syntax,
-                vec![
-                    wildcards_segments.successors(),
-                    occurrences.successors(),
+        let non_negative_indices: Vec<(ArrayElementCondition, ProcedureSegments)> = segments
+            .non_negative_index_selectors()
+            .into_iter()
+            .sorted_by_key(|(index, _)| *index)
+            .map(|(index, occurrences)| (
+                ArrayElementCondition::Index(u64::from_ne_bytes(index.to_ne_bytes())),
+                occurrences,
+            ))
+            .collect();
+        let negative_indices = segments
+            .negative_index_selectors()
+            .into_iter()
+            .sorted_by_key(|(index, _)| -*index)
+            .map(|(index, occurrences)| (
+                ArrayElementCondition::IndexFromEnd(u64::from_ne_bytes(i64::abs(index).to_ne_bytes())),
+                occurrences,
+            ))
+            .collect();
+        let slices = segments
+            .slice_selectors()
+            .into_iter()
+            .filter(|(slice, _)| !slice.is_empty())
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(slice, occurrences)| vec![(ArrayElementCondition::InSlice(slice), occurrences)]);
+        let condition_groups: Vec<Vec<(ArrayElementCondition, ProcedureSegments)>> =
+            vec![non_negative_indices, negative_indices]
+                .into_iter()
+                .chain(slices)
+                .filter(|group| !group.is_empty())
+                .collect();
+        if condition_groups.len() > MAX_NESTED_ARRAY_CONDITION_GROUPS
+            && self.is_active_segments_procedure_supported()
+        {
+            return self.generate_array_selectors_with_active_segments(segments);
+        }
+        let empty_segments = ProcedureSegments::new(self.parsed_query, vec![]);
+        instructions.append(&mut self.generate_array_element_conditions(
+            segments,
+            &condition_groups,
+            &empty_segments,
+            &empty_segments,
+            false,
+        ));
+        instructions.append(&mut self.generate_wildcard_filter_and_descendant_selectors(segments));
+        self.generate_end_filters_execution_instruction(segments, &mut instructions);
+        match self.get_reverse_order_slice_segment(segments) {
+            Some(segment_index) => vec![ForEachElementInReverseOrder {
+                query_index: self
+                    .parsed_query
+                    .is_merged()
+                    .then(|| self.parsed_query.query_index(segment_index)),
+                instructions,
+            }],
+            None => vec![ForEachElement { instructions }],
+        }
+    }
+
+    /// Returns the segment with the only array selector of the segments, if it is a slice with
+    /// a negative step, which selects the elements in the reverse of the document order.
+    fn get_reverse_order_slice_segment(&self, segments: &ProcedureSegments) -> Option<SegmentIndex> {
+        if !segments.wildcards().is_empty()
+            || !segments.descendants().is_empty()
+            || !segments.filters().is_empty()
+            || !segments.index_selectors().is_empty()
+        {
+            return None;
+        }
+        let slices: Vec<(Slice, ProcedureSegments)> = segments
+            .slice_selectors()
+            .into_iter()
+            .filter(|(slice, _)| !slice.is_empty())
+            .collect();
+        match &slices[..] {
+            [(slice, occurrences)] if slice.step < 0 => match occurrences.segments()[..] {
+                [segment_index] => Some(segment_index),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Tests every array selector of the segments once for each element, passing the segments
+    /// activated by the ones that match it to the procedure to which the active segments are
+    /// passed, instead of nesting the conditions of every combination of them.
+    fn generate_array_selectors_with_active_segments(
+        &mut self,
+        segments: &ProcedureSegments,
+    ) -> Vec<Instruction> {
+        self.is_active_segments_procedure_used = true;
+        let element_instructions = segments
+            .segments()
+            .into_iter()
+            .sorted()
+            .flat_map(|segment_index| self.generate_active_segment_instructions(segment_index).1)
+            .collect();
+        vec![ForEachElement {
+            instructions: vec![self.active_segments_procedure_execution(element_instructions)],
+        }]
+    }
+
+    /// Returns the array selectors of every segment active at the node in turn, if the order in
+    /// which they select the elements differs from the document order.
+    fn get_array_selectors_in_order(
+        &self,
+        segments: &ProcedureSegments,
+    ) -> Option<Vec<(SegmentIndex, OrderedArraySelector)>> {
+        if !self.is_random_access_supported || self.parsed_query.is_merged() {
+            return None;
+        }
+        let mut selectors_in_order = Vec::new();
+        let mut is_document_order = true;
+        for segment_index in segments.segments() {
+            let segment = &self.query_syntax.segments()[segment_index];
+            let mut slices = Vec::new();
+            let mut selectors_count = 0;
+            for (selector_index, selector) in segment.selectors().iter().enumerate() {
+                let slice = match selector {
+                    Selector::Name(_) => continue,
+                    Selector::Wildcard => Slice::all(),
+                    Selector::Index(index) => Slice::from_index(index_to_i64(*index)),
+                    Selector::Slice(slice) => Slice::from_syntax(slice),
+                    Selector::Filter(_) => {
+                        if !slices.is_empty() {
+                            selectors_in_order.push((
+                                segment_index,
+                                OrderedArraySelector::Slices(std::mem::take(&mut slices)),
+                            ));
+                        }
+                        let filter_id = FilterId::new(segment_index, selector_index);
+                        selectors_in_order
+                            .push((segment_index, OrderedArraySelector::Filter(filter_id)));
+                        selectors_count += 1;
+                        continue;
+                    }
+                };
+                if slice.is_empty() {
+                    continue;
+                }
+                if slice.step < 0 {
+                    is_document_order = false;
+                }
+                slices.push(slice);
+                selectors_count += 1;
+            }
+            if !slices.is_empty() {
+                selectors_in_order.push((segment_index, OrderedArraySelector::Slices(slices)));
+            }
+            if selectors_count > 1 {
+                is_document_order = false;
+            }
+        }
+        if is_document_order {
+            None
+        } else {
+            Some(selectors_in_order)
+        }
+    }
+
+    /// Visits the elements selected by the array selectors in their order, and then all elements
+    /// in the document order for the descendant segments.
+    fn generate_array_selectors_in_order(
+        &mut self,
+        segments: &ProcedureSegments,
+        selectors: Vec<(SegmentIndex, OrderedArraySelector)>,
+    ) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        for (segment_index, selector) in selectors {
+            let matched_segments = ProcedureSegments::new(self.parsed_query, vec![segment_index]);
+            instructions.push(match selector {
+                OrderedArraySelector::Slices(slices) => ForEachElementInSlices {
+                    slices,
+                    instructions: self.generate_matched_element_execution(
+                        segments,
+                        &matched_segments,
+                        None,
+                    ),
+                },
+                OrderedArraySelector::Filter(filter_id) => {
+                    let mut filter_instructions =
+                        vec![StartFilterExecution { filter_id: filter_id.clone() }];
+                    filter_instructions.append(&mut self.generate_matched_element_execution(
+                        segments,
+                        &matched_segments,
+                        Some(filter_id),
+                    ));
+                    ForEachElementInSlices {
+                        slices: vec![Slice::all()],
+                        instructions: filter_instructions,
+                    }
+                }
+            });
+        }
+        let descendants_segments = segments.descendants();
+        if !descendants_segments.is_empty() {
+            let conditions = vec![None; descendants_segments.segments().len()];
+            instructions.push(ForEachElement {
+                instructions: vec![
+                    self.generate_procedure_call(&descendants_segments, conditions),
+                    Continue,
                 ],
-            );
-            let mut inner_instructions = Vec::new();
-            for (neg_index, neg_occurrences) in occurrences.negative_index_selectors() {
-                let neg_node_selected = !neg_occurrences.finals().is_empty()
-                    || !wildcards_segments.finals().is_empty()
-                    || !filters_segments.finals().is_empty();
-                let successors_segments_excluding_filters =
-                    successors_segments_excluding_filters.merge_with(&neg_occurrences.successors());
-                let successors_segments_conditions = self.get_successors_segments_conditions(
-                    &successors_segments_excluding_filters,
-                    &segments.descendants(),
-                    &filters_segments,
-                );
-                let selection_condition = self.get_selection_condition_with_filters(
-                    &ProcedureSegments::merge(self.query_syntax, vec![
+            });
+        }
+        instructions
+    }
+
+    /// Generates the execution on an element matched by a selector of the only matched segment,
+    /// which is a filter selector if its id is given.
+    fn generate_matched_element_execution(
+        &mut self,
+        segments: &ProcedureSegments,
+        matched_segments: &ProcedureSegments,
+        filter_id: Option<FilterId>,
+    ) -> Vec<Instruction> {
+        let successors_segments = matched_segments.successors();
+        let final_segments = matched_segments.finals();
+        let empty_segments = ProcedureSegments::new(self.parsed_query, vec![]);
+        let mut successors_segments_conditions = self.get_successors_segments_conditions(
+            &successors_segments,
+            &empty_segments,
+            &empty_segments,
+        );
+        let mut selection_condition = Self::get_selection_condition(&final_segments);
+        if let Some(filter_id) = filter_id {
+            let filter_condition = SelectionCondition::Filter { id: filter_id };
+            if let [successor_index] = successors_segments.segments()[..] {
+                successors_segments_conditions[successor_index] = Some(filter_condition.clone());
+            }
+            selection_condition = selection_condition.map(|condition| SelectionCondition::And {
+                lhs: Box::new(condition),
+                rhs: Box::new(filter_condition),
+            });
+        }
+        self.generate_procedure_execution(
+            segments,
+            &successors_segments,
+            &final_segments,
+            selection_condition,
+            successors_segments_conditions,
+        )
+    }
+
+    /// Generates nested conditions for every combination of array selectors that can match the
+    /// same element. Conditions within one group are mutually exclusive.
+    fn generate_array_element_conditions(
+        &mut self,
+        segments: &ProcedureSegments,
+        condition_groups: &[Vec<(ArrayElementCondition, ProcedureSegments)>],
+        matched_successors: &ProcedureSegments,
+        matched_finals: &ProcedureSegments,
+        any_matched: bool,
+    ) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        if let Some((group, remaining_groups)) = condition_groups.split_first() {
+            for (condition, occurrences) in group {
+                let inner_instructions = self.generate_array_element_conditions(
+                    segments,
+                    remaining_groups,
+                    &matched_successors.merge_with(&occurrences.successors()),
+                    &ProcedureSegments::merge(self.parsed_query, vec![
+                        matched_finals.clone(),
                         occurrences.finals(),
-                        neg_occurrences.finals(),
-                        wildcards_segments.finals(),
                     ]),
-                    &filters_segments.finals(),
-                );
-                let procedure_segments = ProcedureSegments::merge(
-                    self.query_syntax,
-                    vec![
-                        successors_segments_excluding_filters,
-                        segments.descendants(),
-                        filters_segments.successors(),
-                    ],
-                );
-                let inner_inner_instructions = self.generate_procedure_execution(
-                    segments,
-                    &procedure_segments,
-                    node_selected || neg_node_selected,
-                    selection_condition,
-                    successors_segments_conditions,
+                    true,
                 );
-                inner_instructions.push(IfCurrentIndexFromEndEquals {
-                    index: u64::from_ne_bytes(i64::abs(neg_index).to_ne_bytes()),
-                    instructions: inner_inner_instructions,
-                })
+                instructions.push(condition.clone().into_instruction(inner_instructions));
             }
-            let successors_segments_conditions = self.get_successors_segments_conditions(
-                &successors_segments_excluding_filters,
-                &segments.descendants(),
-                &filters_segments,
-            );
-            let selection_condition = self.get_selection_condition_with_filters(
-                &ProcedureSegments::merge(self.query_syntax, vec![
-                    occurrences.finals(),
-                    wildcards_segments.finals(),
-                ]),
-                &filters_segments.finals(),
-            );
-            let procedure_segments = ProcedureSegments::merge(
-                self.query_syntax,
-                vec![
-                    successors_segments_excluding_filters,
-                    segments.descendants(),
-                    filters_segments.successors(),
-                ],
-            );
-            inner_instructions = inner_instructions
-                .into_iter()
-                .chain(self.generate_procedure_execution(
-                    segments,
-                    &procedure_segments,
-                    node_selected,
-                    selection_condition,
-                    successors_segments_conditions,
-                ))
-                .collect();
-            instructions.push(IfCurrentIndexEquals {
-                index: u64::from_ne_bytes(index.to_ne_bytes()),
-                instructions: inner_instructions,
-            });
-        }
-        for (neg_index, occurrences) in segments.negative_index_selectors() {
-            let node_selected = !occurrences.finals().is_empty()
-                || !wildcards_segments.finals().is_empty() || !filters_segments.finals().is_empty();
+            instructions.append(&mut self.generate_array_element_conditions(
+                segments,
+                remaining_groups,
+                matched_successors,
+                matched_finals,
+                any_matched,
+            ));
+        } else if any_matched {
+            let wildcards_segments = segments.wildcards();
+            let filters_segments = segments.filters();
             let successors_segments_excluding_filters = ProcedureSegments::merge(
-                self.query_syntax,
+                self.parsed_query,
                 vec![
                     wildcards_segments.successors(),
-                    occurrences.successors(),
+                    matched_successors.clone(),
                 ],
             );
             let successors_segments_conditions = self.get_successors_segments_conditions(
@@ -370,36 +737,31 @@ impl IRGenerator<'_> {
                 &segments.descendants(),
                 &filters_segments,
             );
+            let final_segments = ProcedureSegments::merge(self.parsed_query, vec![
+                matched_finals.clone(),
+                wildcards_segments.finals(),
+            ]);
             let selection_condition = self.get_selection_condition_with_filters(
-                &ProcedureSegments::merge(self.query_syntax, vec![
-                    occurrences.finals(),
-                    wildcards_segments.finals(),
-                ]),
+                &final_segments,
                 &filters_segments.finals(),
             );
             let procedure_segments = ProcedureSegments::merge(
-                self.query_syntax,
+                self.parsed_query,
                 vec![
                     successors_segments_excluding_filters,
                     segments.descendants(),
                     filters_segments.successors(),
                 ],
             );
-            let inner_instructions = self.generate_procedure_execution(
+            instructions.append(&mut self.generate_procedure_execution(
                 segments,
                 &procedure_segments,
-                node_selected,
+                &final_segments.merge_with(&filters_segments.finals()),
                 selection_condition,
                 successors_segments_conditions,
-            );
-            instructions.push(IfCurrentIndexFromEndEquals {
-                index: u64::from_ne_bytes(i64::abs(neg_index).to_ne_bytes()),
-                instructions: inner_instructions,
-            });
+            ));
         }
-        instructions.append(&mut self.generate_wildcard_filter_and_descendant_selectors(segments));
-        self.generate_end_filters_execution_instruction(segments, &mut instructions);
-        vec![ForEachElement { instructions }]
+        instructions
     }
 
     fn generate_wildcard_filter_and_descendant_selectors(
@@ -412,8 +774,6 @@ impl IRGenerator<'_> {
         let filters_successors = filters_segments.successors();
         let mut instructions = Vec::new();
         if !wildcards_segments.is_empty() || !filters_segments.is_empty() {
-            let node_selected =
-                !wildcards_segments.finals().is_empty() || !filters_segments.finals().is_empty();
             let selection_condition = self.get_selection_condition_with_filters(
                 &wildcards_segments.finals(),
                 &filters_segments.finals(),
@@ -425,7 +785,7 @@ impl IRGenerator<'_> {
                 &filters_segments,
             );
             let procedure_segments = ProcedureSegments::merge(
-                self.query_syntax,
+                self.parsed_query,
                 vec![
                     successors_segments_excluding_filters,
                     segments.descendants(),
@@ -435,18 +795,17 @@ impl IRGenerator<'_> {
             instructions.append(&mut self.generate_procedure_execution(
                 segments,
                 &procedure_segments,
-                node_selected,
+                &wildcards_segments.finals().merge_with(&filters_segments.finals()),
                 selection_condition,
                 successors_segments_conditions,
             ));
         } else if !descendants_segments.is_empty() {
-            let procedure_name = self.get_or_create_procedure_for_segments(&descendants_segments);
-            instructions.push(ExecuteProcedureOnChild {
-                name: procedure_name,
-                segments: descendants_segments.segments(),
-                conditions: vec![None; descendants_segments.segments().len()],
-            });
+            let conditions = vec![None; descendants_segments.segments().len()];
+            instructions.push(self.generate_procedure_call(&descendants_segments, conditions));
             instructions.push(Continue);
+        } else if self.parsed_query.is_merged() {
+            // The node may be saved for another merged query, which then needs all its children.
+            instructions.push(TraverseCurrentNodeSubtree);
         } else {
             instructions.push(IfActiveFilterInstance {
                 instructions: vec![TraverseCurrentNodeSubtree]
@@ -459,49 +818,79 @@ impl IRGenerator<'_> {
         &mut self,
         caller_segments: &ProcedureSegments,
         procedure_segments: &ProcedureSegments,
-        node_selected: bool,
+        selecting_segments: &ProcedureSegments,
         node_selection_condition: Option<SelectionCondition>,
         segments_selection_conditions: Vec<Option<SelectionCondition>>,
     ) -> Vec<Instruction> {
         let mut instructions = Vec::new();
         if !procedure_segments.is_empty() {
-            let procedure_name = self.get_or_create_procedure_for_segments(procedure_segments);
-            instructions.push(
-                Self::wrap_in_save_current_node_during_traversal_conditionally(
-                    ExecuteProcedureOnChild {
-                        name: procedure_name,
-                        segments: procedure_segments.segments(),
-                        conditions: segments_selection_conditions,
-                    },
-                    node_selected,
-                    node_selection_condition,
-                ),
-            );
+            let procedure_call =
+                self.generate_procedure_call(procedure_segments, segments_selection_conditions);
+            instructions.push(self.wrap_in_save_current_node_during_traversal_conditionally(
+                procedure_call,
+                selecting_segments,
+                node_selection_condition,
+            ));
             self.generate_end_filters_execution_instruction(caller_segments, &mut instructions);
             instructions.push(Continue);
         } else {
-            instructions.push(
-                Self::wrap_in_save_current_node_during_traversal_conditionally(
-                    TraverseCurrentNodeSubtree,
-                    node_selected,
-                    node_selection_condition,
-                ),
-            );
+            instructions.push(self.wrap_in_save_current_node_during_traversal_conditionally(
+                TraverseCurrentNodeSubtree,
+                selecting_segments,
+                node_selection_condition,
+            ));
             self.generate_end_filters_execution_instruction(caller_segments, &mut instructions);
             instructions.push(Continue);
         }
         instructions
     }
 
-    fn get_or_create_procedure_for_segments(&mut self, segments: &ProcedureSegments) -> String {
+    /// Generates the execution of the procedure for the segments on the child, or of the
+    /// procedure to which the active segments are passed once the limit of procedures is reached.
+    fn generate_procedure_call(
+        &mut self,
+        segments: &ProcedureSegments,
+        conditions: Vec<Option<SelectionCondition>>,
+    ) -> Instruction {
         let segments_data = segments.segments_data();
         if !self.generated_procedures.contains_key(&segments_data)
             && !self.procedures_to_generate.contains(&segments_data)
         {
+            if self.is_procedures_limit_reached() {
+                if !self.is_active_segments_procedure_supported() {
+                    self.is_procedures_limit_exceeded = self.max_procedures.is_some();
+                } else {
+                    self.is_active_segments_procedure_used = true;
+                    return self.active_segments_procedure_execution(
+                        segments
+                            .segments()
+                            .into_iter()
+                            .map(|segment_index| ActivateSegmentInChild { segment_index })
+                            .collect(),
+                    );
+                }
+            }
             self.procedures_to_generate.insert(segments_data.clone());
             self.procedure_queue.push_back(segments_data);
         }
-        segments.name()
+        ExecuteProcedureOnChild {
+            name: segments.name(),
+            segments: segments.segments(),
+            conditions,
+        }
+    }
+
+    fn is_procedures_limit_reached(&self) -> bool {
+        self.generated_procedures.len() + self.procedures_to_generate.len()
+            >= self.max_procedures.unwrap_or(DEFAULT_MAX_PROCEDURES)
+    }
+
+    /// The procedure to which the active segments are passed visits the elements in the document
+    /// order, as it does not know which slices select them until runtime.
+    fn is_active_segments_procedure_supported(&self) -> bool {
+        !self.are_filters_in_query
+            && !self.are_negative_step_slices_in_query
+            && !self.parsed_query.is_merged()
     }
 
     fn generate_end_filters_execution_instruction(
@@ -521,18 +910,58 @@ impl IRGenerator<'_> {
         }
     }
 
+    /// Wraps the instruction in the saving of the current node if any segment selects it,
+    /// separately for every merged query selecting it.
     fn wrap_in_save_current_node_during_traversal_conditionally(
+        &self,
         instruction: Instruction,
-        condition: bool,
+        selecting_segments: &ProcedureSegments,
         selection_condition: Option<SelectionCondition>,
     ) -> Instruction {
-        if condition {
+        if self.parsed_query.is_merged() {
+            selecting_segments
+                .queries()
+                .into_iter()
+                .rev()
+                .fold(instruction, |instruction, query_index| SaveCurrentNodeDuringTraversal {
+                    instruction: Box::new(instruction),
+                    condition: None,
+                    query_index: Some(query_index),
+                })
+        } else if !selecting_segments.is_empty() {
             SaveCurrentNodeDuringTraversal {
                 instruction: Box::new(instruction),
                 condition: selection_condition,
+                query_index: None,
             }
         } else {
             instruction
         }
     }
 }
+
+/// Array selectors of a segment visiting the elements in the order in which they select them.
+enum OrderedArraySelector {
+    /// Slices selecting the elements selected by consecutive selectors other than filters.
+    Slices(Vec<Slice>),
+    Filter(FilterId),
+}
+
+#[derive(Clone)]
+enum ArrayElementCondition {
+    Index(u64),
+    IndexFromEnd(u64),
+    InSlice(Slice),
+}
+
+impl ArrayElementCondition {
+    fn into_instruction(self, instructions: Vec<Instruction>) -> Instruction {
+        match self {
+            ArrayElementCondition::Index(index) => IfCurrentIndexEquals { index, instructions },
+            ArrayElementCondition::IndexFromEnd(index) => {
+                IfCurrentIndexFromEndEquals { index, instructions }
+            }
+            ArrayElementCondition::InSlice(slice) => IfCurrentIndexInSlice { slice, instructions },
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use clap::ValueEnum;
+
+use crate::IRPass;
+use crate::ir::{Instruction, PassReport, Query};
+use crate::ir::Instruction::{ExecuteProcedureOnChild, ExecuteProcedureOnChildWithActiveSegments, ForEachElement, ForEachElementInReverseOrder, ForEachElementInSlices, ForEachMember, IfActiveFilterInstance, IfCurrentIndexEquals, IfCurrentIndexFromEndEquals, IfCurrentIndexInSlice, IfCurrentMemberNameEquals, IfSegmentActive, SaveCurrentNodeDuringTraversal, StartFilterExecution, UpdateSubqueriesState};
+
+/// Runs the enabled passes on the query in the order of the variants of [`IRPass`] and adds
+/// their reports to the query.
+pub fn run_passes(query: &mut Query, enabled_passes: &[IRPass]) {
+    for &pass in IRPass::value_variants() {
+        if enabled_passes.contains(&pass) {
+            let changes = run_pass(query, pass);
+            query.passes.push(PassReport { pass, changes });
+        }
+    }
+}
+
+fn run_pass(query: &mut Query, pass: IRPass) -> usize {
+    match pass {
+        IRPass::MergeIdenticalProcedures => merge_identical_procedures(query),
+        IRPass::RemoveUnreachableProcedures => remove_unreachable_procedures(query),
+        IRPass::FoldMemberNameConditions => query
+            .procedures
+            .iter_mut()
+            .map(|procedure| {
+                let mut folded = 0;
+                loop {
+                    let folded_now = fold_member_name_conditions(&mut procedure.instructions);
+                    if folded_now == 0 {
+                        break folded;
+                    }
+                    folded += folded_now;
+                }
+            })
+            .sum(),
+        IRPass::RemoveSubqueriesStateUpdates => remove_subqueries_state_updates(query),
+    }
+}
+
+/// Keeps one procedure of every class of procedures with the same instructions, up to the
+/// classes of the procedures they call, and calls it in place of the others.
+fn merge_identical_procedures(query: &mut Query) -> usize {
+    let mut classes: HashMap<String, usize> = query
+        .procedures
+        .iter()
+        .map(|procedure| (procedure.name.clone(), 0))
+        .collect();
+    let mut classes_count = 1;
+    loop {
+        let mut classes_ids = HashMap::new();
+        let mut new_classes = HashMap::new();
+        for procedure in &query.procedures {
+            let mut instructions = procedure.instructions.clone();
+            for_each_instruction_mut(&mut instructions, &mut |instruction| {
+                if let Some(name) = called_procedure_name_mut(instruction) {
+                    *name = classes[name].to_string();
+                }
+            });
+            let signature = (
+                classes[&procedure.name],
+                procedure.are_active_segments_passed,
+                format!("{instructions:?}"),
+            );
+            let next_class_id = classes_ids.len();
+            let class = *classes_ids.entry(signature).or_insert(next_class_id);
+            new_classes.insert(procedure.name.clone(), class);
+        }
+        classes = new_classes;
+        if classes_ids.len() == classes_count {
+            break;
+        }
+        classes_count = classes_ids.len();
+    }
+    let root_procedure_name = query.root_procedure_name();
+    let mut representatives: HashMap<usize, &str> = HashMap::new();
+    for (name, class) in &classes {
+        let representative = representatives.entry(*class).or_insert(name);
+        if *representative != root_procedure_name
+            && (*name == root_procedure_name || name.as_str() < *representative)
+        {
+            *representative = name;
+        }
+    }
+    let representatives: HashMap<&str, String> = classes
+        .iter()
+        .map(|(name, class)| (name.as_str(), representatives[class].to_string()))
+        .collect();
+    let procedures_count = query.procedures.len();
+    query.procedures.retain(|procedure| representatives[procedure.name.as_str()] == procedure.name);
+    for procedure in &mut query.procedures {
+        for_each_instruction_mut(&mut procedure.instructions, &mut |instruction| {
+            if let Some(name) = called_procedure_name_mut(instruction) {
+                *name = representatives[name.as_str()].clone();
+            }
+        });
+    }
+    procedures_count - query.procedures.len()
+}
+
+fn remove_unreachable_procedures(query: &mut Query) -> usize {
+    let callees = callees(query);
+    let root_procedure_name = query.root_procedure_name();
+    let mut reachable = HashSet::from([root_procedure_name.clone()]);
+    let mut queue = VecDeque::from([root_procedure_name]);
+    while let Some(name) = queue.pop_front() {
+        for callee in callees.get(&name).into_iter().flatten() {
+            if reachable.insert(callee.clone()) {
+                queue.push_back(callee.clone());
+            }
+        }
+    }
+    let procedures_count = query.procedures.len();
+    query.procedures.retain(|procedure| reachable.contains(&procedure.name));
+    procedures_count - query.procedures.len()
+}
+
+/// Moves the instructions of conditions on a member name nested in a condition on the same
+/// name to the outer condition, and merges consecutive conditions on the same name.
+fn fold_member_name_conditions(instructions: &mut Vec<Instruction>) -> usize {
+    let mut folded = 0;
+    for instruction in instructions.iter_mut() {
+        if let Some(nested_instructions) = nested_instructions_mut(instruction) {
+            folded += fold_member_name_conditions(nested_instructions);
+        }
+        if let IfCurrentMemberNameEquals { name, instructions: nested_instructions } = instruction {
+            let mut flattened_instructions = Vec::new();
+            for nested_instruction in nested_instructions.drain(..) {
+                match nested_instruction {
+                    IfCurrentMemberNameEquals { name: nested_name, instructions }
+                    if nested_name == *name => {
+                        flattened_instructions.extend(instructions);
+                        folded += 1;
+                    }
+                    nested_instruction => flattened_instructions.push(nested_instruction),
+                }
+            }
+            *nested_instructions = flattened_instructions;
+        }
+    }
+    let mut merged_instructions: Vec<Instruction> = Vec::new();
+    for instruction in instructions.drain(..) {
+        let is_same_name = matches!(
+            (merged_instructions.last(), &instruction),
+            (
+                Some(IfCurrentMemberNameEquals { name: previous_name, .. }),
+                IfCurrentMemberNameEquals { name, .. }
+            ) if previous_name == name
+        );
+        if !is_same_name {
+            merged_instructions.push(instruction);
+            continue;
+        }
+        if let (
+            Some(IfCurrentMemberNameEquals { instructions: previous_instructions, .. }),
+            IfCurrentMemberNameEquals { instructions, .. },
+        ) = (merged_instructions.last_mut(), instruction) {
+            previous_instructions.extend(instructions);
+            folded += 1;
+        }
+    }
+    *instructions = merged_instructions;
+    folded
+}
+
+/// Keeps the updates of the subqueries states only in the procedures called, directly or not,
+/// by the procedures starting filters.
+fn remove_subqueries_state_updates(query: &mut Query) -> usize {
+    let callees = callees(query);
+    let mut with_active_filters = HashSet::new();
+    let mut queue = VecDeque::new();
+    for procedure in &query.procedures {
+        let mut are_filters_started = false;
+        for_each_instruction(&procedure.instructions, &mut |instruction| {
+            are_filters_started |= matches!(instruction, StartFilterExecution { .. });
+        });
+        if are_filters_started {
+            queue.push_back(procedure.name.clone());
+        }
+    }
+    while let Some(name) = queue.pop_front() {
+        for callee in callees.get(&name).into_iter().flatten() {
+            if with_active_filters.insert(callee.clone()) {
+                queue.push_back(callee.clone());
+            }
+        }
+    }
+    let mut removed = 0;
+    for procedure in &mut query.procedures {
+        if !with_active_filters.contains(&procedure.name) {
+            let instructions_count = procedure.instructions.len();
+            procedure.instructions.retain(|instruction| !matches!(instruction, UpdateSubqueriesState));
+            removed += instructions_count - procedure.instructions.len();
+        }
+    }
+    removed
+}
+
+fn callees(query: &Query) -> HashMap<String, Vec<String>> {
+    query
+        .procedures
+        .iter()
+        .map(|procedure| {
+            let mut callees = Vec::new();
+            for_each_instruction(&procedure.instructions, &mut |instruction| {
+                if let ExecuteProcedureOnChild { name, .. }
+                | ExecuteProcedureOnChildWithActiveSegments { name, .. } = instruction
+                {
+                    callees.push(name.clone());
+                }
+            });
+            (procedure.name.clone(), callees)
+        })
+        .collect()
+}
+
+pub(crate) fn for_each_instruction(instructions: &[Instruction], f: &mut impl FnMut(&Instruction)) {
+    for instruction in instructions {
+        for_each_nested_instruction(instruction, f);
+    }
+}
+
+fn for_each_nested_instruction(instruction: &Instruction, f: &mut impl FnMut(&Instruction)) {
+    f(instruction);
+    match instruction {
+        ForEachElement { instructions }
+        | ForEachElementInSlices { instructions, .. }
+        | ForEachElementInReverseOrder { instructions, .. }
+        | ForEachMember { instructions }
+        | IfCurrentIndexEquals { instructions, .. }
+        | IfCurrentIndexFromEndEquals { instructions, .. }
+        | IfCurrentIndexInSlice { instructions, .. }
+        | IfCurrentMemberNameEquals { instructions, .. }
+        | IfActiveFilterInstance { instructions }
+        | IfSegmentActive { instructions, .. }
+        | ExecuteProcedureOnChildWithActiveSegments { instructions, .. } => {
+            for_each_instruction(instructions, f)
+        }
+        SaveCurrentNodeDuringTraversal { instruction, .. } => {
+            for_each_nested_instruction(instruction, f)
+        }
+        _ => {}
+    }
+}
+
+fn for_each_instruction_mut(instructions: &mut [Instruction], f: &mut impl FnMut(&mut Instruction)) {
+    for instruction in instructions {
+        f(instruction);
+        if let SaveCurrentNodeDuringTraversal { instruction, .. } = instruction {
+            f(instruction);
+        }
+        if let Some(nested_instructions) = nested_instructions_mut(instruction) {
+            for_each_instruction_mut(nested_instructions, f);
+        }
+    }
+}
+
+/// Returns the instructions nested in the instruction, including the ones nested in the
+/// instruction executed during the traversal of a saved node.
+fn nested_instructions_mut(instruction: &mut Instruction) -> Option<&mut Vec<Instruction>> {
+    match instruction {
+        ForEachElement { instructions }
+        | ForEachElementInSlices { instructions, .. }
+        | ForEachElementInReverseOrder { instructions, .. }
+        | ForEachMember { instructions }
+        | IfCurrentIndexEquals { instructions, .. }
+        | IfCurrentIndexFromEndEquals { instructions, .. }
+        | IfCurrentIndexInSlice { instructions, .. }
+        | IfCurrentMemberNameEquals { instructions, .. }
+        | IfActiveFilterInstance { instructions }
+        | IfSegmentActive { instructions, .. }
+        | ExecuteProcedureOnChildWithActiveSegments { instructions, .. } => Some(instructions),
+        SaveCurrentNodeDuringTraversal { instruction, .. } => nested_instructions_mut(instruction),
+        _ => None,
+    }
+}
+
+fn called_procedure_name_mut(instruction: &mut Instruction) -> Option<&mut String> {
+    match instruction {
+        ExecuteProcedureOnChild { name, .. }
+        | ExecuteProcedureOnChildWithActiveSegments { name, .. } => Some(name),
+        _ => None,
+    }
+}
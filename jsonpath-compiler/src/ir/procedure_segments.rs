@@ -3,7 +3,8 @@ use std::hash::Hash;
 
 use itertools::Itertools;
 
-use crate::ir::{SegmentIndex};
+use crate::ir::{index_to_i64, SegmentIndex, Slice};
+use crate::syntax::{ParsedQuery, Segment, Selector};
 
 #[derive(Eq, PartialEq, Hash, Clone)]
 pub struct ProcedureSegmentsData {
@@ -11,13 +12,13 @@ pub struct ProcedureSegmentsData {
 }
 
 impl ProcedureSegmentsData {
-    fn new(segments: Vec<SegmentIndex>) -> ProcedureSegmentsData {
+    pub fn new(segments: Vec<SegmentIndex>) -> ProcedureSegmentsData {
         ProcedureSegmentsData {
             segments: segments.into_iter().sorted().collect(),
         }
     }
 
-    fn name(&self) -> String {
+    pub fn name(&self) -> String {
         let segments_str = self
             .segments
             .iter()
@@ -39,13 +40,13 @@ impl ProcedureSegmentsData {
 
 #[derive(Clone)]
 pub struct ProcedureSegments<'a> {
-    query: &'a rsonpath_syntax::JsonPathQuery,
+    query: &'a ParsedQuery,
     segments_data: ProcedureSegmentsData,
 }
 
 impl ProcedureSegments<'_> {
     pub fn new(
-        query: &rsonpath_syntax::JsonPathQuery,
+        query: &ParsedQuery,
         segments: Vec<SegmentIndex>,
     ) -> ProcedureSegments {
         ProcedureSegments {
@@ -70,20 +71,30 @@ impl ProcedureSegments<'_> {
         self.segments_data.is_empty()
     }
 
+    /// Indices of the merged queries to which the segments belong.
+    pub fn queries(&self) -> Vec<usize> {
+        self.segments()
+            .into_iter()
+            .map(|segment_index| self.query.query_index(segment_index))
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
     pub fn descendants(&self) -> ProcedureSegments {
         let segments =
-            self.filter_and_map_segments(|s| self.query.segments()[s].is_descendant(), |s| s);
+            self.filter_and_map_segments(|s| self.segment(s).is_descendant(), |s| s);
         ProcedureSegments::new(self.query, segments)
     }
 
     pub fn successors(&self) -> ProcedureSegments {
         let segments =
-            self.filter_and_map_segments(|s| s + 1 < self.query.segments().len(), |s| s + 1);
+            self.filter_and_map_segments(|s| !self.query.is_last_segment(s), |s| s + 1);
         ProcedureSegments::new(self.query, segments)
     }
 
     pub fn successor(&self, segment_index: SegmentIndex) -> Option<SegmentIndex> {
-        if segment_index + 1 < self.query.segments().len() {
+        if !self.query.is_last_segment(segment_index) {
             Some(segment_index + 1)
         } else {
             None
@@ -91,15 +102,14 @@ impl ProcedureSegments<'_> {
     }
 
     pub fn finals(&self) -> ProcedureSegments {
-        let segment_count = self.query.segments().len();
-        let segments = self.filter_and_map_segments(|s| s == segment_count - 1, |s| s);
+        let segments = self.filter_and_map_segments(|s| self.query.is_last_segment(s), |s| s);
         ProcedureSegments::new(self.query, segments)
     }
 
     pub fn wildcards(&self) -> ProcedureSegments {
         let segments = self.filter_and_map_segments(
             |s| {
-                self.query.segments()[s]
+                self.segment(s)
                     .selectors()
                     .iter()
                     .any(|sel| sel.is_wildcard())
@@ -111,7 +121,7 @@ impl ProcedureSegments<'_> {
 
     pub fn name_selectors(&self) -> HashMap<String, ProcedureSegments> {
         self.selector_to_segments_map(|selector| {
-            if let rsonpath_syntax::Selector::Name(name) = selector {
+            if let Selector::Name(name) = selector {
                 Some(name.unquoted().to_string())
             } else {
                 None
@@ -121,11 +131,8 @@ impl ProcedureSegments<'_> {
 
     pub fn index_selectors(&self) -> HashMap<i64, ProcedureSegments> {
         self.selector_to_segments_map(|selector| {
-            if let rsonpath_syntax::Selector::Index(index) = selector {
-                Some(match index {
-                    rsonpath_syntax::Index::FromStart(num) => num.as_u64() as i64,
-                    rsonpath_syntax::Index::FromEnd(num) => -(num.as_u64() as i64),
-                })
+            if let Selector::Index(index) = selector {
+                Some(index_to_i64(*index))
             } else {
                 None
             }
@@ -146,10 +153,20 @@ impl ProcedureSegments<'_> {
             .collect()
     }
 
+    pub fn slice_selectors(&self) -> HashMap<Slice, ProcedureSegments<'_>> {
+        self.selector_to_segments_map(|selector| {
+            if let Selector::Slice(slice) = selector {
+                Some(Slice::from_syntax(slice))
+            } else {
+                None
+            }
+        })
+    }
+
     pub fn filters(&self) -> ProcedureSegments {
         let segments = self.filter_and_map_segments(
             |s| {
-                self.query.segments()[s]
+                self.segment(s)
                     .selectors()
                     .iter()
                     .any(|sel| sel.is_filter())
@@ -166,24 +183,25 @@ impl ProcedureSegments<'_> {
                 segments.insert(segment_id);
             }
         }
-        let max_descendant_segment = segments
-            .iter()
-            .filter(|segment_id| self.query.segments()[**segment_id].is_descendant())
-            .max();
-        if max_descendant_segment.is_none() {
-            ProcedureSegments::new(self.query, segments.iter().map(|i| *i).collect())
-        } else {
-            let max_descendant_segment = *max_descendant_segment.unwrap();
-            let segments = segments.iter().filter(|i| {
-                **i == max_descendant_segment || !self.query.segments()[**i].is_descendant()
-                    || self.query.segments()[**i].selectors().iter().any(|sel| sel.is_filter())
-            });
-            ProcedureSegments::new(self.query, segments.map(|i| *i).collect())
+        let mut max_descendant_segments: HashMap<usize, SegmentIndex> = HashMap::new();
+        let descendant_segments =
+            segments.iter().filter(|segment_id| self.segment(**segment_id).is_descendant());
+        for segment_id in descendant_segments {
+            let max_descendant_segment = max_descendant_segments
+                .entry(self.query.query_index(*segment_id))
+                .or_insert(*segment_id);
+            *max_descendant_segment = (*max_descendant_segment).max(*segment_id);
         }
+        let segments = segments.iter().filter(|i| {
+            !self.segment(**i).is_descendant()
+                || self.segment(**i).selectors().iter().any(|sel| sel.is_filter())
+                || max_descendant_segments[&self.query.query_index(**i)] == **i
+        });
+        ProcedureSegments::new(self.query, segments.copied().collect())
     }
 
     pub fn merge<'a>(
-        query: &'a rsonpath_syntax::JsonPathQuery,
+        query: &'a ParsedQuery,
         segments: Vec<ProcedureSegments<'a>>,
     ) -> ProcedureSegments<'a> {
         let merged_segments = segments.iter().fold(
@@ -193,6 +211,10 @@ impl ProcedureSegments<'_> {
         ProcedureSegments::new(query, merged_segments.segments().clone())
     }
 
+    fn segment(&self, segment_index: SegmentIndex) -> &Segment {
+        &self.query.syntax.segments()[segment_index]
+    }
+
     fn filter_and_map_segments(
         &self,
         f: impl Fn(SegmentIndex) -> bool,
@@ -207,11 +229,11 @@ impl ProcedureSegments<'_> {
 
     fn selector_to_segments_map<T: Eq + Hash>(
         &self,
-        get_key: impl Fn(&rsonpath_syntax::Selector) -> Option<T>,
+        get_key: impl Fn(&Selector) -> Option<T>,
     ) -> HashMap<T, ProcedureSegments> {
         let mut map: HashMap<T, Vec<SegmentIndex>> = HashMap::new();
         for segment_index in self.segments() {
-            let selectors = self.query.segments()[segment_index].selectors();
+            let selectors = self.segment(segment_index).selectors();
             for selector in selectors.iter() {
                 if let Some(key) = get_key(selector) {
                     match map.get_mut(&key) {
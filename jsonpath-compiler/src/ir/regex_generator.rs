@@ -0,0 +1,76 @@
+use crate::ir::{Regex, RegexState};
+use crate::syntax::i_regexp::IRegexp;
+
+pub struct RegexGenerator {
+    states: Vec<RegexState>,
+}
+
+impl RegexGenerator {
+    pub fn generate(regexp: &IRegexp) -> Regex {
+        let mut generator = RegexGenerator { states: Vec::new() };
+        let match_state = generator.add_state(RegexState::Match);
+        let start = generator.generate_state(regexp, match_state);
+        Regex {
+            start,
+            states: generator.states,
+        }
+    }
+
+    /// Generates states matching `regexp` and continuing to `next`, returning the entry state.
+    fn generate_state(&mut self, regexp: &IRegexp, next: usize) -> usize {
+        match regexp {
+            IRegexp::Chars(char_set) => self.add_state(RegexState::Chars {
+                ranges: char_set.ranges.clone(),
+                next,
+            }),
+            IRegexp::Concatenation(pieces) => pieces
+                .iter()
+                .rev()
+                .fold(next, |next, piece| self.generate_state(piece, next)),
+            IRegexp::Alternation(branches) => {
+                let starts: Vec<usize> = branches
+                    .iter()
+                    .map(|branch| self.generate_state(branch, next))
+                    .collect();
+                starts
+                    .into_iter()
+                    .rev()
+                    .reduce(|alt, start| self.add_state(RegexState::Split { next: start, alt }))
+                    .unwrap()
+            }
+            IRegexp::Repetition { expr, min, max } => {
+                let mut start = match max {
+                    Some(max) => {
+                        let mut start = next;
+                        for _ in *min..*max {
+                            let expr_start = self.generate_state(expr, start);
+                            start = self.add_state(RegexState::Split {
+                                next: expr_start,
+                                alt: next,
+                            });
+                        }
+                        start
+                    }
+                    None => {
+                        let split = self.add_state(RegexState::Match);
+                        let expr_start = self.generate_state(expr, split);
+                        self.states[split] = RegexState::Split {
+                            next: expr_start,
+                            alt: next,
+                        };
+                        split
+                    }
+                };
+                for _ in 0..*min {
+                    start = self.generate_state(expr, start);
+                }
+                start
+            }
+        }
+    }
+
+    fn add_state(&mut self, state: RegexState) -> usize {
+        self.states.push(state);
+        self.states.len() - 1
+    }
+}
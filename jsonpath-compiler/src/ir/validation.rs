@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use crate::ir::{FilterId, FilterSubquerySelector, Instruction, Query, SelectionCondition};
+
+pub fn validate(query: &Query) -> Result<(), String> {
+    let procedure_names: HashSet<String> = query
+        .procedures
+        .iter()
+        .map(|procedure| procedure.name.to_lowercase())
+        .collect();
+    let validator = Validator { query, procedure_names };
+    if query.has_root_procedure() {
+        validator.check_procedure_exists(&query.root_procedure_name())?;
+    }
+    for procedure in &query.procedures {
+        validator
+            .check_instructions(&procedure.instructions, &mut HashSet::new())
+            .map_err(|message| format!("procedure {}: {message}", procedure.name))?;
+    }
+    for subquery in query.filter_subqueries.values().flatten() {
+        for segment in &subquery.segments {
+            for selector in &segment.selectors {
+                if let FilterSubquerySelector::Filter(filter_id) = selector {
+                    validator.check_filter_exists(filter_id)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Validator<'q> {
+    query: &'q Query,
+    procedure_names: HashSet<String>,
+}
+
+impl<'q> Validator<'q> {
+    /// `executed_filters` are the filters executed earlier on the current node, whose results
+    /// can be used by the instructions.
+    fn check_instructions(
+        &self,
+        instructions: &'q [Instruction],
+        executed_filters: &mut HashSet<&'q FilterId>,
+    ) -> Result<(), String> {
+        for instruction in instructions {
+            self.check_instruction(instruction, executed_filters)?;
+        }
+        Ok(())
+    }
+
+    fn check_instruction(
+        &self,
+        instruction: &'q Instruction,
+        executed_filters: &mut HashSet<&'q FilterId>,
+    ) -> Result<(), String> {
+        match instruction {
+            Instruction::ForEachElement { instructions }
+            | Instruction::ForEachMember { instructions }
+            | Instruction::ForEachElementInSlices { instructions, .. }
+            | Instruction::ForEachElementInReverseOrder { instructions, .. } => {
+                self.check_instructions(instructions, &mut HashSet::new())
+            }
+            Instruction::IfCurrentIndexEquals { instructions, .. }
+            | Instruction::IfCurrentIndexFromEndEquals { instructions, .. }
+            | Instruction::IfCurrentIndexInSlice { instructions, .. }
+            | Instruction::IfCurrentMemberNameEquals { instructions, .. }
+            | Instruction::IfActiveFilterInstance { instructions }
+            | Instruction::IfSegmentActive { instructions, .. } => {
+                self.check_instructions(instructions, &mut executed_filters.clone())
+            }
+            Instruction::ExecuteProcedureOnChild { conditions, name, .. } => {
+                for condition in conditions.iter().flatten() {
+                    Self::check_condition(condition, executed_filters)?;
+                }
+                self.check_procedure_exists(name)
+            }
+            Instruction::SaveCurrentNodeDuringTraversal { condition, instruction, .. } => {
+                if let Some(condition) = condition {
+                    Self::check_condition(condition, executed_filters)?;
+                }
+                self.check_instruction(instruction, executed_filters)
+            }
+            Instruction::StartFilterExecution { filter_id } => {
+                self.check_filter_exists(filter_id)?;
+                executed_filters.insert(filter_id);
+                Ok(())
+            }
+            Instruction::ExecuteProcedureOnChildWithActiveSegments { name, instructions, .. } => {
+                self.check_instructions(instructions, &mut executed_filters.clone())?;
+                self.check_procedure_exists(name)
+            }
+            Instruction::Continue
+            | Instruction::TraverseCurrentNodeSubtree
+            | Instruction::EndFiltersExecution
+            | Instruction::UpdateSubqueriesState
+            | Instruction::ActivateSegmentInChild { .. }
+            | Instruction::SelectChild => Ok(()),
+        }
+    }
+
+    fn check_condition(
+        condition: &SelectionCondition,
+        executed_filters: &HashSet<&FilterId>,
+    ) -> Result<(), String> {
+        match condition {
+            SelectionCondition::Filter { id } if !executed_filters.contains(id) => {
+                Err(format!("filter {id} is used before its execution"))
+            }
+            SelectionCondition::Filter { .. }
+            | SelectionCondition::RuntimeSegmentCondition { .. } => Ok(()),
+            SelectionCondition::Or { lhs, rhs } | SelectionCondition::And { lhs, rhs } => {
+                Self::check_condition(lhs, executed_filters)?;
+                Self::check_condition(rhs, executed_filters)
+            }
+        }
+    }
+
+    fn check_filter_exists(&self, filter_id: &FilterId) -> Result<(), String> {
+        if self.query.filter_procedures.contains_key(filter_id)
+            && self.query.filter_subqueries.contains_key(filter_id)
+        {
+            Ok(())
+        } else {
+            Err(format!("filter {filter_id} is not defined"))
+        }
+    }
+
+    fn check_procedure_exists(&self, name: &str) -> Result<(), String> {
+        if self.procedure_names.contains(&name.to_lowercase()) {
+            Ok(())
+        } else {
+            Err(format!("procedure {name} is not defined"))
+        }
+    }
+}
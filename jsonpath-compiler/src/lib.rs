@@ -1,20 +1,55 @@
 use clap::ValueEnum;
-use rsonpath_syntax::JsonPathQuery;
+use serde::{Deserialize, Serialize};
 
 use crate::ir::Query;
+use crate::syntax::ParsedQuery;
 
 pub mod targets;
 pub mod compiler;
+pub mod interpreter;
 pub mod test_helper;
 mod ir;
+mod syntax;
 
 #[derive(ValueEnum, Debug, Clone)]
 #[clap(rename_all = "kebab_case")]
 pub enum Target {
     SimdjsonOndemand,
     SimdjsonDom,
+    Rust,
+    /// Object members are visited in the iteration order of `serde_json::Map`, which is the
+    /// document order only if the generated code is built with the `preserve_order` feature of
+    /// serde_json.
+    RustSerdeJson,
+}
+
+/// Optimization pass run on the IR of a query before the code is generated from it. All passes
+/// are run by default, in the order of the variants.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum IRPass {
+    /// Merges procedures with the same instructions, which call equivalent procedures.
+    MergeIdenticalProcedures,
+    /// Removes procedures which are never executed.
+    RemoveUnreachableProcedures,
+    /// Merges conditions on the same member name which are nested or follow each other.
+    FoldMemberNameConditions,
+    /// Removes the updates of the states of filter subqueries from procedures executed only
+    /// when no filter is being executed.
+    RemoveSubqueriesStateUpdates,
+}
+
+/// Format of the paths of the selected nodes, returned with the nodes when requested.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[clap(rename_all = "kebab_case")]
+pub enum NodePathFormat {
+    /// Normalized Path, as defined in RFC 9535, e.g. `$['a'][0]`.
+    NormalizedPath,
+    /// JSON Pointer, as defined in RFC 6901, e.g. `/a/0`.
+    JsonPointer,
 }
 
 type NamedRawQuery = (String, String);
-type NamedParsedQuery = (String, JsonPathQuery);
+type NamedParsedQuery = (String, ParsedQuery);
 type NamedQuery = (String, Query);
\ No newline at end of file
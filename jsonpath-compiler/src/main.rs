@@ -3,10 +3,13 @@ use std::process::ExitCode;
 use clap::Parser;
 
 use jsonpath_compiler::compiler::{CompilationError, LibGeneratingCompiler, QueriesSource, StandaloneProgGeneratingCompiler};
-use jsonpath_compiler::Target;
+use jsonpath_compiler::{IRPass, NodePathFormat, Target};
+use jsonpath_compiler::targets::{InputFormat, TargetCodeStandaloneProgGenerator};
+use jsonpath_compiler::targets::rust::{RustCodeLibGenerator, RustCodeStandaloneProgGenerator};
+use jsonpath_compiler::targets::rust::value::{ValueCodeLibGenerator, ValueCodeStandaloneProgGenerator};
 use jsonpath_compiler::targets::simdjson::dom::{DomCodeLibGenerator, DomCodeStandaloneProgGenerator};
 use jsonpath_compiler::targets::simdjson::ondemand::{OnDemandCodeLibGenerator, OnDemandCodeStandaloneProgGenerator};
-use jsonpath_compiler::targets::simdjson::RustBindingsGenerator;
+use jsonpath_compiler::targets::simdjson::{CHeaderGenerator, RustBindingsGenerator};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -25,6 +28,11 @@ pub struct Args {
     #[arg(long)]
     pub ir_output: Option<String>,
 
+    /// The input is a file with intermediate query code, as placed in the file given with
+    /// --ir-output, instead of queries.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub ir_input: bool,
+
     /// Generate code of a standalone program which executes the query.
     #[arg(long, action = clap::ArgAction::SetTrue)]
     pub standalone: bool,
@@ -40,13 +48,71 @@ pub struct Args {
     #[arg(long, action = clap::ArgAction::SetTrue)]
     pub eager_filter_evaluation: bool,
 
+    /// Optimization pass not to run on the intermediate query code.
+    #[arg(long)]
+    pub disable_ir_pass: Vec<IRPass>,
+
+    /// Maximum number of procedures generated for a query, 1024 by default, beyond which the
+    /// active segments are passed at runtime. Queries with filters, slices with negative steps
+    /// and merged queries fail to compile beyond a limit given this way.
+    #[arg(long)]
+    pub max_procedures: Option<usize>,
+
+    /// Execute all queries of the library in a single pass over the document, in a function
+    /// with the given name, which returns their results grouped by query name.
+    #[arg(long)]
+    pub single_pass: Option<String>,
+
+    /// Generate additional functions returning the results as (offset, length) spans of the
+    /// padded input instead of copies of the selected nodes.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub result_spans: bool,
+
+    /// Generate additional functions passing each result to a callback as soon as it is known
+    /// to be selected.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub result_callbacks: bool,
+
+    /// Return every selected node together with its path in the given format, as an object
+    /// {"path": ..., "value": ...}.
+    #[arg(long)]
+    pub node_paths: Option<NodePathFormat>,
+
+    /// Execute the query on every document of an NDJSON input, returning a line with the
+    /// results for every document.
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub ndjson: bool,
+
+    /// Tag the results of every document of an NDJSON input with its line number.
+    #[arg(long, action = clap::ArgAction::SetTrue, requires = "ndjson")]
+    pub line_numbers: bool,
+
     // File in which to place Rust bindings for query procedures.
     #[arg(long, action)]
     pub rust_bindings: Option<String>,
+
+    /// File in which to place a C header declaring the functions through which the library is
+    /// called from C and C++ code.
+    #[arg(long)]
+    pub c_header: Option<String>,
+
+    /// Prefix of the names of the functions called through the Rust bindings or the C header,
+    /// required to link several libraries together.
+    #[arg(long)]
+    pub symbol_prefix: Option<String>,
+}
+
+fn main() -> ExitCode {
+    match compile(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
 
-fn main() -> Result<ExitCode, CompilationError> {
-    let args = Args::parse();
+fn compile(args: Args) -> Result<(), CompilationError> {
     let input = args.input.trim();
     if args.standalone {
         let mut compiler = StandaloneProgGeneratingCompiler::new();
@@ -59,28 +125,76 @@ fn main() -> Result<ExitCode, CompilationError> {
         if args.eager_filter_evaluation {
             compiler = compiler.with_eager_filter_evaluation()
         }
-        if let Some(ir_output_file_path) = args.ir_output {
-            compiler = compiler.write_ir_to_file(&ir_output_file_path);
+        if let Some(ir_output_file_path) = &args.ir_output {
+            compiler = compiler.write_ir_to_file(ir_output_file_path);
         }
-        match args.target {
+        for &pass in &args.disable_ir_pass {
+            compiler = compiler.without_ir_pass(pass);
+        }
+        if let Some(max_procedures) = args.max_procedures {
+            compiler = compiler.with_max_procedures(max_procedures);
+        }
+        if let Some(format) = args.node_paths {
+            compiler = compiler.with_node_paths(format);
+        }
+        compiler = compiler.with_input_format(input_format(&args));
+        match &args.target {
             Target::SimdjsonOndemand => {
-                compiler.compile::<OnDemandCodeStandaloneProgGenerator>(input, &args.output)
+                compile_standalone_prog::<OnDemandCodeStandaloneProgGenerator>(compiler, input, &args)
             }
             Target::SimdjsonDom => {
-                compiler.compile::<DomCodeStandaloneProgGenerator>(input, &args.output)
+                compile_standalone_prog::<DomCodeStandaloneProgGenerator>(compiler, input, &args)
+            }
+            Target::Rust => {
+                compile_standalone_prog::<RustCodeStandaloneProgGenerator>(compiler, input, &args)
+            }
+            Target::RustSerdeJson => {
+                compile_standalone_prog::<ValueCodeStandaloneProgGenerator>(compiler, input, &args)
             }
         }?;
     } else {
-        let queries = QueriesSource::File { file_path: input.to_string() };
+        let queries = if args.ir_input {
+            QueriesSource::IRFile { file_path: input.to_string() }
+        } else {
+            QueriesSource::File { file_path: input.to_string() }
+        };
         let mut compiler = LibGeneratingCompiler::new();
-        if let Some(bindings_file_path) = args.rust_bindings {
+        if let Some(bindings_file_path) = &args.rust_bindings {
             compiler = compiler.add_bindings_generator(
-                RustBindingsGenerator::new(&bindings_file_path)
+                RustBindingsGenerator::new(bindings_file_path)
             );
         }
+        if let Some(header_file_path) = &args.c_header {
+            compiler = compiler.add_bindings_generator(CHeaderGenerator::new(header_file_path));
+        }
+        if let Some(symbol_prefix) = &args.symbol_prefix {
+            compiler = compiler.with_symbol_prefix(symbol_prefix);
+        }
         if args.eager_filter_evaluation {
             compiler = compiler.with_eager_filter_evaluation()
         }
+        if let Some(ir_output_file_path) = &args.ir_output {
+            compiler = compiler.write_ir_to_file(ir_output_file_path);
+        }
+        for &pass in &args.disable_ir_pass {
+            compiler = compiler.without_ir_pass(pass);
+        }
+        if let Some(max_procedures) = args.max_procedures {
+            compiler = compiler.with_max_procedures(max_procedures);
+        }
+        if let Some(query_name) = &args.single_pass {
+            compiler = compiler.with_single_pass(query_name);
+        }
+        if args.result_spans {
+            compiler = compiler.with_result_spans();
+        }
+        if args.result_callbacks {
+            compiler = compiler.with_result_callbacks();
+        }
+        if let Some(format) = args.node_paths {
+            compiler = compiler.with_node_paths(format);
+        }
+        compiler = compiler.with_input_format(input_format(&args));
         match args.target {
             Target::SimdjsonOndemand => {
                 compiler.compile::<OnDemandCodeLibGenerator>(queries, &args.output)
@@ -88,7 +202,33 @@ fn main() -> Result<ExitCode, CompilationError> {
             Target::SimdjsonDom => {
                 compiler.compile::<DomCodeLibGenerator>(queries, &args.output)
             }
+            Target::Rust => {
+                compiler.compile::<RustCodeLibGenerator>(queries, &args.output)
+            }
+            Target::RustSerdeJson => {
+                compiler.compile::<ValueCodeLibGenerator>(queries, &args.output)
+            }
         }?;
     }
-    Ok(ExitCode::SUCCESS)
+    Ok(())
+}
+
+fn input_format(args: &Args) -> InputFormat {
+    if args.ndjson {
+        InputFormat::Ndjson { line_numbers: args.line_numbers }
+    } else {
+        InputFormat::Json
+    }
+}
+
+fn compile_standalone_prog<T: TargetCodeStandaloneProgGenerator>(
+    compiler: StandaloneProgGeneratingCompiler,
+    input: &str,
+    args: &Args,
+) -> Result<(), CompilationError> {
+    if args.ir_input {
+        compiler.compile_ir::<T>(input, &args.output)
+    } else {
+        compiler.compile::<T>(input, &args.output)
+    }
 }
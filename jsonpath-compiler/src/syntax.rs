@@ -0,0 +1,233 @@
+use rsonpath_syntax::str::JsonString;
+use rsonpath_syntax::{ComparisonOp, Index, Literal, SingularJsonPathQuery, Slice};
+
+use crate::compiler::CompilationError;
+use crate::NamedParsedQuery;
+use crate::syntax::i_regexp::IRegexp;
+
+pub mod i_regexp;
+mod parser;
+
+/// A query parsed into its syntax tree, which includes the function expressions in filters.
+pub struct ParsedQuery {
+    pub syntax: JsonPathQuery,
+    /// Names and numbers of segments of the queries merged into this one by
+    /// [`ParsedQuery::merge`], in the order of their segments. Empty for a single query.
+    pub merged_queries: Vec<(String, usize)>,
+}
+
+impl ParsedQuery {
+    pub fn parse(query: &str) -> Result<ParsedQuery, CompilationError> {
+        Ok(ParsedQuery {
+            syntax: parser::parse(query)?,
+            merged_queries: Vec::new(),
+        })
+    }
+
+    /// Merges the queries without filters into one, with their segments following each other,
+    /// to execute them in a single pass.
+    pub fn merge(queries: &[NamedParsedQuery]) -> Result<ParsedQuery, CompilationError> {
+        let errors: Vec<(String, CompilationError)> = queries
+            .iter()
+            .filter(|(_, query)| {
+                query.syntax.segments().iter().any(|segment| {
+                    segment.selectors().iter().any(|selector| selector.is_filter())
+                })
+            })
+            .map(|(name, _)| (name.to_string(), CompilationError::FiltersInSinglePass))
+            .collect();
+        if !errors.is_empty() {
+            return Err(CompilationError::MultipleErrors(errors));
+        }
+        Ok(ParsedQuery {
+            syntax: queries
+                .iter()
+                .flat_map(|(_, query)| query.syntax.segments().iter().cloned())
+                .collect(),
+            merged_queries: queries
+                .iter()
+                .map(|(name, query)| (name.to_string(), query.syntax.segments().len()))
+                .collect(),
+        })
+    }
+
+    pub fn is_merged(&self) -> bool {
+        !self.merged_queries.is_empty()
+    }
+
+    /// Index of the merged query to which the segment belongs, or 0 for a single query.
+    pub fn query_index(&self, segment_index: usize) -> usize {
+        self.queries_ends()
+            .iter()
+            .position(|end| segment_index < *end)
+            .unwrap_or(0)
+    }
+
+    /// Whether the segment is the last one of its query.
+    pub fn is_last_segment(&self, segment_index: usize) -> bool {
+        self.queries_ends().contains(&(segment_index + 1))
+    }
+
+    fn queries_ends(&self) -> Vec<usize> {
+        if self.is_merged() {
+            self.merged_queries
+                .iter()
+                .scan(0, |end, (_, segments_count)| {
+                    *end += segments_count;
+                    Some(*end)
+                })
+                .collect()
+        } else {
+            vec![self.syntax.segments().len()]
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct JsonPathQuery {
+    segments: Vec<Segment>,
+}
+
+impl JsonPathQuery {
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+}
+
+impl FromIterator<Segment> for JsonPathQuery {
+    fn from_iter<T: IntoIterator<Item = Segment>>(iter: T) -> Self {
+        JsonPathQuery {
+            segments: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+impl Segment {
+    pub fn selectors(&self) -> &[Selector] {
+        match self {
+            Segment::Child(selectors) | Segment::Descendant(selectors) => selectors,
+        }
+    }
+
+    pub fn is_descendant(&self) -> bool {
+        matches!(self, Segment::Descendant(_))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Selector {
+    Name(JsonString),
+    Wildcard,
+    Index(Index),
+    Slice(Slice),
+    Filter(LogicalExpr),
+}
+
+impl Selector {
+    pub fn is_wildcard(&self) -> bool {
+        matches!(self, Selector::Wildcard)
+    }
+
+    pub fn is_filter(&self) -> bool {
+        matches!(self, Selector::Filter(_))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum LogicalExpr {
+    Or(Box<LogicalExpr>, Box<LogicalExpr>),
+    And(Box<LogicalExpr>, Box<LogicalExpr>),
+    Not(Box<LogicalExpr>),
+    Comparison(ComparisonExpr),
+    Test(TestExpr),
+}
+
+#[derive(Clone, Debug)]
+pub struct ComparisonExpr {
+    pub lhs: Comparable,
+    pub op: ComparisonOp,
+    pub rhs: Comparable,
+}
+
+#[derive(Clone, Debug)]
+pub enum Comparable {
+    Literal(Literal),
+    RelativeSingularQuery(SingularJsonPathQuery),
+    AbsoluteSingularQuery(SingularJsonPathQuery),
+    FunctionExpr(FunctionCall),
+}
+
+#[derive(Clone, Debug)]
+pub enum TestExpr {
+    Query(FilterQuery),
+    FunctionExpr(FunctionCall),
+}
+
+#[derive(Clone, Debug)]
+pub enum FilterQuery {
+    Relative(JsonPathQuery),
+    Absolute(JsonPathQuery),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FunctionType {
+    Value,
+    Logical,
+    Nodes,
+}
+
+struct FunctionSignature {
+    name: &'static str,
+    parameters: &'static [FunctionType],
+    result: FunctionType,
+}
+
+const FUNCTIONS: &[FunctionSignature] = &[
+    FunctionSignature {
+        name: "length",
+        parameters: &[FunctionType::Value],
+        result: FunctionType::Value,
+    },
+    FunctionSignature {
+        name: "count",
+        parameters: &[FunctionType::Nodes],
+        result: FunctionType::Value,
+    },
+    FunctionSignature {
+        name: "value",
+        parameters: &[FunctionType::Nodes],
+        result: FunctionType::Value,
+    },
+    FunctionSignature {
+        name: "match",
+        parameters: &[FunctionType::Value, FunctionType::Value],
+        result: FunctionType::Logical,
+    },
+    FunctionSignature {
+        name: "search",
+        parameters: &[FunctionType::Value, FunctionType::Value],
+        result: FunctionType::Logical,
+    },
+];
+
+#[derive(Clone, Debug)]
+pub struct FunctionCall {
+    pub name: &'static str,
+    pub args: Vec<FunctionArgument>,
+    /// Pattern of `match` or `search`, if it is a string. Patterns are compiled into the
+    /// generated code, so they have to be given as literals.
+    pub regexp: Option<IRegexp>,
+    result: FunctionType,
+}
+
+#[derive(Clone, Debug)]
+pub enum FunctionArgument {
+    Value(Comparable),
+    Nodes(FilterQuery),
+}
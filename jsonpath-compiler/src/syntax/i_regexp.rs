@@ -0,0 +1,353 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::compiler::CompilationError;
+
+const MAX_CODE_POINT: u32 = 0x10FFFF;
+
+/// Patterns are compiled into automata in the generated code, so large counted repetitions,
+/// such as `a{100000}`, are rejected instead of bloating it.
+const MAX_STATE_COUNT: u64 = 10_000;
+
+/// Regular expression in the I-Regexp format (RFC 9485), as used by the `match` and `search`
+/// function extensions.
+#[derive(Clone, Debug)]
+pub enum IRegexp {
+    Alternation(Vec<IRegexp>),
+    Concatenation(Vec<IRegexp>),
+    Repetition {
+        expr: Box<IRegexp>,
+        min: u32,
+        max: Option<u32>,
+    },
+    Chars(CharSet),
+}
+
+/// Set of code points, stored as sorted, non-overlapping and non-adjacent inclusive ranges.
+#[derive(Clone, Debug)]
+pub struct CharSet {
+    pub ranges: Vec<(u32, u32)>,
+}
+
+impl CharSet {
+    fn new(mut ranges: Vec<(u32, u32)>) -> CharSet {
+        ranges.sort();
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some((_, last_end)) if start <= *last_end + 1 => *last_end = (*last_end).max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        CharSet { ranges: merged }
+    }
+
+    fn single(c: char) -> CharSet {
+        CharSet::new(vec![(c as u32, c as u32)])
+    }
+
+    fn union(sets: impl IntoIterator<Item = CharSet>) -> CharSet {
+        CharSet::new(sets.into_iter().flat_map(|set| set.ranges).collect())
+    }
+
+    fn complement(&self) -> CharSet {
+        let mut ranges = Vec::with_capacity(self.ranges.len() + 1);
+        let mut next_start = 0;
+        for &(start, end) in &self.ranges {
+            if start > next_start {
+                ranges.push((next_start, start - 1));
+            }
+            next_start = end + 1;
+        }
+        if next_start <= MAX_CODE_POINT {
+            ranges.push((next_start, MAX_CODE_POINT));
+        }
+        CharSet { ranges }
+    }
+
+    fn category(name: &str) -> CharSet {
+        let hir = regex_syntax::parse(&format!(r"\p{{{name}}}")).expect("valid general category");
+        match hir.kind() {
+            regex_syntax::hir::HirKind::Class(regex_syntax::hir::Class::Unicode(class)) => CharSet::new(
+                class
+                    .ranges()
+                    .iter()
+                    .map(|range| (range.start() as u32, range.end() as u32))
+                    .collect(),
+            ),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl IRegexp {
+    pub fn parse(pattern: &str) -> Result<IRegexp, CompilationError> {
+        let mut parser = IRegexpParser {
+            chars: pattern.chars().peekable(),
+        };
+        let regexp = parser.parse_regexp();
+        let regexp = match parser.chars.next() {
+            Some(c) => regexp.and(Err(format!("unexpected `{c}`"))),
+            None => regexp,
+        };
+        let regexp = regexp.and_then(|regexp| {
+            if regexp.state_count() > MAX_STATE_COUNT {
+                Err("pattern is too large".to_string())
+            } else {
+                Ok(regexp)
+            }
+        });
+        regexp.map_err(|message| CompilationError::InvalidIRegexp {
+            pattern: pattern.to_string(),
+            message,
+        })
+    }
+
+    /// Returns the number of automaton states needed to match the expression.
+    fn state_count(&self) -> u64 {
+        match self {
+            IRegexp::Alternation(branches) => branches
+                .iter()
+                .fold(branches.len() as u64 - 1, |count, branch| count.saturating_add(branch.state_count())),
+            IRegexp::Concatenation(pieces) => pieces
+                .iter()
+                .fold(0, |count: u64, piece| count.saturating_add(piece.state_count())),
+            IRegexp::Repetition { expr, min, max } => {
+                let optional_count = max.map_or(1, |max| u64::from(max - min));
+                let expr_count = expr.state_count();
+                expr_count
+                    .saturating_mul(u64::from(*min))
+                    .saturating_add(expr_count.saturating_add(1).saturating_mul(optional_count))
+            }
+            IRegexp::Chars(_) => 1,
+        }
+    }
+}
+
+struct IRegexpParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+type ParseResult<T> = Result<T, String>;
+
+impl IRegexpParser<'_> {
+    fn parse_regexp(&mut self) -> ParseResult<IRegexp> {
+        let mut branches = vec![self.parse_branch()?];
+        while self.chars.next_if_eq(&'|').is_some() {
+            branches.push(self.parse_branch()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            IRegexp::Alternation(branches)
+        })
+    }
+
+    fn parse_branch(&mut self) -> ParseResult<IRegexp> {
+        let mut pieces = Vec::new();
+        while !matches!(self.chars.peek(), None | Some('|') | Some(')')) {
+            pieces.push(self.parse_piece()?);
+        }
+        Ok(if pieces.len() == 1 {
+            pieces.pop().unwrap()
+        } else {
+            IRegexp::Concatenation(pieces)
+        })
+    }
+
+    fn parse_piece(&mut self) -> ParseResult<IRegexp> {
+        let atom = self.parse_atom()?;
+        let (min, max) = match self.chars.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.chars.next();
+                return self.parse_range_quantifier(atom);
+            }
+            _ => return Ok(atom),
+        };
+        self.chars.next();
+        Ok(IRegexp::Repetition {
+            expr: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    fn parse_range_quantifier(&mut self, atom: IRegexp) -> ParseResult<IRegexp> {
+        let min = self.parse_quantity()?;
+        let max = if self.chars.next_if_eq(&',').is_some() {
+            if self.chars.peek() == Some(&'}') {
+                None
+            } else {
+                Some(self.parse_quantity()?)
+            }
+        } else {
+            Some(min)
+        };
+        self.expect('}')?;
+        if max.is_some_and(|max| max < min) {
+            return Err(format!("invalid quantifier `{{{min},{}}}`", max.unwrap()));
+        }
+        Ok(IRegexp::Repetition {
+            expr: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    fn parse_quantity(&mut self) -> ParseResult<u32> {
+        let mut digits = String::new();
+        while let Some(digit) = self.chars.next_if(char::is_ascii_digit) {
+            digits.push(digit);
+        }
+        if digits.is_empty() {
+            return Err("expected a number in a quantifier".to_string());
+        }
+        digits
+            .parse()
+            .map_err(|_| format!("quantifier `{digits}` is too large"))
+    }
+
+    fn parse_atom(&mut self) -> ParseResult<IRegexp> {
+        let c = self.chars.next().expect("atom is not empty");
+        match c {
+            '(' => {
+                let regexp = self.parse_regexp()?;
+                self.expect(')')?;
+                Ok(regexp)
+            }
+            '.' => Ok(IRegexp::Chars(CharSet::new(vec![('\n' as u32, '\n' as u32), ('\r' as u32, '\r' as u32)]).complement())),
+            '[' => Ok(IRegexp::Chars(self.parse_char_class_expr()?)),
+            '\\' => Ok(IRegexp::Chars(self.parse_escape()?)),
+            ')' | '*' | '+' | '?' | ']' | '{' | '|' | '}' => Err(format!("unexpected `{c}`")),
+            c => Ok(IRegexp::Chars(CharSet::single(c))),
+        }
+    }
+
+    /// Parses an escape sequence following a backslash, either a single character escape
+    /// or a character class escape.
+    fn parse_escape(&mut self) -> ParseResult<CharSet> {
+        match self.chars.next() {
+            Some(c @ ('p' | 'P')) => {
+                let category = self.parse_category()?;
+                Ok(if c == 'p' { category } else { category.complement() })
+            }
+            Some(c) => Self::single_char_escape(c).map(CharSet::single),
+            None => Err("unterminated escape sequence".to_string()),
+        }
+    }
+
+    fn single_char_escape(c: char) -> ParseResult<char> {
+        match c {
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            '(' | ')' | '*' | '+' | '-' | '.' | '?' | '[' | '\\' | ']' | '^' | '{' | '|' | '}' => Ok(c),
+            c => Err(format!("invalid escape sequence `\\{c}`")),
+        }
+    }
+
+    fn parse_category(&mut self) -> ParseResult<CharSet> {
+        self.expect('{')?;
+        let mut name = String::new();
+        while let Some(c) = self.chars.next_if(|c| *c != '}') {
+            name.push(c);
+        }
+        self.expect('}')?;
+        let is_valid = matches!(
+            name.as_bytes(),
+            [b'L' | b'M' | b'N' | b'P' | b'Z' | b'S' | b'C']
+                | [b'L', b'l' | b'm' | b'o' | b't' | b'u']
+                | [b'M', b'c' | b'e' | b'n']
+                | [b'N', b'd' | b'l' | b'o']
+                | [b'P', b'c' | b'd' | b'e' | b'f' | b'i' | b'o' | b's']
+                | [b'Z', b'l' | b'p' | b's']
+                | [b'S', b'c' | b'k' | b'm' | b'o']
+                | [b'C', b'c' | b'f' | b'n' | b'o']
+        );
+        if !is_valid {
+            return Err(format!("unknown character category `{name}`"));
+        }
+        Ok(CharSet::category(&name))
+    }
+
+    fn parse_char_class_expr(&mut self) -> ParseResult<CharSet> {
+        let is_negated = self.chars.next_if_eq(&'^').is_some();
+        let mut sets = Vec::new();
+        if self.chars.next_if_eq(&'-').is_some() {
+            sets.push(CharSet::single('-'));
+        } else {
+            sets.push(self.parse_char_class_item()?);
+        }
+        loop {
+            match self.chars.peek() {
+                Some(']') => break,
+                Some('-') => {
+                    self.chars.next();
+                    if self.chars.peek() != Some(&']') {
+                        return Err("unexpected `-` in a character class".to_string());
+                    }
+                    sets.push(CharSet::single('-'));
+                }
+                Some(_) => sets.push(self.parse_char_class_item()?),
+                None => return Err("unterminated character class".to_string()),
+            }
+        }
+        self.chars.next();
+        let set = CharSet::union(sets);
+        Ok(if is_negated { set.complement() } else { set })
+    }
+
+    fn parse_char_class_item(&mut self) -> ParseResult<CharSet> {
+        if self.chars.next_if_eq(&'\\').is_some() {
+            if let Some(c @ ('p' | 'P')) = self.chars.peek().copied() {
+                self.chars.next();
+                let category = self.parse_category()?;
+                return Ok(if c == 'p' { category } else { category.complement() });
+            }
+            let start = self.parse_char_class_char_after_backslash()?;
+            return self.parse_char_range(start);
+        }
+        let start = self.parse_char_class_char()?;
+        self.parse_char_range(start)
+    }
+
+    fn parse_char_range(&mut self, start: char) -> ParseResult<CharSet> {
+        let mut lookahead = self.chars.clone();
+        if lookahead.next() != Some('-') || matches!(lookahead.next(), None | Some(']')) {
+            return Ok(CharSet::single(start));
+        }
+        self.chars.next();
+        let end = self.parse_char_class_char()?;
+        if end < start {
+            return Err(format!("invalid character range `{start}-{end}`"));
+        }
+        Ok(CharSet::new(vec![(start as u32, end as u32)]))
+    }
+
+    fn parse_char_class_char(&mut self) -> ParseResult<char> {
+        match self.chars.next() {
+            Some('\\') => self.parse_char_class_char_after_backslash(),
+            Some(c @ ('-' | '[' | ']')) => Err(format!("unexpected `{c}` in a character class")),
+            Some(c) => Ok(c),
+            None => Err("unterminated character class".to_string()),
+        }
+    }
+
+    fn parse_char_class_char_after_backslash(&mut self) -> ParseResult<char> {
+        match self.chars.next() {
+            Some(c) => Self::single_char_escape(c),
+            None => Err("unterminated escape sequence".to_string()),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> ParseResult<()> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{expected}`, found `{c}`")),
+            None => Err(format!("expected `{expected}`")),
+        }
+    }
+}
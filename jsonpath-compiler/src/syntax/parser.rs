@@ -0,0 +1,568 @@
+use std::str::FromStr;
+
+use rsonpath_syntax::{ComparisonOp, Index, Literal, SingularJsonPathQuery, SingularSegment, Slice, Step};
+use rsonpath_syntax::num::{JsonFloat, JsonInt, JsonNumber};
+use rsonpath_syntax::str::JsonString;
+
+use crate::compiler::CompilationError;
+use crate::syntax::{Comparable, ComparisonExpr, FilterQuery, FunctionArgument, FunctionCall, FUNCTIONS,
+                    FunctionType, JsonPathQuery, LogicalExpr, Segment, Selector, TestExpr};
+use crate::syntax::i_regexp::IRegexp;
+
+const WHITESPACE: [char; 4] = [' ', '\t', '\n', '\r'];
+
+/// Parses a query following the grammar of RFC 9535. Function expressions are type-checked
+/// where they appear (section 2.4.3).
+pub fn parse(query: &str) -> Result<JsonPathQuery, CompilationError> {
+    let mut parser = Parser { query, position: 0 };
+    parser.expect("$")?;
+    let query_syntax = parser.parse_segments()?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected("a segment"));
+    }
+    Ok(query_syntax)
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    position: usize,
+}
+
+/// Expression which is a comparable, a test expression or a function argument, depending on
+/// what follows it.
+enum Operand {
+    Literal(Literal),
+    Query(FilterQuery),
+    FunctionExpr(FunctionCall),
+}
+
+enum FunctionArgumentSyntax {
+    Operand(Operand),
+    LogicalExpr,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_segments(&mut self) -> Result<JsonPathQuery, CompilationError> {
+        let mut segments = Vec::new();
+        loop {
+            let position = self.position;
+            self.skip_whitespace();
+            if self.eat("..") {
+                if self.peek() == Some('[') {
+                    segments.push(Segment::Descendant(self.parse_bracketed_selection()?));
+                } else {
+                    segments.push(Segment::Descendant(vec![self.parse_shorthand_selector()?]));
+                }
+            } else if self.eat(".") {
+                segments.push(Segment::Child(vec![self.parse_shorthand_selector()?]));
+            } else if self.peek() == Some('[') {
+                segments.push(Segment::Child(self.parse_bracketed_selection()?));
+            } else {
+                self.position = position;
+                return Ok(segments.into_iter().collect());
+            }
+        }
+    }
+
+    fn parse_shorthand_selector(&mut self) -> Result<Selector, CompilationError> {
+        if self.eat("*") {
+            return Ok(Selector::Wildcard);
+        }
+        if !self.peek().is_some_and(is_name_first) {
+            return Err(self.unexpected("a member name or `*`"));
+        }
+        let name = self.eat_while(|c| is_name_first(c) || c.is_ascii_digit());
+        Ok(Selector::Name(JsonString::new(name)))
+    }
+
+    fn parse_bracketed_selection(&mut self) -> Result<Vec<Selector>, CompilationError> {
+        self.expect("[")?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.parse_selector()?);
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(selectors);
+            }
+            if !self.eat(",") {
+                return Err(self.unexpected("`,` or `]`"));
+            }
+        }
+    }
+
+    fn parse_selector(&mut self) -> Result<Selector, CompilationError> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.parse_string_literal()?)),
+            Some('*') => {
+                self.position += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.position += 1;
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.parse_logical_expr()?))
+            }
+            Some('-' | '0'..='9' | ':') => self.parse_index_or_slice_selector(),
+            _ => Err(self.unexpected("a selector")),
+        }
+    }
+
+    fn parse_index_or_slice_selector(&mut self) -> Result<Selector, CompilationError> {
+        let start = self.parse_int()?;
+        if !self.eat_after_whitespace(":") {
+            return match start {
+                Some(index) => Ok(Selector::Index(Index::from(index))),
+                None => Err(self.unexpected("an index or a slice")),
+            };
+        }
+        self.skip_whitespace();
+        let end = self.parse_int()?;
+        let step = if self.eat_after_whitespace(":") {
+            self.skip_whitespace();
+            self.parse_int()?
+        } else {
+            None
+        };
+        let step = Step::from(step.unwrap_or(JsonInt::ONE));
+        // Without the start, a slice with a negative step starts at the last element.
+        let default_start = if step.is_backward() { JsonInt::ONE.neg() } else { JsonInt::ZERO };
+        Ok(Selector::Slice(Slice::new(
+            Index::from(start.unwrap_or(default_start)),
+            end.map(Index::from),
+            step,
+        )))
+    }
+
+    /// Parses an integer in the I-JSON range, if the text at the current position starts
+    /// like one.
+    fn parse_int(&mut self) -> Result<Option<JsonInt>, CompilationError> {
+        if !matches!(self.peek(), Some('-' | '0'..='9')) {
+            return Ok(None);
+        }
+        let start = self.position;
+        self.eat("-");
+        let digits = self.eat_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return Err(self.unexpected("a digit"));
+        }
+        let text = &self.query[start..self.position];
+        if text == "-0" || (digits.len() > 1 && digits.starts_with('0')) {
+            return Err(self.error_at(start, format!("invalid integer `{text}`")));
+        }
+        JsonInt::from_str(text)
+            .map(Some)
+            .map_err(|_| self.error_at(start, format!("integer `{text}` is out of range")))
+    }
+
+    fn parse_number(&mut self) -> Result<JsonNumber, CompilationError> {
+        let start = self.position;
+        self.eat("-");
+        let digits = self.eat_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return Err(self.unexpected("a digit"));
+        }
+        if digits.len() > 1 && digits.starts_with('0') {
+            return Err(self.error_at(start, "number with leading zeros".to_string()));
+        }
+        if self.eat(".") && self.eat_while(|c| c.is_ascii_digit()).is_empty() {
+            return Err(self.unexpected("a digit"));
+        }
+        if self.eat("e") || self.eat("E") {
+            let _ = self.eat("+") || self.eat("-");
+            if self.eat_while(|c| c.is_ascii_digit()).is_empty() {
+                return Err(self.unexpected("a digit"));
+            }
+        }
+        let text = &self.query[start..self.position];
+        JsonFloat::from_str(text)
+            .map(|number| JsonNumber::from(number).normalize())
+            .map_err(|_| self.error_at(start, format!("number `{text}` is out of range")))
+    }
+
+    fn parse_string_literal(&mut self) -> Result<JsonString, CompilationError> {
+        let quote = self.next_char();
+        let mut string = String::new();
+        loop {
+            let position = self.position;
+            match self.next_char() {
+                None => return Err(self.unexpected(&format!("`{}`", quote.unwrap_or('\'')))),
+                Some(c) if Some(c) == quote => return Ok(JsonString::new(&string)),
+                Some('\\') => string.push(self.parse_escape_sequence(quote, position)?),
+                Some(c) if c < '\u{20}' => {
+                    return Err(self.error_at(position, "unescaped control character in a string".to_string()));
+                }
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn parse_escape_sequence(&mut self, quote: Option<char>, start: usize) -> Result<char, CompilationError> {
+        match self.next_char() {
+            Some('b') => Ok('\u{8}'),
+            Some('f') => Ok('\u{c}'),
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some(c @ ('/' | '\\')) => Ok(c),
+            Some(c) if Some(c) == quote => Ok(c),
+            Some('u') => {
+                let code_point = match self.parse_hex_digits(start)? {
+                    high @ 0xD800..=0xDBFF => {
+                        let low = if self.eat("\\u") { self.parse_hex_digits(start)? } else { 0 };
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(self.error_at(start, "unpaired surrogate in a string".to_string()));
+                        }
+                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => {
+                        return Err(self.error_at(start, "unpaired surrogate in a string".to_string()));
+                    }
+                    code_point => code_point,
+                };
+                Ok(char::from_u32(code_point).expect("surrogates are paired"))
+            }
+            _ => Err(self.error_at(start, "invalid escape sequence".to_string())),
+        }
+    }
+
+    fn parse_hex_digits(&mut self, start: usize) -> Result<u32, CompilationError> {
+        match self.rest().get(..4) {
+            Some(digits) if digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+                self.position += 4;
+                Ok(u32::from_str_radix(digits, 16).expect("digits are hexadecimal"))
+            }
+            _ => Err(self.error_at(start, "invalid escape sequence".to_string())),
+        }
+    }
+
+    fn parse_logical_expr(&mut self) -> Result<LogicalExpr, CompilationError> {
+        let mut expr = self.parse_logical_and_expr()?;
+        while self.eat_after_whitespace("||") {
+            self.skip_whitespace();
+            expr = LogicalExpr::Or(Box::new(expr), Box::new(self.parse_logical_and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_logical_and_expr(&mut self) -> Result<LogicalExpr, CompilationError> {
+        let mut expr = self.parse_basic_expr()?;
+        while self.eat_after_whitespace("&&") {
+            self.skip_whitespace();
+            expr = LogicalExpr::And(Box::new(expr), Box::new(self.parse_basic_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_basic_expr(&mut self) -> Result<LogicalExpr, CompilationError> {
+        if self.eat("!") {
+            self.skip_whitespace();
+            let expr = if self.peek() == Some('(') {
+                self.parse_paren_expr()?
+            } else {
+                let position = self.position;
+                let operand = self.parse_operand()?;
+                LogicalExpr::Test(self.to_test_expr(operand, position)?)
+            };
+            return Ok(LogicalExpr::Not(Box::new(expr)));
+        }
+        if self.peek() == Some('(') {
+            return self.parse_paren_expr();
+        }
+        let lhs_position = self.position;
+        let lhs = self.parse_operand()?;
+        let lhs_end = self.position;
+        self.skip_whitespace();
+        let Some(op) = self.parse_comparison_op() else {
+            self.position = lhs_end;
+            return Ok(LogicalExpr::Test(self.to_test_expr(lhs, lhs_position)?));
+        };
+        self.skip_whitespace();
+        let rhs_position = self.position;
+        let rhs = self.parse_operand()?;
+        Ok(LogicalExpr::Comparison(ComparisonExpr {
+            lhs: self.to_comparable(lhs, lhs_position)?,
+            op,
+            rhs: self.to_comparable(rhs, rhs_position)?,
+        }))
+    }
+
+    fn parse_paren_expr(&mut self) -> Result<LogicalExpr, CompilationError> {
+        self.expect("(")?;
+        self.skip_whitespace();
+        let expr = self.parse_logical_expr()?;
+        self.skip_whitespace();
+        self.expect(")")?;
+        Ok(expr)
+    }
+
+    fn parse_comparison_op(&mut self) -> Option<ComparisonOp> {
+        let ops = [
+            ("==", ComparisonOp::EqualTo),
+            ("!=", ComparisonOp::NotEqualTo),
+            ("<=", ComparisonOp::LesserOrEqualTo),
+            (">=", ComparisonOp::GreaterOrEqualTo),
+            ("<", ComparisonOp::LessThan),
+            (">", ComparisonOp::GreaterThan),
+        ];
+        ops.into_iter().find(|(text, _)| self.eat(text)).map(|(_, op)| op)
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, CompilationError> {
+        match self.peek() {
+            Some('@') => {
+                self.position += 1;
+                Ok(Operand::Query(FilterQuery::Relative(self.parse_segments()?)))
+            }
+            Some('$') => {
+                self.position += 1;
+                Ok(Operand::Query(FilterQuery::Absolute(self.parse_segments()?)))
+            }
+            Some('\'' | '"') => Ok(Operand::Literal(Literal::String(self.parse_string_literal()?))),
+            Some('-' | '0'..='9') => Ok(Operand::Literal(Literal::Number(self.parse_number()?))),
+            Some('a'..='z') => {
+                let start = self.position;
+                let name = self.eat_while(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+                match name {
+                    _ if self.peek() == Some('(') => {
+                        Ok(Operand::FunctionExpr(self.parse_function_expr(name, start)?))
+                    }
+                    "true" => Ok(Operand::Literal(Literal::Bool(true))),
+                    "false" => Ok(Operand::Literal(Literal::Bool(false))),
+                    "null" => Ok(Operand::Literal(Literal::Null)),
+                    _ => Err(self.error_at(start, format!("unexpected `{name}`"))),
+                }
+            }
+            _ => Err(self.unexpected("a query, a literal or a function expression")),
+        }
+    }
+
+    fn to_comparable(&self, operand: Operand, position: usize) -> Result<Comparable, CompilationError> {
+        match operand {
+            Operand::Literal(literal) => Ok(Comparable::Literal(literal)),
+            Operand::Query(query) => to_singular_query(&query).ok_or_else(|| {
+                self.error_at(position, "a query compared with a value has to be singular".to_string())
+            }),
+            Operand::FunctionExpr(function_call) if function_call.result != FunctionType::Value => {
+                Err(CompilationError::IllTypedFunctionExpression(format!(
+                    "result of `{}` is of type {:?}Type and cannot be compared",
+                    function_call.name, function_call.result
+                )))
+            }
+            Operand::FunctionExpr(function_call) => Ok(Comparable::FunctionExpr(function_call)),
+        }
+    }
+
+    fn to_test_expr(&self, operand: Operand, position: usize) -> Result<TestExpr, CompilationError> {
+        match operand {
+            Operand::Literal(_) => Err(self.error_at(position, "a literal has to be compared with a value".to_string())),
+            Operand::Query(query) => Ok(TestExpr::Query(query)),
+            Operand::FunctionExpr(function_call) if function_call.result == FunctionType::Value => {
+                Err(CompilationError::IllTypedFunctionExpression(format!(
+                    "result of `{}` is of type ValueType and cannot be used as a test expression",
+                    function_call.name
+                )))
+            }
+            Operand::FunctionExpr(function_call) => Ok(TestExpr::FunctionExpr(function_call)),
+        }
+    }
+
+    /// Parses the arguments of a function expression and checks them against the parameters
+    /// of the function. `start` is the position of the function name.
+    fn parse_function_expr(&mut self, name: &str, start: usize) -> Result<FunctionCall, CompilationError> {
+        let signature = FUNCTIONS
+            .iter()
+            .find(|signature| signature.name == name)
+            .ok_or_else(|| CompilationError::InvalidFunctionExpression(format!("unknown function `{name}`")))?;
+        self.expect("(")?;
+        self.skip_whitespace();
+        let mut args = Vec::new();
+        if !self.eat(")") {
+            loop {
+                args.push(self.parse_function_argument()?);
+                self.skip_whitespace();
+                if self.eat(")") {
+                    break;
+                }
+                if !self.eat(",") {
+                    return Err(self.unexpected("`,` or `)`"));
+                }
+                self.skip_whitespace();
+            }
+        }
+        if args.len() != signature.parameters.len() {
+            return Err(CompilationError::InvalidFunctionExpression(format!(
+                "function `{name}` expects {} argument(s), got {}",
+                signature.parameters.len(),
+                args.len()
+            )));
+        }
+        let args = args
+            .into_iter()
+            .zip(signature.parameters)
+            .enumerate()
+            .map(|(i, (arg, parameter_type))| {
+                to_function_argument(arg, *parameter_type).ok_or_else(|| {
+                    CompilationError::IllTypedFunctionExpression(format!(
+                        "argument {} of `{name}` is not of type {parameter_type:?}Type",
+                        i + 1
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let regexp = match signature.name {
+            "match" | "search" => self.parse_regexp(signature.name, &args[1], start)?,
+            _ => None,
+        };
+        Ok(FunctionCall {
+            name: signature.name,
+            args,
+            regexp,
+            result: signature.result,
+        })
+    }
+
+    /// Parses an argument as a logical expression only if it is not a literal, a query or a
+    /// function expression, to report the syntax errors in it.
+    fn parse_function_argument(&mut self) -> Result<FunctionArgumentSyntax, CompilationError> {
+        let position = self.position;
+        if !matches!(self.peek(), Some('!' | '(')) {
+            let operand = self.parse_operand()?;
+            if self.rest().trim_start_matches(WHITESPACE).starts_with([',', ')']) {
+                return Ok(FunctionArgumentSyntax::Operand(operand));
+            }
+            self.position = position;
+        }
+        self.parse_logical_expr()?;
+        Ok(FunctionArgumentSyntax::LogicalExpr)
+    }
+
+    /// Patterns are compiled into the generated code, so they have to be given as literals.
+    fn parse_regexp(
+        &self,
+        name: &str,
+        pattern: &FunctionArgument,
+        start: usize,
+    ) -> Result<Option<IRegexp>, CompilationError> {
+        match pattern {
+            FunctionArgument::Value(Comparable::Literal(Literal::String(pattern))) => {
+                IRegexp::parse(pattern.unquoted()).map(Some)
+            }
+            FunctionArgument::Value(Comparable::Literal(_)) => Ok(None),
+            _ => Err(CompilationError::UnsupportedFeature {
+                query_name: String::new(),
+                feature: format!("pattern of `{name}` which is not a literal"),
+                fragment: self.query[start..self.position].to_string(),
+                span: start..self.position,
+            }),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.query[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the token if it follows the whitespace at the current position, or nothing.
+    fn eat_after_whitespace(&mut self, token: &str) -> bool {
+        let position = self.position;
+        self.skip_whitespace();
+        if self.eat(token) {
+            true
+        } else {
+            self.position = position;
+            false
+        }
+    }
+
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let length = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.eat_while(|c| WHITESPACE.contains(&c));
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), CompilationError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{token}`")))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> CompilationError {
+        let found = match self.peek() {
+            Some(c) => format!("`{c}`"),
+            None => "the end of the query".to_string(),
+        };
+        self.error_at(self.position, format!("expected {expected}, found {found}"))
+    }
+
+    fn error_at(&self, position: usize, message: String) -> CompilationError {
+        CompilationError::ParseError { message, position }
+    }
+}
+
+fn to_singular_query(query: &FilterQuery) -> Option<Comparable> {
+    let (FilterQuery::Relative(query_syntax) | FilterQuery::Absolute(query_syntax)) = query;
+    let segments = query_syntax
+        .segments()
+        .iter()
+        .map(|segment| match (segment, segment.selectors()) {
+            (Segment::Child(_), [Selector::Name(name)]) => Some(SingularSegment::Name(name.clone())),
+            (Segment::Child(_), [Selector::Index(index)]) => Some(SingularSegment::Index(*index)),
+            _ => None,
+        })
+        .collect::<Option<SingularJsonPathQuery>>()?;
+    match query {
+        FilterQuery::Relative(_) => Some(Comparable::RelativeSingularQuery(segments)),
+        FilterQuery::Absolute(_) => Some(Comparable::AbsoluteSingularQuery(segments)),
+    }
+}
+
+/// Converts the argument to the declared type of the parameter, returning `None` if the
+/// argument is not well-typed.
+fn to_function_argument(arg: FunctionArgumentSyntax, parameter_type: FunctionType) -> Option<FunctionArgument> {
+    let FunctionArgumentSyntax::Operand(operand) = arg else {
+        return None;
+    };
+    match (parameter_type, operand) {
+        (FunctionType::Value, Operand::Literal(literal)) => {
+            Some(FunctionArgument::Value(Comparable::Literal(literal)))
+        }
+        (FunctionType::Value, Operand::Query(query)) => to_singular_query(&query).map(FunctionArgument::Value),
+        (FunctionType::Value, Operand::FunctionExpr(function_call)) if function_call.result == FunctionType::Value => {
+            Some(FunctionArgument::Value(Comparable::FunctionExpr(function_call)))
+        }
+        (FunctionType::Nodes, Operand::Query(query)) => Some(FunctionArgument::Nodes(query)),
+        (FunctionType::Logical, _) => unreachable!("no function takes a LogicalType parameter"),
+        _ => None,
+    }
+}
+
+/// Whether the character can start a member name shorthand, such as `$.name`.
+fn is_name_first(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c >= '\u{80}'
+}
@@ -1,6 +1,9 @@
-use crate::ir::Query;
-use crate::NamedQuery;
+use crate::compiler::CompilationError;
+use crate::ir::{Instruction, Query, Slice};
+use crate::ir::Instruction::{ExecuteProcedureOnChildWithActiveSegments, ForEachElement, ForEachElementInReverseOrder, ForEachElementInSlices, ForEachMember, IfActiveFilterInstance, IfCurrentIndexEquals, IfCurrentIndexFromEndEquals, IfCurrentIndexInSlice, IfCurrentMemberNameEquals, IfSegmentActive};
+use crate::{NamedQuery, NodePathFormat};
 
+pub mod rust;
 pub mod simdjson;
 
 pub trait TargetCodeGenerator {
@@ -14,7 +17,27 @@ pub trait TargetCodeGenerator {
         self.base().eager_filter_evaluation
     }
 
-    fn generate(&self) -> String;
+    fn input_format(&self) -> InputFormat {
+        self.base().input_format
+    }
+
+    fn generate(&self) -> Result<String, CompilationError>;
+
+    /// Whether the target can return the paths of the selected nodes together with the nodes.
+    fn are_node_paths_supported() -> bool {
+        false
+    }
+
+    /// Whether the target can execute the queries on every document of an NDJSON input.
+    fn is_ndjson_input_supported() -> bool {
+        false
+    }
+
+    /// Whether the target can visit the array elements in any order, as needed to select them
+    /// in the order of the selectors.
+    fn is_random_access_supported() -> bool {
+        false
+    }
 }
 
 pub trait TargetCodeStandaloneProgGenerator: TargetCodeGenerator {
@@ -23,6 +46,8 @@ pub trait TargetCodeStandaloneProgGenerator: TargetCodeGenerator {
         logging: bool,
         mmap: bool,
         eager_filter_evaluation: bool,
+        node_paths: Option<NodePathFormat>,
+        input_format: InputFormat,
     ) -> impl TargetCodeStandaloneProgGenerator;
 
     fn base(&self) -> &TargetCodeStandaloneProgGeneratorBase;
@@ -34,6 +59,10 @@ pub trait TargetCodeStandaloneProgGenerator: TargetCodeGenerator {
     fn query(&self) -> &Query {
         &TargetCodeStandaloneProgGenerator::base(self).query
     }
+
+    fn node_paths(&self) -> Option<NodePathFormat> {
+        TargetCodeStandaloneProgGenerator::base(self).node_paths
+    }
 }
 
 pub trait TargetCodeLibGenerator: TargetCodeGenerator {
@@ -41,8 +70,10 @@ pub trait TargetCodeLibGenerator: TargetCodeGenerator {
         named_queries: Vec<NamedQuery>,
         filename: String,
         logging: bool,
-        bindings: bool,
+        bindings: Option<Bindings>,
         eager_filter_evaluation: bool,
+        results_delivery: ResultsDelivery,
+        input_format: InputFormat,
     ) -> impl TargetCodeLibGenerator;
 
     fn base(&self) -> &TargetCodeLibGeneratorBase;
@@ -55,29 +86,103 @@ pub trait TargetCodeLibGenerator: TargetCodeGenerator {
         &TargetCodeLibGenerator::base(self).filename
     }
 
-    fn bindings(&self) -> bool {
-        TargetCodeLibGenerator::base(self).bindings
+    fn bindings(&self) -> Option<&Bindings> {
+        TargetCodeLibGenerator::base(self).bindings.as_ref()
+    }
+
+    fn results_delivery(&self) -> ResultsDelivery {
+        TargetCodeLibGenerator::base(self).results_delivery
+    }
+
+    /// Whether the target can generate `extern "C"` functions called through bindings.
+    fn are_bindings_supported() -> bool {
+        false
+    }
+
+    /// Whether the target can generate code for queries merged to be executed in a single pass.
+    fn is_single_pass_supported() -> bool {
+        false
+    }
+
+    /// Whether the target can return the results of the queries as spans of the input document.
+    fn are_result_spans_supported() -> bool {
+        false
+    }
+
+    /// Whether the target can pass the results of the queries to a callback during the query
+    /// execution.
+    fn are_result_callbacks_supported() -> bool {
+        false
+    }
+
+    /// Whether the target can execute the queries with a context holding the parser and the
+    /// buffers, created once and reused across the executions.
+    fn are_execution_contexts_supported() -> bool {
+        false
     }
 }
 
 pub trait BindingsGenerator {
-    fn generate(&self, named_queries: &Vec<NamedQuery>) -> Result<(), std::io::Error>;
+    fn generate(
+        &self,
+        named_queries: &[NamedQuery],
+        results_delivery: ResultsDelivery,
+        execution_contexts: bool,
+        symbol_prefix: &str,
+    ) -> Result<(), CompilationError>;
+}
+
+/// Options of the `extern "C"` functions generated for calling the library through bindings.
+#[derive(Clone, Default)]
+pub struct Bindings {
+    /// Prefix of the names of the functions.
+    pub symbol_prefix: String,
+}
+
+/// How the code generated for a library returns the results of the queries, in addition to
+/// returning all of them at the end of the execution.
+#[derive(Clone, Copy, Default)]
+pub struct ResultsDelivery {
+    /// Results are also returned as spans of the input, instead of copies of the selected nodes.
+    pub spans: bool,
+    /// Results are also passed to a callback during the execution.
+    pub callbacks: bool,
+    /// Results are returned together with the paths of the selected nodes, in the given format.
+    pub node_paths: Option<NodePathFormat>,
+}
+
+/// Format of the input on which the generated code executes the queries.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum InputFormat {
+    /// A single JSON document.
+    #[default]
+    Json,
+    /// A JSON document in every line (NDJSON). The queries are executed on every document and
+    /// their results are returned in a line for every document, tagged with its line number if
+    /// `line_numbers` is set. Malformed documents are reported in place of their results.
+    Ndjson { line_numbers: bool },
 }
 
 pub struct TargetCodeGeneratorBase {
     logging: bool,
     eager_filter_evaluation: bool,
+    input_format: InputFormat,
 }
 
 impl TargetCodeGeneratorBase {
-    pub fn new(logging: bool, eager_filter_evaluation: bool) -> TargetCodeGeneratorBase {
-        TargetCodeGeneratorBase { logging, eager_filter_evaluation }
+    pub fn new(
+        logging: bool,
+        eager_filter_evaluation: bool,
+        input_format: InputFormat,
+    ) -> TargetCodeGeneratorBase {
+        TargetCodeGeneratorBase { logging, eager_filter_evaluation, input_format }
     }
 }
 
 pub struct TargetCodeStandaloneProgGeneratorBase {
     base: TargetCodeGeneratorBase,
     mmap: bool,
+    node_paths: Option<NodePathFormat>,
     query: Query,
 }
 
@@ -87,10 +192,13 @@ impl TargetCodeStandaloneProgGeneratorBase {
         logging: bool,
         mmap: bool,
         eager_filter_evaluation: bool,
+        node_paths: Option<NodePathFormat>,
+        input_format: InputFormat,
     ) -> TargetCodeStandaloneProgGeneratorBase {
         TargetCodeStandaloneProgGeneratorBase {
-            base: TargetCodeGeneratorBase::new(logging, eager_filter_evaluation),
+            base: TargetCodeGeneratorBase::new(logging, eager_filter_evaluation, input_format),
             mmap,
+            node_paths,
             query,
         }
     }
@@ -99,7 +207,8 @@ impl TargetCodeStandaloneProgGeneratorBase {
 pub struct TargetCodeLibGeneratorBase {
     base: TargetCodeGeneratorBase,
     filename: String,
-    bindings: bool,
+    bindings: Option<Bindings>,
+    results_delivery: ResultsDelivery,
     queries: Vec<NamedQuery>,
 }
 
@@ -108,14 +217,62 @@ impl TargetCodeLibGeneratorBase {
         named_queries: Vec<NamedQuery>,
         filename: String,
         logging: bool,
-        bindings: bool,
+        bindings: Option<Bindings>,
         eager_filter_evaluation: bool,
+        results_delivery: ResultsDelivery,
+        input_format: InputFormat,
     ) -> TargetCodeLibGeneratorBase {
         TargetCodeLibGeneratorBase {
-            base: TargetCodeGeneratorBase::new(logging, eager_filter_evaluation),
+            base: TargetCodeGeneratorBase::new(logging, eager_filter_evaluation, input_format),
             filename,
             bindings,
+            results_delivery,
             queries: named_queries,
         }
     }
-}
\ No newline at end of file
+}
+
+fn is_array_length_needed(instructions: &Vec<Instruction>) -> bool {
+    for instruction in instructions {
+        let is_needed = match instruction {
+            IfCurrentIndexFromEndEquals { .. } => true,
+            IfCurrentIndexInSlice {
+                slice,
+                instructions,
+            } => is_slice_length_dependent(slice) || is_array_length_needed(instructions),
+            IfCurrentIndexEquals {
+                index: _index,
+                instructions,
+            } => is_array_length_needed(instructions),
+            IfCurrentMemberNameEquals {
+                name: _name,
+                instructions,
+            } => is_array_length_needed(instructions),
+            IfActiveFilterInstance {
+                instructions
+            } => is_array_length_needed(instructions),
+            IfSegmentActive {
+                segment_index: _segment_index,
+                instructions,
+            } => is_array_length_needed(instructions),
+            ExecuteProcedureOnChildWithActiveSegments {
+                instructions,
+                ..
+            } => is_array_length_needed(instructions),
+            ForEachMember { instructions } => is_array_length_needed(instructions),
+            ForEachElement { instructions } => is_array_length_needed(instructions),
+            ForEachElementInReverseOrder { instructions, .. } => is_array_length_needed(instructions),
+            ForEachElementInSlices { .. } => true,
+            _ => false,
+        };
+        if is_needed {
+            return true;
+        }
+    }
+    false
+}
+
+
+fn is_slice_length_dependent(slice: &Slice) -> bool {
+    slice.step < 0 || slice.start < 0 || slice.end.is_some_and(|end| end < 0)
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use askama::Template;
+
+use crate::compiler::CompilationError;
+use crate::ir::{
+    Comparable, FilterExpression, FilterId, FilterProcedure, FilterSubquery,
+    FilterSubquerySelector, Instruction, LiteralValue, LogicalFunction, Procedure, Query, Regex,
+    RegexState, SelectionCondition, Slice, ValueFunction,
+};
+use crate::{NamedQuery, NodePathFormat};
+use crate::targets::{Bindings, InputFormat, ResultsDelivery, TargetCodeGenerator, TargetCodeGeneratorBase, TargetCodeLibGenerator, TargetCodeLibGeneratorBase, TargetCodeStandaloneProgGenerator, TargetCodeStandaloneProgGeneratorBase};
+
+pub mod value;
+
+#[derive(Template)]
+#[template(path = "rust/standalone.rs", escape = "none")]
+struct RustStandaloneProgTemplate<'a> {
+    procedures: Vec<ProcedureTemplate<'a>>,
+    filter_procedures: Vec<FilterProcedureTemplate<'a>>,
+    filter_subqueries: &'a HashMap<FilterId, Vec<FilterSubquery>>,
+    segments_count: usize,
+}
+
+impl RustStandaloneProgTemplate<'_> {
+    fn new(query: &Query) -> RustStandaloneProgTemplate<'_> {
+        RustStandaloneProgTemplate {
+            procedures: query
+                .procedures
+                .iter()
+                .map(|procedure| {
+                    ProcedureTemplate::new(procedure, "", !query.filter_procedures.is_empty())
+                })
+                .collect(),
+            filter_procedures: query
+                .filter_procedures
+                .values()
+                .map(|filter_procedure| FilterProcedureTemplate::new(filter_procedure, ""))
+                .collect(),
+            filter_subqueries: &query.filter_subqueries,
+            segments_count: query.segments_count,
+        }
+    }
+
+    fn are_any_filters(&self) -> bool {
+        !self.filter_procedures.is_empty()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/lib.rs", escape = "none")]
+struct RustLibTemplate<'a> {
+    procedures: Vec<ProcedureTemplate<'a>>,
+    query_names: Vec<String>,
+    filter_procedures: HashMap<String, Vec<FilterProcedureTemplate<'a>>>,
+    filter_subqueries: HashMap<String, &'a HashMap<FilterId, Vec<FilterSubquery>>>,
+    query_segments_counts: HashMap<String, usize>,
+}
+
+impl RustLibTemplate<'_> {
+    fn new(queries: &Vec<NamedQuery>) -> RustLibTemplate<'_> {
+        let mut procedures = Vec::new();
+        let mut query_names = Vec::new();
+        for (name, query) in queries {
+            for procedure in &query.procedures {
+                procedures.push(ProcedureTemplate::new(
+                    procedure,
+                    name,
+                    !query.filter_procedures.is_empty(),
+                ));
+            }
+            if !query_names.contains(name) {
+                query_names.push(name.to_string());
+            }
+        }
+        RustLibTemplate {
+            procedures,
+            query_names,
+            filter_procedures: queries
+                .iter()
+                .map(|(name, query)| {
+                    (
+                        name.to_string(),
+                        query
+                            .filter_procedures
+                            .values()
+                            .map(|fp| FilterProcedureTemplate::new(fp, name))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            filter_subqueries: queries
+                .iter()
+                .map(|(name, query)| (name.to_string(), &query.filter_subqueries))
+                .collect(),
+            query_segments_counts: queries
+                .iter()
+                .map(|(name, query)| (name.to_string(), query.segments_count))
+                .collect(),
+        }
+    }
+
+    fn are_any_filters(&self) -> bool {
+        self.filter_procedures.values().any(|procedures| !procedures.is_empty())
+    }
+
+    fn are_any_filters_in_query(&self, query_name: &str) -> bool {
+        !self.filter_procedures.get(query_name).unwrap().is_empty()
+    }
+
+    fn query_filter_subqueries(&self, query_name: &str) -> &HashMap<FilterId, Vec<FilterSubquery>> {
+        self.filter_subqueries.get(query_name).unwrap()
+    }
+
+    fn query_segments_count(&self, query_name: &str) -> usize {
+        *self.query_segments_counts.get(query_name).unwrap()
+    }
+
+    fn all_filters_procedures(&self) -> Vec<&FilterProcedureTemplate<'_>> {
+        self.filter_procedures.values().flatten().collect()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/procedure.rs", escape = "none")]
+struct ProcedureTemplate<'a> {
+    name: String,
+    instructions: Vec<InstructionTemplate<'a>>,
+    query_name: &'a str,
+    are_any_filters: bool,
+    are_active_segments_passed: bool,
+}
+
+impl ProcedureTemplate<'_> {
+    fn new<'a>(
+        procedure: &'a Procedure,
+        query_name: &'a str,
+        are_any_filters: bool,
+    ) -> ProcedureTemplate<'a> {
+        ProcedureTemplate {
+            name: format!("{}{}", name_prefix(query_name), procedure.name.to_lowercase()),
+            instructions: procedure
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    InstructionTemplate::new(instruction, "node", "end", query_name, are_any_filters)
+                })
+                .collect(),
+            query_name,
+            are_any_filters,
+            are_active_segments_passed: procedure.are_active_segments_passed,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/instruction.rs", escape = "none")]
+struct InstructionTemplate<'a> {
+    instruction: &'a Instruction,
+    current_node: &'a str,
+    current_node_end: &'a str,
+    query_name: &'a str,
+    are_any_filters: bool,
+}
+
+impl InstructionTemplate<'_> {
+    fn new<'a>(
+        instruction: &'a Instruction,
+        current_node: &'a str,
+        current_node_end: &'a str,
+        query_name: &'a str,
+        are_any_filters: bool,
+    ) -> InstructionTemplate<'a> {
+        InstructionTemplate {
+            instruction,
+            current_node,
+            current_node_end,
+            query_name,
+            are_any_filters,
+        }
+    }
+
+    fn procedure_name(&self, name: &str) -> String {
+        format!("{}{}", name_prefix(self.query_name), name.to_lowercase())
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/selection_condition.rs", escape = "none")]
+struct SelectionConditionTemplate<'a> {
+    condition: &'a SelectionCondition,
+}
+
+impl SelectionConditionTemplate<'_> {
+    fn new(condition: &SelectionCondition) -> SelectionConditionTemplate<'_> {
+        SelectionConditionTemplate { condition }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/filter_procedure.rs", escape = "none")]
+struct FilterProcedureTemplate<'a> {
+    name: String,
+    expression: FilterExpressionTemplate<'a>,
+    regexes: &'a Vec<Regex>,
+}
+
+impl FilterProcedureTemplate<'_> {
+    fn new<'a>(
+        filter_procedure: &'a FilterProcedure,
+        query_name: &'a str,
+    ) -> FilterProcedureTemplate<'a> {
+        FilterProcedureTemplate {
+            name: format!("{}{}", name_prefix(query_name), filter_procedure.name.to_lowercase()),
+            expression: FilterExpressionTemplate::new(&filter_procedure.expression),
+            regexes: &filter_procedure.regexes,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/filter_expression.rs", escape = "none")]
+struct FilterExpressionTemplate<'a> {
+    expression: &'a FilterExpression,
+}
+
+impl FilterExpressionTemplate<'_> {
+    fn new(expression: &FilterExpression) -> FilterExpressionTemplate<'_> {
+        FilterExpressionTemplate { expression }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/comparable.rs", escape = "none")]
+struct ComparableTemplate<'a> {
+    comparable: &'a Comparable,
+}
+
+impl ComparableTemplate<'_> {
+    fn new(comparable: &Comparable) -> ComparableTemplate<'_> {
+        ComparableTemplate { comparable }
+    }
+}
+
+/// Prefix of the names of the functions and statics generated for a query, so that the ones
+/// of different queries in a library do not clash.
+fn name_prefix(query_name: &str) -> String {
+    if query_name.is_empty() {
+        String::new()
+    } else {
+        format!("{query_name}_")
+    }
+}
+
+/// Name of the constant with the number of segments of the query, which is the length of the
+/// segment conditions arrays passed to its procedures.
+fn segment_count_name(query_name: &str) -> String {
+    format!("{}SEGMENT_COUNT", name_prefix(query_name).to_uppercase())
+}
+
+/// Compiles a Rust condition checking whether the element at `index` is selected by the slice.
+fn compile_slice_condition(slice: &Slice) -> String {
+    let bound = |index: i64| {
+        if index >= 0 {
+            format!("{index}i64")
+        } else {
+            format!("(array_length as i64 - {})", -index)
+        }
+    };
+    let mut conditions = Vec::new();
+    if slice.step > 0 {
+        let lower = if slice.start >= 0 {
+            bound(slice.start)
+        } else {
+            format!("{}.max(0)", bound(slice.start))
+        };
+        conditions.push(format!("(index as i64) >= {lower}"));
+        if let Some(end) = slice.end {
+            conditions.push(format!("(index as i64) < {}", bound(end)));
+        }
+        if slice.step > 1 {
+            conditions.push(format!("((index as i64) - {lower}) % {} == 0", slice.step));
+        }
+    } else {
+        let upper = if slice.start >= 0 {
+            format!("{}.min(array_length as i64 - 1)", bound(slice.start))
+        } else {
+            bound(slice.start)
+        };
+        conditions.push(format!("(index as i64) <= {upper}"));
+        if let Some(end) = slice.end {
+            conditions.push(format!("(index as i64) > {}", bound(end)));
+        }
+        if slice.step < -1 {
+            conditions.push(format!("({upper} - (index as i64)) % {} == 0", -slice.step));
+        }
+    }
+    conditions.join(" && ")
+}
+
+/// Compiles a range of the indices of the elements in the slice, in the order in which they
+/// are selected, for an array of `array_length` elements.
+fn compile_slice_indices(slice: &Slice) -> String {
+    let bound = |index: i64| {
+        if index >= 0 {
+            format!("{index}i64")
+        } else {
+            format!("(array_length - {})", -index)
+        }
+    };
+    if slice.step > 0 {
+        let end = match slice.end {
+            Some(end) => format!("{}.clamp(0, array_length)", bound(end)),
+            None => "array_length".to_string(),
+        };
+        format!("({}.clamp(0, array_length)..{end}).step_by({})", bound(slice.start), slice.step)
+    } else {
+        let end = match slice.end {
+            Some(end) => format!("({}.clamp(-1, array_length - 1) + 1)", bound(end)),
+            None => "0".to_string(),
+        };
+        format!(
+            "({end}..={}.clamp(-1, array_length - 1)).rev().step_by({})",
+            bound(slice.start),
+            -slice.step
+        )
+    }
+}
+
+pub struct RustCodeStandaloneProgGenerator {
+    base: TargetCodeStandaloneProgGeneratorBase,
+}
+
+impl TargetCodeGenerator for RustCodeStandaloneProgGenerator {
+    fn base(&self) -> &TargetCodeGeneratorBase {
+        &self.base.base
+    }
+
+    fn generate(&self) -> Result<String, CompilationError> {
+        let template = RustStandaloneProgTemplate::new(self.query());
+        Ok(template.render()?)
+    }
+}
+
+impl TargetCodeStandaloneProgGenerator for RustCodeStandaloneProgGenerator {
+    fn new(
+        query: Query,
+        logging: bool,
+        mmap: bool,
+        eager_filter_evaluation: bool,
+        node_paths: Option<NodePathFormat>,
+        input_format: InputFormat,
+    ) -> impl TargetCodeStandaloneProgGenerator {
+        RustCodeStandaloneProgGenerator {
+            base: TargetCodeStandaloneProgGeneratorBase::new(
+                query,
+                logging,
+                mmap,
+                eager_filter_evaluation,
+                node_paths,
+                input_format,
+            )
+        }
+    }
+
+    fn base(&self) -> &TargetCodeStandaloneProgGeneratorBase {
+        &self.base
+    }
+}
+
+pub struct RustCodeLibGenerator {
+    base: TargetCodeLibGeneratorBase,
+}
+
+impl TargetCodeGenerator for RustCodeLibGenerator {
+    fn base(&self) -> &TargetCodeGeneratorBase {
+        &self.base.base
+    }
+
+    fn generate(&self) -> Result<String, CompilationError> {
+        let template = RustLibTemplate::new(self.queries());
+        Ok(template.render()?)
+    }
+}
+
+impl TargetCodeLibGenerator for RustCodeLibGenerator {
+    fn new(
+        named_queries: Vec<NamedQuery>,
+        filename: String,
+        logging: bool,
+        bindings: Option<Bindings>,
+        eager_filter_evaluation: bool,
+        results_delivery: ResultsDelivery,
+        input_format: InputFormat,
+    ) -> impl TargetCodeLibGenerator {
+        RustCodeLibGenerator {
+            base: TargetCodeLibGeneratorBase::new(
+                named_queries,
+                filename,
+                logging,
+                bindings,
+                eager_filter_evaluation,
+                results_delivery,
+                input_format,
+            )
+        }
+    }
+
+    fn base(&self) -> &TargetCodeLibGeneratorBase {
+        &self.base
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use askama::Template;
+
+use crate::compiler::CompilationError;
+use crate::ir::{FilterId, FilterSubquery, FilterSubquerySelector, Instruction, Procedure, Query};
+use crate::{NamedQuery, NodePathFormat};
+use crate::targets::rust::{name_prefix, FilterProcedureTemplate, SelectionConditionTemplate};
+use crate::targets::{Bindings, InputFormat, ResultsDelivery, TargetCodeGenerator, TargetCodeGeneratorBase, TargetCodeLibGenerator, TargetCodeLibGeneratorBase, TargetCodeStandaloneProgGenerator, TargetCodeStandaloneProgGeneratorBase};
+
+#[derive(Template)]
+#[template(path = "rust/value/standalone.rs", escape = "none")]
+struct ValueStandaloneProgTemplate<'a> {
+    procedures: Vec<ProcedureTemplate<'a>>,
+    filter_procedures: Vec<FilterProcedureTemplate<'a>>,
+    filter_subqueries: &'a HashMap<FilterId, Vec<FilterSubquery>>,
+    segments_count: usize,
+}
+
+impl ValueStandaloneProgTemplate<'_> {
+    fn new(query: &Query) -> ValueStandaloneProgTemplate<'_> {
+        ValueStandaloneProgTemplate {
+            procedures: query
+                .procedures
+                .iter()
+                .map(|procedure| {
+                    ProcedureTemplate::new(procedure, "", !query.filter_procedures.is_empty())
+                })
+                .collect(),
+            filter_procedures: query
+                .filter_procedures
+                .values()
+                .map(|filter_procedure| FilterProcedureTemplate::new(filter_procedure, ""))
+                .collect(),
+            filter_subqueries: &query.filter_subqueries,
+            segments_count: query.segments_count,
+        }
+    }
+
+    fn are_any_filters(&self) -> bool {
+        !self.filter_procedures.is_empty()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/value/lib.rs", escape = "none")]
+struct ValueLibTemplate<'a> {
+    procedures: Vec<ProcedureTemplate<'a>>,
+    query_names: Vec<String>,
+    filter_procedures: HashMap<String, Vec<FilterProcedureTemplate<'a>>>,
+    filter_subqueries: HashMap<String, &'a HashMap<FilterId, Vec<FilterSubquery>>>,
+    query_segments_counts: HashMap<String, usize>,
+}
+
+impl ValueLibTemplate<'_> {
+    fn new(queries: &Vec<NamedQuery>) -> ValueLibTemplate<'_> {
+        let mut procedures = Vec::new();
+        let mut query_names = Vec::new();
+        for (name, query) in queries {
+            for procedure in &query.procedures {
+                procedures.push(ProcedureTemplate::new(
+                    procedure,
+                    name,
+                    !query.filter_procedures.is_empty(),
+                ));
+            }
+            if !query_names.contains(name) {
+                query_names.push(name.to_string());
+            }
+        }
+        ValueLibTemplate {
+            procedures,
+            query_names,
+            filter_procedures: queries
+                .iter()
+                .map(|(name, query)| {
+                    (
+                        name.to_string(),
+                        query
+                            .filter_procedures
+                            .values()
+                            .map(|fp| FilterProcedureTemplate::new(fp, name))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            filter_subqueries: queries
+                .iter()
+                .map(|(name, query)| (name.to_string(), &query.filter_subqueries))
+                .collect(),
+            query_segments_counts: queries
+                .iter()
+                .map(|(name, query)| (name.to_string(), query.segments_count))
+                .collect(),
+        }
+    }
+
+    fn are_any_filters(&self) -> bool {
+        self.filter_procedures.values().any(|procedures| !procedures.is_empty())
+    }
+
+    fn are_any_filters_in_query(&self, query_name: &str) -> bool {
+        !self.filter_procedures.get(query_name).unwrap().is_empty()
+    }
+
+    fn query_filter_subqueries(&self, query_name: &str) -> &HashMap<FilterId, Vec<FilterSubquery>> {
+        self.filter_subqueries.get(query_name).unwrap()
+    }
+
+    fn query_segments_count(&self, query_name: &str) -> usize {
+        *self.query_segments_counts.get(query_name).unwrap()
+    }
+
+    fn all_filters_procedures(&self) -> Vec<&FilterProcedureTemplate<'_>> {
+        self.filter_procedures.values().flatten().collect()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/value/procedure.rs", escape = "none")]
+struct ProcedureTemplate<'a> {
+    name: String,
+    instructions: Vec<InstructionTemplate<'a>>,
+    query_name: &'a str,
+    are_any_filters: bool,
+    are_active_segments_passed: bool,
+}
+
+impl ProcedureTemplate<'_> {
+    fn new<'a>(
+        procedure: &'a Procedure,
+        query_name: &'a str,
+        are_any_filters: bool,
+    ) -> ProcedureTemplate<'a> {
+        ProcedureTemplate {
+            name: format!("{}{}", name_prefix(query_name), procedure.name.to_lowercase()),
+            instructions: procedure
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    InstructionTemplate::new(instruction, "node", query_name, are_any_filters)
+                })
+                .collect(),
+            query_name,
+            are_any_filters,
+            are_active_segments_passed: procedure.are_active_segments_passed,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "rust/value/instruction.rs", escape = "none")]
+struct InstructionTemplate<'a> {
+    instruction: &'a Instruction,
+    current_node: &'a str,
+    query_name: &'a str,
+    are_any_filters: bool,
+}
+
+impl InstructionTemplate<'_> {
+    fn new<'a>(
+        instruction: &'a Instruction,
+        current_node: &'a str,
+        query_name: &'a str,
+        are_any_filters: bool,
+    ) -> InstructionTemplate<'a> {
+        InstructionTemplate {
+            instruction,
+            current_node,
+            query_name,
+            are_any_filters,
+        }
+    }
+
+    fn procedure_name(&self, name: &str) -> String {
+        format!("{}{}", name_prefix(self.query_name), name.to_lowercase())
+    }
+}
+
+pub struct ValueCodeStandaloneProgGenerator {
+    base: TargetCodeStandaloneProgGeneratorBase,
+}
+
+impl TargetCodeGenerator for ValueCodeStandaloneProgGenerator {
+    fn base(&self) -> &TargetCodeGeneratorBase {
+        &self.base.base
+    }
+
+    fn generate(&self) -> Result<String, CompilationError> {
+        let template = ValueStandaloneProgTemplate::new(self.query());
+        Ok(template.render()?)
+    }
+
+    fn is_random_access_supported() -> bool {
+        true
+    }
+}
+
+impl TargetCodeStandaloneProgGenerator for ValueCodeStandaloneProgGenerator {
+    fn new(
+        query: Query,
+        logging: bool,
+        mmap: bool,
+        eager_filter_evaluation: bool,
+        node_paths: Option<NodePathFormat>,
+        input_format: InputFormat,
+    ) -> impl TargetCodeStandaloneProgGenerator {
+        ValueCodeStandaloneProgGenerator {
+            base: TargetCodeStandaloneProgGeneratorBase::new(
+                query,
+                logging,
+                mmap,
+                eager_filter_evaluation,
+                node_paths,
+                input_format,
+            )
+        }
+    }
+
+    fn base(&self) -> &TargetCodeStandaloneProgGeneratorBase {
+        &self.base
+    }
+}
+
+pub struct ValueCodeLibGenerator {
+    base: TargetCodeLibGeneratorBase,
+}
+
+impl TargetCodeGenerator for ValueCodeLibGenerator {
+    fn base(&self) -> &TargetCodeGeneratorBase {
+        &self.base.base
+    }
+
+    fn generate(&self) -> Result<String, CompilationError> {
+        let template = ValueLibTemplate::new(self.queries());
+        Ok(template.render()?)
+    }
+
+    fn is_random_access_supported() -> bool {
+        true
+    }
+}
+
+impl TargetCodeLibGenerator for ValueCodeLibGenerator {
+    fn new(
+        named_queries: Vec<NamedQuery>,
+        filename: String,
+        logging: bool,
+        bindings: Option<Bindings>,
+        eager_filter_evaluation: bool,
+        results_delivery: ResultsDelivery,
+        input_format: InputFormat,
+    ) -> impl TargetCodeLibGenerator {
+        ValueCodeLibGenerator {
+            base: TargetCodeLibGeneratorBase::new(
+                named_queries,
+                filename,
+                logging,
+                bindings,
+                eager_filter_evaluation,
+                results_delivery,
+                input_format,
+            )
+        }
+    }
+
+    fn base(&self) -> &TargetCodeLibGeneratorBase {
+        &self.base
+    }
+}
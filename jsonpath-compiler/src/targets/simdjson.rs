@@ -1,11 +1,16 @@
 use std::fs;
+use std::path::Path;
 
 use askama::Template;
 
-use crate::ir::Instruction;
-use crate::ir::Instruction::{ForEachElement, ForEachMember, IfActiveFilterInstance, IfCurrentIndexEquals, IfCurrentIndexFromEndEquals, IfCurrentMemberNameEquals};
+use crate::compiler::CompilationError;
+
+use crate::ir::{
+    Comparable, FilterExpression, FilterProcedure, LiteralValue,
+    LogicalFunction, Regex, RegexState, Slice, ValueFunction,
+};
 use crate::NamedQuery;
-use crate::targets::BindingsGenerator;
+use crate::targets::{BindingsGenerator, ResultsDelivery};
 
 pub mod dom;
 pub mod ondemand;
@@ -15,11 +20,27 @@ pub mod ondemand;
 #[template(path = "simdjson/bindings.rs", escape = "none")]
 struct RustBindingsTemplate {
     query_names: Vec<String>,
+    result_spans: bool,
+    result_callbacks: bool,
+    execution_contexts: bool,
+    symbol_prefix: String,
 }
 
 impl RustBindingsTemplate {
-    pub fn new(query_names: Vec<String>) -> RustBindingsTemplate {
-        RustBindingsTemplate { query_names }
+    pub fn new(
+        query_names: Vec<String>,
+        result_spans: bool,
+        result_callbacks: bool,
+        execution_contexts: bool,
+        symbol_prefix: &str,
+    ) -> RustBindingsTemplate {
+        RustBindingsTemplate {
+            query_names,
+            result_spans,
+            result_callbacks,
+            execution_contexts,
+            symbol_prefix: symbol_prefix.to_string(),
+        }
     }
 }
 
@@ -36,37 +57,192 @@ impl RustBindingsGenerator {
 }
 
 impl BindingsGenerator for RustBindingsGenerator {
-    fn generate(&self, named_queries: &Vec<NamedQuery>) -> Result<(), std::io::Error> {
+    fn generate(
+        &self,
+        named_queries: &[NamedQuery],
+        results_delivery: ResultsDelivery,
+        execution_contexts: bool,
+        symbol_prefix: &str,
+    ) -> Result<(), CompilationError> {
         let query_names = named_queries.iter().map(|(name, _)| name.to_string()).collect();
-        let template = RustBindingsTemplate::new(query_names);
-        let bindings = template.render().unwrap();
+        let template = RustBindingsTemplate::new(
+            query_names,
+            results_delivery.spans,
+            results_delivery.callbacks,
+            execution_contexts,
+            symbol_prefix,
+        );
+        let bindings = template.render()?;
         fs::write(&self.bindings_file_path, bindings)?;
         Ok(())
     }
 }
 
-fn is_array_length_needed(instructions: &Vec<Instruction>) -> bool {
-    for instruction in instructions {
-        let is_needed = match instruction {
-            IfCurrentIndexFromEndEquals { .. } => true,
-            IfCurrentIndexEquals {
-                index: _index,
-                instructions,
-            } => is_array_length_needed(instructions),
-            IfCurrentMemberNameEquals {
-                name: _name,
-                instructions,
-            } => is_array_length_needed(instructions),
-            IfActiveFilterInstance {
-                instructions
-            } => is_array_length_needed(instructions),
-            ForEachMember { instructions } => is_array_length_needed(instructions),
-            ForEachElement { instructions } => is_array_length_needed(instructions),
-            _ => false,
+#[derive(Template)]
+#[template(path = "simdjson/bindings.h", escape = "none")]
+struct CHeaderTemplate {
+    filename: String,
+    query_names: Vec<String>,
+    result_spans: bool,
+    result_callbacks: bool,
+    execution_contexts: bool,
+    symbol_prefix: String,
+}
+
+/// Generates a C header declaring the `extern "C"` functions of the library, so that it can be
+/// called from C and C++ code.
+pub struct CHeaderGenerator {
+    header_file_path: String,
+}
+
+impl CHeaderGenerator {
+    pub fn new(header_file_path: &str) -> CHeaderGenerator {
+        CHeaderGenerator {
+            header_file_path: header_file_path.to_string()
+        }
+    }
+}
+
+impl BindingsGenerator for CHeaderGenerator {
+    fn generate(
+        &self,
+        named_queries: &[NamedQuery],
+        results_delivery: ResultsDelivery,
+        execution_contexts: bool,
+        symbol_prefix: &str,
+    ) -> Result<(), CompilationError> {
+        let filename = Path::new(&self.header_file_path)
+            .file_name()
+            .ok_or_else(|| CompilationError::InvalidCHeaderPath(self.header_file_path.clone()))?;
+        let template = CHeaderTemplate {
+            filename: filename.to_string_lossy().to_string(),
+            query_names: named_queries.iter().map(|(name, _)| name.to_string()).collect(),
+            result_spans: results_delivery.spans,
+            result_callbacks: results_delivery.callbacks,
+            execution_contexts,
+            symbol_prefix: symbol_prefix.to_string(),
+        };
+        fs::write(&self.header_file_path, template.render()?)?;
+        Ok(())
+    }
+}
+
+/// Compiles a C++ condition checking whether the element at `index` is selected by the slice.
+fn compile_slice_condition(slice: &Slice) -> String {
+    let bound = |index: i64| {
+        if index >= 0 {
+            format!("(int64_t){index}ll")
+        } else {
+            format!("((int64_t)array_length - (int64_t){}ll)", -index)
+        }
+    };
+    let mut conditions = Vec::new();
+    if slice.step > 0 {
+        let lower = if slice.start >= 0 {
+            bound(slice.start)
+        } else {
+            format!("max({}, (int64_t)0)", bound(slice.start))
         };
-        if is_needed {
-            return true;
+        conditions.push(format!("(int64_t)index >= {lower}"));
+        if let Some(end) = slice.end {
+            conditions.push(format!("(int64_t)index < {}", bound(end)));
         }
+        if slice.step > 1 {
+            conditions.push(format!("((int64_t)index - {lower}) % {}ll == 0", slice.step));
+        }
+    } else {
+        let upper = if slice.start >= 0 {
+            format!("min({}, (int64_t)array_length - 1)", bound(slice.start))
+        } else {
+            bound(slice.start)
+        };
+        conditions.push(format!("(int64_t)index <= {upper}"));
+        if let Some(end) = slice.end {
+            conditions.push(format!("(int64_t)index > {}", bound(end)));
+        }
+        if slice.step < -1 {
+            conditions.push(format!("({upper} - (int64_t)index) % {}ll == 0", -slice.step));
+        }
+    }
+    conditions.join(" && ")
+}
+
+/// Compiles a loop over the indices of the elements in the slice, in the order in which they
+/// are selected, for an array of `array_length` elements.
+fn compile_slice_loop(slice: &Slice) -> String {
+    let bound = |index: i64| {
+        if index >= 0 {
+            format!("(int64_t){index}ll")
+        } else {
+            format!("(array_length - (int64_t){}ll)", -index)
+        }
+    };
+    if slice.step > 0 {
+        let end = match slice.end {
+            Some(end) => format!("clamp({}, (int64_t)0, array_length)", bound(end)),
+            None => "array_length".to_string(),
+        };
+        format!(
+            "for (int64_t index = clamp({}, (int64_t)0, array_length); index < {end}; index += {})",
+            bound(slice.start),
+            slice.step
+        )
+    } else {
+        let end = match slice.end {
+            Some(end) => format!("clamp({}, (int64_t)-1, array_length - 1)", bound(end)),
+            None => "-1".to_string(),
+        };
+        format!(
+            "for (int64_t index = clamp({}, (int64_t)-1, array_length - 1); index > {end}; index -= {})",
+            bound(slice.start),
+            -slice.step
+        )
+    }
+}
+
+#[derive(Template)]
+#[template(path = "simdjson/filter_procedure.cpp", escape = "none")]
+struct FilterProcedureTemplate<'a> {
+    name: String,
+    expression: FilterExpressionTemplate<'a>,
+    regexes: &'a Vec<Regex>,
+    query_name: &'a str,
+}
+
+impl FilterProcedureTemplate<'_> {
+    fn new<'a>(
+        filter_procedure: &'a FilterProcedure,
+        query_name: &'a str,
+    ) -> FilterProcedureTemplate<'a> {
+        FilterProcedureTemplate {
+            name: filter_procedure.name.clone(),
+            expression: FilterExpressionTemplate::new(&filter_procedure.expression),
+            regexes: &filter_procedure.regexes,
+            query_name,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "simdjson/filter_expression.cpp", escape = "none")]
+struct FilterExpressionTemplate<'a> {
+    expression: &'a FilterExpression,
+}
+
+impl FilterExpressionTemplate<'_> {
+    fn new(expression: &FilterExpression) -> FilterExpressionTemplate<'_> {
+        FilterExpressionTemplate { expression }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "simdjson/comparable.cpp", escape = "none")]
+struct ComparableTemplate<'a> {
+    comparable: &'a Comparable,
+}
+
+impl ComparableTemplate<'_> {
+    fn new(comparable: &Comparable) -> ComparableTemplate<'_> {
+        ComparableTemplate { comparable }
     }
-    false
 }
@@ -1,12 +1,15 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::string::ToString;
 
 use askama::Template;
 
-use crate::ir::{Instruction, Procedure, Query};
+use crate::compiler::CompilationError;
+
+use crate::ir::{FilterId, FilterSubquery, FilterSubquerySelector, Instruction, Procedure, Query, SelectionCondition};
 use crate::ir::Instruction::{ForEachElement, ForEachMember};
-use crate::NamedQuery;
-use crate::targets::{TargetCodeGenerator, TargetCodeGeneratorBase, TargetCodeLibGenerator, TargetCodeLibGeneratorBase, TargetCodeStandaloneProgGenerator, TargetCodeStandaloneProgGeneratorBase};
+use crate::{NamedQuery, NodePathFormat};
+use crate::targets::simdjson::FilterProcedureTemplate;
+use crate::targets::{Bindings, InputFormat, ResultsDelivery, TargetCodeGenerator, TargetCodeGeneratorBase, TargetCodeLibGenerator, TargetCodeLibGeneratorBase, TargetCodeStandaloneProgGenerator, TargetCodeStandaloneProgGeneratorBase};
 
 #[derive(Template)]
 #[template(path = "simdjson/dom/standalone.cpp", escape = "none")]
@@ -14,6 +17,9 @@ struct DomStandaloneProgTemplate<'a> {
     logging: bool,
     mmap: bool,
     procedures: Vec<ProcedureTemplate<'a>>,
+    filter_procedures: Vec<FilterProcedureTemplate<'a>>,
+    filter_subqueries: &'a HashMap<FilterId, Vec<FilterSubquery>>,
+    segments_count: usize,
 }
 
 impl DomStandaloneProgTemplate<'_> {
@@ -24,10 +30,44 @@ impl DomStandaloneProgTemplate<'_> {
             procedures: query
                 .procedures
                 .iter()
-                .map(|procedure| ProcedureTemplate::new(procedure))
+                .map(|procedure| {
+                    ProcedureTemplate::new(
+                        procedure,
+                        &query.filter_subqueries,
+                        !query.filter_procedures.is_empty(),
+                    )
+                })
+                .collect(),
+            filter_procedures: query
+                .filter_procedures
+                .values()
+                .map(|filter_procedure| FilterProcedureTemplate::new(filter_procedure, ""))
                 .collect(),
+            filter_subqueries: &query.filter_subqueries,
+            segments_count: query.segments_count,
         }
     }
+
+    fn are_any_filters(&self) -> bool {
+        !self.filter_procedures.is_empty()
+    }
+
+    fn max_subqueries_in_filter_count(&self) -> usize {
+        self.filter_subqueries
+            .values()
+            .map(|subqueries| subqueries.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn max_subquery_segments_count(&self) -> usize {
+        self.filter_subqueries
+            .values()
+            .flatten()
+            .map(|subquery| subquery.segments.len())
+            .max()
+            .unwrap_or(0)
+    }
 }
 
 #[derive(Template)]
@@ -36,33 +76,109 @@ struct DomLibTemplate<'a> {
     filename: &'a str,
     logging: bool,
     bindings: bool,
+    symbol_prefix: &'a str,
     procedures: Vec<ProcedureTemplate<'a>>,
     query_names: Vec<String>,
+    filter_procedures: HashMap<String, Vec<FilterProcedureTemplate<'a>>>,
+    filter_subqueries: HashMap<String, &'a HashMap<FilterId, Vec<FilterSubquery>>>,
+    query_segments_counts: HashMap<String, usize>,
 }
 
 impl DomLibTemplate<'_> {
     fn new<'a>(
         queries: &'a Vec<NamedQuery>,
         logging: bool,
-        bindings: bool,
+        bindings: Option<&'a Bindings>,
         filename: &'a str,
     ) -> DomLibTemplate<'a> {
         let mut procedures = Vec::new();
-        let mut query_names = HashSet::new();
+        let mut query_names = Vec::new();
         for (name, query) in queries {
             for procedure in &query.procedures {
-                procedures.push(ProcedureTemplate::new_with_query_name(procedure, name));
-                query_names.insert(name.to_string());
+                procedures.push(ProcedureTemplate::new_with_query_name(
+                    procedure,
+                    name,
+                    &query.filter_subqueries,
+                    !query.filter_procedures.is_empty(),
+                ));
+            }
+            if !query_names.contains(name) {
+                query_names.push(name.to_string());
             }
         }
         DomLibTemplate {
             logging,
-            bindings,
+            bindings: bindings.is_some(),
+            symbol_prefix: bindings.map_or("", |bindings| &bindings.symbol_prefix),
             filename,
             procedures,
-            query_names: query_names.into_iter().collect(),
+            query_names,
+            filter_procedures: queries
+                .iter()
+                .map(|(name, query)| {
+                    (
+                        name.to_string(),
+                        query
+                            .filter_procedures
+                            .values()
+                            .map(|fp| FilterProcedureTemplate::new(fp, name))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            filter_subqueries: queries
+                .iter()
+                .map(|(name, query)| (name.to_string(), &query.filter_subqueries))
+                .collect(),
+            query_segments_counts: queries
+                .iter()
+                .map(|(name, query)| (name.to_string(), query.segments_count))
+                .collect(),
         }
     }
+
+    fn are_any_filters(&self) -> bool {
+        self.filter_procedures.values().any(|procedures| !procedures.is_empty())
+    }
+
+    fn are_any_filters_in_query(&self, query_name: &str) -> bool {
+        !self.filter_procedures.get(query_name).unwrap().is_empty()
+    }
+
+    fn max_subqueries_in_filter_count(&self) -> usize {
+        self.filter_subqueries
+            .values()
+            .flat_map(|v| v.values())
+            .map(|subqueries| subqueries.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn max_subquery_segments_count(&self) -> usize {
+        self.filter_subqueries
+            .values()
+            .flat_map(|v| v.values())
+            .flatten()
+            .map(|subquery| subquery.segments.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn query_filter_procedures(&self, query_name: &str) -> &Vec<FilterProcedureTemplate<'_>> {
+        self.filter_procedures.get(query_name).unwrap()
+    }
+
+    fn query_filter_subqueries(&self, query_name: &str) -> &HashMap<FilterId, Vec<FilterSubquery>> {
+        self.filter_subqueries.get(query_name).unwrap()
+    }
+
+    fn query_segments_count(&self, query_name: &str) -> usize {
+        *self.query_segments_counts.get(query_name).unwrap()
+    }
+
+    fn all_filters_procedures(&self) -> Vec<&FilterProcedureTemplate<'_>> {
+        self.filter_procedures.values().flatten().collect()
+    }
 }
 
 #[derive(Template)]
@@ -70,23 +186,41 @@ impl DomLibTemplate<'_> {
 struct ProcedureTemplate<'a> {
     name: String,
     instructions: Vec<InstructionTemplate<'a>>,
+    are_any_filters: bool,
+    are_active_segments_passed: bool,
 }
 
 impl ProcedureTemplate<'_> {
-    fn new(procedure: &Procedure) -> ProcedureTemplate {
+    fn new<'a>(
+        procedure: &'a Procedure,
+        filter_subqueries: &'a HashMap<FilterId, Vec<FilterSubquery>>,
+        are_any_filters: bool,
+    ) -> ProcedureTemplate<'a> {
         ProcedureTemplate {
             name: procedure.name.clone(),
             instructions: procedure
                 .instructions
                 .iter()
-                .map(|instruction| InstructionTemplate::new(instruction, "node", ""))
+                .map(|instruction| {
+                    InstructionTemplate::new(
+                        instruction,
+                        "node",
+                        "",
+                        Some(filter_subqueries),
+                        are_any_filters,
+                    )
+                })
                 .collect(),
+            are_any_filters,
+            are_active_segments_passed: procedure.are_active_segments_passed,
         }
     }
 
     fn new_with_query_name<'a>(
         procedure: &'a Procedure,
         query_name: &'a str,
+        filter_subqueries: &'a HashMap<FilterId, Vec<FilterSubquery>>,
+        are_any_filters: bool,
     ) -> ProcedureTemplate<'a> {
         let procedure_name = format!("{}_{}", query_name, procedure.name);
         ProcedureTemplate {
@@ -94,8 +228,18 @@ impl ProcedureTemplate<'_> {
             instructions: procedure
                 .instructions
                 .iter()
-                .map(|instruction| InstructionTemplate::new(instruction, "node", query_name))
+                .map(|instruction| {
+                    InstructionTemplate::new(
+                        instruction,
+                        "node",
+                        query_name,
+                        Some(filter_subqueries),
+                        are_any_filters,
+                    )
+                })
                 .collect(),
+            are_any_filters,
+            are_active_segments_passed: procedure.are_active_segments_passed,
         }
     }
 
@@ -118,6 +262,8 @@ struct InstructionTemplate<'a> {
     instruction: &'a Instruction,
     current_node: &'a str,
     query_name: &'a str,
+    filter_subqueries: Option<&'a HashMap<FilterId, Vec<FilterSubquery>>>,
+    are_any_filters: bool,
 }
 
 impl InstructionTemplate<'_> {
@@ -125,15 +271,39 @@ impl InstructionTemplate<'_> {
         instruction: &'a Instruction,
         current_node: &'a str,
         query_name: &'a str,
+        filter_subqueries: Option<&'a HashMap<FilterId, Vec<FilterSubquery>>>,
+        are_any_filters: bool,
     ) -> InstructionTemplate<'a> {
         InstructionTemplate {
             instruction,
             current_node,
             query_name,
+            filter_subqueries,
+            are_any_filters,
+        }
+    }
+
+    fn procedure_name(&self, name: &str) -> String {
+        if self.query_name.is_empty() {
+            name.to_lowercase()
+        } else {
+            format!("{}_{}", self.query_name, name.to_lowercase())
         }
     }
 }
 
+#[derive(Template)]
+#[template(path = "simdjson/dom/selection_condition.cpp", escape = "none")]
+struct SelectionConditionTemplate<'a> {
+    condition: &'a SelectionCondition,
+}
+
+impl SelectionConditionTemplate<'_> {
+    fn new(condition: &SelectionCondition) -> SelectionConditionTemplate<'_> {
+        SelectionConditionTemplate { condition }
+    }
+}
+
 pub struct DomCodeStandaloneProgGenerator {
     base: TargetCodeStandaloneProgGeneratorBase,
 }
@@ -143,13 +313,17 @@ impl TargetCodeGenerator for DomCodeStandaloneProgGenerator {
         &self.base.base
     }
 
-    fn generate(&self) -> String {
+    fn generate(&self) -> Result<String, CompilationError> {
         let template = DomStandaloneProgTemplate::new(
             self.query(),
             self.logging(),
             self.mmap(),
         );
-        template.render().unwrap()
+        Ok(template.render()?)
+    }
+
+    fn is_random_access_supported() -> bool {
+        true
     }
 }
 
@@ -159,6 +333,8 @@ impl TargetCodeStandaloneProgGenerator for DomCodeStandaloneProgGenerator {
         logging: bool,
         mmap: bool,
         eager_filter_evaluation: bool,
+        node_paths: Option<NodePathFormat>,
+        input_format: InputFormat,
     ) -> impl TargetCodeStandaloneProgGenerator {
         DomCodeStandaloneProgGenerator {
             base: TargetCodeStandaloneProgGeneratorBase::new(
@@ -166,6 +342,8 @@ impl TargetCodeStandaloneProgGenerator for DomCodeStandaloneProgGenerator {
                 logging,
                 mmap,
                 eager_filter_evaluation,
+                node_paths,
+                input_format,
             )
         }
     }
@@ -184,14 +362,18 @@ impl TargetCodeGenerator for DomCodeLibGenerator {
         &self.base.base
     }
 
-    fn generate(&self) -> String {
+    fn generate(&self) -> Result<String, CompilationError> {
         let template = DomLibTemplate::new(
             self.queries(),
             self.logging(),
             self.bindings(),
             self.filename(),
         );
-        template.render().unwrap()
+        Ok(template.render()?)
+    }
+
+    fn is_random_access_supported() -> bool {
+        true
     }
 }
 
@@ -200,8 +382,10 @@ impl TargetCodeLibGenerator for DomCodeLibGenerator {
         named_queries: Vec<NamedQuery>,
         filename: String,
         logging: bool,
-        bindings: bool,
+        bindings: Option<Bindings>,
         eager_filter_evaluation: bool,
+        results_delivery: ResultsDelivery,
+        input_format: InputFormat,
     ) -> impl TargetCodeLibGenerator {
         DomCodeLibGenerator {
             base: TargetCodeLibGeneratorBase::new(
@@ -210,6 +394,8 @@ impl TargetCodeLibGenerator for DomCodeLibGenerator {
                 logging,
                 bindings,
                 eager_filter_evaluation,
+                results_delivery,
+                input_format,
             )
         }
     }
@@ -217,6 +403,10 @@ impl TargetCodeLibGenerator for DomCodeLibGenerator {
     fn base(&self) -> &TargetCodeLibGeneratorBase {
         &self.base
     }
+
+    fn are_bindings_supported() -> bool {
+        true
+    }
 }
 
 static EMPTY_OBJECT_ITERATION: InstructionTemplate = InstructionTemplate {
@@ -225,6 +415,8 @@ static EMPTY_OBJECT_ITERATION: InstructionTemplate = InstructionTemplate {
     },
     current_node: "node",
     query_name: "",
+    filter_subqueries: None,
+    are_any_filters: false,
 };
 static EMPTY_ARRAY_ITERATION: InstructionTemplate = InstructionTemplate {
     instruction: &ForEachElement {
@@ -232,4 +424,6 @@ static EMPTY_ARRAY_ITERATION: InstructionTemplate = InstructionTemplate {
     },
     current_node: "node",
     query_name: "",
+    filter_subqueries: None,
+    are_any_filters: false,
 };
@@ -3,12 +3,16 @@ use std::string::ToString;
 
 use askama::Template;
 
+use crate::compiler::CompilationError;
+use crate::NodePathFormat;
+
 use crate::ir::{
-    Comparable, FilterExpression, FilterId, FilterProcedure, FilterSubquery, FilterSubquerySegment,
-    Instruction, LiteralValue, Procedure, Query, SelectionCondition,
+    FilterId, FilterSubquery, FilterSubquerySelector, Instruction, MergedQuery, Procedure, Query,
+    SelectionCondition,
 };
-use crate::ir::Instruction::{ForEachElement, ForEachMember};
-use crate::targets::{NamedQuery, TargetCodeGenerator, TargetCodeGeneratorBase,
+use crate::ir::Instruction::{ForEachElement, ForEachElementInReverseOrder, ForEachMember};
+use crate::targets::simdjson::FilterProcedureTemplate;
+use crate::targets::{Bindings, InputFormat, NamedQuery, ResultsDelivery, TargetCodeGenerator, TargetCodeGeneratorBase,
                      TargetCodeLibGenerator, TargetCodeLibGeneratorBase,
                      TargetCodeStandaloneProgGenerator, TargetCodeStandaloneProgGeneratorBase};
 
@@ -18,6 +22,8 @@ struct OnDemandStandaloneProgTemplate<'a> {
     logging: bool,
     mmap: bool,
     eager_filter_evaluation: bool,
+    node_paths: Option<NodePathFormat>,
+    input_format: InputFormat,
     procedures: Vec<ProcedureTemplate<'a>>,
     filter_procedures: Vec<FilterProcedureTemplate<'a>>,
     filter_subqueries: &'a HashMap<FilterId, Vec<FilterSubquery>>,
@@ -30,11 +36,15 @@ impl OnDemandStandaloneProgTemplate<'_> {
         logging: bool,
         mmap: bool,
         eager_filter_evaluation: bool,
+        node_paths: Option<NodePathFormat>,
+        input_format: InputFormat,
     ) -> OnDemandStandaloneProgTemplate {
         OnDemandStandaloneProgTemplate {
             logging,
             mmap,
             eager_filter_evaluation,
+            node_paths,
+            input_format,
             procedures: query
                 .procedures
                 .iter()
@@ -45,6 +55,8 @@ impl OnDemandStandaloneProgTemplate<'_> {
                         &query.filter_subqueries,
                         !query.filter_procedures.is_empty(),
                         eager_filter_evaluation,
+                        false,
+                        ResultsDelivery { node_paths, ..ResultsDelivery::default() },
                     )
                 })
                 .collect(),
@@ -63,12 +75,33 @@ impl OnDemandStandaloneProgTemplate<'_> {
     }
 
     fn max_subqueries_in_filter_count(&self) -> usize {
+        self.filter_subqueries
+            .iter()
+            .map(|(id, subqueries)| subqueries.len() + usize::from(id.is_nested()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn max_subquery_segments_count(&self) -> usize {
         self.filter_subqueries
             .values()
-            .map(|subqueries| subqueries.len())
+            .flatten()
+            .map(|subquery| subquery.segments.len())
             .max()
             .unwrap_or(0)
     }
+
+    fn are_node_paths_json_pointers(&self) -> bool {
+        self.node_paths == Some(NodePathFormat::JsonPointer)
+    }
+
+    fn is_ndjson_input(&self) -> bool {
+        self.input_format != InputFormat::Json
+    }
+
+    fn are_line_numbers_output(&self) -> bool {
+        self.input_format == InputFormat::Ndjson { line_numbers: true }
+    }
 }
 
 #[derive(Template)]
@@ -77,19 +110,25 @@ struct OnDemandLibTemplate<'a> {
     filename: &'a str,
     logging: bool,
     bindings: bool,
+    symbol_prefix: &'a str,
     eager_filter_evaluation: bool,
+    results_delivery: ResultsDelivery,
+    input_format: InputFormat,
     procedures: HashMap<String, Vec<ProcedureTemplate<'a>>>,
     filter_procedures: HashMap<String, Vec<FilterProcedureTemplate<'a>>>,
     filter_subqueries: HashMap<String, &'a HashMap<FilterId, Vec<FilterSubquery>>>,
     query_segments_counts: HashMap<String, usize>,
+    query_irs: HashMap<String, &'a Query>,
 }
 
 impl OnDemandLibTemplate<'_> {
     fn new<'a>(
         queries: &'a Vec<NamedQuery>,
         logging: bool,
-        bindings: bool,
+        bindings: Option<&'a Bindings>,
         eager_filter_evaluation: bool,
+        results_delivery: ResultsDelivery,
+        input_format: InputFormat,
         filename: &'a str,
     ) -> OnDemandLibTemplate<'a> {
         let mut procedures = HashMap::new();
@@ -106,6 +145,8 @@ impl OnDemandLibTemplate<'_> {
                             &query.filter_subqueries,
                             !query.filter_procedures.is_empty(),
                             eager_filter_evaluation,
+                            !query.merged_queries.is_empty(),
+                            results_delivery,
                         )
                     })
                     .collect::<Vec<ProcedureTemplate>>(),
@@ -113,8 +154,11 @@ impl OnDemandLibTemplate<'_> {
         }
         OnDemandLibTemplate {
             logging,
-            bindings,
+            bindings: bindings.is_some(),
+            symbol_prefix: bindings.map_or("", |bindings| &bindings.symbol_prefix),
             eager_filter_evaluation,
+            results_delivery,
+            input_format,
             filename,
             procedures,
             query_segments_counts: queries
@@ -138,6 +182,10 @@ impl OnDemandLibTemplate<'_> {
                 .iter()
                 .map(|(name, query)| (name.to_string(), &query.filter_subqueries))
                 .collect(),
+            query_irs: queries
+                .iter()
+                .map(|(name, query)| (name.to_string(), query))
+                .collect(),
         }
     }
 
@@ -158,10 +206,20 @@ impl OnDemandLibTemplate<'_> {
     }
 
     fn max_subqueries_in_filter_count(&self) -> usize {
+        self.filter_subqueries
+            .values()
+            .flat_map(|v| v.iter())
+            .map(|(id, subqueries)| subqueries.len() + usize::from(id.is_nested()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn max_subquery_segments_count(&self) -> usize {
         self.filter_subqueries
             .values()
             .flat_map(|v| v.values())
-            .map(|subqueries| subqueries.len())
+            .flatten()
+            .map(|subquery| subquery.segments.len())
             .max()
             .unwrap_or(0)
     }
@@ -181,6 +239,34 @@ impl OnDemandLibTemplate<'_> {
     fn all_filters_procedures(&self) -> Vec<&FilterProcedureTemplate> {
         self.filter_procedures.values().flatten().collect()
     }
+
+    fn query_merged_queries(&self, query_name: &str) -> &Vec<MergedQuery> {
+        &self.query_irs.get(query_name).unwrap().merged_queries
+    }
+
+    fn query_root_procedure_name(&self, query_name: &str) -> String {
+        self.query_irs.get(query_name).unwrap().root_procedure_name()
+    }
+
+    fn results_vector_type(&self, query_name: &str) -> &str {
+        if self.are_any_filters_in_query(query_name) {
+            "vector<tuple<string *, size_t, size_t, selection_condition*>>"
+        } else {
+            "vector<tuple<string *, size_t, size_t>>"
+        }
+    }
+
+    fn are_node_paths_json_pointers(&self) -> bool {
+        self.results_delivery.node_paths == Some(NodePathFormat::JsonPointer)
+    }
+
+    fn is_ndjson_input(&self) -> bool {
+        self.input_format != InputFormat::Json
+    }
+
+    fn are_line_numbers_output(&self) -> bool {
+        self.input_format == InputFormat::Ndjson { line_numbers: true }
+    }
 }
 
 #[derive(Template)]
@@ -190,6 +276,8 @@ struct ProcedureTemplate<'a> {
     name: String,
     instructions: Vec<InstructionTemplate<'a>>,
     are_any_filters: bool,
+    are_active_segments_passed: bool,
+    are_queries_merged: bool,
 }
 
 impl ProcedureTemplate<'_> {
@@ -199,6 +287,8 @@ impl ProcedureTemplate<'_> {
         filter_subqueries: &'a HashMap<FilterId, Vec<FilterSubquery>>,
         are_any_filters: bool,
         eager_filter_evaluation: bool,
+        are_queries_merged: bool,
+        results_delivery: ResultsDelivery,
     ) -> ProcedureTemplate<'a> {
         ProcedureTemplate {
             query_name: query_name.to_string(),
@@ -214,13 +304,22 @@ impl ProcedureTemplate<'_> {
                         Some(filter_subqueries),
                         are_any_filters,
                         eager_filter_evaluation,
+                        results_delivery,
                     )
                 })
                 .collect(),
             are_any_filters,
+            are_active_segments_passed: procedure.are_active_segments_passed,
+            are_queries_merged,
         }
     }
 
+    fn are_subqueries_states_updated(&self) -> bool {
+        self.instructions
+            .iter()
+            .any(|ins| matches!(ins.instruction, Instruction::UpdateSubqueriesState))
+    }
+
     fn are_object_members_iterated(&self) -> bool {
         self.instructions
             .iter()
@@ -243,6 +342,7 @@ struct InstructionTemplate<'a> {
     filter_subqueries: Option<&'a HashMap<FilterId, Vec<FilterSubquery>>>,
     are_any_filters: bool,
     eager_filter_evaluation: bool,
+    results_delivery: ResultsDelivery,
 }
 
 impl InstructionTemplate<'_> {
@@ -253,6 +353,7 @@ impl InstructionTemplate<'_> {
         filter_subqueries: Option<&'a HashMap<FilterId, Vec<FilterSubquery>>>,
         are_any_filters: bool,
         eager_filter_evaluation: bool,
+        results_delivery: ResultsDelivery,
     ) -> InstructionTemplate<'a> {
         InstructionTemplate {
             instruction,
@@ -261,42 +362,58 @@ impl InstructionTemplate<'_> {
             filter_subqueries,
             are_any_filters,
             eager_filter_evaluation,
+            results_delivery,
         }
     }
-}
 
-#[derive(Template)]
-#[template(path = "simdjson/ondemand/filter_procedure.cpp", escape = "none")]
-struct FilterProcedureTemplate<'a> {
-    name: String,
-    filter_id: FilterId,
-    expression: FilterExpressionTemplate<'a>,
-    query_name: &'a str,
-}
+    /// Buffer and start position of a result saved from the current node. When the results
+    /// are saved as spans of the input, the node is not copied to a buffer and the positions
+    /// are addresses in the input, converted to offsets when the query finishes.
+    fn result_start(&self) -> &str {
+        if self.results_delivery.spans {
+            "nullptr, reinterpret_cast<size_t>(node_token.data())"
+        } else {
+            "result_buf, result_buf->size()"
+        }
+    }
 
-impl FilterProcedureTemplate<'_> {
-    fn new<'a>(
-        filter_procedure: &'a FilterProcedure,
-        query_name: &'a str,
-    ) -> FilterProcedureTemplate<'a> {
-        FilterProcedureTemplate {
-            name: filter_procedure.name.clone(),
-            filter_id: filter_procedure.filter_id.clone(),
-            expression: FilterExpressionTemplate::new(&filter_procedure.expression),
-            query_name,
+    /// Buffer allocated for a result which is not a part of another one. When the results are
+    /// passed to a callback, a single buffer is reused after all the results in it are passed.
+    fn new_result_buffer(&self) -> &str {
+        if self.results_delivery.callbacks {
+            "context->current_result_callback != nullptr ? &context->results_buffer : new string()"
+        } else {
+            "new string()"
         }
     }
-}
 
-#[derive(Template)]
-#[template(path = "simdjson/ondemand/filter_expression.cpp", escape = "none")]
-struct FilterExpressionTemplate<'a> {
-    expression: &'a FilterExpression,
-}
+    /// Vector of the results to which the current node is saved, separate for every merged
+    /// query.
+    fn element_iteration_instructions(instruction: &Instruction) -> Option<&Vec<Instruction>> {
+        match instruction {
+            ForEachElement { instructions } | ForEachElementInReverseOrder { instructions, .. } => {
+                Some(instructions)
+            }
+            _ => None,
+        }
+    }
 
-impl FilterExpressionTemplate<'_> {
-    fn new(expression: &FilterExpression) -> FilterExpressionTemplate {
-        FilterExpressionTemplate { expression }
+    /// Returns the vector of the results whose order is reversed after the iteration over the
+    /// array elements, if the instruction reverses it.
+    fn reversed_results_vector(instruction: &Instruction) -> Option<String> {
+        match instruction {
+            ForEachElementInReverseOrder { query_index, .. } => {
+                Some(Self::results_vector(query_index))
+            }
+            _ => None,
+        }
+    }
+
+    fn results_vector(query_index: &Option<usize>) -> String {
+        match query_index {
+            Some(query_index) => format!("all_results[{query_index}]"),
+            None => "all_results".to_string(),
+        }
     }
 }
 
@@ -321,14 +438,24 @@ impl TargetCodeGenerator for OnDemandCodeStandaloneProgGenerator {
         &self.base.base
     }
 
-    fn generate(&self) -> String {
+    fn generate(&self) -> Result<String, CompilationError> {
         let template = OnDemandStandaloneProgTemplate::new(
             self.query(),
             self.logging(),
             self.mmap(),
             self.eager_filter_evaluation(),
+            self.node_paths(),
+            self.input_format(),
         );
-        template.render().unwrap()
+        Ok(template.render()?)
+    }
+
+    fn are_node_paths_supported() -> bool {
+        true
+    }
+
+    fn is_ndjson_input_supported() -> bool {
+        true
     }
 }
 
@@ -338,6 +465,8 @@ impl TargetCodeStandaloneProgGenerator for OnDemandCodeStandaloneProgGenerator {
         logging: bool,
         mmap: bool,
         eager_filter_evaluation: bool,
+        node_paths: Option<NodePathFormat>,
+        input_format: InputFormat,
     ) -> impl TargetCodeStandaloneProgGenerator {
         OnDemandCodeStandaloneProgGenerator {
             base: TargetCodeStandaloneProgGeneratorBase::new(
@@ -345,6 +474,8 @@ impl TargetCodeStandaloneProgGenerator for OnDemandCodeStandaloneProgGenerator {
                 logging,
                 mmap,
                 eager_filter_evaluation,
+                node_paths,
+                input_format,
             )
         }
     }
@@ -354,6 +485,11 @@ impl TargetCodeStandaloneProgGenerator for OnDemandCodeStandaloneProgGenerator {
     }
 }
 
+/// Generates a C++ library executing the queries with the simdjson On-Demand API. The state of
+/// every execution of a query is kept in a separate context, so the generated functions are safe
+/// to call from multiple threads at once, as well as from a callback receiving the results.
+/// Every function also has an overload taking a `jsonpath_context`, which keeps the parser and
+/// the execution state between the calls, and can be used by one execution at a time.
 pub struct OnDemandCodeLibGenerator {
     base: TargetCodeLibGeneratorBase,
 }
@@ -363,15 +499,25 @@ impl TargetCodeGenerator for OnDemandCodeLibGenerator {
         &self.base.base
     }
 
-    fn generate(&self) -> String {
+    fn generate(&self) -> Result<String, CompilationError> {
         let template = OnDemandLibTemplate::new(
             self.queries(),
             self.logging(),
             self.bindings(),
             self.eager_filter_evaluation(),
+            self.results_delivery(),
+            self.input_format(),
             self.filename(),
         );
-       template.render().unwrap()
+        Ok(template.render()?)
+    }
+
+    fn are_node_paths_supported() -> bool {
+        true
+    }
+
+    fn is_ndjson_input_supported() -> bool {
+        true
     }
 }
 
@@ -380,8 +526,10 @@ impl TargetCodeLibGenerator for OnDemandCodeLibGenerator {
         named_queries: Vec<NamedQuery>,
         filename: String,
         logging: bool,
-        bindings: bool,
+        bindings: Option<Bindings>,
         eager_filter_evaluation: bool,
+        results_delivery: ResultsDelivery,
+        input_format: InputFormat,
     ) -> impl TargetCodeLibGenerator {
         OnDemandCodeLibGenerator {
             base: TargetCodeLibGeneratorBase::new(
@@ -390,6 +538,8 @@ impl TargetCodeLibGenerator for OnDemandCodeLibGenerator {
                 logging,
                 bindings,
                 eager_filter_evaluation,
+                results_delivery,
+                input_format,
             )
         }
     }
@@ -397,6 +547,26 @@ impl TargetCodeLibGenerator for OnDemandCodeLibGenerator {
     fn base(&self) -> &TargetCodeLibGeneratorBase {
         &self.base
     }
+
+    fn are_bindings_supported() -> bool {
+        true
+    }
+
+    fn is_single_pass_supported() -> bool {
+        true
+    }
+
+    fn are_result_spans_supported() -> bool {
+        true
+    }
+
+    fn are_result_callbacks_supported() -> bool {
+        true
+    }
+
+    fn are_execution_contexts_supported() -> bool {
+        true
+    }
 }
 
 static EMPTY_OBJECT_ITERATION: InstructionTemplate = InstructionTemplate {
@@ -408,6 +578,7 @@ static EMPTY_OBJECT_ITERATION: InstructionTemplate = InstructionTemplate {
     filter_subqueries: None,
     are_any_filters: false,
     eager_filter_evaluation: false,
+    results_delivery: ResultsDelivery { spans: false, callbacks: false, node_paths: None },
 };
 static EMPTY_ARRAY_ITERATION: InstructionTemplate = InstructionTemplate {
     instruction: &ForEachElement {
@@ -418,4 +589,5 @@ static EMPTY_ARRAY_ITERATION: InstructionTemplate = InstructionTemplate {
     filter_subqueries: None,
     are_any_filters: false,
     eager_filter_evaluation: false,
+    results_delivery: ResultsDelivery { spans: false, callbacks: false, node_paths: None },
 };
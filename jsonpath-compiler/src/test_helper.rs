@@ -1,18 +1,27 @@
 use std::{env, fs};
-use std::path::Path;
-use std::process::Command;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 use serde_json::{from_str, Value};
 use uuid::Uuid;
 
-use crate::compiler::StandaloneProgGeneratingCompiler;
+use crate::compiler::{LibGeneratingCompiler, QueriesSource, StandaloneProgGeneratingCompiler};
+use crate::interpreter::Interpreter;
+use crate::targets::TargetCodeStandaloneProgGenerator;
+use crate::targets::rust::RustCodeStandaloneProgGenerator;
+use crate::targets::rust::value::ValueCodeStandaloneProgGenerator;
 use crate::targets::simdjson::dom::DomCodeStandaloneProgGenerator;
-use crate::targets::simdjson::ondemand::OnDemandCodeStandaloneProgGenerator;
+use crate::targets::simdjson::ondemand::{OnDemandCodeLibGenerator, OnDemandCodeStandaloneProgGenerator};
 
 pub enum TestTarget {
     SimdjsonOndemand,
     SimdjsonOndemandEagerFilters,
     SimdjsonDom,
+    Rust,
+    RustSerdeJson,
+    /// Runs the IR of the query in the interpreter instead of generating code.
+    Interpreter,
 }
 
 
@@ -21,11 +30,13 @@ pub struct TestHelper {
     document: String,
     expected_result: Value,
     target: TestTarget,
-    simdjson_path: String,
+    simdjson_path: Option<String>,
     query_code_file_path: String,
     query_prog_file_path: String,
     document_file_path: String,
     ignore_order_and_duplicates: bool,
+    max_procedures: Option<usize>,
+    invalid_document: bool,
 }
 
 impl TestHelper {
@@ -33,19 +44,34 @@ impl TestHelper {
 
     pub fn new(query: &str, document: &str, expected_result: &str, target: TestTarget) -> TestHelper {
         let tmp_path = Self::random_file_path();
+        let code_file_extension = match target {
+            TestTarget::Rust | TestTarget::RustSerdeJson => "rs",
+            _ => "cpp",
+        };
         TestHelper {
             query: query.to_string(),
             document: document.to_string(),
-            simdjson_path: env::var("SIMDJSON_PATH").unwrap(),
-            query_code_file_path: format!("{tmp_path}.cpp"),
+            simdjson_path: env::var("SIMDJSON_PATH").ok(),
+            query_code_file_path: format!("{tmp_path}.{code_file_extension}"),
             query_prog_file_path: tmp_path.clone(),
             document_file_path: format!("{tmp_path}.json"),
             expected_result: from_str(expected_result).unwrap(),
             ignore_order_and_duplicates: false,
+            max_procedures: None,
+            invalid_document: false,
             target,
         }
     }
 
+    /// Creates a helper checking that the execution of the query fails, as the document is not
+    /// valid JSON.
+    pub fn with_invalid_document(query: &str, document: &str, target: TestTarget) -> TestHelper {
+        TestHelper {
+            invalid_document: true,
+            ..TestHelper::new(query, document, "null", target)
+        }
+    }
+
     pub fn ignore_order_and_duplicates(self) -> TestHelper {
         TestHelper {
             ignore_order_and_duplicates: true,
@@ -53,10 +79,37 @@ impl TestHelper {
         }
     }
 
+    /// Ignores the order of the results and their duplicates only for the targets without
+    /// random access to the array elements, which select them in the document order.
+    pub fn ignore_order_and_duplicates_without_random_access(self) -> TestHelper {
+        let ignore_order_and_duplicates = !matches!(
+            self.target,
+            TestTarget::SimdjsonDom | TestTarget::RustSerdeJson | TestTarget::Interpreter
+        );
+        TestHelper {
+            ignore_order_and_duplicates,
+            ..self
+        }
+    }
+
+    pub fn with_max_procedures(self, max_procedures: usize) -> TestHelper {
+        TestHelper {
+            max_procedures: Some(max_procedures),
+            ..self
+        }
+    }
+
     pub fn run(&mut self) {
-        self.generate_query_code();
-        self.compile_query_code();
-        let mut result = self.execute_query();
+        if self.invalid_document {
+            return self.assert_document_rejected();
+        }
+        let mut result = if let TestTarget::Interpreter = self.target {
+            self.interpret_query()
+        } else {
+            self.generate_query_code();
+            self.compile_query_code();
+            self.execute_query()
+        };
         if self.ignore_order_and_duplicates {
             Self::normalize_result(&mut result);
             Self::normalize_result(&mut self.expected_result);
@@ -71,6 +124,9 @@ impl TestHelper {
 
     fn generate_query_code(&self) {
         let mut compiler = StandaloneProgGeneratingCompiler::new();
+        if let Some(max_procedures) = self.max_procedures {
+            compiler = compiler.with_max_procedures(max_procedures);
+        }
         match self.target {
             TestTarget::SimdjsonOndemand => {
                 compiler.compile::<OnDemandCodeStandaloneProgGenerator>(
@@ -91,22 +147,73 @@ impl TestHelper {
                     &self.query_code_file_path,
                 )
             }
+            TestTarget::Rust => {
+                compiler.compile::<RustCodeStandaloneProgGenerator>(
+                    &self.query,
+                    &self.query_code_file_path,
+                )
+            }
+            TestTarget::RustSerdeJson => {
+                compiler.compile::<ValueCodeStandaloneProgGenerator>(
+                    &self.query,
+                    &self.query_code_file_path,
+                )
+            }
+            TestTarget::Interpreter => unreachable!("no code is generated for the interpreter"),
         }.unwrap();
     }
 
     fn compile_query_code(&self) {
-        let result = Command::new("c++")
-            .arg(&self.query_code_file_path)
-            .arg(format!("{}/simdjson.cpp", self.simdjson_path))
-            .arg("-std=c++20")
-            .arg("-O3")
-            .arg(format!("-I{}", self.simdjson_path))
-            .arg("-o").arg(&self.query_prog_file_path)
-            .status()
-            .unwrap();
+        let result = match self.target {
+            TestTarget::Rust => {
+                Command::new("rustc")
+                    .arg(&self.query_code_file_path)
+                    .arg("--edition=2021")
+                    .arg("-O")
+                    .arg("-o").arg(&self.query_prog_file_path)
+                    .status()
+            }
+            TestTarget::RustSerdeJson => {
+                let dependencies_path = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+                Command::new("rustc")
+                    .arg(&self.query_code_file_path)
+                    .arg("--edition=2021")
+                    .arg("-O")
+                    .arg(format!("--extern=serde_json={}", Self::serde_json_lib_path(&dependencies_path).display()))
+                    .arg("-L").arg(format!("dependency={}", dependencies_path.display()))
+                    .arg("-o").arg(&self.query_prog_file_path)
+                    .status()
+            }
+            _ => {
+                let simdjson_path = self.simdjson_path.as_ref().expect("SIMDJSON_PATH is not set");
+                Command::new("c++")
+                    .arg(&self.query_code_file_path)
+                    .arg(format!("{simdjson_path}/simdjson.cpp"))
+                    .arg("-std=c++20")
+                    .arg("-O3")
+                    .arg(format!("-I{simdjson_path}"))
+                    .arg("-o").arg(&self.query_prog_file_path)
+                    .status()
+            }
+        }.unwrap();
         assert!(result.success(), "query code compilation failed")
     }
 
+    // The generated code is compiled against the serde_json library built as a dependency of
+    // the tests, which are run from the directory in which it is placed. Libraries left there
+    // by earlier builds with other features are older than the one the tests are built with.
+    fn serde_json_lib_path(dependencies_path: &Path) -> PathBuf {
+        fs::read_dir(dependencies_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                let name = path.file_name().unwrap().to_str().unwrap();
+                name.starts_with("libserde_json-") && name.ends_with(".rlib")
+            })
+            .max_by_key(|path| fs::metadata(path).unwrap().modified().unwrap())
+            .expect("serde_json library not found")
+    }
+
     fn execute_query(&self) -> Value {
         fs::write(&self.document_file_path, &self.document).unwrap();
         let output = Command::new(&self.query_prog_file_path)
@@ -118,9 +225,178 @@ impl TestHelper {
         from_str(&result).unwrap()
     }
 
+    fn assert_document_rejected(&self) {
+        if let TestTarget::Interpreter = self.target {
+            assert!(from_str::<Value>(&self.document).is_err(), "invalid document accepted");
+            return;
+        }
+        self.generate_query_code();
+        self.compile_query_code();
+        fs::write(&self.document_file_path, &self.document).unwrap();
+        let status = Command::new(&self.query_prog_file_path)
+            .arg(&self.document_file_path)
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(!status.success(), "invalid document accepted");
+    }
+
+    fn interpret_query(&self) -> Value {
+        let document: Value = from_str(&self.document).unwrap();
+        let interpreter = match self.max_procedures {
+            Some(max_procedures) => Interpreter::with_max_procedures(&self.query, max_procedures),
+            None => Interpreter::new(&self.query),
+        };
+        let result = interpreter.unwrap().run(&document);
+        Value::Array(result.into_iter().cloned().collect())
+    }
+
     fn normalize_result(result: &mut Value) {
         let result_arr = result.as_array_mut().unwrap();
         result_arr.sort_by_key(|v| v.to_string());
         result_arr.dedup()
     }
-}
\ No newline at end of file
+}
+
+/// Directory for the files of the tests in the running test file, named after it. Test
+/// executables are named after their files, followed by a hash.
+pub fn workdir_path() -> String {
+    let test_exe_path = env::current_exe().unwrap();
+    let test_exe_name = test_exe_path.file_stem().unwrap().to_str().unwrap();
+    let test_file_name = test_exe_name.rsplit_once('-').map_or(test_exe_name, |(name, _)| name);
+    format!("/tmp/jsonpath-compiler-tests/{}", test_file_name.replace('_', "-"))
+}
+
+/// Creates a separate directory for the files of a single execution, as the tests run in
+/// parallel.
+pub fn create_execution_dir() -> String {
+    let execution_dir = Path::new(&workdir_path()).join(Uuid::new_v4().to_string());
+    fs::create_dir_all(&execution_dir).unwrap();
+    execution_dir.to_str().unwrap().to_string()
+}
+
+/// Main code of a program using the libraries in `queries.hpp`, which executes the statements
+/// with the document given as its first argument loaded into `input`, and its length with the
+/// padding in `length`. The statements can pass `print_result` as the callback receiving the
+/// results, which prints each of them on a separate line.
+pub fn lib_main_code(statements: &str) -> String {
+    format!(
+        r#"
+#include <iostream>
+#include "queries.hpp"
+
+void print_result(const char *result, size_t result_length, void *user_data)
+{{
+    cout << string_view(result, result_length) << "\n";
+}}
+
+int main(int argc, char **argv)
+{{
+    padded_string input = padded_string::load(argv[1]);
+    size_t length = input.size() + SIMDJSON_PADDING;
+    {statements}
+}}
+"#
+    )
+}
+
+/// Compiles the program from the main source file and the library source file, if it is not
+/// header-only, into the directory of the main source file, returning the path of the program.
+pub fn compile_cpp(main_src: &str, lib_src: Option<&str>) -> PathBuf {
+    let prog_path = Path::new(main_src).with_file_name("main");
+    let simdjson_path = env::var("SIMDJSON_PATH").expect("SIMDJSON_PATH is not set");
+    let status = Command::new("c++")
+        .arg(main_src)
+        .args(lib_src)
+        .arg(format!("{simdjson_path}/simdjson.cpp"))
+        .arg("-std=c++20")
+        .arg("-O3")
+        .arg(format!("-I{simdjson_path}"))
+        .arg("-o").arg(&prog_path)
+        .status()
+        .unwrap();
+    assert!(status.success(), "query code compilation failed");
+    prog_path
+}
+
+/// Compiles the program as [`compile_cpp`] does and runs it, returning its output.
+pub fn compile_and_run_cpp<I, S>(main_src: &str, lib_src: Option<&str>, args: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new(compile_cpp(main_src, lib_src)).args(args).output().unwrap();
+    assert!(output.status.success(), "query execution failed");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Compiles the queries to a simdjson ondemand library, `queries.hpp`, and runs the main code
+/// using it on the documents, returning its output.
+pub fn execute_lib(
+    compiler: LibGeneratingCompiler,
+    queries: &[(&str, &str)],
+    main_code: &str,
+    documents: &[&str],
+) -> String {
+    let execution_dir = create_execution_dir();
+    let queries = queries
+        .iter()
+        .map(|(name, query)| (name.to_string(), query.to_string()))
+        .collect();
+    compiler
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate { queries },
+            &format!("{execution_dir}/queries.hpp"),
+        )
+        .unwrap();
+    let main_file_path = format!("{execution_dir}/main.cpp");
+    fs::write(&main_file_path, main_code).unwrap();
+    compile_and_run_cpp(&main_file_path, None, write_documents(&execution_dir, documents))
+}
+
+/// Compiles the query to a standalone program and runs it on the documents followed by the
+/// arguments, returning its output.
+pub fn execute_standalone_prog<T: TargetCodeStandaloneProgGenerator>(
+    compiler: StandaloneProgGeneratingCompiler,
+    query: &str,
+    documents: &[&str],
+    args: &[&str],
+) -> String {
+    let execution_dir = create_execution_dir();
+    let query_file_path = format!("{execution_dir}/query.cpp");
+    compiler.compile::<T>(query, &query_file_path).unwrap();
+    let document_paths = write_documents(&execution_dir, documents);
+    let args = document_paths.iter().map(String::as_str).chain(args.iter().copied());
+    compile_and_run_cpp(&query_file_path, None, args)
+}
+
+fn write_documents(execution_dir: &str, documents: &[&str]) -> Vec<String> {
+    documents
+        .iter()
+        .enumerate()
+        .map(|(i, document)| {
+            let document_path = format!("{execution_dir}/document_{i}.json");
+            fs::write(&document_path, document).unwrap();
+            document_path
+        })
+        .collect()
+}
+
+/// Compilers of the libraries executing the query in the tests. Queries with filters or slices
+/// with negative steps cannot fall back to passing the active segments at runtime, so the limit
+/// of procedures is set only for the other queries.
+pub fn lib_compilers(query: &str) -> Vec<LibGeneratingCompiler> {
+    let mut compilers = vec![
+        LibGeneratingCompiler::new(),
+        LibGeneratingCompiler::new().with_eager_filter_evaluation(),
+    ];
+    if Interpreter::with_max_procedures(query, 1).is_ok() {
+        compilers.push(LibGeneratingCompiler::new().with_max_procedures(1));
+    }
+    compilers
+}
+
+/// Returns the results of the query on the document, as selected by the interpreter.
+pub fn interpreter_results(query: &str, document: &Value) -> Vec<Value> {
+    Interpreter::new(query).unwrap().run(document).into_iter().cloned().collect()
+}
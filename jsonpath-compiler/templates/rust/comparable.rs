@@ -0,0 +1,19 @@
+{%- match comparable -%}
+    {%- when Comparable::Param with {id} -%} &params[{{id}}].value
+    {%- when Comparable::Literal with {value} -%}
+        {%- match value -%}
+            {%- when LiteralValue::String with (str) -%} &Value::String(Cow::Borrowed({{ format!("{:?}", str) }}))
+            {%- when LiteralValue::Int with (x) -%} &Value::Int({{x}})
+            {%- when LiteralValue::Float with (x) -%} &Value::Float({{ format!("{:?}", x) }})
+            {%- when LiteralValue::Bool with (x) -%} &Value::Bool({{x}})
+            {%- when LiteralValue::Null -%} &Value::Null
+        {%- endmatch -%}
+    {%- when Comparable::FunctionCall with {function} -%}
+        {%- match function -%}
+            {%- when ValueFunction::Length with {arg} -%}
+                {%- let arg_template = ComparableTemplate::new(arg) -%}
+                &length_of({{ arg_template.render()? }})
+            {%- when ValueFunction::Count with {param_id} -%} &Value::Int(params[{{param_id}}].count)
+            {%- when ValueFunction::Value with {param_id} -%} &params[{{param_id}}].value
+        {%- endmatch -%}
+{%- endmatch -%}
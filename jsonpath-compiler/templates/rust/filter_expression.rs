@@ -0,0 +1,37 @@
+{%- match expression -%}
+    {%- when FilterExpression::Or with {lhs, rhs} -%}
+        {%- let lhs_template = FilterExpressionTemplate::new(lhs) -%}
+        {%- let rhs_template = FilterExpressionTemplate::new(rhs) -%}
+        ({{ lhs_template.render()? }} || {{ rhs_template.render()? }})
+    {%- when FilterExpression::And with {lhs, rhs} -%}
+        {%- let lhs_template = FilterExpressionTemplate::new(lhs) -%}
+        {%- let rhs_template = FilterExpressionTemplate::new(rhs) -%}
+        ({{ lhs_template.render()? }} && {{ rhs_template.render()? }})
+    {%- when FilterExpression::Not with {expr} -%}
+        {%- let template = FilterExpressionTemplate::new(expr) -%}
+        !({{ template.render()? }})
+    {%- when FilterExpression::Comparison with {lhs, rhs, op} -%}
+        {%- match op -%}
+            {%- when crate::ir::ComparisonOp::EqualTo -%} equal({%- call compile_comparable(lhs) -%}, {% call compile_comparable(rhs) -%})
+            {%- when crate::ir::ComparisonOp::NotEqualTo -%} !equal({%- call compile_comparable(lhs) -%}, {% call compile_comparable(rhs) -%})
+            {%- when crate::ir::ComparisonOp::LessOrEqualTo -%} less_or_equal({%- call compile_comparable(lhs) -%}, {% call compile_comparable(rhs) -%})
+            {%- when crate::ir::ComparisonOp::GreaterOrEqualTo -%} less_or_equal({%- call compile_comparable(rhs) -%}, {% call compile_comparable(lhs) -%})
+            {%- when crate::ir::ComparisonOp::LessThan -%} less({%- call compile_comparable(lhs) -%}, {% call compile_comparable(rhs) -%})
+            {%- when crate::ir::ComparisonOp::GreaterThan -%} less({%- call compile_comparable(rhs) -%}, {% call compile_comparable(lhs) -%})
+        {%- endmatch -%}
+    {%- when FilterExpression::ExistenceTest with {param_id} -%} params[{{param_id}}].exists
+    {%- when FilterExpression::FunctionCall with {function} -%}
+        {%- match function -%}
+            {%- when LogicalFunction::Match with {arg, regex_id, is_full_match} -%}
+                {%- if let Some(regex_id) = regex_id -%}
+                    REGEX_{{regex_id}}.matches({%- call compile_comparable(arg) -%}, {{is_full_match}})
+                {%- else -%}
+                    false
+                {%- endif -%}
+        {%- endmatch -%}
+{%- endmatch -%}
+
+{%- macro compile_comparable(comparable) -%}
+    {%- let template = ComparableTemplate::new(comparable) -%}
+    {{ template.render()? }}
+{%- endmacro -%}
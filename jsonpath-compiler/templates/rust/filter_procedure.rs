@@ -0,0 +1,19 @@
+fn {{name}}(params: &[SubqueryResult]) -> bool
+{
+    {%- for (regex_id, regex) in regexes.iter().enumerate() -%}
+        const REGEX_{{regex_id}}: Regex = Regex {
+            start: {{regex.start}},
+            states: &[
+            {%- for state in regex.states -%}
+                {%- match state -%}
+                    {%- when RegexState::Chars with { ranges, next } -%}
+                        RegexState::Chars { ranges: &[{%- for (start, end) in ranges -%} ({{start}}, {{end}}), {%- endfor -%}], next: {{next}} },
+                    {%- when RegexState::Split with { next, alt } -%} RegexState::Split { next: {{next}}, alt: {{alt}} },
+                    {%- when RegexState::Match -%} RegexState::Match,
+                {%- endmatch -%}
+            {%- endfor -%}
+            ],
+        };
+    {%- endfor -%}
+    {{expression.render()?}}
+}
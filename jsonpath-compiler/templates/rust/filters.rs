@@ -0,0 +1,285 @@
+use std::cmp::Ordering;
+
+#[derive(Clone, Debug)]
+enum Value<'a> {
+    Nothing,
+    String(Cow<'a, str>),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    // Canonical form of a complex value, in which the members of objects are ordered by their
+    // names and equal numbers are written in the same way. Complex values are equal if their
+    // canonical forms are.
+    Complex { length: i64, canonical_form: String },
+}
+
+struct SubqueryResult<'a> {
+    value: Value<'a>,
+    exists: bool,
+    count: i64,
+}
+
+fn equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Nothing, Value::Nothing) | (Value::Null, Value::Null) => true,
+        (Value::String(lhs), Value::String(rhs)) => lhs == rhs,
+        (Value::Bool(lhs), Value::Bool(rhs)) => lhs == rhs,
+        (
+            Value::Complex { canonical_form: lhs, .. },
+            Value::Complex { canonical_form: rhs, .. },
+        ) => lhs == rhs,
+        _ => compare_numbers(lhs, rhs) == Some(Ordering::Equal),
+    }
+}
+
+fn less(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::String(lhs), Value::String(rhs)) => lhs < rhs,
+        _ => compare_numbers(lhs, rhs) == Some(Ordering::Less),
+    }
+}
+
+fn less_or_equal(lhs: &Value, rhs: &Value) -> bool {
+    less(lhs, rhs) || equal(lhs, rhs)
+}
+
+fn compare_numbers(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => Some(lhs.cmp(rhs)),
+        (Value::Int(lhs), Value::Float(rhs)) => (*lhs as f64).partial_cmp(rhs),
+        (Value::Float(lhs), Value::Int(rhs)) => lhs.partial_cmp(&(*rhs as f64)),
+        (Value::Float(lhs), Value::Float(rhs)) => lhs.partial_cmp(rhs),
+        _ => None,
+    }
+}
+
+fn length_of<'a>(value: &Value) -> Value<'a> {
+    match value {
+        Value::String(str) => Value::Int(str.chars().count() as i64),
+        Value::Complex { length, .. } => Value::Int(*length),
+        _ => Value::Nothing,
+    }
+}
+
+fn number_value<'a>(text: &[u8]) -> Value<'a> {
+    let Ok(text) = std::str::from_utf8(text) else {
+        return Value::Nothing;
+    };
+    if !text.contains(['.', 'e', 'E']) {
+        if let Ok(number) = text.parse::<i64>() {
+            return Value::Int(number);
+        }
+    }
+    text.parse::<f64>().map_or(Value::Nothing, Value::Float)
+}
+
+fn node_value<'a>(input: &'a [u8], node: usize) -> Value<'a> {
+    let end = skip_value(input, node);
+    match peek(input, node) {
+        b'"' => Value::String(decode_string(&input[node + 1..end.max(node + 2) - 1])),
+        b'[' => Value::Complex {
+            length: array_length(input, node) as i64,
+            canonical_form: canonical_form(input, node),
+        },
+        b'{' => Value::Complex {
+            length: members(input, node).len() as i64,
+            canonical_form: canonical_form(input, node),
+        },
+        b't' => Value::Bool(true),
+        b'f' => Value::Bool(false),
+        b'n' => Value::Null,
+        _ => number_value(&input[node..end]),
+    }
+}
+
+fn canonical_form(input: &[u8], node: usize) -> String {
+    let mut buf = String::new();
+    append_canonical_form(input, node, &mut buf);
+    buf
+}
+
+fn append_canonical_string(str: &str, buf: &mut String) {
+    buf.push_str(&str.len().to_string());
+    buf.push('"');
+    buf.push_str(str);
+}
+
+fn append_canonical_form(input: &[u8], node: usize, buf: &mut String) {
+    match peek(input, node) {
+        b'[' => {
+            buf.push('[');
+            for element in elements(input, node) {
+                append_canonical_form(input, element, buf);
+                buf.push(',');
+            }
+            buf.push(']');
+        }
+        b'{' => {
+            let mut members: Vec<(Cow<str>, usize)> = members(input, node)
+                .into_iter()
+                .map(|(name, value)| (decode_string(name), value))
+                .collect();
+            members.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+            buf.push('{');
+            for (name, value) in members {
+                append_canonical_string(&name, buf);
+                buf.push(':');
+                append_canonical_form(input, value, buf);
+                buf.push(',');
+            }
+            buf.push('}');
+        }
+        _ => match node_value(input, node) {
+            Value::String(str) => append_canonical_string(&str, buf),
+            Value::Int(number) => buf.push_str(&number.to_string()),
+            Value::Float(number) if number == number.trunc() && number.abs() < 9223372036854775808.0 => {
+                buf.push_str(&(number as i64).to_string())
+            }
+            Value::Float(number) => buf.push_str(&format!("{number:?}")),
+            Value::Bool(value) => buf.push_str(if value { "true" } else { "false" }),
+            _ => buf.push_str("null"),
+        },
+    }
+}
+
+fn elements(input: &[u8], node: usize) -> Vec<usize> {
+    let mut elements = Vec::new();
+    if peek(input, node) == b'[' {
+        let mut iterator = ArrayIterator::new(input, node);
+        while let Some(element) = iterator.next_element() {
+            elements.push(element);
+            iterator.advance(input, skip_value(input, element));
+        }
+    }
+    elements
+}
+
+fn members(input: &[u8], node: usize) -> Vec<(&[u8], usize)> {
+    let mut members = Vec::new();
+    if peek(input, node) == b'{' {
+        let mut iterator = ObjectIterator::new(input, node);
+        while let Some((name, value)) = iterator.next_member(input) {
+            members.push((name, value));
+            iterator.advance(input, skip_value(input, value));
+        }
+    }
+    members
+}
+
+fn children(input: &[u8], node: usize) -> Vec<usize> {
+    match peek(input, node) {
+        b'[' => elements(input, node),
+        _ => members(input, node).into_iter().map(|(_, value)| value).collect(),
+    }
+}
+
+impl Regex {
+    fn matches(&self, value: &Value, is_full_match: bool) -> bool {
+        matches!(value, Value::String(str) if self.matches_str(str, is_full_match))
+    }
+}
+
+enum SubquerySelector {
+    Name(&'static str),
+    Index(i64),
+    Wildcard,
+    Slice { start: i64, end: Option<i64>, step: i64 },
+    Filter(fn(&Context, usize) -> bool),
+}
+
+impl SubquerySelector {
+    fn is_in_slice(start: i64, end: Option<i64>, step: i64, i: i64, length: i64) -> bool {
+        let bound = |index: i64| if index >= 0 { index } else { length + index };
+        if step > 0 {
+            let lower = bound(start).max(0);
+            if i < lower || end.is_some_and(|end| i >= bound(end)) {
+                return false;
+            }
+            (i - lower) % step == 0
+        } else if step < 0 {
+            let upper = bound(start).min(length - 1);
+            if i > upper || end.is_some_and(|end| i <= bound(end)) {
+                return false;
+            }
+            (upper - i) % -step == 0
+        } else {
+            false
+        }
+    }
+}
+
+struct SubquerySegment {
+    is_descendant: bool,
+    selectors: &'static [SubquerySelector],
+}
+
+struct Subquery {
+    segments: &'static [SubquerySegment],
+}
+
+fn select_children(ctx: &Context, subquery: &Subquery, segment_index: usize, node: usize, nodes: &mut Vec<usize>) {
+    let input = ctx.input;
+    for selector in subquery.segments[segment_index].selectors {
+        match selector {
+            SubquerySelector::Name(name) => {
+                let member = members(input, node).into_iter().find(|(raw_name, _)| name_equals(raw_name, name));
+                if let Some((_, child)) = member {
+                    select_nodes(ctx, subquery, segment_index + 1, child, nodes);
+                }
+            }
+            SubquerySelector::Index(index) => {
+                let elements = elements(input, node);
+                let index = if *index >= 0 { *index } else { elements.len() as i64 + index };
+                if let Some(&child) = usize::try_from(index).ok().and_then(|index| elements.get(index)) {
+                    select_nodes(ctx, subquery, segment_index + 1, child, nodes);
+                }
+            }
+            SubquerySelector::Wildcard => {
+                for child in children(input, node) {
+                    select_nodes(ctx, subquery, segment_index + 1, child, nodes);
+                }
+            }
+            SubquerySelector::Slice { start, end, step } => {
+                let elements = elements(input, node);
+                let length = elements.len() as i64;
+                for j in 0..length {
+                    let index = if *step > 0 { j } else { length - 1 - j };
+                    if SubquerySelector::is_in_slice(*start, *end, *step, index, length) {
+                        select_nodes(ctx, subquery, segment_index + 1, elements[index as usize], nodes);
+                    }
+                }
+            }
+            SubquerySelector::Filter(filter) => {
+                for child in children(input, node) {
+                    if filter(ctx, child) {
+                        select_nodes(ctx, subquery, segment_index + 1, child, nodes);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn select_nodes(ctx: &Context, subquery: &Subquery, segment_index: usize, node: usize, nodes: &mut Vec<usize>) {
+    if segment_index == subquery.segments.len() {
+        nodes.push(node);
+        return;
+    }
+    select_children(ctx, subquery, segment_index, node, nodes);
+    if subquery.segments[segment_index].is_descendant {
+        for child in children(ctx.input, node) {
+            select_nodes(ctx, subquery, segment_index, child, nodes);
+        }
+    }
+}
+
+fn evaluate_subquery<'a>(ctx: &Context<'a>, subquery: &Subquery, node: usize) -> SubqueryResult<'a> {
+    let mut nodes = Vec::new();
+    select_nodes(ctx, subquery, 0, node, &mut nodes);
+    SubqueryResult {
+        value: if nodes.len() == 1 { node_value(ctx.input, nodes[0]) } else { Value::Nothing },
+        exists: !nodes.is_empty(),
+        count: nodes.len() as i64,
+    }
+}
@@ -0,0 +1,148 @@
+{%- match instruction -%}
+    {%- when Instruction::ForEachElement with { instructions } -%}
+        if peek(input, node) == b'[' {
+            {%- if crate::targets::is_array_length_needed(instructions) -%}
+                let array_length = array_length(input, node);
+            {%- endif -%}
+            let mut elements = ArrayIterator::new(input, node);
+            let mut index: usize = 0;
+            while let Some(child) = elements.next_element() {
+                {%- call compile_child_instructions(instructions) -%}
+                elements.advance(input, child_end);
+                index += 1;
+            }
+            end = elements.end(input);
+        }
+    {%- when Instruction::ForEachMember with { instructions } -%}
+        if peek(input, node) == b'{' {
+            let mut members = ObjectIterator::new(input, node);
+            while let Some((key, child)) = members.next_member(input) {
+                {%- call compile_child_instructions(instructions) -%}
+                members.advance(input, child_end);
+            }
+            end = members.end(input);
+        }
+    {%- when Instruction::ForEachElementInSlices with { slices, instructions } -%}
+    {%- when Instruction::ForEachElementInReverseOrder with { query_index, instructions } -%}
+        if peek(input, node) == b'[' {
+            {%- if crate::targets::is_array_length_needed(instructions) -%}
+                let array_length = array_length(input, node);
+            {%- endif -%}
+            let mut elements = ArrayIterator::new(input, node);
+            let mut index: usize = 0;
+            let mut first_element_results = Vec::new();
+            while let Some(child) = elements.next_element() {
+                first_element_results.push(ctx.results.len());
+                {%- call compile_child_instructions(instructions) -%}
+                elements.advance(input, child_end);
+                index += 1;
+            }
+            end = elements.end(input);
+            ctx.reverse_results(&first_element_results);
+        }
+    {%- when Instruction::IfCurrentIndexEquals with { index, instructions } -%}
+        if index == {{index}} {
+            {%- call compile_instructions(instructions) -%}
+        }
+    {%- when Instruction::IfCurrentIndexFromEndEquals with { index, instructions } -%}
+        if index + {{index}} == array_length {
+            {%- call compile_instructions(instructions) -%}
+        }
+    {%- when Instruction::IfCurrentIndexInSlice with { slice, instructions } -%}
+        if {{ crate::targets::rust::compile_slice_condition(slice) }} {
+            {%- call compile_instructions(instructions) -%}
+        }
+    {%- when Instruction::IfCurrentMemberNameEquals with { name, instructions } -%}
+        if name_equals(key, {{ format!("{:?}", name) }}) {
+            {%- call compile_instructions(instructions) -%}
+        }
+    {%- when Instruction::IfActiveFilterInstance with { instructions } -%}
+    {%- when Instruction::IfSegmentActive with { segment_index, instructions } -%}
+        if active_segments[{{segment_index}}] {
+            {%- call compile_instructions(instructions) -%}
+        }
+    {%- when Instruction::ExecuteProcedureOnChild with { conditions, segments, name } -%}
+        {%- let procedure_name = Self::procedure_name(self, name) -%}
+        {%- if are_any_filters -%}
+            {
+                let mut new_segment_conditions = *segment_conditions;
+                {%- for (i, condition) in conditions.iter().enumerate() -%}
+                    {%- if let Some(condition) = condition -%}
+                        {%- let template = SelectionConditionTemplate::new(condition) -%}
+                        new_segment_conditions[{{i}}] = {{ template.render()? }}
+                    {%- else -%}
+                        new_segment_conditions[{{i}}] = segment_conditions[{{i}}]
+                    {%- endif -%}
+                    {%- if i > 0 -%}
+                        {{" "}}&& segment_conditions[{{i - 1}}]
+                    {%- endif -%}
+                    ;
+                {%- endfor -%}
+                {%- if !segments.is_empty() -%}
+                    if {% for segment in segments %}new_segment_conditions[{{segment}}]{% if !loop.last %} || {% endif %}{% endfor %}
+                {%- endif -%}
+                {
+                    {{current_node_end}} = {{procedure_name}}(ctx, {{current_node}}, &new_segment_conditions);
+                }
+            }
+        {%- else -%}
+            {{current_node_end}} = {{procedure_name}}(ctx, {{current_node}});
+        {%- endif -%}
+    {%- when Instruction::SaveCurrentNodeDuringTraversal with { condition, instruction, query_index } -%}
+        {%- if are_any_filters -%}
+            {%- if let Some(condition) = condition -%}
+                {%- let template = SelectionConditionTemplate::new(condition) -%}
+                if {{ template.render()? }}
+            {%- endif -%}
+        {%- endif -%}
+        {
+            ctx.save({{current_node}});
+        }
+        {%- let template = InstructionTemplate::new(instruction, current_node, current_node_end, query_name, are_any_filters.clone()) -%}
+        {{ template.render()? }}
+    {%- when Instruction::Continue -%}
+        break 'child;
+    {%- when Instruction::TraverseCurrentNodeSubtree -%}
+    {%- when Instruction::StartFilterExecution with { filter_id } -%}
+        let filter_{{filter_id}}_value = {{ crate::targets::rust::name_prefix(query_name) }}filter_{{filter_id}}_evaluate(ctx, {{current_node}});
+    {%- when Instruction::EndFiltersExecution -%}
+    {%- when Instruction::UpdateSubqueriesState -%}
+    {%- when Instruction::ExecuteProcedureOnChildWithActiveSegments with { name, segments_count, instructions } -%}
+        {
+            let mut child_active_segments = [false; {{segments_count}}];
+            let mut is_child_selected = false;
+            {%- call compile_instructions(instructions) -%}
+            if is_child_selected {
+                ctx.save({{current_node}});
+            }
+            if child_active_segments.contains(&true) {
+                {{current_node_end}} = {{ Self::procedure_name(self, name) }}(ctx, {{current_node}}, &child_active_segments);
+            }
+        }
+    {%- when Instruction::ActivateSegmentInChild with { segment_index } -%}
+        child_active_segments[{{segment_index}}] = true;
+    {%- when Instruction::SelectChild -%}
+        is_child_selected = true;
+{%- endmatch -%}
+
+{%- macro compile_instructions(instructions) -%}
+    {%- for instruction in instructions -%}
+        {%- let template = InstructionTemplate::new(instruction, current_node, current_node_end, query_name, are_any_filters.clone()) -%}
+        {{ template.render()? }}
+    {%- endfor -%}
+{%- endmacro -%}
+
+{%- macro compile_child_instructions(instructions) -%}
+    let first_open_child_result = ctx.open_results.len();
+    let mut child_end = 0;
+    'child: {
+        {%- for instruction in instructions -%}
+            {%- let template = InstructionTemplate::new(instruction, "child", "child_end", query_name, are_any_filters.clone()) -%}
+            {{ template.render()? }}
+        {%- endfor -%}
+    }
+    if child_end == 0 {
+        child_end = skip_value(input, child);
+    }
+    ctx.close_results(first_open_child_result, child_end);
+{%- endmacro -%}
@@ -0,0 +1,37 @@
+{%- import "macros.rs" as common -%}
+#![allow(dead_code, unused_labels, unused_assignments, unused_mut, unused_parens, unused_variables)]
+
+{%- include "runtime.rs" -%}
+
+{%- if Self::are_any_filters(self) -%}
+    {%- include "filters.rs" -%}
+    {%- include "regex.rs" -%}
+
+    {%- for query_name in query_names -%}
+        {%- if Self::are_any_filters_in_query(self, query_name) -%}
+            const {{ crate::targets::rust::segment_count_name(query_name) }}: usize = {{Self::query_segments_count(self, query_name)}};
+            {%- call common::generate_filters_definitions(Self::query_filter_subqueries(self, query_name), query_name.to_string(), "usize") -%}
+        {%- endif -%}
+    {%- endfor -%}
+{%- endif -%}
+
+{%- for query_name in query_names -%}
+    pub fn {{query_name}}(input: &[u8]) -> Result<String, InvalidJson> {
+        let mut ctx = Context::new(input)?;
+        let root = ctx.root;
+        {%- if Self::are_any_filters_in_query(self, query_name) -%}
+            {{query_name}}_selectors_0(&mut ctx, root, &[true; {{ crate::targets::rust::segment_count_name(query_name) }}]);
+        {%- else -%}
+            {{query_name}}_selectors_0(&mut ctx, root);
+        {%- endif -%}
+        Ok(ctx.results_json(" "))
+    }
+{%- endfor -%}
+
+{%- for procedure in procedures -%}
+    {{ procedure.render()? }}
+{%- endfor -%}
+
+{%- for filter_procedure in Self::all_filters_procedures(self) -%}
+    {{ filter_procedure.render()? }}
+{%- endfor -%}
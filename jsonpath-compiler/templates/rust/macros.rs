@@ -0,0 +1,43 @@
+{%- macro generate_filters_definitions(filter_subqueries, query_name, node_type) -%}
+    {%- let prefix = crate::targets::rust::name_prefix(query_name) -%}
+    {%- for (filter_id, subqueries) in filter_subqueries -%}
+        {%- for (subquery_index, subquery) in subqueries.iter().enumerate() -%}
+            static {{prefix|upper}}FILTER_{{filter_id}}_SUBQUERY_{{subquery_index}}: Subquery = Subquery {
+                segments: &[
+                {%- for segment in subquery.segments -%}
+                    SubquerySegment {
+                        is_descendant: {{segment.is_descendant}},
+                        selectors: &[
+                        {%- for selector in segment.selectors -%}
+                            {%- match selector -%}
+                                {%- when FilterSubquerySelector::Name(name) -%}
+                                    SubquerySelector::Name({{ format!("{:?}", name) }}),
+                                {%- when FilterSubquerySelector::Index(index) -%}
+                                    SubquerySelector::Index({{index}}),
+                                {%- when FilterSubquerySelector::Wildcard -%}
+                                    SubquerySelector::Wildcard,
+                                {%- when FilterSubquerySelector::Slice(slice) -%}
+                                    SubquerySelector::Slice { start: {{slice.start}}, end: {{ format!("{:?}", slice.end) }}, step: {{slice.step}} },
+                                {%- when FilterSubquerySelector::Filter(nested_filter_id) -%}
+                                    SubquerySelector::Filter({{prefix}}filter_{{nested_filter_id}}_evaluate),
+                            {%- endmatch -%}
+                        {%- endfor -%}
+                        ],
+                    },
+                {%- endfor -%}
+                ],
+            };
+        {%- endfor -%}
+        fn {{prefix}}filter_{{filter_id}}_evaluate(ctx: &Context, node: {{node_type}}) -> bool {
+            {{prefix}}filter_{{filter_id}}(&[
+            {%- for (subquery_index, subquery) in subqueries.iter().enumerate() -%}
+                {%- if subquery.is_absolute -%}
+                    evaluate_subquery(ctx, &{{prefix|upper}}FILTER_{{filter_id}}_SUBQUERY_{{subquery_index}}, ctx.root),
+                {%- else -%}
+                    evaluate_subquery(ctx, &{{prefix|upper}}FILTER_{{filter_id}}_SUBQUERY_{{subquery_index}}, node),
+                {%- endif -%}
+            {%- endfor -%}
+            ])
+        }
+    {%- endfor -%}
+{%- endmacro -%}
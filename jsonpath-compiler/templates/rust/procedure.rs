@@ -0,0 +1,20 @@
+{%- if are_any_filters -%}
+    fn {{name}}(ctx: &mut Context, node: usize, segment_conditions: &[bool; {{ crate::targets::rust::segment_count_name(query_name) }}]) -> usize
+{%- else if are_active_segments_passed -%}
+    fn {{name}}(ctx: &mut Context, node: usize, active_segments: &[bool]) -> usize
+{%- else -%}
+    fn {{name}}(ctx: &mut Context, node: usize) -> usize
+{%- endif -%}
+{
+    let input = ctx.input;
+    let first_open_result = ctx.open_results.len();
+    let mut end = 0;
+    {%- for instruction in instructions -%}
+        {{ instruction.render()? }}
+    {%- endfor -%}
+    if end == 0 {
+        end = skip_value(input, node);
+    }
+    ctx.close_results(first_open_result, end);
+    end
+}
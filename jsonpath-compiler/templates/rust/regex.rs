@@ -0,0 +1,72 @@
+enum RegexState {
+    Chars { ranges: &'static [(u32, u32)], next: usize },
+    Split { next: usize, alt: usize },
+    Match,
+}
+
+struct Regex {
+    start: usize,
+    states: &'static [RegexState],
+}
+
+impl Regex {
+    fn matches_str(&self, str: &str, is_full_match: bool) -> bool {
+        let mut current_states = Vec::new();
+        let mut next_states = Vec::new();
+        let mut added_at_step = vec![usize::MAX; self.states.len()];
+        let mut pos = 0;
+        for step in 0.. {
+            if pos == 0 || !is_full_match {
+                self.add_state(&mut current_states, self.start, step, &mut added_at_step);
+            }
+            if current_states.iter().any(|&state| {
+                matches!(self.states[state], RegexState::Match) && (!is_full_match || pos == str.len())
+            }) {
+                return true;
+            }
+            if pos == str.len() || (is_full_match && current_states.is_empty()) {
+                return false;
+            }
+            let c = str[pos..].chars().next().unwrap();
+            pos += c.len_utf8();
+            next_states.clear();
+            for &state in &current_states {
+                if let RegexState::Chars { ranges, next } = self.states[state] {
+                    if Self::contains(ranges, c as u32) {
+                        self.add_state(&mut next_states, next, step + 1, &mut added_at_step);
+                    }
+                }
+            }
+            std::mem::swap(&mut current_states, &mut next_states);
+        }
+        false
+    }
+
+    fn add_state(&self, states: &mut Vec<usize>, state: usize, step: usize, added_at_step: &mut [usize]) {
+        if added_at_step[state] == step {
+            return;
+        }
+        added_at_step[state] = step;
+        match self.states[state] {
+            RegexState::Split { next, alt } => {
+                self.add_state(states, next, step, added_at_step);
+                self.add_state(states, alt, step, added_at_step);
+            }
+            _ => states.push(state),
+        }
+    }
+
+    fn contains(ranges: &[(u32, u32)], c: u32) -> bool {
+        ranges
+            .binary_search_by(|&(start, end)| {
+                if c < start {
+                    std::cmp::Ordering::Greater
+                } else if c > end {
+                    std::cmp::Ordering::Less
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+}
@@ -0,0 +1,384 @@
+use std::borrow::Cow;
+
+struct Context<'a> {
+    input: &'a [u8],
+    root: usize,
+    // Selected nodes as spans of the input, in the order of their selection.
+    results: Vec<(usize, usize)>,
+    // Indices of the results whose ends are not known yet, because they are being traversed.
+    open_results: Vec<usize>,
+}
+
+impl<'a> Context<'a> {
+    fn new(input: &'a [u8]) -> Result<Context<'a>, InvalidJson> {
+        validate(input)?;
+        Ok(Context {
+            input,
+            root: skip_whitespace(input, 0),
+            results: Vec::new(),
+            open_results: Vec::new(),
+        })
+    }
+
+    fn save(&mut self, node: usize) {
+        self.open_results.push(self.results.len());
+        self.results.push((node, node));
+    }
+
+    fn close_results(&mut self, first_open_result: usize, end: usize) {
+        for result in self.open_results.drain(first_open_result..) {
+            self.results[result].1 = end;
+        }
+    }
+
+    // Reverses the order of the results of consecutive elements, given the index of the first
+    // result of each of them, keeping the order of the results of every element.
+    fn reverse_results(&mut self, first_element_results: &[usize]) {
+        let Some(&first_result) = first_element_results.first() else {
+            return;
+        };
+        let mut reversed_results = Vec::with_capacity(self.results.len() - first_result);
+        let mut end = self.results.len();
+        for &start in first_element_results.iter().rev() {
+            reversed_results.extend_from_slice(&self.results[start..end]);
+            end = start;
+        }
+        self.results.truncate(first_result);
+        self.results.append(&mut reversed_results);
+    }
+
+    fn results_json(&self, indent: &str) -> String {
+        let mut json = String::from("[\n");
+        for (i, &(start, end)) in self.results.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(indent);
+            json.push_str(&String::from_utf8_lossy(&self.input[start..end]));
+        }
+        json.push_str("]\n");
+        json
+    }
+}
+
+/// Error returned when the input is not a single JSON value, with the position of the first
+/// byte at which it is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidJson {
+    pub position: usize,
+}
+
+impl std::fmt::Display for InvalidJson {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid JSON at byte {}", self.position)
+    }
+}
+
+impl std::error::Error for InvalidJson {}
+
+// Checks the whole input once before it is traversed, as the nodes are skipped assuming that
+// they are well-formed.
+fn validate(input: &[u8]) -> Result<(), InvalidJson> {
+    if let Err(error) = std::str::from_utf8(input) {
+        return Err(InvalidJson { position: error.valid_up_to() });
+    }
+    // Whether each of the containers enclosing the current value is an object.
+    let mut containers = Vec::new();
+    let mut pos = skip_whitespace(input, 0);
+    loop {
+        match peek(input, pos) {
+            opening @ (b'{' | b'[') => {
+                let is_object = opening == b'{';
+                pos = skip_whitespace(input, pos + 1);
+                if peek(input, pos) == if is_object { b'}' } else { b']' } {
+                    pos += 1;
+                } else {
+                    containers.push(is_object);
+                    if is_object {
+                        pos = validate_member_name(input, pos)?;
+                    }
+                    continue;
+                }
+            }
+            b'"' => pos = validate_string(input, pos)?,
+            b'-' | b'0'..=b'9' => pos = validate_number(input, pos)?,
+            b't' => pos = validate_literal(input, pos, b"true")?,
+            b'f' => pos = validate_literal(input, pos, b"false")?,
+            b'n' => pos = validate_literal(input, pos, b"null")?,
+            _ => return Err(InvalidJson { position: pos }),
+        }
+        // Closes the containers ending after the value, up to the start of the next one.
+        loop {
+            pos = skip_whitespace(input, pos);
+            let Some(&is_object) = containers.last() else {
+                return if pos == input.len() { Ok(()) } else { Err(InvalidJson { position: pos }) };
+            };
+            match peek(input, pos) {
+                b',' => {
+                    pos = skip_whitespace(input, pos + 1);
+                    if is_object {
+                        pos = validate_member_name(input, pos)?;
+                    }
+                    break;
+                }
+                b'}' if is_object => pos += 1,
+                b']' if !is_object => pos += 1,
+                _ => return Err(InvalidJson { position: pos }),
+            }
+            containers.pop();
+        }
+    }
+}
+
+// Returns the position of the value of the member whose name starts at `pos`.
+fn validate_member_name(input: &[u8], pos: usize) -> Result<usize, InvalidJson> {
+    if peek(input, pos) != b'"' {
+        return Err(InvalidJson { position: pos });
+    }
+    let pos = skip_whitespace(input, validate_string(input, pos)?);
+    if peek(input, pos) != b':' {
+        return Err(InvalidJson { position: pos });
+    }
+    Ok(skip_whitespace(input, pos + 1))
+}
+
+fn validate_string(input: &[u8], mut pos: usize) -> Result<usize, InvalidJson> {
+    pos += 1;
+    loop {
+        match peek(input, pos) {
+            b'"' => return Ok(pos + 1),
+            b'\\' => match peek(input, pos + 1) {
+                b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => pos += 2,
+                b'u' if input.get(pos + 2..pos + 6).is_some_and(|digits| digits.iter().all(u8::is_ascii_hexdigit)) => {
+                    pos += 6
+                }
+                _ => return Err(InvalidJson { position: pos }),
+            },
+            // Control characters, including the end of the input, are not allowed in strings.
+            0x00..=0x1f => return Err(InvalidJson { position: pos }),
+            _ => pos += 1,
+        }
+    }
+}
+
+fn validate_number(input: &[u8], mut pos: usize) -> Result<usize, InvalidJson> {
+    let skip_digits = |pos: usize| -> Result<usize, InvalidJson> {
+        let end = pos + input[pos.min(input.len())..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if end == pos { Err(InvalidJson { position: pos }) } else { Ok(end) }
+    };
+    if peek(input, pos) == b'-' {
+        pos += 1;
+    }
+    pos = if peek(input, pos) == b'0' { pos + 1 } else { skip_digits(pos)? };
+    if peek(input, pos) == b'.' {
+        pos = skip_digits(pos + 1)?;
+    }
+    if matches!(peek(input, pos), b'e' | b'E') {
+        pos += 1;
+        if matches!(peek(input, pos), b'+' | b'-') {
+            pos += 1;
+        }
+        pos = skip_digits(pos)?;
+    }
+    Ok(pos)
+}
+
+fn validate_literal(input: &[u8], pos: usize, literal: &[u8]) -> Result<usize, InvalidJson> {
+    if input[pos..].starts_with(literal) {
+        Ok(pos + literal.len())
+    } else {
+        Err(InvalidJson { position: pos })
+    }
+}
+
+fn peek(input: &[u8], pos: usize) -> u8 {
+    input.get(pos).copied().unwrap_or(0)
+}
+
+fn skip_whitespace(input: &[u8], mut pos: usize) -> usize {
+    while matches!(peek(input, pos), b' ' | b'\t' | b'\n' | b'\r') {
+        pos += 1;
+    }
+    pos
+}
+
+// Returns the position after the string starting at `pos`.
+fn skip_string(input: &[u8], mut pos: usize) -> usize {
+    pos += 1;
+    while let Some(offset) = input[pos.min(input.len())..].iter().position(|&c| c == b'"' || c == b'\\') {
+        pos += offset;
+        if input[pos] == b'"' {
+            return pos + 1;
+        }
+        pos += 2;
+    }
+    input.len()
+}
+
+// Returns the position after the value starting at `pos`, without looking into the value
+// further than needed to find its end.
+fn skip_value(input: &[u8], mut pos: usize) -> usize {
+    match peek(input, pos) {
+        b'"' => skip_string(input, pos),
+        b'{' | b'[' => {
+            let mut depth = 0usize;
+            while pos < input.len() {
+                match input[pos] {
+                    b'"' => {
+                        pos = skip_string(input, pos);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return pos + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                pos += 1;
+            }
+            input.len()
+        }
+        _ => {
+            while pos < input.len() && !matches!(input[pos], b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r') {
+                pos += 1;
+            }
+            pos
+        }
+    }
+}
+
+struct ObjectIterator {
+    pos: usize,
+    done: bool,
+}
+
+impl ObjectIterator {
+    fn new(input: &[u8], node: usize) -> ObjectIterator {
+        let pos = skip_whitespace(input, node + 1);
+        ObjectIterator { pos, done: peek(input, pos) != b'"' }
+    }
+
+    // Returns the raw name and the position of the value of the next member.
+    fn next_member<'a>(&mut self, input: &'a [u8]) -> Option<(&'a [u8], usize)> {
+        if self.done {
+            return None;
+        }
+        let name_end = skip_string(input, self.pos);
+        let name = input.get(self.pos + 1..name_end - 1).unwrap_or(&[]);
+        let value = skip_whitespace(input, skip_whitespace(input, name_end) + 1);
+        Some((name, value))
+    }
+
+    fn advance(&mut self, input: &[u8], value_end: usize) {
+        self.pos = skip_whitespace(input, value_end);
+        if peek(input, self.pos) == b',' {
+            self.pos = skip_whitespace(input, self.pos + 1);
+            self.done = peek(input, self.pos) != b'"';
+        } else {
+            self.done = true;
+        }
+    }
+
+    // Returns the position after the object, once all of its members are iterated.
+    fn end(&self, input: &[u8]) -> usize {
+        (self.pos + 1).min(input.len())
+    }
+}
+
+struct ArrayIterator {
+    pos: usize,
+    done: bool,
+}
+
+impl ArrayIterator {
+    fn new(input: &[u8], node: usize) -> ArrayIterator {
+        let pos = skip_whitespace(input, node + 1);
+        ArrayIterator { pos, done: matches!(peek(input, pos), b']' | 0) }
+    }
+
+    fn next_element(&mut self) -> Option<usize> {
+        if self.done {
+            None
+        } else {
+            Some(self.pos)
+        }
+    }
+
+    fn advance(&mut self, input: &[u8], value_end: usize) {
+        self.pos = skip_whitespace(input, value_end);
+        if peek(input, self.pos) == b',' {
+            self.pos = skip_whitespace(input, self.pos + 1);
+            self.done = peek(input, self.pos) == 0;
+        } else {
+            self.done = true;
+        }
+    }
+
+    fn end(&self, input: &[u8]) -> usize {
+        (self.pos + 1).min(input.len())
+    }
+}
+
+fn array_length(input: &[u8], node: usize) -> usize {
+    let mut elements = ArrayIterator::new(input, node);
+    let mut length = 0;
+    while let Some(element) = elements.next_element() {
+        elements.advance(input, skip_value(input, element));
+        length += 1;
+    }
+    length
+}
+
+fn decode_string(raw: &[u8]) -> Cow<'_, str> {
+    if !raw.contains(&b'\\') {
+        return String::from_utf8_lossy(raw);
+    }
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] != b'\\' {
+            bytes.push(raw[i]);
+            i += 1;
+            continue;
+        }
+        let escaped = peek(raw, i + 1);
+        i += 2;
+        let c = match escaped {
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let mut code_point = hex_code_unit(raw, i).unwrap_or(0xFFFD);
+                i += 4;
+                if (0xD800..0xDC00).contains(&code_point) && raw.get(i..i + 2) == Some(b"\\u") {
+                    if let Some(low) = hex_code_unit(raw, i + 2).filter(|low| (0xDC00..0xE000).contains(low)) {
+                        code_point = 0x10000 + ((code_point - 0xD800) << 10) + (low - 0xDC00);
+                        i += 6;
+                    }
+                }
+                char::from_u32(code_point).unwrap_or('\u{FFFD}')
+            }
+            c => c as char,
+        };
+        bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+    Cow::Owned(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn hex_code_unit(raw: &[u8], pos: usize) -> Option<u32> {
+    let digits = std::str::from_utf8(raw.get(pos..pos + 4)?).ok()?;
+    u32::from_str_radix(digits, 16).ok()
+}
+
+fn name_equals(raw_name: &[u8], name: &str) -> bool {
+    if raw_name.contains(&b'\\') {
+        decode_string(raw_name) == name
+    } else {
+        raw_name == name.as_bytes()
+    }
+}
@@ -0,0 +1,14 @@
+{%- match condition -%}
+    {%- when SelectionCondition::Filter with {id} -%}
+        filter_{{id}}_value
+    {%- when SelectionCondition::RuntimeSegmentCondition with {segment_index} -%}
+        segment_conditions[{{segment_index}}]
+    {%- when SelectionCondition::And with {lhs, rhs} -%}
+        {%- let lhs_template = SelectionConditionTemplate::new(lhs) -%}
+        {%- let rhs_template = SelectionConditionTemplate::new(rhs) -%}
+        ({{ lhs_template.render()? }} && {{ rhs_template.render()? }})
+    {%- when SelectionCondition::Or with {lhs, rhs} -%}
+        {%- let lhs_template = SelectionConditionTemplate::new(lhs) -%}
+        {%- let rhs_template = SelectionConditionTemplate::new(rhs) -%}
+        ({{ lhs_template.render()? }} || {{ rhs_template.render()? }})
+{%- endmatch -%}
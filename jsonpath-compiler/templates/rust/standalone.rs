@@ -0,0 +1,33 @@
+{%- import "macros.rs" as common -%}
+#![allow(dead_code, unused_labels, unused_assignments, unused_mut, unused_parens, unused_variables)]
+
+{%- include "runtime.rs" -%}
+
+{%- if Self::are_any_filters(self) -%}
+    {%- include "filters.rs" -%}
+    {%- include "regex.rs" -%}
+
+    const SEGMENT_COUNT: usize = {{segments_count}};
+
+    {%- call common::generate_filters_definitions(filter_subqueries, "", "usize") -%}
+{%- endif -%}
+
+fn main() {
+    let input = std::fs::read(std::env::args().nth(1).expect("no input file given")).expect("cannot read the input file");
+    let mut ctx = Context::new(&input).expect("invalid input document");
+    let root = ctx.root;
+    {%- if Self::are_any_filters(self) -%}
+        selectors_0(&mut ctx, root, &[true; SEGMENT_COUNT]);
+    {%- else -%}
+        selectors_0(&mut ctx, root);
+    {%- endif -%}
+    print!("{}", ctx.results_json("  "));
+}
+
+{%- for procedure in procedures -%}
+    {{ procedure.render()? }}
+{%- endfor -%}
+
+{%- for filter_procedure in filter_procedures -%}
+    {{ filter_procedure.render()? }}
+{%- endfor -%}
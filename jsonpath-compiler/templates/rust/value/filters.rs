@@ -0,0 +1,223 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+struct Context<'a> {
+    root: &'a JsonValue,
+}
+
+#[derive(Clone, Debug)]
+enum Value<'a> {
+    Nothing,
+    String(Cow<'a, str>),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    Complex(&'a JsonValue),
+}
+
+impl<'a> Value<'a> {
+    fn from_node(node: &'a JsonValue) -> Value<'a> {
+        match node {
+            JsonValue::String(str) => Value::String(Cow::Borrowed(str)),
+            JsonValue::Number(number) => match number.as_i64() {
+                Some(number) => Value::Int(number),
+                None => number.as_f64().map_or(Value::Nothing, Value::Float),
+            },
+            JsonValue::Bool(value) => Value::Bool(*value),
+            JsonValue::Null => Value::Null,
+            _ => Value::Complex(node),
+        }
+    }
+}
+
+struct SubqueryResult<'a> {
+    value: Value<'a>,
+    exists: bool,
+    count: i64,
+}
+
+fn equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Nothing, Value::Nothing) | (Value::Null, Value::Null) => true,
+        (Value::String(lhs), Value::String(rhs)) => lhs == rhs,
+        (Value::Bool(lhs), Value::Bool(rhs)) => lhs == rhs,
+        (Value::Complex(lhs), Value::Complex(rhs)) => nodes_equal(lhs, rhs),
+        _ => compare_numbers(lhs, rhs) == Some(Ordering::Equal),
+    }
+}
+
+// Structural equality of values, under which numbers are equal if their values are, regardless
+// of how they are represented.
+fn nodes_equal(lhs: &JsonValue, rhs: &JsonValue) -> bool {
+    match (lhs, rhs) {
+        (JsonValue::Array(lhs), JsonValue::Array(rhs)) => {
+            lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| nodes_equal(lhs, rhs))
+        }
+        (JsonValue::Object(lhs), JsonValue::Object(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs.iter().all(|(name, lhs)| rhs.get(name).is_some_and(|rhs| nodes_equal(lhs, rhs)))
+        }
+        (JsonValue::Number(_), JsonValue::Number(_)) => {
+            compare_numbers(&Value::from_node(lhs), &Value::from_node(rhs)) == Some(Ordering::Equal)
+        }
+        _ => lhs == rhs,
+    }
+}
+
+fn less(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::String(lhs), Value::String(rhs)) => lhs < rhs,
+        _ => compare_numbers(lhs, rhs) == Some(Ordering::Less),
+    }
+}
+
+fn less_or_equal(lhs: &Value, rhs: &Value) -> bool {
+    less(lhs, rhs) || equal(lhs, rhs)
+}
+
+fn compare_numbers(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => Some(lhs.cmp(rhs)),
+        (Value::Int(lhs), Value::Float(rhs)) => (*lhs as f64).partial_cmp(rhs),
+        (Value::Float(lhs), Value::Int(rhs)) => lhs.partial_cmp(&(*rhs as f64)),
+        (Value::Float(lhs), Value::Float(rhs)) => lhs.partial_cmp(rhs),
+        _ => None,
+    }
+}
+
+fn length_of<'a>(value: &Value) -> Value<'a> {
+    match value {
+        Value::String(str) => Value::Int(str.chars().count() as i64),
+        Value::Complex(JsonValue::Array(array)) => Value::Int(array.len() as i64),
+        Value::Complex(JsonValue::Object(object)) => Value::Int(object.len() as i64),
+        _ => Value::Nothing,
+    }
+}
+
+impl Regex {
+    fn matches(&self, value: &Value, is_full_match: bool) -> bool {
+        matches!(value, Value::String(str) if self.matches_str(str, is_full_match))
+    }
+}
+
+enum SubquerySelector {
+    Name(&'static str),
+    Index(i64),
+    Wildcard,
+    Slice { start: i64, end: Option<i64>, step: i64 },
+    Filter(fn(&Context, &JsonValue) -> bool),
+}
+
+impl SubquerySelector {
+    fn is_in_slice(start: i64, end: Option<i64>, step: i64, i: i64, length: i64) -> bool {
+        let bound = |index: i64| if index >= 0 { index } else { length + index };
+        if step > 0 {
+            let lower = bound(start).max(0);
+            if i < lower || end.is_some_and(|end| i >= bound(end)) {
+                return false;
+            }
+            (i - lower) % step == 0
+        } else if step < 0 {
+            let upper = bound(start).min(length - 1);
+            if i > upper || end.is_some_and(|end| i <= bound(end)) {
+                return false;
+            }
+            (upper - i) % -step == 0
+        } else {
+            false
+        }
+    }
+}
+
+struct SubquerySegment {
+    is_descendant: bool,
+    selectors: &'static [SubquerySelector],
+}
+
+struct Subquery {
+    segments: &'static [SubquerySegment],
+}
+
+fn children(node: &JsonValue) -> Box<dyn Iterator<Item = &JsonValue> + '_> {
+    match node {
+        JsonValue::Array(array) => Box::new(array.iter()),
+        JsonValue::Object(object) => Box::new(object.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn select_children<'a>(
+    ctx: &Context,
+    subquery: &Subquery,
+    segment_index: usize,
+    node: &'a JsonValue,
+    nodes: &mut Vec<&'a JsonValue>,
+) {
+    for selector in subquery.segments[segment_index].selectors {
+        match (selector, node) {
+            (SubquerySelector::Name(name), JsonValue::Object(object)) => {
+                if let Some(child) = object.get(*name) {
+                    select_nodes(ctx, subquery, segment_index + 1, child, nodes);
+                }
+            }
+            (SubquerySelector::Index(index), JsonValue::Array(array)) => {
+                let index = if *index >= 0 { *index } else { array.len() as i64 + index };
+                if let Some(child) = usize::try_from(index).ok().and_then(|index| array.get(index)) {
+                    select_nodes(ctx, subquery, segment_index + 1, child, nodes);
+                }
+            }
+            (SubquerySelector::Wildcard, _) => {
+                for child in children(node) {
+                    select_nodes(ctx, subquery, segment_index + 1, child, nodes);
+                }
+            }
+            (SubquerySelector::Slice { start, end, step }, JsonValue::Array(array)) => {
+                let length = array.len() as i64;
+                for j in 0..length {
+                    let index = if *step > 0 { j } else { length - 1 - j };
+                    if SubquerySelector::is_in_slice(*start, *end, *step, index, length) {
+                        select_nodes(ctx, subquery, segment_index + 1, &array[index as usize], nodes);
+                    }
+                }
+            }
+            (SubquerySelector::Filter(filter), _) => {
+                for child in children(node) {
+                    if filter(ctx, child) {
+                        select_nodes(ctx, subquery, segment_index + 1, child, nodes);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn select_nodes<'a>(
+    ctx: &Context,
+    subquery: &Subquery,
+    segment_index: usize,
+    node: &'a JsonValue,
+    nodes: &mut Vec<&'a JsonValue>,
+) {
+    if segment_index == subquery.segments.len() {
+        nodes.push(node);
+        return;
+    }
+    select_children(ctx, subquery, segment_index, node, nodes);
+    if subquery.segments[segment_index].is_descendant {
+        for child in children(node) {
+            select_nodes(ctx, subquery, segment_index, child, nodes);
+        }
+    }
+}
+
+fn evaluate_subquery<'a>(ctx: &Context, subquery: &Subquery, node: &'a JsonValue) -> SubqueryResult<'a> {
+    let mut nodes = Vec::new();
+    select_nodes(ctx, subquery, 0, node, &mut nodes);
+    SubqueryResult {
+        value: if nodes.len() == 1 { Value::from_node(nodes[0]) } else { Value::Nothing },
+        exists: !nodes.is_empty(),
+        count: nodes.len() as i64,
+    }
+}
@@ -0,0 +1,122 @@
+{%- match instruction -%}
+    {%- when Instruction::ForEachElement with { instructions } -%}
+        if let JsonValue::Array(array) = node {
+            let array_length = array.len();
+            for (index, element) in array.iter().enumerate() {
+                {%- call compile_instructions(instructions, "element") -%}
+            }
+        }
+    {%- when Instruction::ForEachMember with { instructions } -%}
+        if let JsonValue::Object(object) = node {
+            for (key, value) in object {
+                {%- call compile_instructions(instructions, "value") -%}
+            }
+        }
+    {%- when Instruction::ForEachElementInSlices with { slices, instructions } -%}
+        if let JsonValue::Array(array) = node {
+            let array_length = array.len() as i64;
+            {%- for slice in slices -%}
+                for index in {{ crate::targets::rust::compile_slice_indices(slice) }} {
+                    let element = &array[index as usize];
+                    {%- call compile_instructions(instructions, "element") -%}
+                }
+            {%- endfor -%}
+        }
+    {%- when Instruction::ForEachElementInReverseOrder with { query_index, instructions } -%}
+        if let JsonValue::Array(array) = node {
+            let array_length = array.len();
+            for (index, element) in array.iter().enumerate().rev() {
+                {%- call compile_instructions(instructions, "element") -%}
+            }
+        }
+    {%- when Instruction::IfCurrentIndexEquals with { index, instructions } -%}
+        if index == {{index}} {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
+    {%- when Instruction::IfCurrentIndexFromEndEquals with { index, instructions } -%}
+        if index + {{index}} == array_length {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
+    {%- when Instruction::IfCurrentIndexInSlice with { slice, instructions } -%}
+        if {{ crate::targets::rust::compile_slice_condition(slice) }} {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
+    {%- when Instruction::IfCurrentMemberNameEquals with { name, instructions } -%}
+        if key == {{ format!("{:?}", name) }} {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
+    {%- when Instruction::IfActiveFilterInstance with { instructions } -%}
+    {%- when Instruction::IfSegmentActive with { segment_index, instructions } -%}
+        if active_segments[{{segment_index}}] {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
+    {%- when Instruction::ExecuteProcedureOnChild with { conditions, segments, name } -%}
+        {%- let procedure_name = Self::procedure_name(self, name) -%}
+        {%- if are_any_filters -%}
+            {
+                let mut new_segment_conditions = *segment_conditions;
+                {%- for (i, condition) in conditions.iter().enumerate() -%}
+                    {%- if let Some(condition) = condition -%}
+                        {%- let template = SelectionConditionTemplate::new(condition) -%}
+                        new_segment_conditions[{{i}}] = {{ template.render()? }}
+                    {%- else -%}
+                        new_segment_conditions[{{i}}] = segment_conditions[{{i}}]
+                    {%- endif -%}
+                    {%- if i > 0 -%}
+                        {{" "}}&& segment_conditions[{{i - 1}}]
+                    {%- endif -%}
+                    ;
+                {%- endfor -%}
+                {%- if !segments.is_empty() -%}
+                    if {% for segment in segments %}new_segment_conditions[{{segment}}]{% if !loop.last %} || {% endif %}{% endfor %}
+                {%- endif -%}
+                {
+                    {{procedure_name}}({{current_node}}, all_results, ctx, &new_segment_conditions);
+                }
+            }
+        {%- else -%}
+            {{procedure_name}}({{current_node}}, all_results);
+        {%- endif -%}
+    {%- when Instruction::SaveCurrentNodeDuringTraversal with { condition, instruction, query_index } -%}
+        {%- if are_any_filters -%}
+            {%- if let Some(condition) = condition -%}
+                {%- let template = SelectionConditionTemplate::new(condition) -%}
+                if {{ template.render()? }}
+            {%- endif -%}
+        {%- endif -%}
+        {
+            all_results.push({{current_node}});
+        }
+        {%- let template = InstructionTemplate::new(instruction, current_node, query_name, are_any_filters.clone()) -%}
+        {{ template.render()? }}
+    {%- when Instruction::Continue -%}
+        continue;
+    {%- when Instruction::TraverseCurrentNodeSubtree -%}
+    {%- when Instruction::StartFilterExecution with { filter_id } -%}
+        let filter_{{filter_id}}_value = {{ crate::targets::rust::name_prefix(query_name) }}filter_{{filter_id}}_evaluate(ctx, {{current_node}});
+    {%- when Instruction::EndFiltersExecution -%}
+    {%- when Instruction::UpdateSubqueriesState -%}
+    {%- when Instruction::ExecuteProcedureOnChildWithActiveSegments with { name, segments_count, instructions } -%}
+        {
+            let mut child_active_segments = [false; {{segments_count}}];
+            let mut is_child_selected = false;
+            {%- call compile_instructions(instructions, current_node) -%}
+            if is_child_selected {
+                all_results.push({{current_node}});
+            }
+            if child_active_segments.contains(&true) {
+                {{ Self::procedure_name(self, name) }}({{current_node}}, all_results, &child_active_segments);
+            }
+        }
+    {%- when Instruction::ActivateSegmentInChild with { segment_index } -%}
+        child_active_segments[{{segment_index}}] = true;
+    {%- when Instruction::SelectChild -%}
+        is_child_selected = true;
+{%- endmatch -%}
+
+{%- macro compile_instructions(instructions, current_node) -%}
+    {%- for instruction in instructions -%}
+        {%- let template = InstructionTemplate::new(instruction, current_node, query_name, are_any_filters.clone()) -%}
+        {{ template.render()? }}
+    {%- endfor -%}
+{%- endmacro -%}
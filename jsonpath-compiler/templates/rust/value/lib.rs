@@ -0,0 +1,38 @@
+{%- import "rust/macros.rs" as common -%}
+#![allow(dead_code, unused_assignments, unused_mut, unused_parens, unused_variables)]
+
+// Object members are visited in the iteration order of serde_json::Map, which is the document
+// order only with the preserve_order feature of serde_json.
+use serde_json::Value as JsonValue;
+
+{%- if Self::are_any_filters(self) -%}
+    {%- include "filters.rs" -%}
+    {%- include "rust/regex.rs" -%}
+
+    {%- for query_name in query_names -%}
+        {%- if Self::are_any_filters_in_query(self, query_name) -%}
+            const {{ crate::targets::rust::segment_count_name(query_name) }}: usize = {{Self::query_segments_count(self, query_name)}};
+            {%- call common::generate_filters_definitions(Self::query_filter_subqueries(self, query_name), query_name.to_string(), "&JsonValue") -%}
+        {%- endif -%}
+    {%- endfor -%}
+{%- endif -%}
+
+{%- for query_name in query_names -%}
+    pub fn {{query_name}}(root_node: &JsonValue) -> Vec<&JsonValue> {
+        let mut all_results = Vec::new();
+        {%- if Self::are_any_filters_in_query(self, query_name) -%}
+            {{query_name}}_selectors_0(root_node, &mut all_results, &Context { root: root_node }, &[true; {{ crate::targets::rust::segment_count_name(query_name) }}]);
+        {%- else -%}
+            {{query_name}}_selectors_0(root_node, &mut all_results);
+        {%- endif -%}
+        all_results
+    }
+{%- endfor -%}
+
+{%- for procedure in procedures -%}
+    {{ procedure.render()? }}
+{%- endfor -%}
+
+{%- for filter_procedure in Self::all_filters_procedures(self) -%}
+    {{ filter_procedure.render()? }}
+{%- endfor -%}
@@ -0,0 +1,12 @@
+{%- if are_any_filters -%}
+    fn {{name}}<'a>(node: &'a JsonValue, all_results: &mut Vec<&'a JsonValue>, ctx: &Context<'a>, segment_conditions: &[bool; {{ crate::targets::rust::segment_count_name(query_name) }}])
+{%- else if are_active_segments_passed -%}
+    fn {{name}}<'a>(node: &'a JsonValue, all_results: &mut Vec<&'a JsonValue>, active_segments: &[bool])
+{%- else -%}
+    fn {{name}}<'a>(node: &'a JsonValue, all_results: &mut Vec<&'a JsonValue>)
+{%- endif -%}
+{
+    {%- for instruction in instructions -%}
+        {{ instruction.render()? }}
+    {%- endfor -%}
+}
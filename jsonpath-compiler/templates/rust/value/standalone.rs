@@ -0,0 +1,35 @@
+{%- import "rust/macros.rs" as common -%}
+#![allow(dead_code, unused_assignments, unused_mut, unused_parens, unused_variables)]
+
+// Object members are visited in the iteration order of serde_json::Map, which is the document
+// order only with the preserve_order feature of serde_json.
+use serde_json::Value as JsonValue;
+
+{%- if Self::are_any_filters(self) -%}
+    {%- include "filters.rs" -%}
+    {%- include "rust/regex.rs" -%}
+
+    const SEGMENT_COUNT: usize = {{segments_count}};
+
+    {%- call common::generate_filters_definitions(filter_subqueries, "", "&JsonValue") -%}
+{%- endif -%}
+
+fn main() {
+    let input = std::fs::read(std::env::args().nth(1).expect("no input file given")).expect("cannot read the input file");
+    let root_node: JsonValue = serde_json::from_slice(&input).expect("invalid input document");
+    let mut all_results = Vec::new();
+    {%- if Self::are_any_filters(self) -%}
+        selectors_0(&root_node, &mut all_results, &Context { root: &root_node }, &[true; SEGMENT_COUNT]);
+    {%- else -%}
+        selectors_0(&root_node, &mut all_results);
+    {%- endif -%}
+    println!("{}", serde_json::to_string(&all_results).unwrap());
+}
+
+{%- for procedure in procedures -%}
+    {{ procedure.render()? }}
+{%- endfor -%}
+
+{%- for filter_procedure in filter_procedures -%}
+    {{ filter_procedure.render()? }}
+{%- endfor -%}
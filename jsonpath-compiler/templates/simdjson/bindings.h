@@ -0,0 +1,71 @@
+#ifndef {{filename.replace(".", "_").replace("-", "_")|upper}}
+#define {{filename.replace(".", "_").replace("-", "_")|upper}}
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Every function, including the ones freeing the results, is defined by the library. Linking several libraries
+// together requires each of them to be compiled with a different symbol prefix.
+
+// The input of every function is a JSON document followed by SIMDJSON_PADDING (64) bytes of padding, and
+// input_length is the length of the document together with the padding. The input is only borrowed by the call.
+{%- if result_callbacks %}
+
+// Receives a result of a query, which is only valid until the callback returns.
+typedef void (*{{symbol_prefix}}result_callback)(const char *result, size_t result_length, void *user_data);
+{%- endif %}
+{%- if execution_contexts %}
+
+// Parser and buffers reused across the executions of the queries. A context can be used by a single execution at a
+// time, and has to be freed with {{symbol_prefix}}jsonpath_context_free.
+typedef struct {{symbol_prefix}}jsonpath_context {{symbol_prefix}}jsonpath_context;
+
+{{symbol_prefix}}jsonpath_context *{{symbol_prefix}}jsonpath_context_new(void);
+void {{symbol_prefix}}jsonpath_context_free({{symbol_prefix}}jsonpath_context *query_context);
+{%- endif %}
+
+// Frees the results returned by the *_binding functions.
+void {{symbol_prefix}}free_result_buffer(const char *result_buf);
+{%- if result_spans %}
+
+// Frees the spans returned by the *_spans_binding functions.
+void {{symbol_prefix}}free_spans_buffer(size_t *spans_buf);
+{%- endif %}
+{% for query_name in query_names %}
+// Returns the results of the query as a JSON array of result_length bytes, owned by the caller.
+const char *{{symbol_prefix}}{{query_name}}_binding(const char *padded_input, size_t input_length, size_t *result_length);
+{%- if result_spans %}
+// Returns the results of the query as spans_count (offset, length) pairs of the input, owned by the caller.
+size_t *{{symbol_prefix}}{{query_name}}_spans_binding(const char *padded_input, size_t input_length, size_t *spans_count);
+{%- endif %}
+{%- if result_callbacks %}
+// Passes every result of the query to the callback, in document order.
+void {{symbol_prefix}}{{query_name}}_callback_binding(const char *padded_input, size_t input_length,
+    {{symbol_prefix}}result_callback callback, void *user_data);
+{%- endif %}
+{%- if execution_contexts %}
+// Returns the results of the query as a JSON array of result_length bytes, owned by the context and valid until its
+// next use.
+const char *{{symbol_prefix}}{{query_name}}_context_binding({{symbol_prefix}}jsonpath_context *query_context,
+    const char *padded_input, size_t input_length, size_t *result_length);
+{%- if result_spans %}
+// Returns the results of the query as spans_count (offset, length) pairs of the input, owned by the context and valid
+// until its next use.
+const size_t *{{symbol_prefix}}{{query_name}}_spans_context_binding({{symbol_prefix}}jsonpath_context *query_context,
+    const char *padded_input, size_t input_length, size_t *spans_count);
+{%- endif %}
+{%- if result_callbacks %}
+// Passes every result of the query to the callback, in document order.
+void {{symbol_prefix}}{{query_name}}_callback_context_binding({{symbol_prefix}}jsonpath_context *query_context,
+    const char *padded_input, size_t input_length, {{symbol_prefix}}result_callback callback, void *user_data);
+{%- endif %}
+{%- endif %}
+{% endfor %}
+#ifdef __cplusplus
+}
+#endif
+
+#endif
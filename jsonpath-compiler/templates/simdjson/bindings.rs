@@ -1,20 +1,185 @@
 extern "C" {
-    fn free_result_buffer(result_buf: *mut u8);
+    fn {{symbol_prefix}}free_result_buffer(result_buf: *mut u8);
+{%- if result_spans %}
+    fn {{symbol_prefix}}free_spans_buffer(spans_buf: *mut usize);
+{%- endif %}
 {% for query_name in query_names %}
-    fn {{query_name}}_binding(padded_input: *const u8, input_length: usize, result_length: *mut usize) -> *mut u8;
+    fn {{symbol_prefix}}{{query_name}}_binding(padded_input: *const u8, input_length: usize, result_length: *mut usize) -> *mut u8;
+{%- if result_spans %}
+    fn {{symbol_prefix}}{{query_name}}_spans_binding(padded_input: *const u8, input_length: usize, spans_count: *mut usize) -> *mut usize;
+{%- endif %}
+{%- if result_callbacks %}
+    fn {{symbol_prefix}}{{query_name}}_callback_binding(
+        padded_input: *const u8,
+        input_length: usize,
+        callback: extern "C" fn(*const u8, usize, *mut std::ffi::c_void),
+        user_data: *mut std::ffi::c_void,
+    );
+{%- endif %}
+{%- if execution_contexts %}
+    fn {{symbol_prefix}}{{query_name}}_context_binding(
+        query_context: *mut RawJsonPathContext,
+        padded_input: *const u8,
+        input_length: usize,
+        result_length: *mut usize,
+    ) -> *const u8;
+{%- if result_spans %}
+    fn {{symbol_prefix}}{{query_name}}_spans_context_binding(
+        query_context: *mut RawJsonPathContext,
+        padded_input: *const u8,
+        input_length: usize,
+        spans_count: *mut usize,
+    ) -> *const usize;
+{%- endif %}
+{%- if result_callbacks %}
+    fn {{symbol_prefix}}{{query_name}}_callback_context_binding(
+        query_context: *mut RawJsonPathContext,
+        padded_input: *const u8,
+        input_length: usize,
+        callback: extern "C" fn(*const u8, usize, *mut std::ffi::c_void),
+        user_data: *mut std::ffi::c_void,
+    );
+{%- endif %}
+{%- endif %}
 {% endfor %}
+{%- if execution_contexts %}
+    fn {{symbol_prefix}}jsonpath_context_new() -> *mut RawJsonPathContext;
+    fn {{symbol_prefix}}jsonpath_context_free(query_context: *mut RawJsonPathContext);
+{%- endif %}
 }
+{%- if execution_contexts %}
+
+#[repr(C)]
+struct RawJsonPathContext {
+    _private: [u8; 0],
+}
+{%- endif %}
+{%- if result_callbacks %}
+
+extern "C" fn call_result_callback<F: FnMut(&str)>(
+    result: *const u8,
+    result_length: usize,
+    user_data: *mut std::ffi::c_void,
+) {
+    unsafe {
+        let callback = &mut *(user_data as *mut F);
+        callback(std::str::from_utf8_unchecked(std::slice::from_raw_parts(result, result_length)));
+    }
+}
+{%- endif %}
 
 {% for query_name in query_names %}
 pub fn {{query_name}}(padded_input: &[u8]) -> String {
     let input_ptr = padded_input.as_ptr();
     let mut result_length: usize = 0;
     unsafe {
-        let result_ptr: *mut u8 = {{query_name}}_binding(input_ptr, padded_input.len(), &mut result_length);
+        let result_ptr: *mut u8 = {{symbol_prefix}}{{query_name}}_binding(input_ptr, padded_input.len(), &mut result_length);
         let result_slice = std::slice::from_raw_parts(result_ptr, result_length);
         let result_str = String::from_utf8_unchecked(result_slice.to_vec());
-        free_result_buffer(result_ptr);
+        {{symbol_prefix}}free_result_buffer(result_ptr);
         result_str
     }
 }
-{% endfor %}
\ No newline at end of file
+{%- if result_spans %}
+
+pub fn {{query_name}}_spans(padded_input: &[u8]) -> Vec<&str> {
+    let input_ptr = padded_input.as_ptr();
+    let mut spans_count: usize = 0;
+    unsafe {
+        let spans_ptr: *mut usize = {{symbol_prefix}}{{query_name}}_spans_binding(input_ptr, padded_input.len(), &mut spans_count);
+        let spans = std::slice::from_raw_parts(spans_ptr, 2 * spans_count);
+        let results = spans
+            .chunks_exact(2)
+            .map(|span| std::str::from_utf8_unchecked(&padded_input[span[0]..span[0] + span[1]]))
+            .collect();
+        {{symbol_prefix}}free_spans_buffer(spans_ptr);
+        results
+    }
+}
+{%- endif %}
+{%- if result_callbacks %}
+
+pub fn {{query_name}}_with_callback<F: FnMut(&str)>(padded_input: &[u8], mut callback: F) {
+    let input_ptr = padded_input.as_ptr();
+    let user_data = &mut callback as *mut F as *mut std::ffi::c_void;
+    unsafe {
+        {{symbol_prefix}}{{query_name}}_callback_binding(input_ptr, padded_input.len(), call_result_callback::<F>, user_data);
+    }
+}
+{%- endif %}
+{% endfor %}
+{%- if execution_contexts %}
+
+/// Parser and buffers reused across the executions of the queries. The results returned by an
+/// execution borrow the context until the next one.
+pub struct JsonPathContext {
+    raw: *mut RawJsonPathContext,
+}
+
+unsafe impl Send for JsonPathContext {}
+
+impl JsonPathContext {
+    pub fn new() -> JsonPathContext {
+        JsonPathContext { raw: unsafe { {{symbol_prefix}}jsonpath_context_new() } }
+    }
+{% for query_name in query_names %}
+    pub fn {{query_name}}(&mut self, padded_input: &[u8]) -> &str {
+        let mut result_length: usize = 0;
+        unsafe {
+            let result_ptr = {{symbol_prefix}}{{query_name}}_context_binding(
+                self.raw,
+                padded_input.as_ptr(),
+                padded_input.len(),
+                &mut result_length,
+            );
+            std::str::from_utf8_unchecked(std::slice::from_raw_parts(result_ptr, result_length))
+        }
+    }
+{%- if result_spans %}
+
+    pub fn {{query_name}}_spans<'a>(&mut self, padded_input: &'a [u8]) -> Vec<&'a str> {
+        let mut spans_count: usize = 0;
+        unsafe {
+            let spans_ptr = {{symbol_prefix}}{{query_name}}_spans_context_binding(
+                self.raw,
+                padded_input.as_ptr(),
+                padded_input.len(),
+                &mut spans_count,
+            );
+            std::slice::from_raw_parts(spans_ptr, 2 * spans_count)
+                .chunks_exact(2)
+                .map(|span| std::str::from_utf8_unchecked(&padded_input[span[0]..span[0] + span[1]]))
+                .collect()
+        }
+    }
+{%- endif %}
+{%- if result_callbacks %}
+
+    pub fn {{query_name}}_with_callback<F: FnMut(&str)>(&mut self, padded_input: &[u8], mut callback: F) {
+        let user_data = &mut callback as *mut F as *mut std::ffi::c_void;
+        unsafe {
+            {{symbol_prefix}}{{query_name}}_callback_context_binding(
+                self.raw,
+                padded_input.as_ptr(),
+                padded_input.len(),
+                call_result_callback::<F>,
+                user_data,
+            );
+        }
+    }
+{%- endif %}
+{% endfor %}
+}
+
+impl Default for JsonPathContext {
+    fn default() -> JsonPathContext {
+        JsonPathContext::new()
+    }
+}
+
+impl Drop for JsonPathContext {
+    fn drop(&mut self) {
+        unsafe { {{symbol_prefix}}jsonpath_context_free(self.raw) }
+    }
+}
+{%- endif %}
@@ -0,0 +1,19 @@
+{%- match comparable -%}
+    {%- when Comparable::Param with {id} -%} params[{{id}}]
+    {%- when Comparable::Literal with {value} -%}
+        {%- match value -%}
+            {%- when LiteralValue::String with (str) -%} subquery_result {.str_value = string_view{"{{ rsonpath_syntax::str::escape(str, rsonpath_syntax::str::EscapeMode::DoubleQuoted) }}"}, .type = STRING}
+            {%- when LiteralValue::Int with (x) -%} subquery_result {.int_value = (int64_t){{x}}ll, .type = INT}
+            {%- when LiteralValue::Float with (x) -%} subquery_result {.float_value = {{format!("{:e}", x)}}, .type = FLOAT}
+            {%- when LiteralValue::Bool with (x) -%} subquery_result {.bool_value = {{x}}, .type = BOOL}
+            {%- when LiteralValue::Null -%} subquery_result {.type = __NULL}
+        {%- endmatch -%}
+    {%- when Comparable::FunctionCall with {function} -%}
+        {%- match function -%}
+            {%- when ValueFunction::Length with {arg} -%}
+                {%- let arg_template = ComparableTemplate::new(arg) -%}
+                length_of({{ arg_template.render()? }})
+            {%- when ValueFunction::Count with {param_id} -%} count_of(params[{{param_id}}])
+            {%- when ValueFunction::Value with {param_id} -%} params[{{param_id}}]
+        {%- endmatch -%}
+{%- endmatch -%}
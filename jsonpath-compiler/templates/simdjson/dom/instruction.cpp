@@ -3,6 +3,36 @@
         {%- call compile_array_iteration(instruction) -%}
     {%- when Instruction::ForEachMember with { instructions } -%}
         {%- call compile_object_iteration(instruction) -%}
+    {%- when Instruction::ForEachElementInSlices with { slices, instructions } -%}
+        {
+            dom::array array;
+            if (!node.get_array().get(array))
+            {
+                vector<dom::element> elements(array.begin(), array.end());
+                int64_t array_length = elements.size();
+                {%- for slice in slices -%}
+                    {{ crate::targets::simdjson::compile_slice_loop(slice) }}
+                    {
+                        dom::element element = elements[index];
+                        {%- call compile_instructions(instructions, "element") -%}
+                    }
+                {%- endfor -%}
+            }
+        }
+    {%- when Instruction::ForEachElementInReverseOrder with { query_index, instructions } -%}
+        {
+            dom::array array;
+            if (!node.get_array().get(array))
+            {
+                vector<dom::element> elements(array.begin(), array.end());
+                int64_t array_length = elements.size();
+                for (int64_t index = array_length - 1; index >= 0; index--)
+                {
+                    dom::element element = elements[index];
+                    {%- call compile_instructions(instructions, "element") -%}
+                }
+            }
+        }
     {%- when Instruction::IfCurrentIndexEquals with { index, instructions } -%}
         if (index == {{index}})
         {
@@ -13,34 +43,85 @@
         {
             {%- call compile_instructions(instructions, current_node) -%}
         }
+    {%- when Instruction::IfCurrentIndexInSlice with { slice, instructions } -%}
+        if ({{ crate::targets::simdjson::compile_slice_condition(slice) }})
+        {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
     {%- when Instruction::IfCurrentMemberNameEquals with { name, instructions } -%}
         if (key == "{{ rsonpath_syntax::str::escape(name, rsonpath_syntax::str::EscapeMode::DoubleQuoted) }}")
         {
             {%- call compile_instructions(instructions, current_node) -%}
         }
     {%- when Instruction::IfActiveFilterInstance with { instructions } -%}
+    {%- when Instruction::IfSegmentActive with { segment_index, instructions } -%}
+        if (active_segments[{{segment_index}}])
+        {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
     {%- when Instruction::ExecuteProcedureOnChild with { conditions, segments, name } -%}
-        {%- if !query_name.is_empty() -%}
-            {{query_name}}_{{name|lower}}({{current_node}}, all_results);
+        {%- let procedure_name = Self::procedure_name(self, name) -%}
+        {%- if are_any_filters -%}
+            {
+                bool new_segment_conditions[{{query_name}}_SEGMENT_COUNT];
+                copy(segment_conditions, segment_conditions + {{query_name}}_SEGMENT_COUNT, new_segment_conditions);
+                {%- for (i, condition) in conditions.iter().enumerate() -%}
+                    {%- if let Some(condition) = condition -%}
+                        {%- let template = SelectionConditionTemplate::new(condition) -%}
+                        new_segment_conditions[{{i}}] = {{ template.render()? }}
+                    {%- else -%}
+                        new_segment_conditions[{{i}}] = segment_conditions[{{i}}]
+                    {%- endif -%}
+                    {%- if i > 0 -%}
+                        {{" "}}&& segment_conditions[{{i - 1}}]
+                    {%- endif -%}
+                    ;
+                {%- endfor -%}
+                {%- if !segments.is_empty() -%}
+                    if ({% for segment in segments %}new_segment_conditions[{{segment}}]{% if !loop.last %} || {% endif %}{% endfor %})
+                {%- endif -%}
+                    {{procedure_name}}({{current_node}}, all_results, root_node, new_segment_conditions);
+            }
         {%- else -%}
-            {{name|lower}}({{current_node}}, all_results);
+            {{procedure_name}}({{current_node}}, all_results);
+        {%- endif -%}
+    {%- when Instruction::SaveCurrentNodeDuringTraversal with { condition, instruction, query_index } -%}
+        {%- if are_any_filters -%}
+            {%- if let Some(condition) = condition -%}
+                {%- let template = SelectionConditionTemplate::new(condition) -%}
+                if ({{ template.render()? }})
+            {%- endif -%}
         {%- endif -%}
-    {%- when Instruction::SaveCurrentNodeDuringTraversal with { condition, instruction } -%}
         all_results.push_back(simdjson::to_string({{current_node}}));
-        {%- let template = InstructionTemplate::new(instruction, current_node, query_name) -%}
-        {{ template.render().unwrap() }}
+        {%- let template = InstructionTemplate::new(instruction, current_node, query_name, filter_subqueries.to_owned(), are_any_filters.clone()) -%}
+        {{ template.render()? }}
     {%- when Instruction::Continue -%}
         continue;
     {%- when Instruction::TraverseCurrentNodeSubtree -%}
-    {%- when Instruction::StartFilterExecution with { filter_id} -%}
+    {%- when Instruction::StartFilterExecution with { filter_id } -%}
+        bool filter_{{filter_id}}_value = {{query_name}}_filter_{{filter_id}}_evaluate({{current_node}}, root_node);
     {%- when Instruction::EndFiltersExecution -%}
     {%- when Instruction::UpdateSubqueriesState -%}
+    {%- when Instruction::ExecuteProcedureOnChildWithActiveSegments with { name, segments_count, instructions } -%}
+        {
+            bool child_active_segments[{{segments_count}}] = {};
+            bool is_child_selected = false;
+            {%- call compile_instructions(instructions, current_node) -%}
+            if (is_child_selected)
+                all_results.push_back(simdjson::to_string({{current_node}}));
+            if (any_of(child_active_segments, child_active_segments + {{segments_count}}, [](bool is_active) { return is_active; }))
+                {{ Self::procedure_name(self, name) }}({{current_node}}, all_results, child_active_segments);
+        }
+    {%- when Instruction::ActivateSegmentInChild with { segment_index } -%}
+        child_active_segments[{{segment_index}}] = true;
+    {%- when Instruction::SelectChild -%}
+        is_child_selected = true;
 {%- endmatch -%}
 
 {%- macro compile_instructions(instructions, current_node) -%}
     {%- for instruction in instructions -%}
-    {%- let template = InstructionTemplate::new(instruction, current_node, query_name) -%}
-        {{ template.render().unwrap() }}
+        {%- let template = InstructionTemplate::new(instruction, current_node, query_name, filter_subqueries.to_owned(), are_any_filters.clone()) -%}
+        {{ template.render()? }}
     {%- endfor -%}
 {%- endmacro -%}
 
@@ -66,7 +147,7 @@
         {
             bool first = true;
             size_t index = 0;
-            {%- if crate::targets::simdjson::is_array_length_needed(instructions) -%}
+            {%- if crate::targets::is_array_length_needed(instructions) -%}
                 size_t array_length = array.size();
             {%- endif -%}
             for (dom::element element : array)
@@ -1,3 +1,6 @@
+{%- import "macros.cpp" as common -%}
+{%- import "simdjson/filters.cpp" as filters -%}
+
 {%- if !bindings -%}
     #ifndef {{filename.replace(".", "_")|upper}}
     #define {{filename.replace(".", "_")|upper}}
@@ -12,6 +15,8 @@
 #include <set>
 #include <string>
 #include <algorithm>
+#include <charconv>
+#include <cmath>
 #include <fcntl.h>
 #include <sys/mman.h>
 #include <sys/stat.h>
@@ -20,9 +25,21 @@
 using namespace std;
 using namespace simdjson;
 
-{%- for procedure in procedures -%}
-    void {{procedure.name|lower}}(dom::element &node, vector<string> &all_results);
-{%- endfor -%}
+{%- if Self::are_any_filters(self) -%}
+    {%- call filters::generate_filter_value_structures(Self::max_subqueries_in_filter_count(self), Self::max_subquery_segments_count(self)) -%}
+    {%- call common::generate_filter_aux_structures() -%}
+
+    {%- for query_name in query_names -%}
+        {%- if Self::are_any_filters_in_query(self, query_name) -%}
+            constexpr uint8_t {{query_name}}_SEGMENT_COUNT = {{Self::query_segments_count(self, query_name)}};
+            {%- call filters::generate_filter_procedures_declarations(Self::query_filter_procedures(self, query_name), query_name.to_string()) -%}
+            {%- call filters::generate_filter_subqueries_instances(Self::query_filter_subqueries(self, query_name), query_name.to_string()) -%}
+            {%- call common::generate_filters_definitions(Self::query_filter_subqueries(self, query_name), query_name.to_string()) -%}
+        {%- endif -%}
+    {%- endfor -%}
+{%- endif -%}
+
+{%- call common::generate_procedures_declarations(procedures) -%}
 
 {%- for query_name in query_names -%}
     string {{query_name}}(const char* padded_input, size_t length)
@@ -30,7 +47,13 @@ using namespace simdjson;
         dom::parser parser;
         dom::element root_node = parser.parse(padded_input, length - 64, length);
         vector<string> all_results;
-        {{query_name}}_selectors_0(root_node, all_results);
+        {%- if Self::are_any_filters_in_query(self, query_name) -%}
+            bool segment_conditions[{{query_name}}_SEGMENT_COUNT];
+            fill(segment_conditions, segment_conditions + {{query_name}}_SEGMENT_COUNT, true);
+            {{query_name}}_selectors_0(root_node, all_results, root_node, segment_conditions);
+        {%- else -%}
+            {{query_name}}_selectors_0(root_node, all_results);
+        {%- endif -%}
         string result;
         bool first = true;
         result += "[\n";
@@ -47,7 +70,7 @@ using namespace simdjson;
     }
 
     {%- if bindings -%}
-        extern "C" const char* {{query_name}}_binding(const char* padded_input, size_t input_length, size_t* result_length)
+        extern "C" const char* {{symbol_prefix}}{{query_name}}_binding(const char* padded_input, size_t input_length, size_t* result_length)
         {
             string res_str = {{query_name}}(padded_input, input_length);
             char* res = new char[res_str.length() + 1];
@@ -58,8 +81,19 @@ using namespace simdjson;
     {%- endif -%}
 {%- endfor -%}
 
+{%- if bindings -%}
+    extern "C" void {{symbol_prefix}}free_result_buffer(const char* result_buf)
+    {
+        delete[] result_buf;
+    }
+{%- endif -%}
+
 {%- for procedure in procedures -%}
-    {{ procedure.render().unwrap() }}
+    {{ procedure.render()? }}
+{%- endfor -%}
+
+{%- for filter_procedure in Self::all_filters_procedures(self) -%}
+    {{ filter_procedure.render()? }}
 {%- endfor -%}
 
 {%- if !bindings -%}
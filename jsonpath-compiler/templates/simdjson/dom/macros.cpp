@@ -0,0 +1,179 @@
+{%- macro generate_filter_aux_structures() -%}
+    struct filter {
+        bool (*evaluate)(const dom::element &node, const dom::element &root_node);
+    };
+
+    void select_nodes(const subquery &query, uint8_t segment_index, const dom::element &node,
+                      const dom::element &root_node, vector<dom::element> &nodes);
+
+    void select_children(const subquery &query, uint8_t segment_index, const dom::element &node,
+                         const dom::element &root_node, vector<dom::element> &nodes)
+    {
+        const subquery_path_segment &segment = query.segments[segment_index];
+        for (uint8_t i = 0; i < segment.selector_count; i++)
+        {
+            const subquery_selector &selector = segment.selectors[i];
+            dom::element child;
+            dom::array array;
+            dom::object object;
+            switch (selector.type)
+            {
+                case subquery_selector::NAME:
+                    if (!node.at_key(selector.name).get(child))
+                        select_nodes(query, segment_index + 1, child, root_node, nodes);
+                    break;
+                case subquery_selector::INDEX:
+                    if (!node.get_array().get(array))
+                    {
+                        int64_t index = selector.index >= 0 ? selector.index : (int64_t)array.size() + selector.index;
+                        if (index >= 0 && !array.at(index).get(child))
+                            select_nodes(query, segment_index + 1, child, root_node, nodes);
+                    }
+                    break;
+                case subquery_selector::WILDCARD:
+                    if (!node.get_array().get(array))
+                    {
+                        for (dom::element element : array)
+                            select_nodes(query, segment_index + 1, element, root_node, nodes);
+                    }
+                    else if (!node.get_object().get(object))
+                    {
+                        for (dom::key_value_pair field : object)
+                            select_nodes(query, segment_index + 1, field.value, root_node, nodes);
+                    }
+                    break;
+                case subquery_selector::SLICE:
+                    if (!node.get_array().get(array))
+                    {
+                        vector<dom::element> elements(array.begin(), array.end());
+                        int64_t length = elements.size();
+                        for (int64_t j = 0; j < length; j++)
+                        {
+                            int64_t index = selector.step > 0 ? j : length - 1 - j;
+                            if (selector.is_in_slice(index, length))
+                                select_nodes(query, segment_index + 1, elements[index], root_node, nodes);
+                        }
+                    }
+                    break;
+                case subquery_selector::FILTER:
+                    if (!node.get_array().get(array))
+                    {
+                        for (dom::element element : array)
+                            if (selector.filter->evaluate(element, root_node))
+                                select_nodes(query, segment_index + 1, element, root_node, nodes);
+                    }
+                    else if (!node.get_object().get(object))
+                    {
+                        for (dom::key_value_pair field : object)
+                            if (selector.filter->evaluate(field.value, root_node))
+                                select_nodes(query, segment_index + 1, field.value, root_node, nodes);
+                    }
+                    break;
+            }
+        }
+    }
+
+    void select_nodes(const subquery &query, uint8_t segment_index, const dom::element &node,
+                      const dom::element &root_node, vector<dom::element> &nodes)
+    {
+        if (segment_index == query.segment_count)
+        {
+            nodes.push_back(node);
+            return;
+        }
+        select_children(query, segment_index, node, root_node, nodes);
+        if (!query.segments[segment_index].is_descendant)
+            return;
+        dom::array array;
+        dom::object object;
+        if (!node.get_array().get(array))
+        {
+            for (dom::element element : array)
+                select_nodes(query, segment_index, element, root_node, nodes);
+        }
+        else if (!node.get_object().get(object))
+        {
+            for (dom::key_value_pair field : object)
+                select_nodes(query, segment_index, field.value, root_node, nodes);
+        }
+    }
+
+    subquery_result evaluate_subquery(const subquery &query, const dom::element &node, const dom::element &root_node,
+                                      string &canonical_form)
+    {
+        vector<dom::element> nodes;
+        select_nodes(query, 0, node, root_node, nodes);
+        subquery_result result {.exists = !nodes.empty(), .count = (int64_t)nodes.size()};
+        if (nodes.size() != 1)
+            return result;
+        switch (nodes[0].type())
+        {
+            case dom::element_type::STRING:
+                result.type = STRING;
+                result.str_value = nodes[0].get_string().value();
+                break;
+            case dom::element_type::INT64:
+                result.type = INT;
+                result.int_value = nodes[0].get_int64().value();
+                break;
+            case dom::element_type::UINT64:
+            case dom::element_type::DOUBLE:
+                result.type = FLOAT;
+                result.float_value = nodes[0].get_double().value();
+                break;
+            case dom::element_type::BOOL:
+                result.type = BOOL;
+                result.bool_value = nodes[0].get_bool().value();
+                break;
+            case dom::element_type::NULL_VALUE:
+                result.type = __NULL;
+                break;
+            case dom::element_type::ARRAY:
+                result.type = COMPLEX;
+                result.length = nodes[0].get_array().value().size();
+                break;
+            case dom::element_type::OBJECT:
+                result.type = COMPLEX;
+                result.length = nodes[0].get_object().value().size();
+                break;
+        }
+        if (result.type == COMPLEX)
+        {
+            canonical_form = to_canonical_form(nodes[0]);
+            result.canonical_form = canonical_form;
+        }
+        return result;
+    }
+{%- endmacro -%}
+
+{%- macro generate_filters_definitions(filter_subqueries, query_name) -%}
+    {%- for (filter_id, subqueries) in filter_subqueries -%}
+        {%- let filter_name = format!("{}_filter_{}", query_name, filter_id) -%}
+        bool {{filter_name}}_evaluate(const dom::element &node, const dom::element &root_node)
+        {
+            std::array<string, MAX_SUBQUERIES_IN_FILTER> canonical_forms;
+            return {{filter_name}}({
+            {%- for (subquery_index, subquery) in subqueries.iter().enumerate() -%}
+                {%- if subquery.is_absolute -%}
+                    evaluate_subquery({{filter_name}}_subquery_{{subquery_index}}, root_node, root_node, canonical_forms[{{subquery_index}}]),
+                {%- else -%}
+                    evaluate_subquery({{filter_name}}_subquery_{{subquery_index}}, node, root_node, canonical_forms[{{subquery_index}}]),
+                {%- endif -%}
+            {%- endfor -%}
+            });
+        }
+        const filter {{filter_name}}_definition {&{{filter_name}}_evaluate};
+    {%- endfor -%}
+{%- endmacro -%}
+
+{%- macro generate_procedures_declarations(procedures) -%}
+    {%- for procedure in procedures -%}
+        {%- if procedure.are_any_filters -%}
+            void {{procedure.name|lower}}(dom::element &node, vector<string> &all_results, dom::element &root_node, bool segment_conditions[]);
+        {%- else if procedure.are_active_segments_passed -%}
+            void {{procedure.name|lower}}(dom::element &node, vector<string> &all_results, bool active_segments[]);
+        {%- else -%}
+            void {{procedure.name|lower}}(dom::element &node, vector<string> &all_results);
+        {%- endif -%}
+    {%- endfor -%}
+{%- endmacro -%}
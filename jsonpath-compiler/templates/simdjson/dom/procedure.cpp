@@ -1,12 +1,18 @@
-void {{name|lower}}(dom::element &node, vector<string> &all_results)
+{%- if are_any_filters -%}
+    void {{name|lower}}(dom::element &node, vector<string> &all_results, dom::element &root_node, bool segment_conditions[])
+{%- else if are_active_segments_passed -%}
+    void {{name|lower}}(dom::element &node, vector<string> &all_results, bool active_segments[])
+{%- else -%}
+    void {{name|lower}}(dom::element &node, vector<string> &all_results)
+{%- endif -%}
 {
     {%- for instruction in instructions -%}
-        {{ instruction.render().unwrap() }}
+        {{ instruction.render()? }}
     {%- endfor -%}
     {%- if !Self::are_object_members_iterated(self) -%}
-        {{ EMPTY_OBJECT_ITERATION.render().unwrap() }}
+        {{ EMPTY_OBJECT_ITERATION.render()? }}
     {%- endif -%}
     {%- if !Self::are_array_elements_iterated(self) -%}
-        {{ EMPTY_ARRAY_ITERATION.render().unwrap() }}
+        {{ EMPTY_ARRAY_ITERATION.render()? }}
     {%- endif -%}
 }
\ No newline at end of file
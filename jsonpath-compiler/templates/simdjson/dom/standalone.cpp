@@ -1,3 +1,6 @@
+{%- import "macros.cpp" as common -%}
+{%- import "simdjson/filters.cpp" as filters -%}
+
 {%- if logging -%}
     #define SIMDJSON_VERBOSE_LOGGING 1
 {%- endif -%}
@@ -9,6 +12,8 @@
 #include <set>
 #include <string>
 #include <algorithm>
+#include <charconv>
+#include <cmath>
 #include <fcntl.h>
 #include <sys/mman.h>
 #include <sys/stat.h>
@@ -17,15 +22,25 @@
 using namespace std;
 using namespace simdjson;
 
+{%- if Self::are_any_filters(self) -%}
+    constexpr uint8_t _SEGMENT_COUNT = {{segments_count}};
+
+    {%- call filters::generate_filter_value_structures(Self::max_subqueries_in_filter_count(self), Self::max_subquery_segments_count(self)) -%}
+    {%- call common::generate_filter_aux_structures() -%}
+
+    {%- call filters::generate_filter_procedures_declarations(filter_procedures, "") -%}
+
+    {%- call filters::generate_filter_subqueries_instances(filter_subqueries, "") -%}
+    {%- call common::generate_filters_definitions(filter_subqueries, "") -%}
+{%- endif -%}
+
 {%- if mmap -%}
     string_view map_input(const char* filename);
 {%- else -%}
     string read_input(const char* filename);
 {%- endif -%}
 
-{%- for procedure in procedures -%}
-    void {{procedure.name|lower}}(dom::element &node, vector<string> &all_results);
-{%- endfor -%}
+{%- call common::generate_procedures_declarations(procedures) -%}
 
 int main(int argc, char **argv)
 {
@@ -38,7 +53,13 @@ int main(int argc, char **argv)
     dom::parser parser;
     dom::element root_node = parser.parse(json);
     vector<string> all_results;
-    selectors_0(root_node, all_results);
+    {%- if Self::are_any_filters(self) -%}
+        bool segment_conditions[_SEGMENT_COUNT];
+        fill(segment_conditions, segment_conditions + _SEGMENT_COUNT, true);
+        selectors_0(root_node, all_results, root_node, segment_conditions);
+    {%- else -%}
+        selectors_0(root_node, all_results);
+    {%- endif -%}
     cout << "[\n";
     bool first = true;
     for (const auto &buf : all_results)
@@ -75,5 +96,9 @@ int main(int argc, char **argv)
 {%- endif -%}
 
 {%- for procedure in procedures -%}
-    {{ procedure.render().unwrap() }}
+    {{ procedure.render()? }}
+{%- endfor -%}
+
+{%- for filter_procedure in filter_procedures -%}
+    {{ filter_procedure.render()? }}
 {%- endfor -%}
\ No newline at end of file
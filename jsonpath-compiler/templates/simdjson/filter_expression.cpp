@@ -2,14 +2,14 @@
     {%- when FilterExpression::Or with {lhs, rhs} -%}
         {%- let lhs_template = FilterExpressionTemplate::new(lhs) -%}
         {%- let rhs_template = FilterExpressionTemplate::new(rhs) -%}
-        ({{ lhs_template.render().unwrap() }} || {{ rhs_template.render().unwrap() }})
+        ({{ lhs_template.render()? }} || {{ rhs_template.render()? }})
     {%- when FilterExpression::And with {lhs, rhs} -%}
         {%- let lhs_template = FilterExpressionTemplate::new(lhs) -%}
         {%- let rhs_template = FilterExpressionTemplate::new(rhs) -%}
-        ({{ lhs_template.render().unwrap() }} && {{ rhs_template.render().unwrap() }})
+        ({{ lhs_template.render()? }} && {{ rhs_template.render()? }})
     {%- when FilterExpression::Not with {expr} -%}
         {%- let template = FilterExpressionTemplate::new(expr) -%}
-        !({{ template.render().unwrap() }})
+        !({{ template.render()? }})
     {%- when FilterExpression::Comparison with {lhs, rhs, op} -%}
         ({%- call compile_comparable(lhs) -%}
         {%- match op -%}
@@ -22,19 +22,18 @@
         {%- endmatch -%}
         {%- call compile_comparable(rhs) -%})
     {%- when FilterExpression::ExistenceTest with {param_id} -%} (params[{{param_id}}].exists)
+    {%- when FilterExpression::FunctionCall with {function} -%}
+        {%- match function -%}
+            {%- when LogicalFunction::Match with {arg, regex_id, is_full_match} -%}
+                {%- if let Some(regex_id) = regex_id -%}
+                    regex_{{regex_id}}.matches({%- call compile_comparable(arg) -%}, {{is_full_match}})
+                {%- else -%}
+                    false
+                {%- endif -%}
+        {%- endmatch -%}
 {%- endmatch -%}
 
 {%- macro compile_comparable(comparable) -%}
-    {%- match comparable -%}
-        {%- when Comparable::Param with {id} -%} params[{{id}}]
-        {%- when Comparable::Literal with {value} -%}
-            {%- match value -%}
-                {%- when LiteralValue::String with (str) -%} string_view{"{{ rsonpath_syntax::str::escape(str, rsonpath_syntax::str::EscapeMode::DoubleQuoted) }}"}
-                {%- when LiteralValue::Int with (x) -%} (int64_t){{x}}ll
-                {%- when LiteralValue::Float with (x) -%} {{format!("{:e}", x)}}
-                {%- when LiteralValue::Bool with (x) -%} {{x}}
-                {%- when LiteralValue::Null -%} subquery_result {.type = __NULL}
-            {%- endmatch -%}
-    {%- endmatch -%}
+    {%- let template = ComparableTemplate::new(comparable) -%}
+    {{ template.render()? }}
 {%- endmacro -%}
-
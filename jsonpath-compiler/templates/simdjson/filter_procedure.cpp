@@ -0,0 +1,27 @@
+bool {{query_name}}_{{name|lower}}(array<subquery_result, MAX_SUBQUERIES_IN_FILTER> params)
+{
+    {%- for (regex_id, regex) in regexes.iter().enumerate() -%}
+        {%- for (state_id, state) in regex.states.iter().enumerate() -%}
+            {%- if let RegexState::Chars with { ranges, next } = state -%}
+                {%- if !ranges.is_empty() -%}
+                    static const char32_t regex_{{regex_id}}_state_{{state_id}}_ranges[] = {
+                    {%- for (start, end) in ranges -%} {{start}}, {{end}}, {%- endfor -%}
+                    };
+                {%- endif -%}
+            {%- endif -%}
+        {%- endfor -%}
+        static const regex_state regex_{{regex_id}}_states[] = {
+        {%- for (state_id, state) in regex.states.iter().enumerate() -%}
+            {%- match state -%}
+                {%- when RegexState::Chars with { ranges, next } -%}
+                    {regex_state::CHARS, {{next}}, 0, {{ranges.len()}},
+                    {%- if ranges.is_empty() -%} nullptr {%- else -%} regex_{{regex_id}}_state_{{state_id}}_ranges {%- endif -%} },
+                {%- when RegexState::Split with { next, alt } -%} {regex_state::SPLIT, {{next}}, {{alt}}, 0, nullptr},
+                {%- when RegexState::Match -%} {regex_state::MATCH, 0, 0, 0, nullptr},
+            {%- endmatch -%}
+        {%- endfor -%}
+        };
+        static const compiled_regex regex_{{regex_id}} { {{regex.start}}, {{regex.states.len()}}, regex_{{regex_id}}_states };
+    {%- endfor -%}
+    return {{expression.render()?}};
+}
\ No newline at end of file
@@ -0,0 +1,485 @@
+{%- macro generate_filter_value_structures(max_subqueries_in_filter_count, max_subquery_segments_count) -%}
+    constexpr uint8_t MAX_SUBQUERIES_IN_FILTER = {{max_subqueries_in_filter_count}};
+    constexpr size_t MAX_SUBQUERY_SEGMENTS = {{max_subquery_segments_count}};
+
+    struct filter_instance;
+    struct filter;
+
+    enum subquery_result_type
+    {
+     NOTHING, STRING, INT, FLOAT, BOOL, __NULL, COMPLEX
+    };
+
+    struct subquery_result {
+        filter_instance* filter;
+        string_view str_value;
+        int64_t int_value;
+        double float_value;
+        bool bool_value;
+        subquery_result_type type;
+        bool exists;
+        int64_t length;
+        int64_t count;
+        // Canonical form of a complex value, if known. Complex values are equal if their canonical forms are.
+        string_view canonical_form;
+
+        partial_ordering operator<=>(const subquery_result& other) const
+        {
+            if (type == NOTHING && other.type == NOTHING) return partial_ordering::equivalent;
+            if (type == __NULL && other.type == __NULL) return partial_ordering::equivalent;
+            if (type == COMPLEX && other.type == COMPLEX && !canonical_form.empty()
+                && canonical_form == other.canonical_form)
+                return partial_ordering::equivalent;
+            if (other.type == STRING) return *this <=> other.str_value;
+            if (other.type == INT) return *this <=> other.int_value;
+            if (other.type == FLOAT) return *this <=> other.float_value;
+            if (other.type == BOOL) return *this <=> other.bool_value;
+
+            return partial_ordering::unordered;
+        }
+        partial_ordering operator<=>(const string_view& other) const
+        {
+            if (type != STRING)
+                return partial_ordering::unordered;
+
+            const int cmp = str_value.compare(other);
+            if (cmp == 0) return partial_ordering::equivalent;
+            if (cmp < 0) return partial_ordering::less;
+            return partial_ordering::greater;
+        }
+        partial_ordering operator<=>(const int64_t& other) const
+        {
+            if (type == INT) {
+                if (int_value == other) return partial_ordering::equivalent;
+                if (int_value < other) return partial_ordering::less;
+                return partial_ordering::greater;
+            }
+
+            if (type == FLOAT) {
+                if (float_value == other) return partial_ordering::equivalent;
+                if (float_value < other) return partial_ordering::less;
+                return partial_ordering::greater;
+            }
+
+            return partial_ordering::unordered;
+        }
+        partial_ordering operator<=>(const double& other) const
+        {
+            if (type == INT) {
+                if (int_value == other) return partial_ordering::equivalent;
+                if (int_value < other) return partial_ordering::less;
+                return partial_ordering::greater;
+            }
+
+            if (type == FLOAT) {
+                if (float_value == other) return partial_ordering::equivalent;
+                if (float_value < other) return partial_ordering::less;
+                return partial_ordering::greater;
+            }
+
+            return partial_ordering::unordered;
+        }
+        partial_ordering operator<=>(const bool& other) const
+        {
+            if (type != BOOL) return partial_ordering::unordered;
+
+            return bool_value == other ? partial_ordering::equivalent : partial_ordering::unordered;
+        }
+        bool operator==(const subquery_result& other) const
+        {
+            return *this <=> other == partial_ordering::equivalent;
+        }
+        bool operator==(const string_view& other) const
+        {
+            return *this <=> other == partial_ordering::equivalent;
+        }
+        bool operator==(const int64_t& other) const
+        {
+            return *this <=> other == partial_ordering::equivalent;
+        }
+        bool operator==(const double& other) const
+        {
+            return *this <=> other == partial_ordering::equivalent;
+        }
+        bool operator==(const bool& other) const
+        {
+            return *this <=> other == partial_ordering::equivalent;
+        }
+    };
+
+    void append_canonical_string(string_view str, string &buf)
+    {
+        buf += to_string(str.size());
+        buf += '"';
+        buf += str;
+    }
+
+    // Appends a form of the value in which the members of objects are ordered by their names and equal numbers are
+    // written in the same way, so that two values are equal if and only if their canonical forms are.
+    void append_canonical_form(const dom::element &value, string &buf)
+    {
+        switch (value.type())
+        {
+            case dom::element_type::ARRAY:
+            {
+                dom::array array = value.get_array().value();
+                buf += '[';
+                for (dom::element element : array)
+                {
+                    append_canonical_form(element, buf);
+                    buf += ',';
+                }
+                buf += ']';
+                break;
+            }
+            case dom::element_type::OBJECT:
+            {
+                dom::object object = value.get_object().value();
+                vector<pair<string_view, dom::element>> members;
+                for (dom::key_value_pair field : object)
+                    members.emplace_back(field.key, field.value);
+                sort(members.begin(), members.end(),
+                     [](const auto &lhs, const auto &rhs) { return lhs.first < rhs.first; });
+                buf += '{';
+                for (const auto &[name, member_value] : members)
+                {
+                    append_canonical_string(name, buf);
+                    buf += ':';
+                    append_canonical_form(member_value, buf);
+                    buf += ',';
+                }
+                buf += '}';
+                break;
+            }
+            case dom::element_type::STRING:
+                append_canonical_string(value.get_string().value(), buf);
+                break;
+            case dom::element_type::INT64:
+                buf += to_string(value.get_int64().value());
+                break;
+            case dom::element_type::UINT64:
+                buf += to_string(value.get_uint64().value());
+                break;
+            case dom::element_type::DOUBLE:
+            {
+                double number = value.get_double().value();
+                if (number == trunc(number) && fabs(number) < 0x1p63)
+                    buf += to_string((int64_t)number);
+                else
+                {
+                    char chars[32];
+                    buf.append(chars, to_chars(chars, chars + sizeof(chars), number).ptr);
+                }
+                break;
+            }
+            case dom::element_type::BOOL:
+                buf += value.get_bool().value() ? "true" : "false";
+                break;
+            case dom::element_type::NULL_VALUE:
+                buf += "null";
+                break;
+        }
+    }
+
+    string to_canonical_form(const dom::element &value)
+    {
+        string buf;
+        append_canonical_form(value, buf);
+        return buf;
+    }
+
+    subquery_result length_of(string_view value)
+    {
+        int64_t length = 0;
+        for (unsigned char c : value)
+            if ((c & 0xC0) != 0x80)
+                length++;
+        return {.int_value = length, .type = INT, .exists = true};
+    }
+
+    subquery_result length_of(const subquery_result &value)
+    {
+        if (value.type == STRING)
+            return length_of(value.str_value);
+        if (value.type == COMPLEX)
+            return {.int_value = value.length, .type = INT, .exists = true};
+        return {.type = NOTHING};
+    }
+
+    subquery_result count_of(const subquery_result &nodes)
+    {
+        return {.int_value = nodes.count, .type = INT, .exists = true};
+    }
+
+    struct regex_state {
+        enum {CHARS, SPLIT, MATCH} type;
+        uint32_t next;
+        uint32_t alt;
+        uint32_t range_count;
+        const char32_t *ranges;
+
+        bool contains(char32_t c) const
+        {
+            uint32_t low = 0;
+            uint32_t high = range_count;
+            while (low < high)
+            {
+                uint32_t mid = (low + high) / 2;
+                if (c < ranges[2 * mid])
+                    high = mid;
+                else if (c > ranges[2 * mid + 1])
+                    low = mid + 1;
+                else
+                    return true;
+            }
+            return false;
+        }
+    };
+
+    struct compiled_regex {
+        uint32_t start;
+        uint32_t state_count;
+        const regex_state *states;
+
+        bool matches(const subquery_result &value, bool is_full_match) const
+        {
+            return value.type == STRING && matches(value.str_value, is_full_match);
+        }
+
+        bool matches(string_view str, bool is_full_match) const
+        {
+            vector<uint32_t> current_states;
+            vector<uint32_t> next_states;
+            vector<size_t> added_at_step(state_count, SIZE_MAX);
+            size_t pos = 0;
+            for (size_t step = 0; ; step++)
+            {
+                if (pos == 0 || !is_full_match)
+                    add_state(current_states, start, step, added_at_step);
+                for (uint32_t state : current_states)
+                    if (states[state].type == regex_state::MATCH && (!is_full_match || pos == str.size()))
+                        return true;
+                if (pos == str.size() || (is_full_match && current_states.empty()))
+                    return false;
+                char32_t c = decode_code_point(str, pos);
+                next_states.clear();
+                for (uint32_t state : current_states)
+                    if (states[state].type == regex_state::CHARS && states[state].contains(c))
+                        add_state(next_states, states[state].next, step + 1, added_at_step);
+                swap(current_states, next_states);
+            }
+        }
+
+        void add_state(vector<uint32_t> &states_set, uint32_t state, size_t step, vector<size_t> &added_at_step) const
+        {
+            if (added_at_step[state] == step)
+                return;
+            added_at_step[state] = step;
+            switch (states[state].type)
+            {
+                case regex_state::SPLIT:
+                    add_state(states_set, states[state].next, step, added_at_step);
+                    add_state(states_set, states[state].alt, step, added_at_step);
+                    break;
+                default:
+                    states_set.push_back(state);
+            }
+        }
+
+        static char32_t decode_code_point(string_view str, size_t &pos)
+        {
+            unsigned char lead_byte = str[pos++];
+            if (lead_byte < 0x80)
+                return lead_byte;
+            int continuation_byte_count = lead_byte >= 0xF0 ? 3 : lead_byte >= 0xE0 ? 2 : 1;
+            char32_t code_point = lead_byte & (0x3F >> continuation_byte_count);
+            for (int i = 0; i < continuation_byte_count && pos < str.size(); i++)
+                code_point = (code_point << 6) | (str[pos++] & 0x3F);
+            return code_point;
+        }
+    };
+
+    struct current_node_data {
+        bool is_member;
+        bool is_element;
+        uint64_t array_length;
+        uint64_t index;
+        string_view key;
+    };
+
+    struct subquery_selector {
+        enum {NAME, INDEX, WILDCARD, SLICE, FILTER} type;
+        const char* name;
+        int64_t index;
+        int64_t end;
+        bool has_end;
+        int64_t step;
+        // Filters are evaluated by the target, so a filter selector never matches by itself.
+        const struct filter *filter;
+
+        bool is_array_length_needed() const
+        {
+            if (type == INDEX)
+                return index < 0;
+            if (type == SLICE)
+                return step < 0 || index < 0 || (has_end && end < 0);
+            return false;
+        }
+
+        bool matches(const current_node_data &node) const
+        {
+            switch (type)
+            {
+                case NAME:
+                    return node.is_member && node.key.compare(name) == 0;
+                case INDEX:
+                    return node.is_element && (int64_t)node.index == (index >= 0 ? index : (int64_t)node.array_length + index);
+                case WILDCARD:
+                    return true;
+                case SLICE:
+                    return node.is_element && is_in_slice(node.index, node.array_length);
+                case FILTER:
+                    return false;
+            }
+            return false;
+        }
+
+        bool is_in_slice(int64_t i, int64_t length) const
+        {
+            if (step > 0)
+            {
+                int64_t lower = index >= 0 ? index : max(length + index, (int64_t)0);
+                if (i < lower || (has_end && i >= (end >= 0 ? end : length + end)))
+                    return false;
+                return (i - lower) % step == 0;
+            }
+            if (step < 0)
+            {
+                int64_t upper = index >= 0 ? min(index, length - 1) : length + index;
+                if (i > upper || (has_end && i <= (end >= 0 ? end : length + end)))
+                    return false;
+                return (upper - i) % -step == 0;
+            }
+            return false;
+        }
+    };
+
+    struct subquery_path_segment {
+        bool is_descendant;
+        uint8_t selector_count;
+        const subquery_selector *selectors;
+    };
+
+    // A subquery state holds, for every segment, the number of paths from the node the filter is applied to, along
+    // which the segment is yet to be matched by the children of the current node. A node reached along several
+    // paths is selected by the subquery several times.
+    struct subquery_state {
+        array<int64_t, MAX_SUBQUERY_SEGMENTS> paths_counts{};
+
+        bool is_empty() const
+        {
+            return all_of(paths_counts.begin(), paths_counts.end(), [](int64_t count) { return count == 0; });
+        }
+    };
+
+    struct subquery {
+        size_t segment_count;
+        const subquery_path_segment *segments;
+        bool has_filters;
+
+        subquery_state initial_state() const
+        {
+            subquery_state state;
+            if (segment_count > 0)
+                state.paths_counts[0] = 1;
+            return state;
+        }
+
+        subquery_state next_state(const subquery_state &state, const current_node_data &node, int64_t &matches_count) const
+        {
+            subquery_state next_state;
+            matches_count = 0;
+            for (size_t i = 0; i < segment_count; i++)
+            {
+                int64_t paths_count = state.paths_counts[i];
+                if (paths_count == 0)
+                    continue;
+                const subquery_path_segment &segment = segments[i];
+                if (segment.is_descendant)
+                    next_state.paths_counts[i] += paths_count;
+                int64_t segment_matches_count = 0;
+                for (uint8_t j = 0; j < segment.selector_count; j++)
+                    if (segment.selectors[j].matches(node))
+                        segment_matches_count++;
+                if (segment_matches_count == 0)
+                    continue;
+                if (i + 1 == segment_count)
+                    matches_count += paths_count * segment_matches_count;
+                else
+                    next_state.paths_counts[i + 1] += paths_count * segment_matches_count;
+            }
+            return next_state;
+        }
+
+        bool is_array_length_needed(const subquery_state &state) const
+        {
+            for (size_t i = 0; i < segment_count; i++)
+            {
+                if (state.paths_counts[i] == 0)
+                    continue;
+                for (uint8_t j = 0; j < segments[i].selector_count; j++)
+                    if (segments[i].selectors[j].is_array_length_needed())
+                        return true;
+            }
+            return false;
+        }
+    };
+
+{%- endmacro -%}
+
+{%- macro generate_filter_subqueries_instances(filter_subqueries, query_name) -%}
+    {%- for (filter_id, subqueries) in filter_subqueries -%}
+        {%- for (subquery_index, subquery) in subqueries.iter().enumerate() -%}
+            {%- let subquery_name = format!("{}_filter_{}_subquery_{}", query_name, filter_id, subquery_index) -%}
+            {%- for (segment_index, segment) in subquery.segments.iter().enumerate() -%}
+                const subquery_selector {{subquery_name}}_segment_{{segment_index}}_selectors[] = {
+                {%- for selector in segment.selectors -%}
+                    {%- match selector -%}
+                        {%- when FilterSubquerySelector::Name(name) -%}
+                            {.type = subquery_selector::NAME, .name = "{{ rsonpath_syntax::str::escape(name, rsonpath_syntax::str::EscapeMode::DoubleQuoted) }}"},
+                        {%- when FilterSubquerySelector::Index(index) -%}
+                            {.type = subquery_selector::INDEX, .index = {{index}}},
+                        {%- when FilterSubquerySelector::Wildcard -%}
+                            {.type = subquery_selector::WILDCARD},
+                        {%- when FilterSubquerySelector::Slice(slice) -%}
+                            {.type = subquery_selector::SLICE, .index = {{slice.start}}ll,
+                            {%- if let Some(end) = slice.end -%}
+                                .end = {{end}}ll, .has_end = true,
+                            {%- endif -%}
+                            .step = {{slice.step}}ll},
+                        {%- when FilterSubquerySelector::Filter(nested_filter_id) -%}
+                            {.type = subquery_selector::FILTER, .filter = &{{query_name}}_filter_{{nested_filter_id}}_definition},
+                    {%- endmatch -%}
+                {%- endfor -%}
+                };
+            {%- endfor -%}
+            {%- if subquery.segments.is_empty() -%}
+                const subquery {{subquery_name}} {0, nullptr, false};
+            {%- else -%}
+                const subquery_path_segment {{subquery_name}}_segments[] = {
+                {%- for (segment_index, segment) in subquery.segments.iter().enumerate() -%}
+                    { {{segment.is_descendant}}, {{segment.selectors.len()}}, {{subquery_name}}_segment_{{segment_index}}_selectors },
+                {%- endfor -%}
+                };
+                const subquery {{subquery_name}} { {{subquery.segments.len()}}, {{subquery_name}}_segments, {{subquery.has_filters()}} };
+            {%- endif -%}
+        {%- endfor -%}
+    {%- endfor -%}
+{%- endmacro -%}
+
+{%- macro generate_filter_procedures_declarations(filter_procedures, query_name) -%}
+    {%- for filter_procedure in filter_procedures -%}
+        bool {{query_name}}_{{filter_procedure.name|lower}}(array<subquery_result, MAX_SUBQUERIES_IN_FILTER> params);
+        extern const filter {{query_name}}_{{filter_procedure.name|lower}}_definition;
+    {%- endfor -%}
+{%- endmacro -%}
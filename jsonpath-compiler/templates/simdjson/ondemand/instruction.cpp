@@ -5,6 +5,24 @@
         {%- call compile_array_iteration(instruction) -%}
     {%- when Instruction::ForEachMember with { instructions } -%}
         {%- call compile_object_iteration(instruction) -%}
+    {%- when Instruction::ForEachElementInSlices with { slices, instructions } -%}
+    {%- when Instruction::ForEachElementInReverseOrder with { query_index, instructions } -%}
+        {
+            {%- let results = Self::results_vector(query_index) -%}
+            vector<size_t> first_element_results;
+            {%- if results_delivery.callbacks -%}
+                context->results_in_progress++;
+            {%- endif -%}
+            {%- call compile_array_iteration(instruction) -%}
+            reverse_results({{results}}, first_element_results);
+            {%- if results_delivery.node_paths.is_some() -%}
+                reverse_results(context->result_node_paths, first_element_results);
+            {%- endif -%}
+            {%- if results_delivery.callbacks -%}
+                if (--context->results_in_progress == 0 && context->current_result_callback != nullptr)
+                    {{query_name}}_deliver_results({{results}});
+            {%- endif -%}
+        }
     {%- when Instruction::IfCurrentIndexEquals with { index, instructions } -%}
         if (index == {{index}})
         {
@@ -15,6 +33,11 @@
         {
             {%- call compile_instructions(instructions, current_node) -%}
         }
+    {%- when Instruction::IfCurrentIndexInSlice with { slice, instructions } -%}
+        if ({{ crate::targets::simdjson::compile_slice_condition(slice) }})
+        {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
     {%- when Instruction::IfCurrentMemberNameEquals with { name, instructions } -%}
         if (key == "{{ rsonpath_syntax::str::escape(name, rsonpath_syntax::str::EscapeMode::DoubleQuoted) }}")
         {
@@ -27,16 +50,22 @@
                 {%- call compile_instructions(instructions, current_node) -%}
             }
         {%- endif -%}
+    {%- when Instruction::IfSegmentActive with { segment_index, instructions } -%}
+        if (active_segments[{{segment_index}}])
+        {
+            {%- call compile_instructions(instructions, current_node) -%}
+        }
     {%- when Instruction::ExecuteProcedureOnChild with { conditions, segments, name } -%}
         {%- if are_any_filters -%}
             selection_condition* new_segment_conditions[{{query_name}}_SEGMENT_COUNT] = {};
             {%- if eager_filter_evaluation -%}
                 bool all_segment_conditions_always_false = true;
+                bool segment_condition_value;
             {%- endif -%}
             {%- for (i, condition) in conditions.iter().enumerate() -%}
                 {%- if let Some(condition) = condition -%}
                     {%- let template = SelectionConditionTemplate::new(condition) -%}
-                    new_segment_conditions[{{i}}] = {{ template.render().unwrap() }};
+                    new_segment_conditions[{{i}}] = {{ template.render()? }};
                     {%- if i > 0 -%}
                         if (segment_conditions[{{i-1}}] != nullptr)
                             new_segment_conditions[{{i}}] = selection_condition::new_and(segment_conditions[{{i-1}}], new_segment_conditions[{{i}}]);
@@ -88,62 +117,96 @@
         {%- else -%}
             {{query_name}}_{{name|lower}}({{current_node}}, result_buf, all_results);
         {%- endif -%}
-    {%- when Instruction::SaveCurrentNodeDuringTraversal with { condition, instruction } -%}
-        if (result_buf == nullptr)
-            result_buf = new string();
-        size_t result_i = all_results.size();
-        size_t buf_start_pos = result_buf->size();
+    {%- when Instruction::SaveCurrentNodeDuringTraversal with { condition, instruction, query_index } -%}
+        {%- let results = Self::results_vector(query_index) -%}
+        {%- if query_index.is_some() -%}
+            {
+        {%- endif -%}
+        {%- if results_delivery.spans -%}
+            string_view node_token = {{current_node}}.raw_json_token();
+            size_t result_i = {{results}}.size();
+        {%- else -%}
+            if (result_buf == nullptr)
+                result_buf = {{self.new_result_buffer()}};
+            size_t result_i = {{results}}.size();
+            size_t buf_start_pos = result_buf->size();
+        {%- endif -%}
+        {%- if results_delivery.callbacks -%}
+            context->results_in_progress++;
+        {%- endif -%}
         {%- if are_any_filters -%}
             {%- if let Some(condition) = condition -%}
                 {%- let template = SelectionConditionTemplate::new(condition) -%}
                 {%- if eager_filter_evaluation -%}
-                    auto condition = {{ template.render().unwrap() }};
+                    auto condition = {{ template.render()? }};
                     bool condition_value;
                     if ({{query_name}}_try_evaluate_selection_condition(condition, condition_value))
                     {
                         if (condition_value)
                         {
                             {% if eager_filter_evaluation %}
-                                result_in_progress_conditions.push_back(&always_true_condition);
+                                context->result_in_progress_conditions.push_back(&always_true_condition);
                             {% endif %}
-                            all_results.emplace_back(result_buf, result_buf->size(), 0, nullptr);
+                            {{results}}.emplace_back({{self.result_start()}}, 0, nullptr);
+                            {%- call save_node_path() -%}
                         }
                         else
-                            result_in_progress_conditions.push_back(&always_false_condition);
+                            context->result_in_progress_conditions.push_back(&always_false_condition);
                     }
                     else
                     {
                         {% if eager_filter_evaluation %}
-                            result_in_progress_conditions.push_back(condition);
+                            context->result_in_progress_conditions.push_back(condition);
                         {% endif %}
-                        all_results.emplace_back(result_buf, result_buf->size(), 0, condition);
+                        {{results}}.emplace_back({{self.result_start()}}, 0, condition);
+                        {%- call save_node_path() -%}
                     }
                 {%- else -%}
-                    all_results.emplace_back(result_buf, result_buf->size(), 0, {{ template.render().unwrap() }});
+                    {{results}}.emplace_back({{self.result_start()}}, 0, {{ template.render()? }});
+                    {%- call save_node_path() -%}
                 {%- endif -%}
             {%- else -%}
                 {% if eager_filter_evaluation %}
-                    result_in_progress_conditions.push_back(&always_true_condition);
+                    context->result_in_progress_conditions.push_back(&always_true_condition);
                 {% endif %}
-                all_results.emplace_back(result_buf, result_buf->size(), 0, nullptr);
+                {{results}}.emplace_back({{self.result_start()}}, 0, nullptr);
+                {%- call save_node_path() -%}
             {%- endif -%}
         {%- else -%}
-            all_results.emplace_back(result_buf, result_buf->size(), 0);
+            {{results}}.emplace_back({{self.result_start()}}, 0);
+            {%- call save_node_path() -%}
         {%- endif -%}
-        {%- let template = InstructionTemplate::new(instruction, current_node, query_name, filter_subqueries.to_owned(), are_any_filters.clone(), eager_filter_evaluation.clone()) -%}
-        {{ template.render().unwrap() }}
+        {%- let template = InstructionTemplate::new(instruction, current_node, query_name, filter_subqueries.to_owned(), are_any_filters.clone(), eager_filter_evaluation.clone(), results_delivery.clone()) -%}
+        {{ template.render()? }}
         {% if are_any_filters && eager_filter_evaluation %}
             if (!{{query_name}}_try_evaluate_selection_condition(condition, condition_value) || condition_value) {
-                get<0>(all_results[result_i]) = new string(*result_buf, buf_start_pos);
-                get<1>(all_results[result_i]) = 0;
-                get<2>(all_results[result_i]) = result_buf->size() - buf_start_pos;
+                {%- if results_delivery.spans -%}
+                    get<2>({{results}}[result_i]) = node_span_end({{current_node}}, node_token);
+                {%- else -%}
+                    get<0>({{results}}[result_i]) = new string(*result_buf, buf_start_pos);
+                    get<1>({{results}}[result_i]) = 0;
+                    get<2>({{results}}[result_i]) = result_buf->size() - buf_start_pos;
+                {%- endif -%}
             }
-            result_in_progress_conditions.pop_back();
-            if (result_in_progress_conditions.empty())
-                result_buf->clear();
+            context->result_in_progress_conditions.pop_back();
+            {%- if !results_delivery.spans -%}
+                if (context->result_in_progress_conditions.empty())
+                    result_buf->clear();
+            {%- endif -%}
         {% else %}
-            get<2>(all_results[result_i]) = result_buf->size();
+            {%- if results_delivery.spans -%}
+                get<2>({{results}}[result_i]) = node_span_end({{current_node}}, node_token);
+            {%- else -%}
+                get<2>({{results}}[result_i]) = result_buf->size();
+            {%- endif -%}
         {% endif %}
+        {%- if results_delivery.callbacks -%}
+            if (--context->results_in_progress == 0 && context->current_result_callback != nullptr)
+                {{query_name}}_deliver_results({{results}});
+        {%- endif -%}
+        {%- if query_index.is_some() -%}
+            }
+        {%- endif -%}
     {%- when Instruction::Continue -%}
         continue;
     {%- when Instruction::TraverseCurrentNodeSubtree -%}
@@ -173,24 +236,65 @@
             {%- if eager_filter_evaluation -%}
                 if (filter_instances_ids.erase(filter_id) == 1)
                 {
-                    auto f_instance = all_filter_instances[filter_id];
-                    auto filter_function = {{query_name}}_get_filter_function(f_instance->filter_segment_index, f_instance->filter_selector_index);
-                    bool value = filter_function(f_instance->subqueries_results);
-                    filters_results.try_emplace(f_instance->id, value);
+                    auto f_instance = context->all_filter_instances[filter_id];
+                    bool value = f_instance->function(f_instance->subqueries_results);
+                    context->filters_results.try_emplace(f_instance->id, value);
                 }
             {%- else -%}
                 filter_instances_ids.erase(filter_id);
+                context->all_filter_instances[filter_id]->is_finished = true;
             {%- endif -%}
         }
         added_filter_instances = 0;
     {%- when Instruction::UpdateSubqueriesState -%}
         {%- call common::compile_update_subqueries_state(query_name, eager_filter_evaluation) -%}
+    {%- when Instruction::ExecuteProcedureOnChildWithActiveSegments with { name, segments_count, instructions } -%}
+        {
+            bool child_active_segments[{{segments_count}}] = {};
+            bool is_child_selected = false;
+            {%- call compile_instructions(instructions, current_node) -%}
+            bool are_child_segments_active = any_of(child_active_segments, child_active_segments + {{segments_count}}, [](bool is_active) { return is_active; });
+            if (is_child_selected)
+            {
+                {%- if results_delivery.spans -%}
+                    string_view node_token = {{current_node}}.raw_json_token();
+                {%- else -%}
+                    if (result_buf == nullptr)
+                        result_buf = {{self.new_result_buffer()}};
+                {%- endif -%}
+                {%- if results_delivery.callbacks -%}
+                    context->results_in_progress++;
+                {%- endif -%}
+                size_t result_i = all_results.size();
+                all_results.emplace_back({{self.result_start()}}, 0);
+                {%- call save_node_path() -%}
+                if (are_child_segments_active)
+                    {{query_name}}_{{name|lower}}({{current_node}}, result_buf, all_results, child_active_segments);
+                else
+                    {{query_name}}_traverse_and_save_selected_nodes({{current_node}}, result_buf);
+                {%- if results_delivery.spans -%}
+                    get<2>(all_results[result_i]) = node_span_end({{current_node}}, node_token);
+                {%- else -%}
+                    get<2>(all_results[result_i]) = result_buf->size();
+                {%- endif -%}
+                {%- if results_delivery.callbacks -%}
+                    if (--context->results_in_progress == 0 && context->current_result_callback != nullptr)
+                        {{query_name}}_deliver_results(all_results);
+                {%- endif -%}
+            }
+            else if (are_child_segments_active)
+                {{query_name}}_{{name|lower}}({{current_node}}, result_buf, all_results, child_active_segments);
+        }
+    {%- when Instruction::ActivateSegmentInChild with { segment_index } -%}
+        child_active_segments[{{segment_index}}] = true;
+    {%- when Instruction::SelectChild -%}
+        is_child_selected = true;
 {%- endmatch -%}
 
 {%- macro compile_instructions(instructions, current_node) -%}
     {%- for instruction in instructions -%}
-        {%- let template = InstructionTemplate::new(instruction, current_node, query_name, filter_subqueries.to_owned(), are_any_filters.clone(), eager_filter_evaluation.clone()) -%}
-        {{ template.render().unwrap() }}
+        {%- let template = InstructionTemplate::new(instruction, current_node, query_name, filter_subqueries.to_owned(), are_any_filters.clone(), eager_filter_evaluation.clone(), results_delivery.clone()) -%}
+        {{ template.render()? }}
     {%- endfor -%}
 {%- endmacro -%}
 
@@ -207,11 +311,12 @@
             if (is_result_saving_in_progress)
                 *result_buf += "{";
             bool first = true;
+            {%- call save_parent_node_path() -%}
             for (ondemand::field field : object)
             {
                 {%- if are_any_filters -%}
                     added_filter_instances = 0;
-                    first_added_filter_id = all_filter_instances.size();
+                    first_added_filter_id = context->all_filter_instances.size();
                 {%- endif -%}
                 string_view key = field.unescaped_key();
                 if (is_result_saving_in_progress)
@@ -223,16 +328,18 @@
                     *result_buf += "\":";
                 }
                 first = false;
+                {%- call update_node_path("append_member_to_node_path(key)") -%}
                 {%- call compile_instructions(instructions, "field.value()") -%}
             }
             if (is_result_saving_in_progress)
                 *result_buf += "}";
+            {%- call restore_parent_node_path() -%}
         }
     {%- endif -%}
 {%- endmacro -%}
 
 {%- macro compile_array_iteration(loop_instruction) -%}
-    {%- if let ForEachElement { instructions } = loop_instruction -%}
+    {%- if let Some(instructions) = Self::element_iteration_instructions(loop_instruction) -%}
         ondemand::array array;
         if (!node.get_array().get(array))
         {
@@ -244,24 +351,28 @@
                 *result_buf += "[";
             bool first = true;
             size_t index = 0;
-            {%- if crate::targets::simdjson::is_array_length_needed(instructions) -%}
+            {%- if crate::targets::is_array_length_needed(instructions) -%}
                 size_t array_length = array.count_elements();
             {%- else if are_any_filters -%}
                 size_t array_length = 0;
                 for (int filter_instance_id : filter_instances_ids)
                 {
-                    if (all_filter_instances[filter_instance_id]->is_any_current_subquery_negative_index())
+                    if (context->all_filter_instances[filter_instance_id]->is_array_length_needed())
                     {
                         array_length = array.count_elements();
                         break;
                     }
                 }
             {%- endif -%}
+            {%- call save_parent_node_path() -%}
             for (ondemand::value element : array)
             {
+                {%- if let Some(results) = Self::reversed_results_vector(loop_instruction) -%}
+                    first_element_results.push_back({{results}}.size());
+                {%- endif -%}
                 {%- if are_any_filters -%}
                     added_filter_instances = 0;
-                    first_added_filter_id = all_filter_instances.size();
+                    first_added_filter_id = context->all_filter_instances.size();
                 {%- endif -%}
                 if (!first)
                 {
@@ -270,10 +381,38 @@
                         *result_buf += ", ";
                 }
                 first = false;
+                {%- call update_node_path("append_index_to_node_path(index)") -%}
                 {%- call compile_instructions(instructions, "element") -%}
             }
             if (is_result_saving_in_progress)
                 *result_buf += "]";
+            {%- call restore_parent_node_path() -%}
         }
     {%- endif -%}
 {%- endmacro -%}
+
+
+{%- macro save_node_path() -%}
+    {%- if results_delivery.node_paths.is_some() -%}
+        context->result_node_paths.push_back(context->current_node_path);
+    {%- endif -%}
+{%- endmacro -%}
+
+{%- macro save_parent_node_path() -%}
+    {%- if results_delivery.node_paths.is_some() -%}
+        size_t parent_node_path_length = context->current_node_path.size();
+    {%- endif -%}
+{%- endmacro -%}
+
+{%- macro update_node_path(append_child) -%}
+    {%- if results_delivery.node_paths.is_some() -%}
+        context->current_node_path.resize(parent_node_path_length);
+        {{append_child}};
+    {%- endif -%}
+{%- endmacro -%}
+
+{%- macro restore_parent_node_path() -%}
+    {%- if results_delivery.node_paths.is_some() -%}
+        context->current_node_path.resize(parent_node_path_length);
+    {%- endif -%}
+{%- endmacro -%}
\ No newline at end of file
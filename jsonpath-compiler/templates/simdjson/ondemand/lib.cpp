@@ -1,13 +1,14 @@
 {%- import "macros.cpp" as common -%}
+{%- import "simdjson/filters.cpp" as filters -%}
 
 {%- if !bindings -%}
-    #ifndef {{filename.replace(".", "_")|upper}}
-    #define {{filename.replace(".", "_")|upper}}
-{%- endif -%}
+#ifndef {{filename.replace(".", "_")|upper}}
+#define {{filename.replace(".", "_")|upper}}
+{% endif -%}
 
 {%- if logging -%}
-    #define SIMDJSON_VERBOSE_LOGGING 1
-{%- endif -%}
+#define SIMDJSON_VERBOSE_LOGGING 1
+{% endif -%}
 
 #include <vector>
 #include <queue>
@@ -16,6 +17,8 @@
 #include <unordered_map>
 #include <string>
 #include <algorithm>
+#include <charconv>
+#include <cmath>
 #include <fcntl.h>
 #include <sys/mman.h>
 #include <sys/stat.h>
@@ -25,17 +28,38 @@ using namespace std;
 using namespace simdjson;
 
 {%- if Self::are_any_filters(self) -%}
-{%- call common::generate_filter_aux_structures(Self::max_subqueries_in_filter_count(self)) -%}
+{%- call filters::generate_filter_value_structures(Self::max_subqueries_in_filter_count(self), Self::max_subquery_segments_count(self)) -%}
+{%- endif -%}
+
+{%- call common::generate_execution_context(Self::are_any_filters(self), results_delivery.node_paths.is_some(), results_delivery.callbacks, results_delivery.spans) -%}
+
+// Parser and execution state reused by the executions of the queries, created once and passed to every call. A context
+// can be used by a single execution at a time.
+struct jsonpath_context {
+    ondemand::parser parser;
+    execution_context execution;
+    {%- if bindings -%}
+        string result;
+        {%- if results_delivery.spans -%}
+            vector<size_t> spans;
+        {%- endif -%}
+    {%- endif -%}
+};
+
+{%- if Self::are_any_filters(self) -%}
+{%- call common::generate_filter_aux_structures() -%}
 
     {%- for query_name in Self::query_names(self) -%}
         {%- if Self::are_any_filters_in_query(self, query_name) -%}
             constexpr uint8_t {{query_name}}_SEGMENT_COUNT = {{Self::query_segments_count(self, query_name)}};
             {%- call common::generate_filter_aux_procedures_declarations(query_name.to_string()) -%}
-            {%- call common::generate_filter_procedures_declarations(Self::query_filter_procedures(self, query_name), query_name.to_string()) -%}
+            {%- call filters::generate_filter_procedures_declarations(Self::query_filter_procedures(self, query_name), query_name.to_string()) -%}
         {%- endif -%}
 
         {%- if Self::are_any_filters_in_query(self, query_name) -%}
-        {%- call common::generate_filter_aux_structures_instances(Self::query_filter_subqueries(self, query_name), query_name.to_string()) -%}
+        {%- call filters::generate_filter_subqueries_instances(Self::query_filter_subqueries(self, query_name), query_name.to_string()) -%}
+        {%- call common::generate_absolute_subqueries_evaluation(Self::query_filter_subqueries(self, query_name), query_name.to_string()) -%}
+        {%- call common::generate_filters_definitions(Self::query_filter_subqueries(self, query_name), query_name.to_string()) -%}
         {%- endif -%}
 
         {%- call common::generate_traverse_and_save_selected_nodes_procedure(Self::are_any_filters_in_query(self, query_name), eager_filter_evaluation, query_name) -%}
@@ -43,28 +67,73 @@ using namespace simdjson;
     {%- endfor -%}
 {%- endif -%}
 
+{%- if results_delivery.spans -%}
+    size_t node_span_end(ondemand::value &node, string_view node_token)
+    {
+        const char *location;
+        if (node.current_location().get(location))
+            return 0;
+        if (location != node_token.data())
+            return reinterpret_cast<size_t>(location);
+        string_view raw_json = node.raw_json().value();
+        return reinterpret_cast<size_t>(raw_json.data() + raw_json.size());
+    }
+
+    const char *trim_span_end(const char *span_start, const char *span_end)
+    {
+        while (span_end > span_start
+               && (span_end[-1] == ' ' || span_end[-1] == '\t' || span_end[-1] == '\n' || span_end[-1] == '\r'))
+            span_end--;
+        return span_end;
+    }
+{%- endif -%}
+
+{%- call common::generate_results_reversal_procedure() -%}
+
+{%- if results_delivery.node_paths.is_some() -%}
+    {%- call common::generate_node_paths_procedures(Self::are_node_paths_json_pointers(self)) -%}
+{%- endif -%}
+
+{%- if Self::is_ndjson_input(self) -%}
+    {%- call common::generate_ndjson_procedures(Self::are_line_numbers_output(self)) -%}
+{%- endif -%}
+
+{%- if results_delivery.callbacks -%}
+    {%- for query_name in Self::query_names(self) -%}
+        void {{query_name}}_deliver_results({{Self::results_vector_type(self, query_name)}} &all_results);
+    {%- endfor -%}
+{%- endif -%}
+
 {%- call common::generate_procedures_declarations(Self::all_procedures(self)) -%}
 
 {%- for query_name in Self::query_names(self) -%}
-    string {{query_name}}(const char* padded_input, size_t length)
+    {%- let merged_queries = Self::query_merged_queries(self, query_name) -%}
+    {%- if !merged_queries.is_empty() -%}
+    {%- call generate_merged_queries_function(query_name, merged_queries) -%}
+    {%- else if results_delivery.spans -%}
+    {%- call generate_spans_functions(query_name) -%}
+    {%- else -%}
+    {%- if Self::is_ndjson_input(self) -%}
+    void {{query_name}}_document(ondemand::document &doc, string &result)
     {
-        ondemand::parser parser;
-        ondemand::document doc = parser.iterate(padded_input, length - SIMDJSON_PADDING, length);
-        ondemand::value root_node = doc.get_value().value();
-        {%- if Self::are_any_filters_in_query(self, query_name) -%}
-            vector<tuple<string *, size_t, size_t, selection_condition*>> all_results;
-            unordered_set<int> filter_instances_ids;
-            selection_condition *segment_conditions[{{query_name}}_SEGMENT_COUNT] = {};
-            current_node_data current_node {false, false, 0, 0, {}};
-            {{query_name}}_selectors_0(root_node, nullptr, all_results, segment_conditions, filter_instances_ids, current_node);
-        {%- else -%}
-            vector<tuple<string *, size_t, size_t>> all_results;
-            {{query_name}}_selectors_0(root_node, nullptr, all_results);
-        {%- endif -%}
+        {%- call generate_interrupted_execution_cleanup(query_name) -%}
+        {%- call generate_query_execution(query_name) -%}
+        bool first = true;
+        result += "[";
+    {%- else -%}
+    string {{query_name}}(jsonpath_context &query_context, const char* padded_input, size_t length)
+    {
+        {%- call enter_query_context(query_name) -%}
+        {%- call generate_document_parsing() -%}
+        {%- call generate_query_execution(query_name) -%}
         string result;
         bool first = true;
         result += "[\n";
+    {%- endif -%}
         unordered_set<string*> bufs_to_free;
+        {%- if results_delivery.node_paths.is_some() -%}
+            auto result_node_path = context->result_node_paths.begin();
+        {%- endif -%}
         {%- if Self::are_any_filters_in_query(self, query_name) -%}
             for (const auto &[buf_ptr, start, end, selection_condition] : all_results)
         {%- else -%}
@@ -80,31 +149,52 @@ using namespace simdjson;
             if (!first)
                 result += ",";
             result += " ";
-            result += string_view(buf_ptr->data() + start, end - start);
+            {%- if results_delivery.node_paths.is_some() -%}
+                append_result_with_node_path(result, string_view(buf_ptr->data() + start, end - start), *result_node_path);
+            {%- else -%}
+                result += string_view(buf_ptr->data() + start, end - start);
+            {%- endif -%}
             first = false;
             {%- if Self::are_any_filters_in_query(self, query_name) -%}
                 }
             {%- endif -%}
+            {%- if results_delivery.node_paths.is_some() -%}
+                result_node_path++;
+            {%- endif -%}
             bufs_to_free.insert(buf_ptr);
         }
-        result += "]\n";
         for (auto buf_ptr : bufs_to_free)
             delete buf_ptr;
-        {%- if Self::are_any_filters_in_query(self, query_name) -%}
-            for (auto filter_instance : all_filter_instances)
-                delete filter_instance;
-            for (auto selection_condition : selection_conditions_to_delete)
-                delete selection_condition;
-            all_filter_instances.clear();
-            filters_results.clear();
-            reached_subqueries_results.clear();
-            selection_conditions_to_delete.clear();
+        {%- call generate_filters_cleanup(query_name) -%}
+        {%- if Self::is_ndjson_input(self) -%}
+            result += "]";
+        {%- else -%}
+            result += "]\n";
+            return result;
         {%- endif -%}
-        return result;
     }
+    {%- if Self::is_ndjson_input(self) -%}
+
+    string {{query_name}}(jsonpath_context &query_context, const char* padded_input, size_t length)
+    {
+        execution_context_scope context_scope(query_context.execution);
+        return execute_on_documents(query_context.parser, padded_input, length, {{query_name}}_document, 1);
+    }
+    {%- endif -%}
+
+    string {{query_name}}(const char* padded_input, size_t length)
+    {
+        jsonpath_context query_context;
+        return {{query_name}}(query_context, padded_input, length);
+    }
+    {%- endif -%}
+
+    {%- if results_delivery.callbacks && merged_queries.is_empty() -%}
+    {%- call generate_callback_functions(query_name) -%}
+    {%- endif -%}
 
     {%- if bindings -%}
-        extern "C" const char* {{query_name}}_binding(const char* padded_input, size_t input_length, size_t* result_length)
+        extern "C" const char* {{symbol_prefix}}{{query_name}}_binding(const char* padded_input, size_t input_length, size_t* result_length)
         {
             string res_str = {{query_name}}(padded_input, input_length);
             char* res = new char[res_str.length() + 1];
@@ -112,9 +202,56 @@ using namespace simdjson;
             *result_length = res_str.length();
             return res;
         }
+        {%- if results_delivery.spans -%}
+            extern "C" size_t* {{symbol_prefix}}{{query_name}}_spans_binding(const char* padded_input, size_t input_length, size_t* spans_count)
+            {
+                vector<pair<size_t, size_t>> spans = {{query_name}}_spans(padded_input, input_length);
+                size_t* res = new size_t[2 * spans.size()];
+                for (size_t i = 0; i < spans.size(); i++)
+                {
+                    res[2 * i] = spans[i].first;
+                    res[2 * i + 1] = spans[i].second;
+                }
+                *spans_count = spans.size();
+                return res;
+            }
+        {%- endif -%}
+        {%- if results_delivery.callbacks -%}
+            extern "C" void {{symbol_prefix}}{{query_name}}_callback_binding(const char* padded_input, size_t input_length, result_callback callback, void *user_data)
+            {
+                {{query_name}}_with_callback(padded_input, input_length, callback, user_data);
+            }
+        {%- endif -%}
+        {%- call generate_context_bindings(query_name) -%}
     {%- endif -%}
 {%- endfor -%}
 
+{%- if bindings -%}
+    extern "C" jsonpath_context* {{symbol_prefix}}jsonpath_context_new()
+    {
+        return new jsonpath_context();
+    }
+
+    extern "C" void {{symbol_prefix}}jsonpath_context_free(jsonpath_context* query_context)
+    {
+        delete query_context;
+    }
+{%- endif -%}
+
+{%- if bindings -%}
+    extern "C" void {{symbol_prefix}}free_result_buffer(const char* result_buf)
+    {
+        delete[] result_buf;
+    }
+{%- endif -%}
+
+{%- if bindings && results_delivery.spans -%}
+    extern "C" void {{symbol_prefix}}free_spans_buffer(size_t* spans_buf)
+    {
+        delete[] spans_buf;
+    }
+{%- endif -%}
+
 
 {%- for (query_name, filter_procedures) in filter_procedures -%}
     {%- call common::generate_filter_aux_procedures_definitions(query_name, filter_procedures) -%}
@@ -122,13 +259,290 @@ using namespace simdjson;
 
 
 {%- for procedure in Self::all_procedures(self) -%}
-    {{ procedure.render().unwrap() }}
+    {{ procedure.render()? }}
 {%- endfor -%}
 
 {%- for filter_procedure in Self::all_filters_procedures(self) -%}
-    {{ filter_procedure.render().unwrap() }}
+    {{ filter_procedure.render()? }}
 {%- endfor -%}
 
-{%- if !bindings -%}
-    #endif
-{%- endif -%}
\ No newline at end of file
+{%- if !bindings %}
+#endif
+{%- endif -%}
+
+{%- macro generate_merged_queries_function(query_name, merged_queries) -%}
+    string {{query_name}}(jsonpath_context &query_context, const char* padded_input, size_t length)
+    {
+        execution_context_scope context_scope(query_context.execution);
+        {%- call generate_document_parsing() -%}
+        ondemand::value root_node = doc.get_value().value();
+        const char *query_names[] = {
+            {%- for merged_query in merged_queries -%}
+                "{{merged_query.name}}",
+            {%- endfor -%}
+        };
+        vector<tuple<string *, size_t, size_t>> all_results[{{merged_queries.len()}}];
+        string *result_buf = nullptr;
+        {%- for (i, merged_query) in merged_queries.iter().enumerate() -%}
+            {%- if merged_query.segments_count == 0 -%}
+                if (result_buf == nullptr)
+                    result_buf = new string();
+                all_results[{{i}}].emplace_back(result_buf, 0, 0);
+            {%- endif -%}
+        {%- endfor -%}
+        {%- if Self::query_segments_count(self, query_name) > 0 -%}
+            {{query_name}}_{{Self::query_root_procedure_name(self, query_name)|lower}}(root_node, result_buf, all_results);
+        {%- else -%}
+            {{query_name}}_traverse_and_save_selected_nodes(root_node, result_buf);
+        {%- endif -%}
+        {%- for (i, merged_query) in merged_queries.iter().enumerate() -%}
+            {%- if merged_query.segments_count == 0 -%}
+                get<2>(all_results[{{i}}][0]) = result_buf->size();
+            {%- endif -%}
+        {%- endfor -%}
+        string result;
+        result += "{\n";
+        unordered_set<string*> bufs_to_free;
+        for (size_t query_i = 0; query_i < {{merged_queries.len()}}; query_i++)
+        {
+            if (query_i > 0)
+                result += ",\n";
+            result += "\"";
+            result += query_names[query_i];
+            result += "\": [";
+            bool first = true;
+            for (const auto &[buf_ptr, start, end] : all_results[query_i])
+            {
+                if (!first)
+                    result += ",";
+                result += " ";
+                result += string_view(buf_ptr->data() + start, end - start);
+                first = false;
+                bufs_to_free.insert(buf_ptr);
+            }
+            result += "]";
+        }
+        result += "\n}\n";
+        for (auto buf_ptr : bufs_to_free)
+            delete buf_ptr;
+        return result;
+    }
+
+    string {{query_name}}(const char* padded_input, size_t length)
+    {
+        jsonpath_context query_context;
+        return {{query_name}}(query_context, padded_input, length);
+    }
+{%- endmacro -%}
+
+
+{%- macro generate_spans_functions(query_name) -%}
+    vector<pair<size_t, size_t>> {{query_name}}_spans(jsonpath_context &query_context, const char* padded_input, size_t length)
+    {
+        {%- call enter_query_context(query_name) -%}
+        {%- call generate_document_parsing() -%}
+        {%- call generate_query_execution(query_name) -%}
+        vector<pair<size_t, size_t>> spans;
+        spans.reserve(all_results.size());
+        {%- if Self::are_any_filters_in_query(self, query_name) -%}
+            for (const auto &[buf_ptr, start, end, selection_condition] : all_results)
+        {%- else -%}
+            for (const auto &[buf_ptr, start, end] : all_results)
+        {%- endif -%}
+        {
+            {%- if Self::are_any_filters_in_query(self, query_name) -%}
+                bool condition_value;
+                {{query_name}}_try_evaluate_selection_condition(selection_condition, condition_value);
+                if (!condition_value)
+                    continue;
+            {%- endif -%}
+            const char *span_start = reinterpret_cast<const char *>(start);
+            const char *span_end = end == 0 ? padded_input + length - SIMDJSON_PADDING : reinterpret_cast<const char *>(end);
+            span_end = trim_span_end(span_start, span_end);
+            spans.emplace_back(span_start - padded_input, span_end - span_start);
+        }
+        {%- call generate_filters_cleanup(query_name) -%}
+        return spans;
+    }
+
+    vector<pair<size_t, size_t>> {{query_name}}_spans(const char* padded_input, size_t length)
+    {
+        jsonpath_context query_context;
+        return {{query_name}}_spans(query_context, padded_input, length);
+    }
+
+    string {{query_name}}(jsonpath_context &query_context, const char* padded_input, size_t length)
+    {
+        string result;
+        bool first = true;
+        result += "[\n";
+        for (const auto &[start, span_length] : {{query_name}}_spans(query_context, padded_input, length))
+        {
+            if (!first)
+                result += ",";
+            result += " ";
+            result += string_view(padded_input + start, span_length);
+            first = false;
+        }
+        result += "]\n";
+        return result;
+    }
+
+    string {{query_name}}(const char* padded_input, size_t length)
+    {
+        jsonpath_context query_context;
+        return {{query_name}}(query_context, padded_input, length);
+    }
+{%- endmacro -%}
+
+{%- macro enter_query_context(query_name) -%}
+    execution_context_scope context_scope(query_context.execution);
+    {%- call generate_interrupted_execution_cleanup(query_name) -%}
+{%- endmacro -%}
+
+{%- macro generate_document_parsing() -%}
+    ondemand::document doc = query_context.parser.iterate(padded_input, length - SIMDJSON_PADDING, length);
+{%- endmacro -%}
+
+{%- macro generate_query_execution(query_name) -%}
+    {%- if Self::are_any_filters_in_query(self, query_name) -%}
+        {{query_name}}_evaluate_absolute_subqueries(doc);
+    {%- endif -%}
+    ondemand::value root_node = doc.get_value().value();
+    {{Self::results_vector_type(self, query_name)}} all_results;
+    {%- if results_delivery.node_paths.is_some() -%}
+        reset_node_paths();
+    {%- endif -%}
+    {%- if Self::are_any_filters_in_query(self, query_name) -%}
+        unordered_set<int> filter_instances_ids;
+        selection_condition *segment_conditions[{{query_name}}_SEGMENT_COUNT] = {};
+        current_node_data current_node {false, false, 0, 0, {}};
+        {{query_name}}_selectors_0(root_node, nullptr, all_results, segment_conditions, filter_instances_ids, current_node);
+    {%- else -%}
+        {{query_name}}_selectors_0(root_node, nullptr, all_results);
+    {%- endif -%}
+{%- endmacro -%}
+
+{%- macro generate_filters_cleanup(query_name) -%}
+    {%- if Self::are_any_filters_in_query(self, query_name) -%}
+        for (auto filter_instance : context->all_filter_instances)
+            delete filter_instance;
+        for (auto selection_condition : context->selection_conditions_to_delete)
+            delete selection_condition;
+        context->all_filter_instances.clear();
+        context->filters_results.clear();
+        context->reached_subqueries_results.clear();
+        context->selection_conditions_to_delete.clear();
+    {%- endif -%}
+{%- endmacro -%}
+
+{%- macro generate_interrupted_execution_cleanup(query_name) -%}
+    {%- if Self::are_any_filters_in_query(self, query_name) -%}
+        {%- call generate_filters_cleanup(query_name) -%}
+        context->nested_filter_instances.clear();
+        context->result_in_progress_conditions.clear();
+    {%- endif -%}
+    {%- if results_delivery.callbacks -%}
+        context->current_result_callback = nullptr;
+        context->results_in_progress = 0;
+        context->delivered_results_count = 0;
+        {%- if !results_delivery.spans -%}
+            context->results_buffer.clear();
+        {%- endif -%}
+    {%- endif -%}
+{%- endmacro -%}
+
+{%- macro generate_callback_functions(query_name) -%}
+    void {{query_name}}_deliver_results({{Self::results_vector_type(self, query_name)}} &all_results)
+    {
+        for (; context->delivered_results_count < all_results.size(); context->delivered_results_count++)
+        {
+            {%- if Self::are_any_filters_in_query(self, query_name) -%}
+                const auto &[buf_ptr, start, end, selection_condition] = all_results[context->delivered_results_count];
+                bool condition_value;
+                if (!{{query_name}}_try_evaluate_selection_condition(selection_condition, condition_value))
+                    return;
+                if (!condition_value)
+                    continue;
+            {%- else -%}
+                const auto &[buf_ptr, start, end] = all_results[context->delivered_results_count];
+            {%- endif -%}
+            {%- if results_delivery.spans -%}
+                const char *span_start = reinterpret_cast<const char *>(start);
+                const char *span_end = end == 0 ? context->current_input_end : reinterpret_cast<const char *>(end);
+                span_end = trim_span_end(span_start, span_end);
+                context->current_result_callback(span_start, span_end - span_start, context->current_result_callback_data);
+            {%- else if results_delivery.node_paths.is_some() -%}
+                string result;
+                append_result_with_node_path(result, string_view(buf_ptr->data() + start, end - start), context->result_node_paths[context->delivered_results_count]);
+                context->current_result_callback(result.data(), result.size(), context->current_result_callback_data);
+            {%- else -%}
+                context->current_result_callback(buf_ptr->data() + start, end - start, context->current_result_callback_data);
+            {%- endif -%}
+        }
+        {%- if !results_delivery.spans -%}
+            for (const auto &result : all_results)
+            {
+                if (get<0>(result) != &context->results_buffer)
+                    delete get<0>(result);
+            }
+            context->results_buffer.clear();
+        {%- endif -%}
+        all_results.clear();
+        {%- if results_delivery.node_paths.is_some() -%}
+            context->result_node_paths.clear();
+        {%- endif -%}
+        context->delivered_results_count = 0;
+    }
+
+    void {{query_name}}_with_callback(jsonpath_context &query_context, const char* padded_input, size_t length,
+                                      result_callback callback, void *user_data)
+    {
+        {%- call enter_query_context(query_name) -%}
+        context->current_result_callback = callback;
+        context->current_result_callback_data = user_data;
+        {%- if results_delivery.spans -%}
+            context->current_input_end = padded_input + length - SIMDJSON_PADDING;
+        {%- endif -%}
+        {%- call generate_document_parsing() -%}
+        {%- call generate_query_execution(query_name) -%}
+        {{query_name}}_deliver_results(all_results);
+        context->current_result_callback = nullptr;
+        {%- call generate_filters_cleanup(query_name) -%}
+    }
+
+    void {{query_name}}_with_callback(const char* padded_input, size_t length, result_callback callback, void *user_data)
+    {
+        jsonpath_context query_context;
+        {{query_name}}_with_callback(query_context, padded_input, length, callback, user_data);
+    }
+{%- endmacro -%}
+
+{%- macro generate_context_bindings(query_name) -%}
+    extern "C" const char* {{symbol_prefix}}{{query_name}}_context_binding(jsonpath_context* query_context, const char* padded_input, size_t input_length, size_t* result_length)
+    {
+        query_context->result = {{query_name}}(*query_context, padded_input, input_length);
+        *result_length = query_context->result.length();
+        return query_context->result.c_str();
+    }
+    {%- if results_delivery.spans -%}
+        extern "C" const size_t* {{symbol_prefix}}{{query_name}}_spans_context_binding(jsonpath_context* query_context, const char* padded_input, size_t input_length, size_t* spans_count)
+        {
+            vector<pair<size_t, size_t>> spans = {{query_name}}_spans(*query_context, padded_input, input_length);
+            query_context->spans.clear();
+            for (const auto &[offset, span_length] : spans)
+            {
+                query_context->spans.push_back(offset);
+                query_context->spans.push_back(span_length);
+            }
+            *spans_count = spans.size();
+            return query_context->spans.data();
+        }
+    {%- endif -%}
+    {%- if results_delivery.callbacks -%}
+        extern "C" void {{symbol_prefix}}{{query_name}}_callback_context_binding(jsonpath_context* query_context, const char* padded_input, size_t input_length, result_callback callback, void *user_data)
+        {
+            {{query_name}}_with_callback(*query_context, padded_input, input_length, callback, user_data);
+        }
+    {%- endif -%}
+{%- endmacro -%}
@@ -1,115 +1,110 @@
-{%- macro generate_filter_aux_structures(max_subqueries_in_filter_count) -%}
-    constexpr uint8_t MAX_SUBQUERIES_IN_FILTER = {{max_subqueries_in_filter_count}};
+{%- macro generate_execution_context(filters, node_paths, callbacks, spans) -%}
+    {%- if filters -%}
+        struct selection_condition;
 
-    struct selection_condition;
-    struct filter;
-    struct filter_instance;
-    struct subquery_path_segment;
-    struct subquery_result;
-    struct current_node_data;
+        // Result of an absolute subquery, together with the strings it refers to.
+        struct absolute_subquery_result {
+            subquery_result result;
+            string str_value;
+            string canonical_form;
+        };
+    {%- endif -%}
+    {%- if callbacks -%}
+        typedef void (*result_callback)(const char *result, size_t result_length, void *user_data);
+    {%- endif -%}
 
-    static vector<selection_condition*> selection_conditions_to_delete;
-    static vector<filter_instance*> all_filter_instances;
-    static unordered_map<int, bool> filters_results;
-
-    static vector<subquery_result *> reached_subqueries_results;
-
-    static vector<selection_condition*> result_in_progress_conditions;
-
-    enum subquery_result_type
-    {
-     NOTHING, STRING, INT, FLOAT, BOOL, __NULL, COMPLEX
+    // State of a single execution of a query. Every execution has its own context, so the queries can be executed
+    // from multiple threads at once, and from a callback receiving the results of another query.
+    struct execution_context {
+        {%- if filters -%}
+            vector<selection_condition*> selection_conditions_to_delete;
+            vector<filter_instance*> all_filter_instances;
+            vector<filter_instance*> nested_filter_instances;
+            unordered_map<int, bool> filters_results;
+            vector<subquery_result *> reached_subqueries_results;
+            vector<selection_condition*> result_in_progress_conditions;
+            unordered_map<const subquery *, absolute_subquery_result> absolute_subqueries_results;
+            dom::parser complex_values_parser;
+        {%- endif -%}
+        {%- if node_paths -%}
+            string current_node_path;
+            vector<string> result_node_paths;
+        {%- endif -%}
+        {%- if callbacks -%}
+            result_callback current_result_callback = nullptr;
+            void *current_result_callback_data = nullptr;
+            size_t results_in_progress = 0;
+            size_t delivered_results_count = 0;
+            {%- if spans -%}
+                const char *current_input_end = nullptr;
+            {%- else -%}
+                string results_buffer;
+            {%- endif -%}
+        {%- endif -%}
     };
 
-    struct subquery_result {
-        filter_instance* filter;
-        string_view str_value;
-        int64_t int_value;
-        double float_value;
-        bool bool_value;
-        subquery_result_type type;
-        bool exists;
+    // Context of the execution in progress in the current thread.
+    thread_local execution_context *context = nullptr;
 
-        partial_ordering operator<=>(const subquery_result& other) const
-        {
-            if (type == NOTHING && other.type == NOTHING) return partial_ordering::equivalent;
-            if (type == __NULL && other.type == __NULL) return partial_ordering::equivalent;
-            if (other.type == STRING) return *this <=> other.str_value;
-            if (other.type == INT) return *this <=> other.int_value;
-            if (other.type == FLOAT) return *this <=> other.float_value;
-            if (other.type == BOOL) return *this <=> other.bool_value;
+    // Makes the given context the current one until the end of the scope, and then restores the previous one.
+    struct execution_context_scope {
+        execution_context *previous_context;
 
-            return partial_ordering::unordered;
-        }
-        partial_ordering operator<=>(const string_view& other) const
+        explicit execution_context_scope(execution_context &new_context) : previous_context(context)
         {
-            if (type != STRING)
-                return partial_ordering::unordered;
-
-            const int cmp = str_value.compare(other);
-            if (cmp == 0) return partial_ordering::equivalent;
-            if (cmp < 0) return partial_ordering::less;
-            return partial_ordering::greater;
+            context = &new_context;
         }
-        partial_ordering operator<=>(const int64_t& other) const
-        {
-            if (type == INT) {
-                if (int_value == other) return partial_ordering::equivalent;
-                if (int_value < other) return partial_ordering::less;
-                return partial_ordering::greater;
-            }
-
-            if (type == FLOAT) {
-                if (float_value == other) return partial_ordering::equivalent;
-                if (float_value < other) return partial_ordering::less;
-                return partial_ordering::greater;
-            }
 
-            return partial_ordering::unordered;
-        }
-        partial_ordering operator<=>(const double& other) const
+        ~execution_context_scope()
         {
-            if (type == INT) {
-                if (int_value == other) return partial_ordering::equivalent;
-                if (int_value < other) return partial_ordering::less;
-                return partial_ordering::greater;
-            }
+            context = previous_context;
+        }
+    };
+{%- endmacro -%}
 
-            if (type == FLOAT) {
-                if (float_value == other) return partial_ordering::equivalent;
-                if (float_value < other) return partial_ordering::less;
-                return partial_ordering::greater;
-            }
+{%- macro enter_execution_context() -%}
+    execution_context new_context;
+    execution_context_scope context_scope(new_context);
+{%- endmacro -%}
 
-            return partial_ordering::unordered;
-        }
-        partial_ordering operator<=>(const bool& other) const
-        {
-            if (type != BOOL) return partial_ordering::unordered;
+{%- macro generate_filter_aux_structures() -%}
+    int64_t count_children(ondemand::value &node)
+    {
+        ondemand::json_type type;
+        if (node.type().get(type))
+            return 0;
+        if (type == ondemand::json_type::array)
+            return node.count_elements().value();
+        return node.count_fields().value();
+    }
 
-            return bool_value == other ? partial_ordering::equivalent : partial_ordering::unordered;
-        }
-        bool operator==(const subquery_result& other) const
-        {
-            return *this <=> other == partial_ordering::equivalent;
-        }
-        bool operator==(const string_view& other) const
-        {
-            return *this <=> other == partial_ordering::equivalent;
-        }
-        bool operator==(const int64_t& other) const
-        {
-            return *this <=> other == partial_ordering::equivalent;
-        }
-        bool operator==(const double& other) const
-        {
-            return *this <=> other == partial_ordering::equivalent;
-        }
-        bool operator==(const bool& other) const
+    // The node is parsed again from its text, which is found without iterating over it, since it is traversed
+    // afterwards.
+    string to_canonical_form(ondemand::value &node)
+    {
+        const char *json = node.raw_json_token().data();
+        size_t length = 0;
+        int depth = 0;
+        bool is_in_string = false;
+        do
         {
-            return *this <=> other == partial_ordering::equivalent;
-        }
-    };
+            char c = json[length++];
+            if (is_in_string)
+            {
+                if (c == '\\')
+                    length++;
+                else if (c == '"')
+                    is_in_string = false;
+            }
+            else if (c == '"')
+                is_in_string = true;
+            else if (c == '{' || c == '[')
+                depth++;
+            else if (c == '}' || c == ']')
+                depth--;
+        } while (depth > 0);
+        return to_canonical_form(context->complex_values_parser.parse(json, length).value());
+    }
 
     struct selection_condition {
         enum {AND, OR, FILTER, ALWAYS_FALSE, ALWAYS_TRUE} type;
@@ -119,119 +114,396 @@
 
         static selection_condition* new_and(selection_condition *lhs, selection_condition *rhs) {
             auto ptr = new selection_condition {AND, lhs, rhs, nullptr};
-            selection_conditions_to_delete.push_back(ptr);
+            context->selection_conditions_to_delete.push_back(ptr);
             return ptr;
         }
 
         static selection_condition* new_or(selection_condition *lhs, selection_condition *rhs) {
             auto ptr = new selection_condition {OR, lhs, rhs, nullptr};
-            selection_conditions_to_delete.push_back(ptr);
+            context->selection_conditions_to_delete.push_back(ptr);
             return ptr;
         }
 
         static selection_condition* new_filter(filter_instance *filter) {
             auto ptr = new selection_condition {FILTER, nullptr, nullptr, filter};
-            selection_conditions_to_delete.push_back(ptr);
+            context->selection_conditions_to_delete.push_back(ptr);
             return ptr;
         }
     };
 
     static selection_condition always_false_condition {.type = selection_condition::ALWAYS_FALSE};
     static selection_condition always_true_condition {.type = selection_condition::ALWAYS_TRUE};
-    static bool segment_condition_value;
 
-    struct subquery_path_segment {
-        const bool is_name;
-        const char* name;
-        const int64_t index;
-        const subquery_path_segment *next;
+    typedef bool (*filter_function_ptr)(array<subquery_result, MAX_SUBQUERIES_IN_FILTER> subquery_result);
+
+    struct filter {
+        filter_function_ptr function;
+        uint8_t subquery_count;
+        array<const subquery *, MAX_SUBQUERIES_IN_FILTER> subqueries;
+        // Absolute subqueries are evaluated before the query is executed, and their results are saved in the execution
+        // context.
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_absolute;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_existence_test;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_length_needed;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_count_needed;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_string_needed;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_value_needed;
     };
 
     struct filter_instance {
         int id;
         bool is_active;
-        uint8_t filter_segment_index;
-        uint8_t filter_selector_index;
+        // Whether the node the filter is applied to has been traversed, so the filter can be evaluated.
+        bool is_finished;
+        filter_function_ptr function;
         uint8_t subquery_count;
         uint8_t reached_subquery_count;
-        array<subquery_path_segment *, MAX_SUBQUERIES_IN_FILTER> current_subqueries_segments;
-        vector<subquery_path_segment *> current_subqueries_segments_backups;
+        // For a filter nested in a subquery, the filter instance following the subquery.
+        filter_instance *parent;
+        uint8_t parent_subquery_index;
+        array<const subquery *, MAX_SUBQUERIES_IN_FILTER> subqueries;
+        array<subquery_state, MAX_SUBQUERIES_IN_FILTER> current_subqueries_states;
+        vector<subquery_state> current_subqueries_states_backups;
         array<subquery_result, MAX_SUBQUERIES_IN_FILTER> subqueries_results;
+        array<string, MAX_SUBQUERIES_IN_FILTER> canonical_forms;
         array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_existence_test;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_length_needed;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_count_needed;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_string_needed;
+        array<bool, MAX_SUBQUERIES_IN_FILTER> is_subquery_value_needed;
+
+        filter_instance(int id, filter_function_ptr function, uint8_t subquery_count)
+            : id(id), is_active(false), is_finished(false), function(function), subquery_count(subquery_count), reached_subquery_count(0),
+              parent(nullptr), parent_subquery_index(0), subqueries{}, current_subqueries_states{},
+              subqueries_results{}, canonical_forms{}, is_subquery_existence_test{}, is_subquery_length_needed{},
+              is_subquery_count_needed{}, is_subquery_string_needed{}, is_subquery_value_needed{}
+        {
+        }
 
-        filter_instance(int id, uint8_t segment_index, uint8_t selector_index, uint8_t subquery_count)
-            : id(id), is_active(false), filter_segment_index(segment_index), filter_selector_index(selector_index),
-              subquery_count(subquery_count), reached_subquery_count(0), current_subqueries_segments{},
-              subqueries_results{}, is_subquery_existence_test{}
+        // An instance of a nested filter also follows the rest of the subquery containing the filter, after the
+        // node the filter is applied to, as an additional subquery with the index subquery_count.
+        uint8_t tracked_subquery_count() const
         {
+            return parent != nullptr ? subquery_count + 1 : subquery_count;
         }
 
-        void save_current_subqueries_segments() {
-            current_subqueries_segments_backups.insert(current_subqueries_segments_backups.end(), current_subqueries_segments.begin(), current_subqueries_segments.begin() + subquery_count);
+        void save_current_subqueries_states() {
+            current_subqueries_states_backups.insert(current_subqueries_states_backups.end(), current_subqueries_states.begin(), current_subqueries_states.begin() + tracked_subquery_count());
         }
 
-        void restore_current_subqueries_segments() {
-            if (current_subqueries_segments_backups.empty())
+        void restore_current_subqueries_states() {
+            if (current_subqueries_states_backups.empty())
                 return;
-            copy(current_subqueries_segments_backups.end() - subquery_count, current_subqueries_segments_backups.end(), current_subqueries_segments.begin());
-            current_subqueries_segments_backups.resize(current_subqueries_segments_backups.size() - subquery_count);
+            copy(current_subqueries_states_backups.end() - tracked_subquery_count(), current_subqueries_states_backups.end(), current_subqueries_states.begin());
+            current_subqueries_states_backups.resize(current_subqueries_states_backups.size() - tracked_subquery_count());
         }
 
-        bool is_any_current_subquery_negative_index()
+        bool is_array_length_needed()
         {
-            for (auto subquery_segment : current_subqueries_segments)
-                if (subquery_segment != nullptr && !subquery_segment->is_name && subquery_segment->index < 0)
+            for (size_t i = 0; i < tracked_subquery_count(); i++)
+                if (subqueries[i]->is_array_length_needed(current_subqueries_states[i]))
                     return true;
             return false;
         }
-    };
 
+        void select_current_node(size_t i, ondemand::value &node)
+        {
+            subqueries_results[i].exists = true;
+            subqueries_results[i].count = 1;
+            if (is_subquery_existence_test[i] || is_subquery_count_needed[i])
+                return;
+            if (node.is_scalar())
+                context->reached_subqueries_results.push_back(&subqueries_results[i]);
+            else
+                select_complex_node(i, node);
+        }
 
-    struct current_node_data {
-        bool is_member;
-        bool is_element;
-        uint64_t array_length;
-        uint64_t index;
-        string_view key;
+        void select_complex_node(size_t i, ondemand::value &node)
+        {
+            subqueries_results[i].type = COMPLEX;
+            if (is_subquery_length_needed[i])
+                subqueries_results[i].length = count_children(node);
+            else if (!is_subquery_string_needed[i])
+            {
+                canonical_forms[i] = to_canonical_form(node);
+                subqueries_results[i].canonical_form = canonical_forms[i];
+            }
+        }
+
+        // Adds the nodes selected by the rest of the subquery after a node satisfying a nested filter.
+        void merge_subquery_result(size_t i, const subquery_result &result)
+        {
+            subquery_result &merged_result = subqueries_results[i];
+            if (is_subquery_count_needed[i])
+            {
+                merged_result.count += result.count;
+                return;
+            }
+            if (is_subquery_value_needed[i])
+            {
+                if (result.count == 0)
+                    return;
+                if (merged_result.count == 0 && result.count == 1)
+                {
+                    merged_result = result;
+                    merged_result.filter = this;
+                    return;
+                }
+                merged_result.count += result.count;
+                merged_result.type = NOTHING;
+                return;
+            }
+            if (!result.exists || merged_result.exists)
+                return;
+            merged_result.exists = true;
+            if (i == subquery_count)
+                return;
+            reached_subquery_count++;
+            {%- if eager_filter_evaluation -%}
+                if (reached_subquery_count == subquery_count && parent == nullptr)
+                    context->filters_results.try_emplace(id, function(subqueries_results));
+            {%- endif -%}
+        }
     };
 
-    typedef bool (*filter_function_ptr)(array<subquery_result, MAX_SUBQUERIES_IN_FILTER> subquery_result);
-{%- endmacro -%}
+    filter_instance* new_filter_instance(const filter &definition, ondemand::value &node)
+    {
+        auto f_instance = new filter_instance(context->all_filter_instances.size(), definition.function, definition.subquery_count);
+        context->all_filter_instances.push_back(f_instance);
+        f_instance->subqueries = definition.subqueries;
+        f_instance->is_subquery_existence_test = definition.is_subquery_existence_test;
+        f_instance->is_subquery_length_needed = definition.is_subquery_length_needed;
+        f_instance->is_subquery_count_needed = definition.is_subquery_count_needed;
+        f_instance->is_subquery_string_needed = definition.is_subquery_string_needed;
+        f_instance->is_subquery_value_needed = definition.is_subquery_value_needed;
+        for (size_t i = 0; i < definition.subquery_count; i++)
+        {
+            if (definition.is_subquery_absolute[i])
+            {
+                f_instance->subqueries_results[i] = context->absolute_subqueries_results[definition.subqueries[i]].result;
+                f_instance->subqueries_results[i].filter = f_instance;
+                f_instance->reached_subquery_count++;
+                continue;
+            }
+            f_instance->subqueries_results[i] = {.filter = f_instance};
+            f_instance->current_subqueries_states[i] = definition.subqueries[i]->initial_state();
+            if (definition.subqueries[i]->segment_count == 0)
+                f_instance->select_current_node(i, node);
+        }
+        return f_instance;
+    }
+
+    // Starts the filters in the segments of the given subquery that are to be matched by the current node. Whether
+    // the node matches such a segment is known only after the node is traversed, so until then the rest of the
+    // subquery is followed by the instance of the nested filter.
+    void start_nested_filters(filter_instance *f_instance, size_t i, ondemand::value &node)
+    {
+        const subquery &query = *f_instance->subqueries[i];
+        const subquery_state &state = f_instance->current_subqueries_states[i];
+        for (size_t j = 0; j < query.segment_count; j++)
+        {
+            if (state.paths_counts[j] == 0)
+                continue;
+            const subquery_path_segment &segment = query.segments[j];
+            for (uint8_t k = 0; k < segment.selector_count; k++)
+            {
+                if (segment.selectors[k].type != subquery_selector::FILTER)
+                    continue;
+                auto nested_f_instance = new_filter_instance(*segment.selectors[k].filter, node);
+                size_t rest_index = nested_f_instance->subquery_count;
+                nested_f_instance->is_active = true;
+                nested_f_instance->parent = f_instance;
+                nested_f_instance->parent_subquery_index = i;
+                nested_f_instance->subqueries[rest_index] = &query;
+                nested_f_instance->subqueries_results[rest_index] = {.filter = nested_f_instance};
+                nested_f_instance->is_subquery_existence_test[rest_index] = f_instance->is_subquery_existence_test[i];
+                nested_f_instance->is_subquery_count_needed[rest_index] = f_instance->is_subquery_count_needed[i];
+                nested_f_instance->is_subquery_value_needed[rest_index] = f_instance->is_subquery_value_needed[i];
+                if (j + 1 < query.segment_count)
+                    nested_f_instance->current_subqueries_states[rest_index].paths_counts[j + 1] = state.paths_counts[j];
+                else
+                {
+                    nested_f_instance->select_current_node(rest_index, node);
+                    nested_f_instance->subqueries_results[rest_index].count = state.paths_counts[j];
+                }
+                context->nested_filter_instances.push_back(nested_f_instance);
+            }
+        }
+    }
+
+    // Ends the execution of the nested filters started at the current node. If a filter is satisfied, the node
+    // matches the segment containing the filter, so the nodes selected by the rest of the subquery are added to the
+    // results of the filter instance following the subquery.
+    void end_nested_filters_execution(size_t first_nested_filter_instance, unordered_set<int> &filter_instances_ids)
+    {
+        while (context->nested_filter_instances.size() > first_nested_filter_instance)
+        {
+            filter_instance *f_instance = context->nested_filter_instances.back();
+            context->nested_filter_instances.pop_back();
+            filter_instances_ids.erase(f_instance->id);
+            if (f_instance->function(f_instance->subqueries_results))
+                f_instance->parent->merge_subquery_result(f_instance->parent_subquery_index,
+                                                          f_instance->subqueries_results[f_instance->subquery_count]);
+        }
+    }
+
+    // Absolute subqueries do not depend on the node the filter is applied to, so each of them
+    // is evaluated once, in a separate pass over the document, before the query is executed.
+    struct absolute_subquery_evaluation {
+        const subquery &query;
+        bool is_existence_test;
+        bool is_count_needed;
+        bool is_value_needed;
+        subquery_result &result;
+        string &str_value;
+        string &canonical_form;
+        void (*traverse)(ondemand::value &node, string *result_buf, unordered_set<int> &filter_instances_ids,
+                         current_node_data &current_node);
+
+        void evaluate(ondemand::document &doc)
+        {
+            result = {};
+            ondemand::value root_node = doc.get_value().value();
+            if (query.has_filters)
+                evaluate_with_filter_instance(root_node);
+            else if (query.segment_count == 0)
+            {
+                result.exists = true;
+                result.count = 1;
+                if (!is_existence_test)
+                    set_value(root_node);
+            }
+            else
+                visit_children(root_node, query.initial_state());
+            if (is_value_needed && result.count > 1)
+                result.type = NOTHING;
+            doc.rewind();
+        }
+
+        // Filters nested in the subquery are evaluated by filter instances, so the document is traversed in the
+        // same way as by the query.
+        void evaluate_with_filter_instance(ondemand::value &root_node)
+        {
+            auto f_instance = new filter_instance(context->all_filter_instances.size(),
+                                                  [](array<subquery_result, MAX_SUBQUERIES_IN_FILTER>) { return true; }, 1);
+            context->all_filter_instances.push_back(f_instance);
+            f_instance->is_active = true;
+            f_instance->subqueries[0] = &query;
+            f_instance->current_subqueries_states[0] = query.initial_state();
+            f_instance->subqueries_results[0] = {.filter = f_instance};
+            f_instance->is_subquery_existence_test[0] = is_existence_test;
+            f_instance->is_subquery_count_needed[0] = is_count_needed;
+            f_instance->is_subquery_value_needed[0] = is_value_needed;
+            unordered_set<int> filter_instances_ids {f_instance->id};
+            current_node_data current_node {false, false, 0, 0, {}};
+            traverse(root_node, nullptr, filter_instances_ids, current_node);
+            result = f_instance->subqueries_results[0];
+            if (result.type == STRING)
+            {
+                str_value = result.str_value;
+                result.str_value = str_value;
+            }
+            else if (result.type == COMPLEX)
+            {
+                canonical_form = result.canonical_form;
+                result.canonical_form = canonical_form;
+            }
+        }
+
+        bool is_evaluated() const
+        {
+            if (is_count_needed)
+                return false;
+            if (is_value_needed)
+                return result.count > 1;
+            return result.exists;
+        }
+
+        void visit_children(ondemand::value &node, const subquery_state &state)
+        {
+            current_node_data current_node {false, false, 0, 0, {}};
+            ondemand::object object;
+            ondemand::array array;
+            if (!node.get_object().get(object))
+            {
+                current_node.is_member = true;
+                for (ondemand::field field : object)
+                {
+                    current_node.key = field.unescaped_key();
+                    ondemand::value value = field.value();
+                    visit(value, state, current_node);
+                    if (is_evaluated())
+                        return;
+                }
+            }
+            else if (!node.get_array().get(array))
+            {
+                current_node.is_element = true;
+                if (query.is_array_length_needed(state))
+                    current_node.array_length = array.count_elements();
+                for (ondemand::value element : array)
+                {
+                    visit(element, state, current_node);
+                    if (is_evaluated())
+                        return;
+                    current_node.index++;
+                }
+            }
+        }
+
+        void visit(ondemand::value &node, const subquery_state &state, const current_node_data &current_node)
+        {
+            int64_t matches_count;
+            subquery_state next_state = query.next_state(state, current_node, matches_count);
+            if (matches_count > 0)
+            {
+                bool is_first_match = !result.exists;
+                result.exists = true;
+                result.count += matches_count;
+                if (is_first_match && !is_existence_test && !is_count_needed)
+                    set_value(node);
+            }
+            if (!next_state.is_empty() && !is_evaluated())
+                visit_children(node, next_state);
+        }
+
+        void set_value(ondemand::value &node)
+        {
+            string_view str;
+            if (!node.is_scalar())
+            {
+                result.type = COMPLEX;
+                result.length = count_children(node);
+                canonical_form = to_canonical_form(node);
+                result.canonical_form = canonical_form;
+            }
+            else if (node.is_null())
+                result.type = __NULL;
+            else if (!node.get_string().get(str))
+            {
+                result.type = STRING;
+                str_value = str;
+                result.str_value = str_value;
+            }
+            else if (!node.get_int64().get(result.int_value))
+                result.type = INT;
+            else if (!node.get_double().get(result.float_value))
+                result.type = FLOAT;
+            else if (!node.get_bool().get(result.bool_value))
+                result.type = BOOL;
+        }
+    };
 
-{%- macro generate_filter_aux_structures_instances(filter_subqueries, query_name) -%}
-    {%- for (filter_id, subqueries) in filter_subqueries -%}
-        {%- for (subquery_index, subquery) in subqueries.iter().enumerate() -%}
-            {%- for (segment_index, segment) in subquery.segments.iter().enumerate().rev() -%}
-                subquery_path_segment {{query_name}}_filter_{{filter_id.segment_index}}_{{filter_id.selector_index}}_subquery_{{subquery_index}}_segment_{{segment_index}} {
-                {%- match segment -%}
-                    {%- when FilterSubquerySegment::Name(name) -%} true, "{{name}}", 0,
-                    {%- when FilterSubquerySegment::Index(index) -%} false, nullptr, {{index}},
-                {%- endmatch -%}
-                {%- if segment_index == subquery.segments.len() - 1 -%}
-                    nullptr
-                {%- else -%}
-                    &{{query_name}}_filter_{{filter_id.segment_index}}_{{filter_id.selector_index}}_subquery_{{subquery_index}}_segment_{{segment_index+1}}
-                {%- endif -%}
-                };
-            {%- endfor -%}
-        {%- endfor -%}
-    {%- endfor -%}
 {%- endmacro -%}
 
 {%- macro generate_filter_aux_procedures_declarations(query_name) -%}
-    filter_function_ptr {{query_name}}_get_filter_function(uint8_t filter_segment_index, uint8_t filter_selector_index);
     bool {{query_name}}_try_evaluate_selection_condition(selection_condition *condition, bool &value);
+    void {{query_name}}_traverse_and_save_selected_nodes(ondemand::value &node, string *result_buf,
+                                                         unordered_set<int> &filter_instances_ids,
+                                                         current_node_data &current_node);
 {%- endmacro -%}
 
 {%- macro generate_filter_aux_procedures_definitions(query_name, filter_procedures) -%}
-    filter_function_ptr {{query_name}}_get_filter_function(uint8_t filter_segment_index, uint8_t filter_selector_index) {
-        {%- for filter_procedure in filter_procedures -%}
-            if (filter_segment_index == {{filter_procedure.filter_id.segment_index}} && filter_selector_index == {{filter_procedure.filter_id.selector_index}})
-                return &{{query_name}}_{{filter_procedure.name|lower}};
-        {%- endfor -%}
-        return nullptr;
-    }
-
     bool {{query_name}}_try_evaluate_selection_condition(selection_condition *condition, bool &value) {
         if (condition == nullptr)
         {
@@ -330,9 +602,9 @@
             case selection_condition::FILTER: {
                 {%- if eager_filter_evaluation -%}
                     int filter_id = condition->filter->id;
-                    if (filters_results.contains(filter_id))
+                    if (context->filters_results.contains(filter_id))
                     {
-                        value = filters_results.at(filter_id);
+                        value = context->filters_results.at(filter_id);
                         condition->type = value
                             ? selection_condition::ALWAYS_TRUE
                             : selection_condition::ALWAYS_FALSE;
@@ -341,8 +613,9 @@
                     return false;
                 {%- else -%}
                     auto filter_instance = condition->filter;
-                    auto filter_function = {{query_name}}_get_filter_function(filter_instance->filter_segment_index, filter_instance->filter_selector_index);
-                    value = filter_function(filter_instance->subqueries_results);
+                    if (!filter_instance->is_finished)
+                        return false;
+                    value = filter_instance->function(filter_instance->subqueries_results);
                     condition->type = value
                         ? selection_condition::ALWAYS_TRUE
                         : selection_condition::ALWAYS_FALSE;
@@ -355,26 +628,20 @@
     }
 {%- endmacro -%}
 
-{%- macro generate_filter_procedures_declarations(filter_procedures, query_name) -%}
-    {%- for filter_procedure in filter_procedures -%}
-        bool {{query_name}}_{{filter_procedure.name|lower}}(array<subquery_result, MAX_SUBQUERIES_IN_FILTER> params);
-    {%- endfor -%}
-{%- endmacro -%}
-
 {%- macro generate_traverse_and_save_selected_nodes_procedure(are_any_filters, eager_filter_evaluation, query_name) -%}
     {%- if are_any_filters -%}
         {% if eager_filter_evaluation %}
             bool {{query_name}}_check_result_in_progress_conditions() {
                 bool condition_value;
-                for (auto condition : result_in_progress_conditions)
+                for (auto condition : context->result_in_progress_conditions)
                     if (!{{query_name}}_try_evaluate_selection_condition(condition, condition_value) || condition_value)
                         return true;
                 return false;
             }
         {% endif %}
         void {{query_name}}_traverse_and_save_selected_nodes(ondemand::value &node, string *result_buf,
-                                              unordered_set<int> &filter_instances_ids,
-                                              current_node_data &current_node)
+                                                             unordered_set<int> &filter_instances_ids,
+                                                             current_node_data &current_node)
         {
             bool is_member = current_node.is_member;
             bool is_element = current_node.is_element;
@@ -384,7 +651,7 @@
             bool is_result_saving_in_progress = result_buf != nullptr;
 
             if (_is_scalar) {
-                if (!reached_subqueries_results.empty()) {
+                if (!context->reached_subqueries_results.empty()) {
                     string_view str_value;
                     int64_t int_value;
                     double float_value;
@@ -402,10 +669,10 @@
                     else if (!node.get_bool().get(bool_value))
                         type = BOOL;
 
-                    while (!reached_subqueries_results.empty())
+                    while (!context->reached_subqueries_results.empty())
                     {
-                        auto subquery_result = reached_subqueries_results.back();
-                        reached_subqueries_results.pop_back();
+                        auto subquery_result = context->reached_subqueries_results.back();
+                        context->reached_subqueries_results.pop_back();
                         subquery_result->type = type;
                         switch (type)
                         {
@@ -430,12 +697,15 @@
                         }
 
                         auto filter_instance = subquery_result->filter;
-                        if (++filter_instance->reached_subquery_count == filter_instance->subquery_count)
+                        size_t subquery_index = subquery_result - filter_instance->subqueries_results.data();
+                        if (filter_instance->is_subquery_value_needed[subquery_index])
+                            continue;
+                        if (++filter_instance->reached_subquery_count == filter_instance->subquery_count
+                            && filter_instance->parent == nullptr)
                         {
                             {%- if eager_filter_evaluation -%}
-                                auto filter_function = {{query_name}}_get_filter_function(filter_instance->filter_segment_index, filter_instance->filter_selector_index);
-                                bool value = filter_function(filter_instance->subqueries_results);
-                                filters_results.try_emplace(filter_instance->id, value);
+                                bool value = filter_instance->function(filter_instance->subqueries_results);
+                                context->filters_results.try_emplace(filter_instance->id, value);
                             {%- endif -%}
                             filter_instances_ids.erase(filter_instance->id);
                         }
@@ -448,9 +718,10 @@
                 if (is_result_saving_in_progress)
                     *result_buf += node.raw_json().value();
 
+                end_nested_filters_execution(first_nested_filter_instance, filter_instances_ids);
                 if (is_member || is_element) {
                     for (int filter_instance_id : filter_instances_ids) {
-                        all_filter_instances[filter_instance_id]->restore_current_subqueries_segments();
+                        context->all_filter_instances[filter_instance_id]->restore_current_subqueries_states();
                     }
                 }
                 return;
@@ -500,7 +771,7 @@
                 size_t array_length = 0;
                 for (int filter_instance_id : filter_instances_ids)
                 {
-                    if (all_filter_instances[filter_instance_id]->is_any_current_subquery_negative_index())
+                    if (context->all_filter_instances[filter_instance_id]->is_array_length_needed())
                     {
                         array_length = array.count_elements();
                         break;
@@ -516,7 +787,7 @@
                     }
                     first = false;
                     current_node.is_member = false;
-                    current_node.is_element = false;
+                    current_node.is_element = true;
                     current_node.array_length = array_length;
                     current_node.index = index;
                     {{query_name}}_traverse_and_save_selected_nodes(element, result_buf, filter_instances_ids, current_node);
@@ -525,9 +796,10 @@
                     *result_buf += "]";
             }
 
+            end_nested_filters_execution(first_nested_filter_instance, filter_instances_ids);
             if (is_member || is_element) {
                 for (int filter_instance_id : filter_instances_ids) {
-                    all_filter_instances[filter_instance_id]->restore_current_subqueries_segments();
+                    context->all_filter_instances[filter_instance_id]->restore_current_subqueries_states();
                 }
             }
         }
@@ -542,85 +814,119 @@
 {%- endmacro -%}
 
 {%- macro compile_start_filter_execution(filter_id, query_name) -%}
-    auto* f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}} = new filter_instance(all_filter_instances.size(), {{filter_id.segment_index}}, {{filter_id.selector_index}}, {{filter_subqueries.unwrap().get(filter_id).unwrap().len()}});
-    {%- for (subquery_index, subquery) in filter_subqueries.unwrap().get(filter_id).unwrap().into_iter().enumerate() -%}
-            f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}}->subqueries_results[{{subquery_index}}] = {.filter = f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}}};
-            f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}}->current_subqueries_segments[{{subquery_index}}] =
-        {%- if subquery.segments.is_empty() -%}
-            nullptr;
-            {%- if subquery.is_existence_test -%}
-                f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}}->subqueries_results[{{subquery_index}}].exists = true;
-            {%- else -%}
-                reached_subqueries_results.push_back(
-                    &f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}}->subqueries_results[{{subquery_index}}]);
-            {%- endif -%}
-        {%- else -%}
-            &{{query_name}}_filter_{{filter_id.segment_index}}_{{filter_id.selector_index}}_subquery_{{subquery_index}}_segment_0;
-        {%- endif -%}
-        f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}}->is_subquery_existence_test[{{subquery_index}}] = {{subquery.is_existence_test}};
-    {%- endfor -%}
-    filter_instances_ids.insert(f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}}->id);
-    all_filter_instances.push_back(f_instance_{{filter_id.segment_index}}_{{filter_id.selector_index}});
+    {%- let f_instance = format!("f_instance_{}", filter_id) -%}
+    auto* {{f_instance}} = new_filter_instance({{query_name}}_filter_{{filter_id}}_definition, {{current_node}});
+    {%- if eager_filter_evaluation -%}
+        if ({{f_instance}}->reached_subquery_count == {{f_instance}}->subquery_count)
+            context->filters_results.try_emplace({{f_instance}}->id, {{f_instance}}->function({{f_instance}}->subqueries_results));
+    {%- endif -%}
+    filter_instances_ids.insert({{f_instance}}->id);
     added_filter_instances++;
 {%- endmacro -%}
 
+{%- macro generate_absolute_subqueries_evaluation(filter_subqueries, query_name) -%}
+    void {{query_name}}_evaluate_absolute_subqueries(ondemand::document &doc)
+    {
+        absolute_subquery_result *result;
+        {%- for (filter_id, subqueries) in filter_subqueries -%}
+            {%- for (subquery_index, subquery) in subqueries.iter().enumerate() -%}
+                {%- if subquery.is_absolute -%}
+                    {%- let subquery_name = format!("{}_filter_{}_subquery_{}", query_name, filter_id, subquery_index) -%}
+                    result = &context->absolute_subqueries_results[&{{subquery_name}}];
+                    absolute_subquery_evaluation {
+                        {{subquery_name}}, {{subquery.is_existence_test}}, {{subquery.is_count_needed}}, {{subquery.is_value_needed}},
+                        result->result, result->str_value, result->canonical_form,
+                        &{{query_name}}_traverse_and_save_selected_nodes
+                    }.evaluate(doc);
+                {%- endif -%}
+            {%- endfor -%}
+        {%- endfor -%}
+    }
+{%- endmacro -%}
+
+{%- macro generate_filters_definitions(filter_subqueries, query_name) -%}
+    {%- for (filter_id, subqueries) in filter_subqueries -%}
+        {%- let filter_name = format!("{}_filter_{}", query_name, filter_id) -%}
+        const filter {{filter_name}}_definition {
+            .function = &{{filter_name}},
+            .subquery_count = {{subqueries.len()}},
+            .subqueries = {
+            {%- for (subquery_index, subquery) in subqueries.iter().enumerate() -%}
+                &{{filter_name}}_subquery_{{subquery_index}},
+            {%- endfor -%}
+            },
+            .is_subquery_absolute = { {%- for subquery in subqueries -%} {{subquery.is_absolute}}, {%- endfor -%} },
+            .is_subquery_existence_test = { {%- for subquery in subqueries -%} {{subquery.is_existence_test}}, {%- endfor -%} },
+            .is_subquery_length_needed = { {%- for subquery in subqueries -%} {{subquery.is_length_needed}}, {%- endfor -%} },
+            .is_subquery_count_needed = { {%- for subquery in subqueries -%} {{subquery.is_count_needed}}, {%- endfor -%} },
+            .is_subquery_string_needed = { {%- for subquery in subqueries -%} {{subquery.is_string_needed}}, {%- endfor -%} },
+            .is_subquery_value_needed = { {%- for subquery in subqueries -%} {{subquery.is_value_needed}}, {%- endfor -%} },
+        };
+    {%- endfor -%}
+{%- endmacro -%}
+
 {%- macro compile_update_subqueries_state(query_name, eager_filter_evaluation) -%}
+    size_t first_nested_filter_instance = context->nested_filter_instances.size();
     if (current_node.is_member || current_node.is_element) {
         for (auto filter_instance_id : filter_instances_ids) {
-            filter_instance* f_instance = all_filter_instances[filter_instance_id];
+            filter_instance* f_instance = context->all_filter_instances[filter_instance_id];
             if (!f_instance->is_active) {
                 f_instance->is_active = true;
                 continue;
             }
-            f_instance->save_current_subqueries_segments();
-            for (size_t i = 0; i < f_instance->subquery_count; i++) {
-                auto subquery_segment = f_instance->current_subqueries_segments[i];
-                if (subquery_segment == nullptr)
+            f_instance->save_current_subqueries_states();
+            for (size_t i = 0; i < f_instance->tracked_subquery_count(); i++) {
+                const subquery_state state = f_instance->current_subqueries_states[i];
+                if (state.is_empty())
                     continue;
-                if (current_node.is_member && !subquery_segment->is_name) {
-                    f_instance->current_subqueries_segments[i] = nullptr;
+                if (f_instance->subqueries[i]->has_filters)
+                    start_nested_filters(f_instance, i, node);
+                int64_t matches_count;
+                f_instance->current_subqueries_states[i] = f_instance->subqueries[i]->next_state(state, current_node, matches_count);
+                if (matches_count == 0)
                     continue;
-                }
-                if (current_node.is_element && subquery_segment->is_name) {
-                    f_instance->current_subqueries_segments[i] = nullptr;
+                if (f_instance->is_subquery_count_needed[i]) {
+                    f_instance->subqueries_results[i].count += matches_count;
                     continue;
                 }
-                if (current_node.is_member && current_node.key.compare(subquery_segment->name) != 0) {
-                    f_instance->current_subqueries_segments[i] = nullptr;
-                    continue;
+                if (f_instance->is_subquery_value_needed[i]) {
+                    f_instance->subqueries_results[i].count += matches_count;
+                    if (f_instance->subqueries_results[i].count > 1) {
+                        f_instance->subqueries_results[i].type = NOTHING;
+                        f_instance->current_subqueries_states[i] = {};
+                        continue;
+                    }
                 }
-                if (current_node.is_element && current_node.index != subquery_segment->index
-                    && (subquery_segment->index >= 0 || subquery_segment->index + current_node.array_length != current_node.index)) {
-                    f_instance->current_subqueries_segments[i] = nullptr;
-                    continue;
+                else {
+                    if (f_instance->subqueries_results[i].exists)
+                        continue;
+                    f_instance->current_subqueries_states[i] = {};
                 }
-                if (subquery_segment->next == nullptr && !f_instance->subqueries_results[i].exists) {
-                    f_instance->subqueries_results[i].exists = true;
-                    f_instance->current_subqueries_segments[i] = nullptr;
-                    if (f_instance->is_subquery_existence_test[i]) {
-                        f_instance->reached_subquery_count++;
-                        {%- if eager_filter_evaluation -%}
-                        if (f_instance->reached_subquery_count == f_instance->subquery_count)
-                        {
-                            auto filter_function = {{query_name}}_get_filter_function(f_instance->filter_segment_index, f_instance->filter_selector_index);
-                            bool value = filter_function(f_instance->subqueries_results);
-                            filters_results.try_emplace(f_instance->id, value);
-                        }
-                        {%- endif -%}
+                f_instance->subqueries_results[i].exists = true;
+                if (f_instance->is_subquery_existence_test[i]) {
+                    if (i == f_instance->subquery_count)
                         continue;
+                    f_instance->reached_subquery_count++;
+                    {%- if eager_filter_evaluation -%}
+                    if (f_instance->reached_subquery_count == f_instance->subquery_count && f_instance->parent == nullptr)
+                    {
+                        bool value = f_instance->function(f_instance->subqueries_results);
+                        context->filters_results.try_emplace(f_instance->id, value);
                     }
-                    if (_is_scalar)
-                        reached_subqueries_results.push_back(&f_instance->subqueries_results[i]);
-                    else
-                        f_instance->subqueries_results[i].type = COMPLEX;
+                    {%- endif -%}
                     continue;
                 }
-                f_instance->current_subqueries_segments[i] = const_cast<subquery_path_segment *>(subquery_segment->next);
+                if (_is_scalar)
+                    context->reached_subqueries_results.push_back(&f_instance->subqueries_results[i]);
+                else
+                    f_instance->select_complex_node(i, node);
             }
         }
+        for (size_t i = first_nested_filter_instance; i < context->nested_filter_instances.size(); i++)
+            filter_instances_ids.insert(context->nested_filter_instances[i]->id);
         erase_if(filter_instances_ids, [](const int id) {
-            auto f_instance = all_filter_instances[id];
-            return f_instance->reached_subquery_count == f_instance->subquery_count;
+            auto f_instance = context->all_filter_instances[id];
+            return f_instance->reached_subquery_count == f_instance->subquery_count && f_instance->parent == nullptr;
         });
     }
 {%- endmacro -%}
@@ -629,8 +935,249 @@
     {%- for procedure in procedures -%}
         {%- if procedure.are_any_filters -%}
             void {{procedure.query_name}}_{{procedure.name|lower}}(ondemand::value &node, string *result_buf, vector<tuple<string *, size_t, size_t, selection_condition*>> &all_results, selection_condition *segment_conditions[], unordered_set<int> &filter_instances_ids, current_node_data &current_node);
+        {%- else if procedure.are_active_segments_passed -%}
+            void {{procedure.query_name}}_{{procedure.name|lower}}(ondemand::value &node, string *result_buf, vector<tuple<string *, size_t, size_t>> &all_results, bool active_segments[]);
+        {%- else if procedure.are_queries_merged -%}
+            void {{procedure.query_name}}_{{procedure.name|lower}}(ondemand::value &node, string *result_buf, vector<tuple<string *, size_t, size_t>> all_results[]);
         {%- else -%}
             void {{procedure.query_name}}_{{procedure.name|lower}}(ondemand::value &node, string *result_buf, vector<tuple<string *, size_t, size_t>> &all_results);
         {%- endif -%}
     {%- endfor -%}
+{%- endmacro -%}
+
+{%- macro generate_results_reversal_procedure() -%}
+    // Reverses the order of the results of consecutive array elements, given the index of the first result of each of
+    // them, keeping the order of the results of every element.
+    template <typename Result>
+    void reverse_results(vector<Result> &results, const vector<size_t> &first_element_results)
+    {
+        if (first_element_results.empty())
+            return;
+        vector<Result> reversed_results;
+        reversed_results.reserve(results.size() - first_element_results.front());
+        size_t end = results.size();
+        for (auto start = first_element_results.rbegin(); start != first_element_results.rend(); start++)
+        {
+            move(results.begin() + *start, results.begin() + end, back_inserter(reversed_results));
+            end = *start;
+        }
+        results.resize(first_element_results.front());
+        move(reversed_results.begin(), reversed_results.end(), back_inserter(results));
+    }
+{%- endmacro -%}
+
+{%- macro generate_node_paths_procedures(json_pointers) -%}
+    void reset_node_paths()
+    {
+        {%- if json_pointers -%}
+            context->current_node_path = "";
+        {%- else -%}
+            context->current_node_path = "$";
+        {%- endif -%}
+        context->result_node_paths.clear();
+    }
+
+    void append_hex_escape(string &str, char c)
+    {
+        str += "\\u00";
+        str += "0123456789abcdef"[static_cast<unsigned char>(c) >> 4];
+        str += "0123456789abcdef"[static_cast<unsigned char>(c) & 0xf];
+    }
+
+    void append_member_to_node_path(string_view name)
+    {
+        {%- if json_pointers -%}
+            context->current_node_path += '/';
+            for (char c : name)
+            {
+                if (c == '~')
+                    context->current_node_path += "~0";
+                else if (c == '/')
+                    context->current_node_path += "~1";
+                else
+                    context->current_node_path += c;
+            }
+        {%- else -%}
+            context->current_node_path += "['";
+            for (char c : name)
+            {
+                switch (c)
+                {
+                    case '\b': context->current_node_path += "\\b"; break;
+                    case '\f': context->current_node_path += "\\f"; break;
+                    case '\n': context->current_node_path += "\\n"; break;
+                    case '\r': context->current_node_path += "\\r"; break;
+                    case '\t': context->current_node_path += "\\t"; break;
+                    case '\'': context->current_node_path += "\\'"; break;
+                    case '\\': context->current_node_path += "\\\\"; break;
+                    default:
+                        if (static_cast<unsigned char>(c) < 0x20)
+                            append_hex_escape(context->current_node_path, c);
+                        else
+                            context->current_node_path += c;
+                }
+            }
+            context->current_node_path += "']";
+        {%- endif -%}
+    }
+
+    void append_index_to_node_path(size_t index)
+    {
+        {%- if json_pointers -%}
+            context->current_node_path += '/';
+            context->current_node_path += to_string(index);
+        {%- else -%}
+            context->current_node_path += '[';
+            context->current_node_path += to_string(index);
+            context->current_node_path += ']';
+        {%- endif -%}
+    }
+
+    void append_result_with_node_path(string &result, string_view value, string_view node_path)
+    {
+        result += "{\"path\": \"";
+        for (char c : node_path)
+        {
+            switch (c)
+            {
+                case '"': result += "\\\""; break;
+                case '\\': result += "\\\\"; break;
+                case '\b': result += "\\b"; break;
+                case '\f': result += "\\f"; break;
+                case '\n': result += "\\n"; break;
+                case '\r': result += "\\r"; break;
+                case '\t': result += "\\t"; break;
+                default:
+                    if (static_cast<unsigned char>(c) < 0x20)
+                        append_hex_escape(result, c);
+                    else
+                        result += c;
+            }
+        }
+        result += "\", \"value\": ";
+        result += value;
+        result += "}";
+    }
+{%- endmacro -%}
+
+{%- macro generate_ndjson_procedures(line_numbers) -%}
+    typedef void (*document_query)(ondemand::document &doc, string &results);
+
+    void append_document_results(string &output, size_t line_number, const string &results)
+    {
+        {%- if line_numbers -%}
+            output += "{\"line\": ";
+            output += to_string(line_number);
+            output += ", \"results\": ";
+            output += results;
+            output += "}\n";
+        {%- else -%}
+            output += results;
+            output += "\n";
+        {%- endif -%}
+    }
+
+    void append_document_error(string &output, size_t line_number, simdjson::error_code error)
+    {
+        {%- if line_numbers -%}
+            output += "{\"line\": ";
+            output += to_string(line_number);
+            output += ", \"error\": \"";
+        {%- else -%}
+            output += "{\"error\": \"";
+        {%- endif -%}
+        for (const char *c = error_message(error); *c != '\0'; c++)
+        {
+            if (*c == '"' || *c == '\\')
+                output += '\\';
+            output += *c;
+        }
+        output += "\"}\n";
+    }
+
+    bool is_whitespace(char c)
+    {
+        return c == ' ' || c == '\t' || c == '\r' || c == '\n';
+    }
+
+    simdjson::error_code try_execute_document_query(document_query query, ondemand::document &doc, string &results)
+    {
+        try
+        {
+            query(doc, results);
+            return SUCCESS;
+        }
+        catch (simdjson_error &error)
+        {
+            return error.error();
+        }
+    }
+
+    string execute_on_documents(ondemand::parser &parser, const char *padded_input, size_t length, document_query query,
+                                size_t first_line_number)
+    {
+        string output;
+        size_t input_length = length - SIMDJSON_PADDING;
+        size_t line_number = first_line_number;
+        size_t line_number_offset = 0;
+        auto line_number_at = [&](size_t offset) {
+            line_number += count(padded_input + line_number_offset, padded_input + offset, '\n');
+            line_number_offset = offset;
+            return line_number;
+        };
+        size_t offset = 0;
+        while (offset < input_length)
+        {
+            size_t error_offset = input_length;
+            {
+                ondemand::document_stream documents;
+                if (parser.iterate_many(padded_input + offset, input_length - offset, ondemand::DEFAULT_BATCH_SIZE).get(documents))
+                    error_offset = offset;
+                else
+                {
+                    for (auto it = documents.begin(); it != documents.end(); ++it)
+                    {
+                        // The index of the first document in the stream may point at the whitespace preceding it.
+                        size_t document_offset = find_if_not(padded_input + offset + it.current_index(),
+                                                             padded_input + input_length, is_whitespace) - padded_input;
+                        ondemand::document_reference doc;
+                        string results;
+                        if ((*it).get(doc) || try_execute_document_query(query, doc, results))
+                        {
+                            error_offset = document_offset;
+                            break;
+                        }
+                        append_document_results(output, line_number_at(document_offset), results);
+                    }
+                    if (error_offset == input_length && documents.truncated_bytes() > 0)
+                        error_offset = input_length - documents.truncated_bytes();
+                }
+            }
+            offset = error_offset;
+            size_t lines_end = min(input_length, error_offset + ondemand::DEFAULT_BATCH_SIZE);
+            while (offset < lines_end)
+            {
+                const char *line_start = padded_input + offset;
+                const char *line_end = static_cast<const char *>(memchr(line_start, '\n', input_length - offset));
+                if (line_end == nullptr)
+                    line_end = padded_input + input_length;
+                offset = line_end - padded_input + 1;
+                if (all_of(line_start, line_end, is_whitespace))
+                    continue;
+                padded_string line(line_start, line_end - line_start);
+                ondemand::document doc;
+                string results;
+                simdjson::error_code error = parser.iterate(line).get(doc);
+                if (!error)
+                    error = try_execute_document_query(query, doc, results);
+                if (error)
+                {
+                    append_document_error(output, line_number_at(line_start - padded_input), error);
+                    break;
+                }
+                append_document_results(output, line_number_at(line_start - padded_input), results);
+            }
+        }
+        return output;
+    }
 {%- endmacro -%}
\ No newline at end of file
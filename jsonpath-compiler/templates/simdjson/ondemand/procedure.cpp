@@ -1,5 +1,9 @@
 {%- if are_any_filters -%}
     void {{query_name}}_{{name|lower}}(ondemand::value &node, string *result_buf, vector<tuple<string *, size_t, size_t, selection_condition*>> &all_results, selection_condition *segment_conditions[], unordered_set<int> &filter_instances_ids, current_node_data &current_node)
+{%- else if are_active_segments_passed -%}
+    void {{query_name}}_{{name|lower}}(ondemand::value &node, string *result_buf, vector<tuple<string *, size_t, size_t>> &all_results, bool active_segments[])
+{%- else if are_queries_merged -%}
+    void {{query_name}}_{{name|lower}}(ondemand::value &node, string *result_buf, vector<tuple<string *, size_t, size_t>> all_results[])
 {%- else -%}
     void {{query_name}}_{{name|lower}}(ondemand::value &node, string *result_buf, vector<tuple<string *, size_t, size_t>> &all_results)
 {%- endif -%}
@@ -10,15 +14,18 @@
         int first_added_filter_id;
         bool is_member = current_node.is_member;
         bool is_element = current_node.is_element;
+        {%- if !Self::are_subqueries_states_updated(self) -%}
+            size_t first_nested_filter_instance = context->nested_filter_instances.size();
+        {%- endif -%}
     {%- endif -%}
     {%- for instruction in instructions -%}
-        {{ instruction.render().unwrap() }}
+        {{ instruction.render()? }}
     {%- endfor -%}
     {%- if !Self::are_object_members_iterated(self) -%}
-        {{ EMPTY_OBJECT_ITERATION.render().unwrap() }}
+        {{ EMPTY_OBJECT_ITERATION.render()? }}
     {%- endif -%}
     {%- if !Self::are_array_elements_iterated(self) -%}
-        {{ EMPTY_ARRAY_ITERATION.render().unwrap() }}
+        {{ EMPTY_ARRAY_ITERATION.render()? }}
     {%- endif -%}
     if (_is_scalar)
         {%- if are_any_filters -%}
@@ -27,8 +34,9 @@
             current_node.is_element = false;
             {{query_name}}_traverse_and_save_selected_nodes(node, result_buf, filter_instances_ids, current_node);
             }
+            end_nested_filters_execution(first_nested_filter_instance, filter_instances_ids);
             for (int filter_instance_id : filter_instances_ids) {
-                all_filter_instances[filter_instance_id]->restore_current_subqueries_segments();
+                context->all_filter_instances[filter_instance_id]->restore_current_subqueries_states();
             }
         {%- else -%}
             {{query_name}}_traverse_and_save_selected_nodes(node, result_buf);
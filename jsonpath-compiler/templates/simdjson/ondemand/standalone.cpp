@@ -1,4 +1,5 @@
 {%- import "macros.cpp" as common -%}
+{%- import "simdjson/filters.cpp" as filters -%}
 
 {%- if logging -%}
     #define SIMDJSON_VERBOSE_LOGGING 1
@@ -6,6 +7,7 @@
 
 #include <iostream>
 #include <fstream>
+#include <sstream>
 #include <vector>
 #include <queue>
 #include <set>
@@ -13,6 +15,10 @@
 #include <unordered_map>
 #include <string>
 #include <algorithm>
+#include <atomic>
+#include <thread>
+#include <charconv>
+#include <cmath>
 #include <fcntl.h>
 #include <sys/mman.h>
 #include <sys/stat.h>
@@ -24,13 +30,29 @@ using namespace simdjson;
 {%- if Self::are_any_filters(self) -%}
     constexpr uint8_t _SEGMENT_COUNT = {{segments_count}};
 
-    {%- call common::generate_filter_aux_structures(Self::max_subqueries_in_filter_count(self)) -%}
+    {%- call filters::generate_filter_value_structures(Self::max_subqueries_in_filter_count(self), Self::max_subquery_segments_count(self)) -%}
+{%- endif -%}
+
+{%- call common::generate_execution_context(Self::are_any_filters(self), node_paths.is_some(), false, false) -%}
+
+{%- if Self::are_any_filters(self) -%}
+    {%- call common::generate_filter_aux_structures() -%}
 
     {%- call common::generate_filter_aux_procedures_declarations("") -%}
 
-    {%- call common::generate_filter_procedures_declarations(filter_procedures, "") -%}
+    {%- call filters::generate_filter_procedures_declarations(filter_procedures, "") -%}
+
+    {%- call filters::generate_filter_subqueries_instances(filter_subqueries, "") -%}
+
+    {%- call common::generate_absolute_subqueries_evaluation(filter_subqueries, "") -%}
+
+    {%- call common::generate_filters_definitions(filter_subqueries, "") -%}
+{%- endif -%}
+
+{%- call common::generate_results_reversal_procedure() -%}
 
-    {%- call common::generate_filter_aux_structures_instances(filter_subqueries, "") -%}
+{%- if node_paths.is_some() -%}
+    {%- call common::generate_node_paths_procedures(Self::are_node_paths_json_pointers(self)) -%}
 {%- endif -%}
 
 {%- if mmap -%}
@@ -41,60 +63,122 @@ using namespace simdjson;
 
 {%- call common::generate_procedures_declarations(procedures) -%}
 
+{%- if Self::is_ndjson_input(self) -%}
+    {%- call common::generate_ndjson_procedures(Self::are_line_numbers_output(self)) -%}
+
+    void _document(ondemand::document &doc, string &result)
+    {
+        {%- if Self::are_any_filters(self) -%}
+            {%- call generate_filters_cleanup() -%}
+            context->nested_filter_instances.clear();
+            context->result_in_progress_conditions.clear();
+        {%- endif -%}
+        {%- call generate_query_execution() -%}
+        ostringstream output;
+        output << "[";
+        {%- call generate_results_output("output", "") -%}
+        output << "]";
+        result += output.str();
+    }
+
+    string execute_on_documents_in_parallel(const char *padded_input, size_t length, document_query query, size_t threads)
+    {
+        size_t input_length = length - SIMDJSON_PADDING;
+        vector<size_t> batches_offsets;
+        vector<size_t> batches_first_line_numbers;
+        size_t batch_size = input_length / (4 * threads) + 1;
+        size_t line_number = 1;
+        size_t offset = 0;
+        while (offset < input_length)
+        {
+            batches_offsets.push_back(offset);
+            batches_first_line_numbers.push_back(line_number);
+            size_t batch_end = min(input_length, offset + batch_size);
+            const char *line_end = static_cast<const char *>(
+                memchr(padded_input + batch_end - 1, '\n', input_length - batch_end + 1));
+            batch_end = line_end == nullptr ? input_length : line_end - padded_input + 1;
+            line_number += count(padded_input + offset, padded_input + batch_end, '\n');
+            offset = batch_end;
+        }
+        batches_offsets.push_back(input_length);
+        size_t batch_count = batches_first_line_numbers.size();
+        vector<string> batches_outputs(batch_count);
+        atomic<size_t> next_batch = 0;
+        auto execute_on_batches = [&]() {
+            {%- call common::enter_execution_context() -%}
+            ondemand::parser parser;
+            for (size_t batch = next_batch++; batch < batch_count; batch = next_batch++)
+            {
+                size_t batch_length = batches_offsets[batch + 1] - batches_offsets[batch];
+                batches_outputs[batch] = execute_on_documents(parser, padded_input + batches_offsets[batch],
+                                                              batch_length + SIMDJSON_PADDING, query,
+                                                              batches_first_line_numbers[batch]);
+            }
+        };
+        vector<thread> workers;
+        for (size_t i = 1; i < min(threads, batch_count); i++)
+            workers.emplace_back(execute_on_batches);
+        execute_on_batches();
+        for (auto &worker : workers)
+            worker.join();
+        string output;
+        for (const auto &batch_output : batches_outputs)
+            output += batch_output;
+        return output;
+    }
+{%- endif -%}
+
 int main(int argc, char **argv)
 {
-    ondemand::parser parser;
-    {%- if mmap -%}
-        size_t capacity;
-        const auto padded_input = map_and_pad_input(argv[1], capacity);
-        ondemand::document doc = parser.iterate(padded_input, capacity);
-    {%- else -%}
-        const auto padded_input = read_and_pad_input(argv[1]);
-        ondemand::document doc = parser.iterate(padded_input);
-    {%- endif -%}
-    ondemand::value root_node = doc.get_value().value();
-    {%- if Self::are_any_filters(self) -%}
-        vector<tuple<string *, size_t, size_t, selection_condition*>> all_results;
-        unordered_set<int> filter_instances_ids;
-        selection_condition *segment_conditions[_SEGMENT_COUNT] = {};
-        current_node_data current_node {false, false, 0, 0, {}};
-        _selectors_0(root_node, nullptr, all_results, segment_conditions, filter_instances_ids, current_node);
+    {%- if Self::is_ndjson_input(self) -%}
+        const auto usage_error = [argv]() {
+            cerr << "usage: " << argv[0] << " [--threads N] INPUT, where N is a positive number\n";
+            return 1;
+        };
+        const char *input_filename = nullptr;
+        size_t threads = 1;
+        for (int i = 1; i < argc; i++)
+        {
+            if (string_view(argv[i]) == "--threads")
+            {
+                const string_view value = i + 1 < argc ? argv[++i] : "";
+                const auto [end, error] = from_chars(value.data(), value.data() + value.size(), threads);
+                if (error != errc() || end != value.data() + value.size() || threads == 0)
+                    return usage_error();
+            }
+            else
+                input_filename = argv[i];
+        }
+        if (input_filename == nullptr)
+            return usage_error();
     {%- else -%}
-        vector<tuple<string *, size_t, size_t>> all_results;
-        _selectors_0(root_node, nullptr, all_results);
+        const char *input_filename = argv[1];
     {%- endif -%}
-    cout << "[\n";
-    bool first = true;
-    unordered_set<string*> bufs_to_free;
-    {%- if Self::are_any_filters(self) -%}
-        for (const auto &[buf_ptr, start, end, selection_condition] : all_results)
+    {%- if mmap -%}
+        size_t capacity;
+        const auto padded_input = map_and_pad_input(input_filename, capacity);
     {%- else -%}
-        for (const auto &[buf_ptr, start, end] : all_results)
+        const auto padded_input = read_and_pad_input(input_filename);
     {%- endif -%}
-    {
-        {%- if Self::are_any_filters(self) -%}
-            bool condition_value;
-            _try_evaluate_selection_condition(selection_condition, condition_value);
-            if (condition_value)
-            {
+    {%- if Self::is_ndjson_input(self) -%}
+        {%- if mmap -%}
+            cout << execute_on_documents_in_parallel(padded_input.data(), capacity, _document, threads);
+        {%- else -%}
+            cout << execute_on_documents_in_parallel(padded_input.data(), padded_input.size() + SIMDJSON_PADDING, _document,
+                                                     threads);
         {%- endif -%}
-        if (!first)
-            cout << ",";
-        cout << "  " << string_view(buf_ptr->data() + start, end - start);
-        first = false;
-        {%- if Self::are_any_filters(self) -%}
-            }
+    {%- else -%}
+        {%- call common::enter_execution_context() -%}
+        ondemand::parser parser;
+        {%- if mmap -%}
+            ondemand::document doc = parser.iterate(padded_input, capacity);
+        {%- else -%}
+            ondemand::document doc = parser.iterate(padded_input);
         {%- endif -%}
-        bufs_to_free.insert(buf_ptr);
-    }
-    cout << "]\n";
-    for (auto buf_ptr : bufs_to_free)
-        delete buf_ptr;
-    {%- if Self::are_any_filters(self) -%}
-        for (auto filter_instance : all_filter_instances)
-            delete filter_instance;
-        for (auto selection_condition : selection_conditions_to_delete)
-            delete selection_condition;
+        {%- call generate_query_execution() -%}
+        cout << "[\n";
+        {%- call generate_results_output("cout", "  ") -%}
+        cout << "]\n";
     {%- endif -%}
     return 0;
 }
@@ -130,9 +214,83 @@ int main(int argc, char **argv)
 {%- call common::generate_traverse_and_save_selected_nodes_procedure(Self::are_any_filters(self), eager_filter_evaluation, "") -%}
 
 {%- for procedure in procedures -%}
-    {{ procedure.render().unwrap() }}
+    {{ procedure.render()? }}
 {%- endfor -%}
 
 {%- for filter_procedure in filter_procedures -%}
-    {{ filter_procedure.render().unwrap() }}
-{%- endfor -%}
\ No newline at end of file
+    {{ filter_procedure.render()? }}
+{%- endfor -%}
+
+{%- macro generate_query_execution() -%}
+    {%- if Self::are_any_filters(self) -%}
+        _evaluate_absolute_subqueries(doc);
+    {%- endif -%}
+    ondemand::value root_node = doc.get_value().value();
+    {%- if node_paths.is_some() -%}
+        reset_node_paths();
+    {%- endif -%}
+    {%- if Self::are_any_filters(self) -%}
+        vector<tuple<string *, size_t, size_t, selection_condition*>> all_results;
+        unordered_set<int> filter_instances_ids;
+        selection_condition *segment_conditions[_SEGMENT_COUNT] = {};
+        current_node_data current_node {false, false, 0, 0, {}};
+        _selectors_0(root_node, nullptr, all_results, segment_conditions, filter_instances_ids, current_node);
+    {%- else -%}
+        vector<tuple<string *, size_t, size_t>> all_results;
+        _selectors_0(root_node, nullptr, all_results);
+    {%- endif -%}
+{%- endmacro -%}
+
+{%- macro generate_results_output(output, indentation) -%}
+    bool first = true;
+    unordered_set<string*> bufs_to_free;
+    {%- if node_paths.is_some() -%}
+        auto result_node_path = context->result_node_paths.begin();
+    {%- endif -%}
+    {%- if Self::are_any_filters(self) -%}
+        for (const auto &[buf_ptr, start, end, selection_condition] : all_results)
+    {%- else -%}
+        for (const auto &[buf_ptr, start, end] : all_results)
+    {%- endif -%}
+    {
+        {%- if Self::are_any_filters(self) -%}
+            bool condition_value;
+            _try_evaluate_selection_condition(selection_condition, condition_value);
+            if (condition_value)
+            {
+        {%- endif -%}
+        if (!first)
+            {{output}} << ",";
+        {%- if node_paths.is_some() -%}
+            string result_with_node_path;
+            append_result_with_node_path(result_with_node_path, string_view(buf_ptr->data() + start, end - start), *result_node_path);
+            {{output}} << "{{indentation}}" << result_with_node_path;
+        {%- else -%}
+            {{output}} << "{{indentation}}" << string_view(buf_ptr->data() + start, end - start);
+        {%- endif -%}
+        first = false;
+        {%- if Self::are_any_filters(self) -%}
+            }
+        {%- endif -%}
+        {%- if node_paths.is_some() -%}
+            result_node_path++;
+        {%- endif -%}
+        bufs_to_free.insert(buf_ptr);
+    }
+    for (auto buf_ptr : bufs_to_free)
+        delete buf_ptr;
+    {%- if Self::are_any_filters(self) -%}
+        {%- call generate_filters_cleanup() -%}
+    {%- endif -%}
+{%- endmacro -%}
+
+{%- macro generate_filters_cleanup() -%}
+    for (auto filter_instance : context->all_filter_instances)
+        delete filter_instance;
+    for (auto selection_condition : context->selection_conditions_to_delete)
+        delete selection_condition;
+    context->all_filter_instances.clear();
+    context->filters_results.clear();
+    context->reached_subqueries_results.clear();
+    context->selection_conditions_to_delete.clear();
+{%- endmacro -%}
\ No newline at end of file
@@ -34,6 +34,116 @@ macro_rules! additional {
                     TestTarget::$target,
                 ).run()
             }
+
+            #[test]
+            fn [<$target:snake _slice_with_negative_start>]() {
+                TestHelper::new(
+                    r#"$.events[-2:]"#,
+                    r#"{"events": [1, 2, 3, 4]}"#,
+                    r#"[3, 4]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _slice_with_negative_step>]() {
+                TestHelper::new(
+                    r#"$[::-2]"#,
+                    r#"[0, 1, 2, 3, 4]"#,
+                    r#"[4, 2, 0]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _slice_with_negative_step_followed_by_wildcard>]() {
+                TestHelper::new(
+                    r#"$.a[:0:-1].*"#,
+                    r#"{"a": [[1, 2], {"b": 3, "c": [4]}, [], [5, 6]]}"#,
+                    r#"[5, 6, 3, [4]]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _slice_with_negative_step_followed_by_filter>]() {
+                TestHelper::new(
+                    r#"$[::-1][?@ > 1]"#,
+                    r#"[[1, 2, 3], [0], [4, 1, 5]]"#,
+                    r#"[4, 5, 2, 3]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _slice_followed_by_index>]() {
+                TestHelper::new(
+                    r#"$[1:3][0]"#,
+                    r#"[[1], [2, 3], [4], [5]]"#,
+                    r#"[2, 4]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _slice_and_index_in_different_segments>]() {
+                TestHelper::new(
+                    r#"$..[0]..[1:]"#,
+                    r#"[[1, [2, 3]], 4]"#,
+                    r#"[[2, 3], 3]"#,
+                    TestTarget::$target,
+                )
+                    .ignore_order_and_duplicates()
+                    .run()
+            }
+
+            #[test]
+            fn [<$target:snake _overlapping_slices_followed_by_index>]() {
+                TestHelper::new(
+                    r#"$[0:2, 1:3, 2:4, 4:][0]"#,
+                    r#"[[0], [1], [2], [3], [4], [5]]"#,
+                    r#"[0, 1, 1, 2, 2, 3, 4, 5]"#,
+                    TestTarget::$target,
+                )
+                    .ignore_order_and_duplicates_without_random_access()
+                    .run()
+            }
+
+            #[test]
+            fn [<$target:snake _overlapping_slices_after_descendant_name>]() {
+                TestHelper::new(
+                    r#"$..a[:2, 1:3, ::2, -1:]"#,
+                    r#"{"a": [1, {"a": [2]}, 3, 4], "b": {"a": [5, 6]}}"#,
+                    r#"[1, {"a": [2]}, {"a": [2]}, 3, 1, 3, 4, 2, 2, 2, 5, 6, 6, 5, 6]"#,
+                    TestTarget::$target,
+                )
+                    .ignore_order_and_duplicates_without_random_access()
+                    .run()
+            }
+
+            #[test]
+            fn [<$target:snake _index_filter_and_index>]() {
+                TestHelper::new(
+                    r#"$[1, ?@>1, 0]"#,
+                    r#"[1, 2, 3]"#,
+                    r#"[2, 2, 3, 1]"#,
+                    TestTarget::$target,
+                )
+                    .ignore_order_and_duplicates_without_random_access()
+                    .run()
+            }
+
+            #[test]
+            fn [<$target:snake _descendant_indices_in_reverse_order>]() {
+                TestHelper::new(
+                    r#"$..[1, 0]"#,
+                    r#"[[5, 6], 7]"#,
+                    r#"[7, [5, 6], 6, 5]"#,
+                    TestTarget::$target,
+                )
+                    .ignore_order_and_duplicates_without_random_access()
+                    .run()
+            }
         }
     }
 }
@@ -41,3 +151,6 @@ macro_rules! additional {
 additional!(SimdjsonOndemand);
 additional!(SimdjsonOndemandEagerFilters);
 additional!(SimdjsonDom);
+additional!(Rust);
+additional!(RustSerdeJson);
+additional!(Interpreter);
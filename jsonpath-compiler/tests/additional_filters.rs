@@ -321,9 +321,151 @@ macro_rules! additional_filters {
                     TestTarget::$target,
                 ).run()
             }
+
+            #[test]
+            fn [<$target:snake _length_of_array_subquery>]() {
+                TestHelper::new(
+                    r#"$.orders[?length(@.items) > 2].id"#,
+                    r#"{"orders": [{"id": 1, "items": [1, 2, 3]}, {"id": 2, "items": [1]}, {"id": 3, "items": "abcd"}]}"#,
+                    r#"[1, 3]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _length_of_current_node>]() {
+                TestHelper::new(
+                    r#"$[?length(@) == 2]"#,
+                    r#"[{"a": 1, "b": 2}, [1], [[1, 2], 3], "ab", 2]"#,
+                    r#"[{"a": 1, "b": 2}, [[1, 2], 3], "ab"]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _count_of_descendants_and_slice>]() {
+                TestHelper::new(
+                    r#"$.orders[?count(@..price) >= 2 && count(@.items[-2:]) == 2].id"#,
+                    r#"{"orders": [{"id": 1, "items": [{"price": 1}, {"price": 2}]}, {"id": 2, "items": [{"price": 1}]}, {"id": 3, "items": [{"price": 3}, {"x": {"price": 4}}, 5]}]}"#,
+                    r#"[1, 3]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _count_of_nodes_selected_by_repeated_selectors>]() {
+                TestHelper::new(r#"$[?count(@[0,0].a)==2]"#, r#"[[{"a": 1}], [{"a": 1}, {"a": 2}]]"#, r#"[[{"a": 1}], [{"a": 1}, {"a": 2}]]"#, TestTarget::$target).run()
+            }
+
+            #[test]
+            fn [<$target:snake _count_of_nodes_reached_through_several_descendants>]() {
+                TestHelper::new(r#"$[?count(@..a..b)==2]"#, r#"[{"a": {"a": {"b": 1}}}, {"a": {"b": 1}}]"#, r#"[{"a": {"a": {"b": 1}}}]"#, TestTarget::$target).run()
+            }
+
+            #[test]
+            fn [<$target:snake _subquery_with_more_than_64_segments>]() {
+                let path = ".a".repeat(70);
+                let document = format!("[{}1{}, 2]", r#"{"a": "#.repeat(70), "}".repeat(70));
+                let expected = format!("[{}1{}]", r#"{"a": "#.repeat(70), "}".repeat(70));
+                TestHelper::new(&format!("$[?@{path} == 1]"), &document, &expected, TestTarget::$target).run()
+            }
+
+            #[test]
+            fn [<$target:snake _match_and_search_on_subqueries>]() {
+                TestHelper::new(
+                    r#"$.items[?match(@.sku, "AB-[0-9]+") && !search(@.msg, "time(out|d out)")].sku"#,
+                    r#"{"items": [{"sku": "AB-12", "msg": "ok"}, {"sku": "AB-3x", "msg": "ok"}, {"sku": "AB-7", "msg": "request timed out"}, {"sku": {"a": "AB-1"}, "msg": "ok"}, {"sku": "AB-99"}]}"#,
+                    r#"["AB-12", "AB-99"]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _match_with_caret_and_dollar_as_normal_characters>]() {
+                TestHelper::new(r#"$[?match(@, '^ab.*$')]"#, r#"["abc", "^abc$", "^ab$", "ab"]"#, r#"["^abc$", "^ab$"]"#, TestTarget::$target).run()
+            }
+
+            #[test]
+            fn [<$target:snake _search_with_caret_and_dollar_as_normal_characters>]() {
+                TestHelper::new(r#"$[?search(@, 'b^|c$')]"#, r#"["ab", "ab^c", "abc", "c$d"]"#, r#"["ab^c", "c$d"]"#, TestTarget::$target).run()
+            }
+
+            #[test]
+            fn [<$target:snake _value_of_non_singular_subqueries>]() {
+                TestHelper::new(
+                    r#"$.orders[?length(value(@..sku)) == 4 && value(@.items[*].qty) >= 2].id"#,
+                    r#"{"orders": [{"id": 1, "items": [{"sku": "AB-1", "qty": 2}]}, {"id": 2, "items": [{"sku": "AB-1", "qty": 2}, {"sku": "AB-2", "qty": 3}]}, {"id": 3, "items": [{"sku": "X-1", "qty": 5}]}, {"id": 4, "items": [{"sku": "AB-9", "qty": 1}]}]}"#,
+                    r#"[1]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _non_singular_existence_tests>]() {
+                TestHelper::new(
+                    r#"$.logs[?@..error && !@.tags[*]].id"#,
+                    r#"{"logs": [{"id": 1, "data": {"x": [{"error": null}]}, "tags": []}, {"id": 2, "data": {"x": []}, "tags": []}, {"id": 3, "error": "e", "tags": ["a"]}, {"id": 4, "error": "e"}]}"#,
+                    r#"[1, 4]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _absolute_subqueries>]() {
+                TestHelper::new(
+                    r#"$.items[?$.config..enabled && @.price <= $.config.limit && count($..discount) == 2].id"#,
+                    r#"{"items": [{"id": 1, "price": 5, "discount": 1}, {"id": 2, "price": 20}, {"id": 3, "price": 10, "discount": 2}], "config": {"flags": {"enabled": false}, "limit": 10}}"#,
+                    r#"[1, 3]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _nested_filters>]() {
+                TestHelper::new(
+                    r#"$.users[?@.roles[?@.name == 'admin' && @.scopes[?@ == 'write']] || count(@..roles[?@.name]) > 2].id"#,
+                    r#"{"users": [{"id": 1, "roles": [{"name": "admin", "scopes": ["read", "write"]}]}, {"id": 2, "roles": [{"name": "admin", "scopes": ["read"]}, {"name": "dev", "scopes": ["write"]}]}, {"id": 3, "roles": {"r": {"name": "admin", "scopes": {"s": "write"}}}}, {"id": 4, "roles": [{"name": "a"}, {"name": "b"}, {"name": "c"}]}, {"id": 5}]}"#,
+                    r#"[1, 3, 4]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _nested_filters_in_absolute_subqueries>]() {
+                TestHelper::new(
+                    r#"$.items[?$.groups[?@.members[?@ == 'x']] && @.price < $.limit].id"#,
+                    r#"{"items": [{"id": 1, "price": 5}, {"id": 2, "price": 20}], "groups": [{"members": ["y"]}, {"members": ["z", "x"]}], "limit": 10}"#,
+                    r#"[1]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _member_names_in_queries_with_function_expressions>]() {
+                TestHelper::new(
+                    r#"$.items[?length( @.a )==2 && @['\uF8FF0'] == 1 && @['length(@)'] == 'x'].id"#,
+                    r#"{"items": [{"id": 1, "a": "xy", "\uF8FF0": 1, "length(@)": "x"}, {"id": 2, "a": "xy", "\uF8FF0": 2, "length(@)": "x"}, {"id": 3, "a": "xyz", "\uF8FF0": 1, "length(@)": "x"}]}"#,
+                    r#"[1]"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _structural_equality_of_complex_values>]() {
+                TestHelper::new(
+                    r#"$.pairs[?@.a == @.b || @.a == $.ref].id"#,
+                    r#"{"pairs": [{"id": 1, "a": {"x": [1, "\u0041"], "y": {}}, "b": {"y": {}, "x": [1.0, "A"]}}, {"id": 2, "a": [1, 2], "b": [2, 1]}, {"id": 3, "a": {"x": 1}, "b": {"x": 1, "y": 2}}, {"id": 4, "a": {"k": [true, null]}, "b": 5}, {"id": 5, "a": [], "b": {}}], "ref": {"k": [true, null]}}"#,
+                    r#"[1, 4]"#,
+                    TestTarget::$target,
+                ).run()
+            }
         }
     }
 }
 
 additional_filters!(SimdjsonOndemand);
-additional_filters!(SimdjsonOndemandEagerFilters);
\ No newline at end of file
+additional_filters!(SimdjsonOndemandEagerFilters);
+additional_filters!(SimdjsonDom);
+additional_filters!(Rust);
+additional_filters!(RustSerdeJson);
+additional_filters!(Interpreter);
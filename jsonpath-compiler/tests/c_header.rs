@@ -0,0 +1,235 @@
+use std::fs;
+use std::process::Command;
+
+use serde_json::{Deserializer, Value};
+
+use jsonpath_compiler::compiler::{CompilationError, LibGeneratingCompiler, QueriesSource};
+use jsonpath_compiler::interpreter::Interpreter;
+use jsonpath_compiler::targets::TargetCodeLibGenerator;
+use jsonpath_compiler::targets::rust::RustCodeLibGenerator;
+use jsonpath_compiler::targets::rust::value::ValueCodeLibGenerator;
+use jsonpath_compiler::targets::simdjson::{CHeaderGenerator, RustBindingsGenerator};
+use jsonpath_compiler::targets::simdjson::dom::DomCodeLibGenerator;
+use jsonpath_compiler::targets::simdjson::ondemand::OnDemandCodeLibGenerator;
+use jsonpath_compiler::test_helper::{compile_and_run_cpp, create_execution_dir};
+
+const MAIN_CODE: &str = r#"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include "queries.h"
+
+int main(int argc, char **argv)
+{
+    FILE *file = fopen(argv[1], "rb");
+    fseek(file, 0, SEEK_END);
+    size_t document_length = ftell(file);
+    fseek(file, 0, SEEK_SET);
+    size_t input_length = document_length + 64;
+    char *input = calloc(input_length, 1);
+    fread(input, 1, document_length, file);
+    fclose(file);
+    size_t result_length;
+    const char *result = jp_first_binding(input, input_length, &result_length);
+    printf("%.*s", (int)result_length, result);
+    jp_free_result_buffer(result);
+    result = jp_second_binding(input, input_length, &result_length);
+    printf("%.*s", (int)result_length, result);
+    jp_free_result_buffer(result);
+    free(input);
+}
+"#;
+
+const RESULTS_DELIVERY_MAIN_CODE: &str = r#"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include "queries.h"
+
+static void print_result(const char *result, size_t result_length, void *user_data)
+{
+    int *results_count = user_data;
+    printf("%s%.*s", (*results_count)++ > 0 ? ", " : "", (int)result_length, result);
+}
+
+static void print_spans(const char *input, const size_t *spans, size_t spans_count)
+{
+    printf("[");
+    for (size_t i = 0; i < spans_count; i++)
+        printf("%s%.*s", i > 0 ? ", " : "", (int)spans[2 * i + 1], input + spans[2 * i]);
+    printf("]\n");
+}
+
+int main(int argc, char **argv)
+{
+    FILE *file = fopen(argv[1], "rb");
+    fseek(file, 0, SEEK_END);
+    size_t document_length = ftell(file);
+    fseek(file, 0, SEEK_SET);
+    size_t input_length = document_length + 64;
+    char *input = calloc(input_length, 1);
+    fread(input, 1, document_length, file);
+    fclose(file);
+    size_t result_length;
+    const char *result = jp_first_binding(input, input_length, &result_length);
+    printf("%.*s", (int)result_length, result);
+    jp_free_result_buffer(result);
+    size_t spans_count;
+    size_t *spans = jp_first_spans_binding(input, input_length, &spans_count);
+    print_spans(input, spans, spans_count);
+    jp_free_spans_buffer(spans);
+    int results_count = 0;
+    printf("[");
+    jp_first_callback_binding(input, input_length, print_result, &results_count);
+    printf("]\n");
+    jp_jsonpath_context *context = jp_jsonpath_context_new();
+    for (int i = 0; i < 2; i++)
+    {
+        result = jp_second_context_binding(context, input, input_length, &result_length);
+        printf("%.*s", (int)result_length, result);
+        const size_t *context_spans = jp_second_spans_context_binding(context, input, input_length, &spans_count);
+        print_spans(input, context_spans, spans_count);
+        results_count = 0;
+        printf("[");
+        jp_second_callback_context_binding(context, input, input_length, print_result, &results_count);
+        printf("]\n");
+    }
+    jp_jsonpath_context_free(context);
+    free(input);
+}
+"#;
+
+const FIRST_QUERY: &str = "$..a[?@.b > $.min]";
+
+const SECOND_QUERY: &str = "$..b";
+
+const DOCUMENT: &str = r#"{"min": 1, "a": [{"b": 2}, {"b": 1}, {"b": 3, "a": [{"b": [4, 5]}]}]}"#;
+
+/// Compiles the queries to a library with a C header, and executes the given C code using it
+/// on the document.
+fn execute<T: TargetCodeLibGenerator>(compiler: LibGeneratingCompiler, main_code: &str) -> Vec<Value> {
+    let execution_dir = create_execution_dir();
+    compiler
+        .add_bindings_generator(CHeaderGenerator::new(&format!("{execution_dir}/queries.h")))
+        .compile::<T>(
+            QueriesSource::Immediate {
+                queries: vec![
+                    ("first".to_string(), FIRST_QUERY.to_string()),
+                    ("second".to_string(), SECOND_QUERY.to_string()),
+                ],
+            },
+            &format!("{execution_dir}/queries.cpp"),
+        )
+        .unwrap();
+    fs::write(format!("{execution_dir}/main.c"), main_code).unwrap();
+    fs::write(format!("{execution_dir}/document.json"), DOCUMENT).unwrap();
+    let status = Command::new("cc")
+        .arg("-c")
+        .arg(format!("{execution_dir}/main.c"))
+        .arg("-std=c99")
+        .arg("-o").arg(format!("{execution_dir}/main.o"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "C code compilation failed");
+    let output = compile_and_run_cpp(
+        &format!("{execution_dir}/main.o"),
+        Some(&format!("{execution_dir}/queries.cpp")),
+        [format!("{execution_dir}/document.json")],
+    );
+    Deserializer::from_str(&output).into_iter().map(|value| value.unwrap()).collect()
+}
+
+fn expected_results(query: &str) -> Value {
+    Value::Array(
+        Interpreter::new(query)
+            .unwrap()
+            .run(&serde_json::from_str(DOCUMENT).unwrap())
+            .into_iter()
+            .cloned()
+            .collect(),
+    )
+}
+
+#[test]
+fn queries_are_called_through_header() {
+    let compiler = || LibGeneratingCompiler::new().with_symbol_prefix("jp_");
+    let expected_output = vec![expected_results(FIRST_QUERY), expected_results(SECOND_QUERY)];
+    assert_eq!(execute::<DomCodeLibGenerator>(compiler(), MAIN_CODE), expected_output);
+    assert_eq!(execute::<OnDemandCodeLibGenerator>(compiler(), MAIN_CODE), expected_output);
+}
+
+#[test]
+fn queries_are_called_through_header_without_symbol_prefix() {
+    let main_code = MAIN_CODE.replace("jp_", "");
+    let expected_output = vec![expected_results(FIRST_QUERY), expected_results(SECOND_QUERY)];
+    assert_eq!(execute::<DomCodeLibGenerator>(LibGeneratingCompiler::new(), &main_code), expected_output);
+    assert_eq!(execute::<OnDemandCodeLibGenerator>(LibGeneratingCompiler::new(), &main_code), expected_output);
+}
+
+#[test]
+fn results_are_delivered_through_header() {
+    let compiler = LibGeneratingCompiler::new()
+        .with_symbol_prefix("jp_")
+        .with_result_spans()
+        .with_result_callbacks();
+    // The results of the first query are returned without a context, and the results of the
+    // second one twice with the same context, each time as a string, as spans and to a callback.
+    let mut expected_output = vec![expected_results(FIRST_QUERY); 3];
+    expected_output.extend(vec![expected_results(SECOND_QUERY); 6]);
+    assert_eq!(
+        execute::<OnDemandCodeLibGenerator>(compiler, RESULTS_DELIVERY_MAIN_CODE),
+        expected_output
+    );
+}
+
+fn compile<T: TargetCodeLibGenerator>(compiler: LibGeneratingCompiler) -> Result<(), CompilationError> {
+    compiler.compile::<T>(
+        QueriesSource::Immediate { queries: vec![("first".to_string(), FIRST_QUERY.to_string())] },
+        &format!("{}/rejected.cpp", create_execution_dir()),
+    )
+}
+
+#[test]
+fn header_path_without_file_name_is_rejected() {
+    for header_file_path in ["/", ".."] {
+        let compiler = LibGeneratingCompiler::new()
+            .add_bindings_generator(CHeaderGenerator::new(header_file_path))
+            .with_symbol_prefix("jp_");
+        assert!(matches!(
+            compile::<OnDemandCodeLibGenerator>(compiler),
+            Err(CompilationError::InvalidCHeaderPath(path)) if path == header_file_path
+        ));
+    }
+}
+
+#[test]
+fn bindings_are_not_supported_by_rust_targets() {
+    let execution_dir = create_execution_dir();
+    let header_compiler = || {
+        LibGeneratingCompiler::new()
+            .add_bindings_generator(CHeaderGenerator::new(&format!("{execution_dir}/rejected.h")))
+            .with_symbol_prefix("jp_")
+    };
+    let bindings_compiler = || {
+        let bindings_file_path = format!("{execution_dir}/rejected.rs");
+        LibGeneratingCompiler::new()
+            .add_bindings_generator(RustBindingsGenerator::new(&bindings_file_path))
+    };
+    for result in [
+        compile::<RustCodeLibGenerator>(header_compiler()),
+        compile::<ValueCodeLibGenerator>(header_compiler()),
+        compile::<RustCodeLibGenerator>(bindings_compiler()),
+        compile::<ValueCodeLibGenerator>(bindings_compiler()),
+    ] {
+        assert!(matches!(result, Err(CompilationError::BindingsNotSupported)));
+    }
+}
+
+#[test]
+fn symbol_prefix_is_not_given_without_bindings() {
+    let compiler = LibGeneratingCompiler::new().with_symbol_prefix("jp_");
+    assert!(matches!(
+        compile::<OnDemandCodeLibGenerator>(compiler),
+        Err(CompilationError::SymbolPrefixWithoutBindings)
+    ));
+}
@@ -0,0 +1,75 @@
+use jsonpath_compiler::compiler::{CompilationError, LibGeneratingCompiler, QueriesSource, StandaloneProgGeneratingCompiler};
+use jsonpath_compiler::targets::simdjson::ondemand::{OnDemandCodeLibGenerator, OnDemandCodeStandaloneProgGenerator};
+
+#[test]
+fn unsupported_feature_is_reported_with_its_position() {
+    let query = "$.values[?@.a && match(@.b, $.pattern)]";
+    let result = StandaloneProgGeneratingCompiler::new()
+        .compile::<OnDemandCodeStandaloneProgGenerator>(query, "/tmp/jsonpath-compiler-tests/unsupported.cpp");
+    match result {
+        Err(CompilationError::UnsupportedFeature { query_name, fragment, span, .. }) => {
+            assert_eq!(query_name, query);
+            assert_eq!(fragment, "match(@.b, $.pattern)");
+            assert_eq!(&query[span], fragment);
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[test]
+fn all_errors_in_queries_are_reported_together() {
+    let queries = vec![
+        ("valid".to_string(), "$.a[?@.b]".to_string()),
+        ("unsupported".to_string(), "$[?search(@, value(@..p))]".to_string()),
+        ("invalid".to_string(), "$[?".to_string()),
+    ];
+    let result = LibGeneratingCompiler::new()
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate { queries },
+            "/tmp/jsonpath-compiler-tests/errors.hpp",
+        );
+    let Err(CompilationError::MultipleErrors(errors)) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+    let names: Vec<&str> = errors.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["unsupported", "invalid"]);
+    assert!(matches!(
+        &errors[0].1,
+        CompilationError::UnsupportedFeature { query_name, span, .. }
+            if query_name == "unsupported" && *span == (3..25)
+    ));
+    assert!(matches!(errors[1].1, CompilationError::ParseError { position: 3, .. }));
+}
+
+#[test]
+fn parse_errors_are_reported_together_with_ir_generation_errors() {
+    let queries = vec![
+        ("invalid".to_string(), "$[?@.a".to_string()),
+        ("valid".to_string(), "$.a".to_string()),
+        ("too_many_procedures".to_string(), "$..a[?@.b].*".to_string()),
+    ];
+    let result = LibGeneratingCompiler::new()
+        .with_max_procedures(1)
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate { queries },
+            "/tmp/jsonpath-compiler-tests/generation-errors.hpp",
+        );
+    let Err(CompilationError::MultipleErrors(errors)) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+    let names: Vec<&str> = errors.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["invalid", "too_many_procedures"]);
+    assert!(matches!(errors[0].1, CompilationError::ParseError { .. }));
+    assert!(matches!(errors[1].1, CompilationError::ProceduresLimitExceeded { max_procedures: 1, .. }));
+}
+
+#[test]
+fn parse_error_is_reported_at_its_position_in_query_with_function_expressions() {
+    let query = "$.a[?length(@.b) == count(@.c[*]) && @.d == ]";
+    let result = StandaloneProgGeneratingCompiler::new()
+        .compile::<OnDemandCodeStandaloneProgGenerator>(query, "/tmp/jsonpath-compiler-tests/invalid.cpp");
+    match result {
+        Err(CompilationError::ParseError { position, .. }) => assert_eq!(position, query.len() - 1),
+        result => panic!("unexpected result: {result:?}"),
+    }
+}
@@ -0,0 +1,117 @@
+use serde_json::{Deserializer, Value};
+
+use jsonpath_compiler::compiler::LibGeneratingCompiler;
+use jsonpath_compiler::test_helper::{execute_lib, interpreter_results, lib_compilers};
+
+const THREADS_MAIN_CODE: &str = r#"
+#include <iostream>
+#include <thread>
+#include "queries.hpp"
+
+int main(int argc, char **argv)
+{
+    vector<padded_string> inputs;
+    for (int i = 1; i < argc; i++)
+        inputs.push_back(padded_string::load(argv[i]).value());
+    vector<string> results(inputs.size());
+    vector<thread> threads;
+    for (size_t i = 0; i < inputs.size(); i++)
+    {
+        threads.emplace_back([&inputs, &results, i]() {
+            for (int j = 0; j < 100; j++)
+            {
+                string result = query(inputs[i].data(), inputs[i].size() + SIMDJSON_PADDING);
+                if (j > 0 && result != results[i])
+                {
+                    results[i] = "null";
+                    return;
+                }
+                results[i] = result;
+            }
+        });
+    }
+    for (auto &thread : threads)
+        thread.join();
+    for (const auto &result : results)
+        cout << result << "\n";
+}
+"#;
+
+const CALLBACK_MAIN_CODE: &str = r#"
+#include <iostream>
+#include "queries.hpp"
+
+void print_result(const char *result, size_t result_length, void *user_data)
+{
+    auto inner_input = static_cast<padded_string *>(user_data);
+    cout << string_view(result, result_length) << "\n";
+    cout << query(inner_input->data(), inner_input->size() + SIMDJSON_PADDING);
+}
+
+int main(int argc, char **argv)
+{
+    padded_string outer_input = padded_string::load(argv[1]);
+    padded_string inner_input = padded_string::load(argv[2]);
+    query_with_callback(outer_input.data(), outer_input.size() + SIMDJSON_PADDING, print_result, &inner_input);
+}
+"#;
+
+/// Compiles the query to a library with result callbacks, and executes the given code using it
+/// on the documents.
+fn execute(compiler: LibGeneratingCompiler, query: &str, main_code: &str, documents: &[&str]) -> Vec<Value> {
+    let compiler = compiler.with_result_callbacks();
+    let output = execute_lib(compiler, &[("query", query)], main_code, documents);
+    Deserializer::from_str(&output).into_iter().map(|value| value.unwrap()).collect()
+}
+
+fn expected_results(query: &str, document: &str) -> Vec<Value> {
+    interpreter_results(query, &serde_json::from_str(document).unwrap())
+}
+
+const QUERY: &str = "$..a[?@.b > $.min && count(@..c) < 3]";
+
+/// Query without filters, for which more than one procedure is needed.
+const DESCENDANTS_QUERY: &str = "$..a..b";
+
+/// Compilers with the queries compiled by them.
+fn compilers() -> Vec<(LibGeneratingCompiler, &'static str)> {
+    [QUERY, DESCENDANTS_QUERY]
+        .into_iter()
+        .flat_map(|query| lib_compilers(query).into_iter().map(move |compiler| (compiler, query)))
+        .collect()
+}
+
+#[test]
+fn queries_are_executed_in_multiple_threads_at_once() {
+    let documents: Vec<String> = (0..8)
+        .map(|i| {
+            format!(
+                r#"{{"min": {i}, "a": [{{"b": {}}}, {{"b": 5, "c": [{{"c": 1}}, {{"c": 2}}]}}, {{"a": [{{"b": 7}}]}}]}}"#,
+                i + 1
+            )
+        })
+        .collect();
+    let documents: Vec<&str> = documents.iter().map(|document| document.as_str()).collect();
+    for (compiler, query) in compilers() {
+        let expected_output: Vec<Value> = documents
+            .iter()
+            .map(|document| Value::Array(expected_results(query, document)))
+            .collect();
+        assert_eq!(execute(compiler, query, THREADS_MAIN_CODE, &documents), expected_output);
+    }
+}
+
+#[test]
+fn queries_are_executed_from_callback() {
+    let outer_document = r#"{"min": 1, "a": [{"b": 2}, {"b": 1}, {"b": 3, "a": [{"b": 4}]}]}"#;
+    let inner_document = r#"{"min": 4, "a": [{"b": 5, "c": 1}, {"b": 6, "c": [{"c": 2}, 3]}]}"#;
+    for (compiler, query) in compilers() {
+        let inner_results = Value::Array(expected_results(query, inner_document));
+        let expected_output: Vec<Value> = expected_results(query, outer_document)
+            .into_iter()
+            .flat_map(|result| [result, inner_results.clone()])
+            .collect();
+        let output = execute(compiler, query, CALLBACK_MAIN_CODE, &[outer_document, inner_document]);
+        assert_eq!(output, expected_output);
+    }
+}
@@ -71,28 +71,28 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _basic_multiple_selectors_index_and_slice>]() {
                 TestHelper::new(r#"$[1,5:7]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[1, 5, 6]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _basic_multiple_selectors_index_and_slice_overlapping>]() {
-                TestHelper::new(r#"$[1,0:3]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[1, 0, 1, 2]"#, TestTarget::$target).run()
+                TestHelper::new(r#"$[1,0:3]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[1, 0, 1, 2]"#, TestTarget::$target)
+                    .ignore_order_and_duplicates_without_random_access()
+                    .run()
             }
 
             #[test]
             fn [<$target:snake _basic_multiple_selectors_duplicate_index>]() {
                 TestHelper::new(r#"$[1,1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[1, 1]"#, TestTarget::$target)
-                    .ignore_order_and_duplicates()
+                    .ignore_order_and_duplicates_without_random_access()
                     .run()
             }
 
             #[test]
             fn [<$target:snake _basic_multiple_selectors_wildcard_and_index>]() {
                 TestHelper::new(r#"$[*,1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 1]"#, TestTarget::$target)
-                    .ignore_order_and_duplicates()
+                    .ignore_order_and_duplicates_without_random_access()
                     .run()
             }
 
@@ -104,15 +104,16 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _basic_multiple_selectors_wildcard_and_slice>]() {
-                TestHelper::new(r#"$[*,0:2]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1]"#, TestTarget::$target).run()
+                TestHelper::new(r#"$[*,0:2]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1]"#, TestTarget::$target)
+                    .ignore_order_and_duplicates_without_random_access()
+                    .run()
             }
 
             #[test]
             fn [<$target:snake _basic_multiple_selectors_multiple_wildcards>]() {
                 TestHelper::new(r#"$[*,*]"#, r#"[0, 1, 2]"#, r#"[0, 1, 2, 0, 1, 2]"#, TestTarget::$target)
-                    .ignore_order_and_duplicates()
+                    .ignore_order_and_duplicates_without_random_access()
                     .run()
             }
 
@@ -236,13 +237,11 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_deep_equality_arrays>]() {
                 TestHelper::new(r#"$[?@.a==@.b]"#, r#"[{"a": false, "b": [1, 2]}, {"a": [[1, [2]]], "b": [[1, [2]]]}, {"a": [[1, [2]]], "b": [[[2], 1]]}, {"a": [[1, [2]]], "b": [[1, 2]]}]"#, r#"[{"a": [[1, [2]]], "b": [[1, [2]]]}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_deep_equality_objects>]() {
                 TestHelper::new(r#"$[?@.a==@.b]"#, r#"[{"a": false, "b": {"x": 1, "y": {"z": 1}}}, {"a": {"x": 1, "y": {"z": 1}}, "b": {"x": 1, "y": {"z": 1}}}, {"a": {"x": 1, "y": {"z": 1}}, "b": {"y": {"z": 1}, "x": 1}}, {"a": {"x": 1, "y": {"z": 1}}, "b": {"x": 1}}, {"a": {"x": 1, "y": {"z": 1}}, "b": {"x": 1, "y": {"z": 2}}}]"#, r#"[{"a": {"x": 1, "y": {"z": 1}}, "b": {"x": 1, "y": {"z": 1}}}, {"a": {"x": 1, "y": {"z": 1}}, "b": {"y": {"z": 1}, "x": 1}}]"#, TestTarget::$target).run()
             }
@@ -468,31 +467,26 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_non_singular_existence_wildcard>]() {
                 TestHelper::new(r#"$[?@.*]"#, r#"[1, [], [2], {}, {"a": 3}]"#, r#"[[2], {"a": 3}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_non_singular_existence_multiple>]() {
                 TestHelper::new(r#"$[?@[0, 0, 'a']]"#, r#"[1, [], [2], [2, 3], {"a": 3}, {"b": 4}, {"a": 3, "b": 4}]"#, r#"[[2], [2, 3], {"a": 3}, {"a": 3, "b": 4}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_non_singular_existence_slice>]() {
                 TestHelper::new(r#"$[?@[0:2]]"#, r#"[1, [], [2], [2, 3, 4], {}, {"a": 3}]"#, r#"[[2], [2, 3, 4]]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_non_singular_existence_negated>]() {
                 TestHelper::new(r#"$[?!@.*]"#, r#"[1, [], [2], {}, {"a": 3}]"#, r#"[1, [], {}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_nested>]() {
                 TestHelper::new(r#"$[?@[?@>1]]"#, r#"[[0], [0, 1], [0, 1, 2], [42]]"#, r#"[[0, 1, 2], [42]]"#, TestTarget::$target).run()
             }
@@ -542,15 +536,15 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_multiple_selectors_filter_and_slice>]() {
                 TestHelper::new(r#"$[?@.a,1:]"#, r#"[{"a": "b", "d": "e"}, {"b": "c", "d": "f"}, {"g": "h"}]"#, r#"[{"a": "b", "d": "e"}, {"b": "c", "d": "f"}, {"g": "h"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_multiple_selectors_comparison_filter_index_and_slice>]() {
-                TestHelper::new(r#"$[1, ?@.a=='b', 1:]"#, r#"[{"a": "b", "d": "e"}, {"b": "c", "d": "f"}]"#, r#"[{"b": "c", "d": "f"}, {"a": "b", "d": "e"}, {"b": "c", "d": "f"}]"#, TestTarget::$target).run()
+                TestHelper::new(r#"$[1, ?@.a=='b', 1:]"#, r#"[{"a": "b", "d": "e"}, {"b": "c", "d": "f"}]"#, r#"[{"b": "c", "d": "f"}, {"a": "b", "d": "e"}, {"b": "c", "d": "f"}]"#, TestTarget::$target)
+                    .ignore_order_and_duplicates_without_random_access()
+                    .run()
             }
 
             #[test]
@@ -639,7 +633,6 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_equals_special_nothing>]() {
                 TestHelper::new(r#"$.values[?length(@.a) == value($..c)]"#, r#"{"c": "cd", "values": [{"a": "ab"}, {"c": "d"}, {"a": null}]}"#, r#"[{"c": "d"}, {"a": null}]"#, TestTarget::$target).run()
             }
@@ -650,7 +643,6 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _filter_equals_empty_node_list_and_special_nothing>]() {
                 TestHelper::new(r#"$[?@.a == length(@.b)]"#, r#"[{"a": 1}, {"b": 2}, {"c": 3}]"#, r#"[{"b": 2}, {"c": 3}]"#, TestTarget::$target).run()
             }
@@ -936,313 +928,261 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_slice_selector>]() {
                 TestHelper::new(r#"$[1:3]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[1, 2]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_slice_selector_with_step>]() {
                 TestHelper::new(r#"$[1:6:2]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[1, 3, 5]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_slice_selector_with_everything_omitted_short_form>]() {
                 TestHelper::new(r#"$[:]"#, r#"[0, 1, 2, 3]"#, r#"[0, 1, 2, 3]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_slice_selector_with_everything_omitted_long_form>]() {
                 TestHelper::new(r#"$[::]"#, r#"[0, 1, 2, 3]"#, r#"[0, 1, 2, 3]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_slice_selector_with_start_omitted>]() {
                 TestHelper::new(r#"$[:2]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[0, 1]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_slice_selector_with_start_and_end_omitted>]() {
                 TestHelper::new(r#"$[::2]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[0, 2, 4, 6, 8]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_step_with_default_start_and_end>]() {
                 TestHelper::new(r#"$[::-1]"#, r#"[0, 1, 2, 3]"#, r#"[3, 2, 1, 0]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_step_with_default_start>]() {
                 TestHelper::new(r#"$[:0:-1]"#, r#"[0, 1, 2, 3]"#, r#"[3, 2, 1]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_step_with_default_end>]() {
                 TestHelper::new(r#"$[2::-1]"#, r#"[0, 1, 2, 3]"#, r#"[2, 1, 0]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_larger_negative_step>]() {
                 TestHelper::new(r#"$[::-2]"#, r#"[0, 1, 2, 3]"#, r#"[3, 1]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_range_with_default_step>]() {
                 TestHelper::new(r#"$[-1:-3]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_range_with_negative_step>]() {
                 TestHelper::new(r#"$[-1:-3:-1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[9, 8]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_range_with_larger_negative_step>]() {
                 TestHelper::new(r#"$[-1:-6:-2]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[9, 7, 5]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_larger_negative_range_with_larger_negative_step>]() {
                 TestHelper::new(r#"$[-1:-7:-2]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[9, 7, 5]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_from_positive_to>]() {
                 TestHelper::new(r#"$[-5:7]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[5, 6]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_from>]() {
                 TestHelper::new(r#"$[-2:]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[8, 9]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_positive_from_negative_to>]() {
                 TestHelper::new(r#"$[1:-1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[1, 2, 3, 4, 5, 6, 7, 8]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_from_positive_to_negative_step>]() {
                 TestHelper::new(r#"$[-1:1:-1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[9, 8, 7, 6, 5, 4, 3, 2]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_positive_from_negative_to_negative_step>]() {
                 TestHelper::new(r#"$[7:-5:-1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[7, 6]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_zero_step>]() {
                 TestHelper::new(r#"$[1:2:0]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_empty_range>]() {
                 TestHelper::new(r#"$[2:2]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_slice_selector_with_everything_omitted_with_empty_array>]() {
                 TestHelper::new(r#"$[:]"#, r#"[]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_negative_step_with_empty_array>]() {
                 TestHelper::new(r#"$[::-1]"#, r#"[]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_maximal_range_with_positive_step>]() {
                 TestHelper::new(r#"$[0:10]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_maximal_range_with_negative_step>]() {
                 TestHelper::new(r#"$[9:0:-1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[9, 8, 7, 6, 5, 4, 3, 2, 1]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_excessively_large_to_value>]() {
                 TestHelper::new(r#"$[2:113667776004]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[2, 3, 4, 5, 6, 7, 8, 9]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_excessively_small_from_value>]() {
                 TestHelper::new(r#"$[-113667776004:1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[0]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_excessively_large_from_value_with_negative_step>]() {
                 TestHelper::new(r#"$[113667776004:0:-1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[9, 8, 7, 6, 5, 4, 3, 2, 1]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_excessively_small_to_value_with_negative_step>]() {
                 TestHelper::new(r#"$[3:-113667776004:-1]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[3, 2, 1, 0]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_excessively_large_step>]() {
                 TestHelper::new(r#"$[1:10:113667776004]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[1]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_excessively_small_step>]() {
                 TestHelper::new(r#"$[-1:-10:-113667776004]"#, r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]"#, r#"[9]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_start_min_exact>]() {
                 TestHelper::new(r#"$[-9007199254740991::]"#, r#"[]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_start_max_exact>]() {
                 TestHelper::new(r#"$[9007199254740991::]"#, r#"[]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_end_min_exact>]() {
                 TestHelper::new(r#"$[:-9007199254740991:]"#, r#"[]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_end_max_exact>]() {
                 TestHelper::new(r#"$[:9007199254740991:]"#, r#"[]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_step_min_exact>]() {
                 TestHelper::new(r#"$[::-9007199254740991]"#, r#"[]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _slice_selector_step_max_exact>]() {
                 TestHelper::new(r#"$[::9007199254740991]"#, r#"[]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_count_count_function>]() {
                 TestHelper::new(r#"$[?count(@..*)>2]"#, r#"[{"a": [1, 2, 3]}, {"a": [1], "d": "f"}, {"a": 1, "d": "f"}]"#, r#"[{"a": [1, 2, 3]}, {"a": [1], "d": "f"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_count_single_node_arg>]() {
                 TestHelper::new(r#"$[?count(@.a)>1]"#, r#"[{"a": [1, 2, 3]}, {"a": [1], "d": "f"}, {"a": 1, "d": "f"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_count_multiple_selector_arg>]() {
                 TestHelper::new(r#"$[?count(@['a','d'])>1]"#, r#"[{"a": [1, 2, 3]}, {"a": [1], "d": "f"}, {"a": 1, "d": "f"}]"#, r#"[{"a": [1], "d": "f"}, {"a": 1, "d": "f"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_string_data>]() {
                 TestHelper::new(r#"$[?length(@.a)>=2]"#, r#"[{"a": "ab"}, {"a": "d"}]"#, r#"[{"a": "ab"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_string_data_unicode>]() {
                 TestHelper::new(r#"$[?length(@)==2]"#, r#"["☺", "☺☺", "☺☺☺", "ж", "жж", "жжж", "磨", "阿美", "形声字"]"#, r#"["☺☺", "жж", "阿美"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_array_data>]() {
                 TestHelper::new(r#"$[?length(@.a)>=2]"#, r#"[{"a": [1, 2, 3]}, {"a": [1]}]"#, r#"[{"a": [1, 2, 3]}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_missing_data>]() {
                 TestHelper::new(r#"$[?length(@.a)>=2]"#, r#"[{"d": "f"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_number_arg>]() {
                 TestHelper::new(r#"$[?length(1)>=2]"#, r#"[{"d": "f"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_true_arg>]() {
                 TestHelper::new(r#"$[?length(true)>=2]"#, r#"[{"d": "f"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_false_arg>]() {
                 TestHelper::new(r#"$[?length(false)>=2]"#, r#"[{"d": "f"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_null_arg>]() {
                 TestHelper::new(r#"$[?length(null)>=2]"#, r#"[{"d": "f"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_arg_is_a_function_expression>]() {
                 TestHelper::new(r#"$.values[?length(@.a)==length(value($..c))]"#, r#"{"c": "cd", "values": [{"a": "ab"}, {"a": "d"}]}"#, r#"[{"a": "ab"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_length_arg_is_special_nothing>]() {
                 TestHelper::new(r#"$[?length(value(@.a))>0]"#, r#"[{"a": "ab"}, {"c": "d"}, {"a": null}]"#, r#"[{"a": "ab"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_found_match>]() {
                 TestHelper::new(r#"$[?match(@.a, 'a.*')]"#, r#"[{"a": "ab"}]"#, r#"[{"a": "ab"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_double_quotes>]() {
                 TestHelper::new(r#"$[?match(@.a, "a.*")]"#, r#"[{"a": "ab"}]"#, r#"[{"a": "ab"}]"#, TestTarget::$target).run()
             }
@@ -1254,61 +1194,51 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_dont_select_match>]() {
                 TestHelper::new(r#"$[?!match(@.a, 'a.*')]"#, r#"[{"a": "ab"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_not_a_match>]() {
                 TestHelper::new(r#"$[?match(@.a, 'a.*')]"#, r#"[{"a": "bc"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_select_non_match>]() {
                 TestHelper::new(r#"$[?!match(@.a, 'a.*')]"#, r#"[{"a": "bc"}]"#, r#"[{"a": "bc"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_non_string_first_arg>]() {
                 TestHelper::new(r#"$[?match(1, 'a.*')]"#, r#"[{"a": "bc"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_non_string_second_arg>]() {
                 TestHelper::new(r#"$[?match(@.a, 1)]"#, r#"[{"a": "bc"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_filter_match_function_unicode_char_class_uppercase>]() {
                 TestHelper::new(r#"$[?match(@, '\\p{Lu}')]"#, r#"["ж", "Ж", "1", "жЖ", true, [], {}]"#, r#"["Ж"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_filter_match_function_unicode_char_class_negated_uppercase>]() {
                 TestHelper::new(r#"$[?match(@, '\\P{Lu}')]"#, r#"["ж", "Ж", "1", true, [], {}]"#, r#"["ж", "1"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_filter_match_function_unicode_surrogate_pair>]() {
                 TestHelper::new(r#"$[?match(@, 'a.b')]"#, r#"["a𐄁b", "ab", "1", true, [], {}]"#, r#"["a𐄁b"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_dot_matcher_on_u2028>]() {
                 TestHelper::new(r#"$[?match(@, '.')]"#, r#"[" ", "\r", "\n", true, [], {}]"#, r#"[" "]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_dot_matcher_on_u2029>]() {
                 TestHelper::new(r#"$[?match(@, '.')]"#, r#"[" ", "\r", "\n", true, [], {}]"#, r#"[" "]"#, TestTarget::$target).run()
             }
@@ -1320,67 +1250,58 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_dot_in_character_class>]() {
                 TestHelper::new(r#"$[?match(@, 'a[.b]c')]"#, r#"["abc", "a.c", "axc"]"#, r#"["abc", "a.c"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_escaped_dot>]() {
                 TestHelper::new(r#"$[?match(@, 'a\\.c')]"#, r#"["abc", "a.c", "axc"]"#, r#"["a.c"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_escaped_backslash_before_dot>]() {
                 TestHelper::new(r#"$[?match(@, 'a\\\\.c')]"#, r#"["abc", "a.c", "axc", "a\\ c"]"#, r#"["a\\ c"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_escaped_left_square_bracket>]() {
                 TestHelper::new(r#"$[?match(@, 'a\\[.c')]"#, r#"["abc", "a.c", "a[ c"]"#, r#"["a[ c"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_match_escaped_right_square_bracket>]() {
                 TestHelper::new(r#"$[?match(@, 'a[\\].]c')]"#, r#"["abc", "a.c", "a c", "a]c"]"#, r#"["a.c", "a]c"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
+            #[ignore = "^ and $ are normal characters in I-Regexp patterns, see additional_filters.rs"]
             fn [<$target:snake _functions_match_explicit_caret>]() {
                 TestHelper::new(r#"$[?match(@, '^ab.*')]"#, r#"["abc", "axc", "ab", "xab"]"#, r#"["abc", "ab"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
+            #[ignore = "^ and $ are normal characters in I-Regexp patterns, see additional_filters.rs"]
             fn [<$target:snake _functions_match_explicit_dollar>]() {
                 TestHelper::new(r#"$[?match(@, '.*bc$')]"#, r#"["abc", "axc", "ab", "abcx"]"#, r#"["abc"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_at_the_end>]() {
                 TestHelper::new(r#"$[?search(@.a, 'a.*')]"#, r#"[{"a": "the end is ab"}]"#, r#"[{"a": "the end is ab"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_double_quotes>]() {
                 TestHelper::new(r#"$[?search(@.a, "a.*")]"#, r#"[{"a": "the end is ab"}]"#, r#"[{"a": "the end is ab"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_at_the_start>]() {
                 TestHelper::new(r#"$[?search(@.a, 'a.*')]"#, r#"[{"a": "ab is at the start"}]"#, r#"[{"a": "ab is at the start"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_in_the_middle>]() {
                 TestHelper::new(r#"$[?search(@.a, 'a.*')]"#, r#"[{"a": "contains two matches"}]"#, r#"[{"a": "contains two matches"}]"#, TestTarget::$target).run()
             }
@@ -1392,61 +1313,51 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_dont_select_match>]() {
                 TestHelper::new(r#"$[?!search(@.a, 'a.*')]"#, r#"[{"a": "contains two matches"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_not_a_match>]() {
                 TestHelper::new(r#"$[?search(@.a, 'a.*')]"#, r#"[{"a": "bc"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_select_non_match>]() {
                 TestHelper::new(r#"$[?!search(@.a, 'a.*')]"#, r#"[{"a": "bc"}]"#, r#"[{"a": "bc"}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_non_string_first_arg>]() {
                 TestHelper::new(r#"$[?search(1, 'a.*')]"#, r#"[{"a": "bc"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_non_string_second_arg>]() {
                 TestHelper::new(r#"$[?search(@.a, 1)]"#, r#"[{"a": "bc"}]"#, r#"[]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_filter_search_function_unicode_char_class_uppercase>]() {
                 TestHelper::new(r#"$[?search(@, '\\p{Lu}')]"#, r#"["ж", "Ж", "1", "жЖ", true, [], {}]"#, r#"["Ж", "жЖ"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_filter_search_function_unicode_char_class_negated_uppercase>]() {
                 TestHelper::new(r#"$[?search(@, '\\P{Lu}')]"#, r#"["ж", "Ж", "1", true, [], {}]"#, r#"["ж", "1"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_filter_search_function_unicode_surrogate_pair>]() {
                 TestHelper::new(r#"$[?search(@, 'a.b')]"#, r#"["a𐄁bc", "abc", "1", true, [], {}]"#, r#"["a𐄁bc"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_dot_matcher_on_u2028>]() {
                 TestHelper::new(r#"$[?search(@, '.')]"#, r#"[" ", "\r \n", "\r", "\n", true, [], {}]"#, r#"[" ", "\r \n"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_dot_matcher_on_u2029>]() {
                 TestHelper::new(r#"$[?search(@, '.')]"#, r#"[" ", "\r \n", "\r", "\n", true, [], {}]"#, r#"[" ", "\r \n"]"#, TestTarget::$target).run()
             }
@@ -1458,43 +1369,36 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_dot_in_character_class>]() {
                 TestHelper::new(r#"$[?search(@, 'a[.b]c')]"#, r#"["x abc y", "x a.c y", "x axc y"]"#, r#"["x abc y", "x a.c y"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_escaped_dot>]() {
                 TestHelper::new(r#"$[?search(@, 'a\\.c')]"#, r#"["x abc y", "x a.c y", "x axc y"]"#, r#"["x a.c y"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_escaped_backslash_before_dot>]() {
                 TestHelper::new(r#"$[?search(@, 'a\\\\.c')]"#, r#"["x abc y", "x a.c y", "x axc y", "x a\\ c y"]"#, r#"["x a\\ c y"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_escaped_left_square_bracket>]() {
                 TestHelper::new(r#"$[?search(@, 'a\\[.c')]"#, r#"["x abc y", "x a.c y", "x a[ c y"]"#, r#"["x a[ c y"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_search_escaped_right_square_bracket>]() {
                 TestHelper::new(r#"$[?search(@, 'a[\\].]c')]"#, r#"["x abc y", "x a.c y", "x a c y", "x a]c y"]"#, r#"["x a.c y", "x a]c y"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_value_single_value_nodelist>]() {
                 TestHelper::new(r#"$[?value(@.*)==4]"#, r#"[[4], {"foo": 4}, [5], {"foo": 5}, 4]"#, r#"[[4], {"foo": 4}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _functions_value_multi_value_nodelist>]() {
                 TestHelper::new(r#"$[?value(@.*)==4]"#, r#"[[4, 4], {"foo": 4, "bar": 4}]"#, r#"[]"#, TestTarget::$target).run()
             }
@@ -1580,145 +1484,121 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_space_between_parenthesis_and_arg>]() {
                 TestHelper::new("$[?count( @.*)==1]", r#"[{"a": 1}, {"b": 2}, {"a": 2, "b": 1}]"#, r#"[{"a": 1}, {"b": 2}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_newline_between_parenthesis_and_arg>]() {
                 TestHelper::new("$[?count(\n@.*)==1]", r#"[{"a": 1}, {"b": 2}, {"a": 2, "b": 1}]"#, r#"[{"a": 1}, {"b": 2}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_tab_between_parenthesis_and_arg>]() {
                 TestHelper::new("$[?count(\t@.*)==1]", r#"[{"a": 1}, {"b": 2}, {"a": 2, "b": 1}]"#, r#"[{"a": 1}, {"b": 2}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_return_between_parenthesis_and_arg>]() {
                 TestHelper::new("$[?count(\r@.*)==1]", r#"[{"a": 1}, {"b": 2}, {"a": 2, "b": 1}]"#, r#"[{"a": 1}, {"b": 2}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_space_between_arg_and_comma>]() {
                 TestHelper::new("$[?search(@ ,'[a-z]+')]", r#"["foo", "123"]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_newline_between_arg_and_comma>]() {
                 TestHelper::new("$[?search(@\n,'[a-z]+')]", r#"["foo", "123"]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_tab_between_arg_and_comma>]() {
                 TestHelper::new("$[?search(@\t,'[a-z]+')]", r#"["foo", "123"]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_return_between_arg_and_comma>]() {
                 TestHelper::new("$[?search(@\r,'[a-z]+')]", r#"["foo", "123"]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_space_between_comma_and_arg>]() {
                 TestHelper::new("$[?search(@, '[a-z]+')]", r#"["foo", "123"]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_newline_between_comma_and_arg>]() {
                 TestHelper::new("$[?search(@,\n'[a-z]+')]", r#"["foo", "123"]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_tab_between_comma_and_arg>]() {
                 TestHelper::new("$[?search(@,\t'[a-z]+')]", r#"["foo", "123"]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_return_between_comma_and_arg>]() {
                 TestHelper::new("$[?search(@,\r'[a-z]+')]", r#"["foo", "123"]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_space_between_arg_and_parenthesis>]() {
                 TestHelper::new("$[?count(@.* )==1]", r#"[{"a": 1}, {"b": 2}, {"a": 2, "b": 1}]"#, r#"[{"a": 1}, {"b": 2}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_newline_between_arg_and_parenthesis>]() {
                 TestHelper::new("$[?count(@.*\n)==1]", r#"[{"a": 1}, {"b": 2}, {"a": 2, "b": 1}]"#, r#"[{"a": 1}, {"b": 2}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_tab_between_arg_and_parenthesis>]() {
                 TestHelper::new("$[?count(@.*\t)==1]", r#"[{"a": 1}, {"b": 2}, {"a": 2, "b": 1}]"#, r#"[{"a": 1}, {"b": 2}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_return_between_arg_and_parenthesis>]() {
                 TestHelper::new("$[?count(@.*\r)==1]", r#"[{"a": 1}, {"b": 2}, {"a": 2, "b": 1}]"#, r#"[{"a": 1}, {"b": 2}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_spaces_in_a_relative_singular_selector>]() {
                 TestHelper::new("$[?length(@ .a .b) == 3]", r#"[{"a": {"b": "foo"}}, {}]"#, r#"[{"a": {"b": "foo"}}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_newlines_in_a_relative_singular_selector>]() {
                 TestHelper::new("$[?length(@\n.a\n.b) == 3]", r#"[{"a": {"b": "foo"}}, {}]"#, r#"[{"a": {"b": "foo"}}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_tabs_in_a_relative_singular_selector>]() {
                 TestHelper::new("$[?length(@\t.a\t.b) == 3]", r#"[{"a": {"b": "foo"}}, {}]"#, r#"[{"a": {"b": "foo"}}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_returns_in_a_relative_singular_selector>]() {
                 TestHelper::new("$[?length(@\r.a\r.b) == 3]", r#"[{"a": {"b": "foo"}}, {}]"#, r#"[{"a": {"b": "foo"}}]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_spaces_in_an_absolute_singular_selector>]() {
                 TestHelper::new("$..[?length(@)==length($ [0] .a)]", r#"[{"a": "foo"}, {}]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_newlines_in_an_absolute_singular_selector>]() {
                 TestHelper::new("$..[?length(@)==length($\n[0]\n.a)]", r#"[{"a": "foo"}, {}]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_tabs_in_an_absolute_singular_selector>]() {
                 TestHelper::new("$..[?length(@)==length($\t[0]\t.a)]", r#"[{"a": "foo"}, {}]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_functions_returns_in_an_absolute_singular_selector>]() {
                 TestHelper::new("$..[?length(@)==length($\r[0]\r.a)]", r#"[{"a": "foo"}, {}]"#, r#"["foo"]"#, TestTarget::$target).run()
             }
@@ -2224,97 +2104,81 @@ macro_rules! cts {
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_space_between_start_and_colon>]() {
                 TestHelper::new("$[1 :5:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_newline_between_start_and_colon>]() {
                 TestHelper::new("$[1\n:5:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_tab_between_start_and_colon>]() {
                 TestHelper::new("$[1\t:5:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_return_between_start_and_colon>]() {
                 TestHelper::new("$[1\r:5:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_space_between_colon_and_end>]() {
                 TestHelper::new("$[1: 5:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_newline_between_colon_and_end>]() {
                 TestHelper::new("$[1:\n5:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_tab_between_colon_and_end>]() {
                 TestHelper::new("$[1:\t5:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_return_between_colon_and_end>]() {
                 TestHelper::new("$[1:\r5:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_space_between_end_and_colon>]() {
                 TestHelper::new("$[1:5 :2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_newline_between_end_and_colon>]() {
                 TestHelper::new("$[1:5\n:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_tab_between_end_and_colon>]() {
                 TestHelper::new("$[1:5\t:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_return_between_end_and_colon>]() {
                 TestHelper::new("$[1:5\r:2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_space_between_colon_and_step>]() {
                 TestHelper::new("$[1:5: 2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_newline_between_colon_and_step>]() {
                 TestHelper::new("$[1:5:\n2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_tab_between_colon_and_step>]() {
                 TestHelper::new("$[1:5:\t2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
 
             #[test]
-            #[ignore]
             fn [<$target:snake _whitespace_slice_return_between_colon_and_step>]() {
                 TestHelper::new("$[1:5:\r2]", r#"[1, 2, 3, 4, 5, 6]"#, r#"[2, 4]"#, TestTarget::$target).run()
             }
@@ -2324,4 +2188,7 @@ macro_rules! cts {
 
 cts!(SimdjsonOndemand);
 cts!(SimdjsonOndemandEagerFilters);
-//cts!(SimdjsonDom);
\ No newline at end of file
+cts!(SimdjsonDom);
+cts!(Rust);
+cts!(RustSerdeJson);
+cts!(Interpreter);
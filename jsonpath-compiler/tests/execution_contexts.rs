@@ -0,0 +1,110 @@
+use serde_json::{Deserializer, Value};
+
+use jsonpath_compiler::compiler::LibGeneratingCompiler;
+use jsonpath_compiler::test_helper::{execute_lib, interpreter_results};
+
+const MAIN_CODE: &str = r#"
+#include <iostream>
+#include "queries.hpp"
+
+void print_result(const char *result, size_t result_length, void *user_data)
+{
+    auto callback_results = static_cast<string *>(user_data);
+    if (callback_results->size() > 1)
+        *callback_results += ", ";
+    *callback_results += string_view(result, result_length);
+}
+
+int main(int argc, char **argv)
+{
+    jsonpath_context context;
+    for (int i = 1; i < argc; i++)
+    {
+        padded_string input = padded_string::load(argv[i]);
+        size_t length = input.size() + SIMDJSON_PADDING;
+        try
+        {
+            string output = filtered(context, input.data(), length);
+            output += descendants(context, input.data(), length);
+            string callback_results = "[";
+            filtered_with_callback(context, input.data(), length, print_result, &callback_results);
+            output += callback_results + "]\n";
+            cout << output;
+        }
+        catch (const simdjson_error &)
+        {
+            cout << "null\n";
+        }
+    }
+}
+"#;
+
+const FILTERED_QUERY: &str = "$..a[?@.b > $.min && count(@..c) < 3]";
+
+const DESCENDANTS_QUERY: &str = "$..b";
+
+/// Compiles the queries to a library with result callbacks, and executes them with a single
+/// context on every document in turn.
+fn execute(compiler: LibGeneratingCompiler, documents: &[&str]) -> Vec<Value> {
+    let output = execute_lib(
+        compiler.with_result_callbacks(),
+        &[("filtered", FILTERED_QUERY), ("descendants", DESCENDANTS_QUERY)],
+        MAIN_CODE,
+        documents,
+    );
+    Deserializer::from_str(&output).into_iter().map(|value| value.unwrap()).collect()
+}
+
+fn expected_results(query: &str, document: &Value) -> Value {
+    Value::Array(interpreter_results(query, document))
+}
+
+/// Returns the output expected from the execution of the queries on the document, or `null` if
+/// the document is malformed.
+fn expected_output(document: &str) -> Vec<Value> {
+    match serde_json::from_str(document) {
+        Ok(document) => {
+            let filtered_results = expected_results(FILTERED_QUERY, &document);
+            vec![
+                filtered_results.clone(),
+                expected_results(DESCENDANTS_QUERY, &document),
+                filtered_results,
+            ]
+        }
+        Err(_) => vec![Value::Null],
+    }
+}
+
+fn assert_context_reused(documents: &[&str]) {
+    let expected_output: Vec<Value> = documents
+        .iter()
+        .flat_map(|document| expected_output(document))
+        .collect();
+    let compilers = [
+        LibGeneratingCompiler::new(),
+        LibGeneratingCompiler::new().with_eager_filter_evaluation(),
+    ];
+    for compiler in compilers {
+        assert_eq!(execute(compiler, documents), expected_output);
+    }
+}
+
+#[test]
+fn context_is_reused_across_queries_and_documents() {
+    assert_context_reused(&[
+        r#"{"min": 1, "a": [{"b": 2}, {"b": 1}, {"b": 3, "a": [{"b": 4}]}]}"#,
+        r#"{"min": 4, "a": [{"b": 5, "c": 1}, {"b": 6, "c": [{"c": 2}, 3]}]}"#,
+        r#"[{"a": [{"b": 0}]}, {"b": {"b": true}}]"#,
+        r#"{"min": 0, "a": [{"b": 1, "c": [{"c": 2}, {"c": 3}]}, {"b": 2}]}"#,
+    ]);
+}
+
+#[test]
+fn context_is_reused_after_interrupted_execution() {
+    assert_context_reused(&[
+        r#"{"min": 1, "a": [{"b": 2, "c": 1}, {"b": 3, "a": [{"b": 4}, {"b": 5, "#,
+        r#"{"min": 4, "a": [{"b": 5, "c": 1}, {"b": 6, "c": [{"c": 2}, 3]}]}"#,
+        r#"{"min": 1, "a": [{"b": 2}, {"b": 1}, {"b": 3, "a": [{"b": "#,
+        r#"{"min": 0, "a": [{"b": 1, "c": [{"c": 2}, {"c": 3}]}, {"b": 2}]}"#,
+    ]);
+}
@@ -0,0 +1,78 @@
+use paste::paste;
+
+use jsonpath_compiler::test_helper::{TestHelper, TestTarget};
+
+macro_rules! invalid_documents {
+    ($target:ident) => {
+        paste! {
+            #[test]
+            fn [<$target:snake _unclosed_array>]() {
+                TestHelper::with_invalid_document(
+                    r#"$.a[0]"#,
+                    r#"{"a": [1, 2"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _invalid_literal_in_skipped_member>]() {
+                TestHelper::with_invalid_document(
+                    r#"$.a"#,
+                    r#"{"a": 1, "b": tru}"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _number_with_leading_zero>]() {
+                TestHelper::with_invalid_document(
+                    r#"$.a"#,
+                    r#"{"b": [01], "a": 1}"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _invalid_escape_in_skipped_string>]() {
+                TestHelper::with_invalid_document(
+                    r#"$..a"#,
+                    r#"{"b": "\x", "a": 1}"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _trailing_comma>]() {
+                TestHelper::with_invalid_document(
+                    r#"$.a[*]"#,
+                    r#"{"a": [1, 2,]}"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _missing_member_value>]() {
+                TestHelper::with_invalid_document(
+                    r#"$.*"#,
+                    r#"{"a": 1, "b"}"#,
+                    TestTarget::$target,
+                ).run()
+            }
+
+            #[test]
+            fn [<$target:snake _content_after_root>]() {
+                TestHelper::with_invalid_document(
+                    r#"$"#,
+                    r#"{"a": 1} 2"#,
+                    TestTarget::$target,
+                ).run()
+            }
+        }
+    }
+}
+
+// The ondemand targets only validate the parts of the document that they traverse.
+invalid_documents!(SimdjsonDom);
+invalid_documents!(Rust);
+invalid_documents!(RustSerdeJson);
+invalid_documents!(Interpreter);
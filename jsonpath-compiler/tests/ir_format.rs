@@ -0,0 +1,175 @@
+use std::fs;
+
+use serde_json::{json, Value};
+
+use jsonpath_compiler::compiler::{CompilationError, LibGeneratingCompiler, QueriesSource, StandaloneProgGeneratingCompiler};
+use jsonpath_compiler::interpreter::Interpreter;
+use jsonpath_compiler::targets::rust::RustCodeStandaloneProgGenerator;
+use jsonpath_compiler::targets::rust::value::{ValueCodeLibGenerator, ValueCodeStandaloneProgGenerator};
+use jsonpath_compiler::test_helper::create_execution_dir;
+
+#[test]
+fn query_ir_is_written_in_the_same_way_after_reading_it() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/query.json");
+    let read_ir_path = format!("{execution_dir}/read_query.json");
+    StandaloneProgGeneratingCompiler::new()
+        .write_ir_to_file(&ir_path)
+        .compile::<ValueCodeStandaloneProgGenerator>(
+            "$..a[?@.b == 'x' || count(@.c[?@.d > 1]) > 2 && match(@.e, '[a-z]+')]..f[1:-1:2]",
+            &format!("{execution_dir}/query.rs"),
+        )
+        .unwrap();
+    StandaloneProgGeneratingCompiler::new()
+        .write_ir_to_file(&read_ir_path)
+        .without_ir_passes()
+        .compile_ir::<ValueCodeStandaloneProgGenerator>(
+            &ir_path,
+            &format!("{execution_dir}/read_query.rs"),
+        )
+        .unwrap();
+    assert_eq!(fs::read_to_string(&ir_path).unwrap(), fs::read_to_string(&read_ir_path).unwrap());
+}
+
+#[test]
+fn queries_irs_are_written_in_the_same_way_after_reading_them() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/queries.json");
+    let read_ir_path = format!("{execution_dir}/read_queries.json");
+    let queries = vec![
+        ("first".to_string(), "$.a[*].b".to_string()),
+        ("second".to_string(), "$..c[?@.d && value(@..e) == 1]".to_string()),
+    ];
+    LibGeneratingCompiler::new()
+        .write_ir_to_file(&ir_path)
+        .compile::<ValueCodeLibGenerator>(
+            QueriesSource::Immediate { queries },
+            &format!("{execution_dir}/queries.rs"),
+        )
+        .unwrap();
+    LibGeneratingCompiler::new()
+        .write_ir_to_file(&read_ir_path)
+        .without_ir_passes()
+        .compile::<ValueCodeLibGenerator>(
+            QueriesSource::IRFile { file_path: ir_path.clone() },
+            &format!("{execution_dir}/read_queries.rs"),
+        )
+        .unwrap();
+    assert_eq!(fs::read_to_string(&ir_path).unwrap(), fs::read_to_string(&read_ir_path).unwrap());
+}
+
+#[test]
+fn edited_ir_is_executed() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/edited.json");
+    StandaloneProgGeneratingCompiler::new()
+        .write_ir_to_file(&ir_path)
+        .compile::<ValueCodeStandaloneProgGenerator>("$.a", &format!("{execution_dir}/edited.rs"))
+        .unwrap();
+    let ir = fs::read_to_string(&ir_path).unwrap();
+    assert!(ir.contains(r#""name": "a""#));
+    let edited_ir = ir.replace(r#""name": "a""#, r#""name": "b""#);
+    let document = json!({"a": 1, "b": 2});
+    let result = Interpreter::from_ir(&edited_ir).unwrap().run(&document);
+    assert_eq!(result, [&Value::from(2)]);
+}
+
+#[test]
+fn invalid_ir_is_reported() {
+    let result = Interpreter::from_ir(r#"{"procedures": [{"name": "Selectors_0"}]}"#);
+    assert!(matches!(result, Err(CompilationError::InvalidIR(_))));
+}
+
+fn assert_invalid_ir(result: Result<impl Sized, CompilationError>, expected_message: &str) {
+    match result {
+        Err(CompilationError::InvalidIR(message)) => assert!(
+            message.contains(expected_message),
+            "`{message}` does not contain `{expected_message}`"
+        ),
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("invalid IR is accepted"),
+    }
+}
+
+fn call_procedure(name: &str) -> Value {
+    json!({
+        "procedures": [
+            {"name": "Selectors_0", "instructions": [{"ForEachMember": {"instructions": [
+                {"ExecuteProcedureOnChild": {"conditions": [], "segments": [], "name": name}}
+            ]}}]},
+        ],
+        "filter_procedures": [],
+        "filter_subqueries": [],
+        "segments_count": 1
+    })
+}
+
+#[test]
+fn ir_calling_undefined_procedure_is_rejected() {
+    let result = Interpreter::from_ir(&call_procedure("Selectors_1").to_string());
+    assert_invalid_ir(result, "procedure Selectors_1 is not defined");
+}
+
+#[test]
+fn ir_without_root_procedure_is_rejected() {
+    let mut ir = call_procedure("Selectors_0");
+    ir["procedures"][0]["name"] = Value::from("Selectors_1");
+    let result = Interpreter::from_ir(&ir.to_string());
+    assert_invalid_ir(result, "procedure Selectors_0 is not defined");
+}
+
+fn remove_filter_executions(value: &mut Value) {
+    match value {
+        Value::Array(values) => {
+            values.retain(|value| value.get("StartFilterExecution").is_none());
+            values.iter_mut().for_each(remove_filter_executions);
+        }
+        Value::Object(members) => members.values_mut().for_each(remove_filter_executions),
+        _ => {}
+    }
+}
+
+#[test]
+fn ir_using_filter_before_its_execution_is_rejected() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/filter.json");
+    StandaloneProgGeneratingCompiler::new()
+        .write_ir_to_file(&ir_path)
+        .compile::<ValueCodeStandaloneProgGenerator>("$[?@.a].b", &format!("{execution_dir}/filter.rs"))
+        .unwrap();
+    let mut ir: Value = serde_json::from_str(&fs::read_to_string(&ir_path).unwrap()).unwrap();
+    remove_filter_executions(&mut ir);
+    fs::write(&ir_path, ir.to_string()).unwrap();
+    let result = StandaloneProgGeneratingCompiler::new()
+        .compile_ir::<ValueCodeStandaloneProgGenerator>(&ir_path, &format!("{execution_dir}/filter.rs"));
+    assert_invalid_ir(result, "filter 0_0 is used before its execution");
+}
+
+#[test]
+fn invalid_ir_of_library_query_is_rejected() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/invalid_queries.json");
+    let queries_irs = json!([{"name": "first", "query": call_procedure("Selectors_2")}]);
+    fs::write(&ir_path, queries_irs.to_string()).unwrap();
+    let result = LibGeneratingCompiler::new().compile::<ValueCodeLibGenerator>(
+        QueriesSource::IRFile { file_path: ir_path },
+        &format!("{execution_dir}/invalid_queries.rs"),
+    );
+    assert_invalid_ir(result, "procedure Selectors_2 is not defined");
+}
+
+#[test]
+fn ir_visiting_elements_out_of_document_order_is_rejected_by_streaming_targets() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/reversed.json");
+    StandaloneProgGeneratingCompiler::new()
+        .write_ir_to_file(&ir_path)
+        .compile::<ValueCodeStandaloneProgGenerator>("$[::-1]", &format!("{execution_dir}/reversed.rs"))
+        .unwrap();
+    let result = StandaloneProgGeneratingCompiler::new()
+        .compile_ir::<RustCodeStandaloneProgGenerator>(
+            &ir_path,
+            &format!("{execution_dir}/reversed_streaming.rs"),
+        );
+    assert!(matches!(result, Err(CompilationError::RandomAccessNotSupported)));
+}
@@ -0,0 +1,132 @@
+use std::fs;
+
+use serde_json::{json, Value};
+
+use jsonpath_compiler::compiler::StandaloneProgGeneratingCompiler;
+use jsonpath_compiler::interpreter::Interpreter;
+use jsonpath_compiler::IRPass;
+use jsonpath_compiler::targets::rust::value::ValueCodeStandaloneProgGenerator;
+use jsonpath_compiler::test_helper::create_execution_dir;
+
+fn select_name(name: &str, procedure_name: &str) -> Value {
+    json!({"IfCurrentMemberNameEquals": {"name": name, "instructions": [
+        {"ExecuteProcedureOnChild": {"conditions": [], "segments": [], "name": procedure_name}},
+        "Continue"
+    ]}})
+}
+
+fn save_member(name: &str) -> Value {
+    json!({"ForEachMember": {"instructions": [
+        {"IfCurrentMemberNameEquals": {"name": name, "instructions": [
+            {"SaveCurrentNodeDuringTraversal": {"condition": null, "instruction": "TraverseCurrentNodeSubtree"}},
+            "Continue"
+        ]}}
+    ]}})
+}
+
+fn optimize(name: &str, ir: &Value, compiler: StandaloneProgGeneratingCompiler) -> Value {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/{name}.json");
+    let optimized_ir_path = format!("{execution_dir}/{name}_optimized.json");
+    fs::write(&ir_path, ir.to_string()).unwrap();
+    compiler
+        .write_ir_to_file(&optimized_ir_path)
+        .compile_ir::<ValueCodeStandaloneProgGenerator>(&ir_path, &format!("{execution_dir}/{name}.rs"))
+        .unwrap();
+    serde_json::from_str(&fs::read_to_string(&optimized_ir_path).unwrap()).unwrap()
+}
+
+fn procedure_names(ir: &Value) -> Vec<&str> {
+    ir["procedures"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|procedure| procedure["name"].as_str().unwrap())
+        .collect()
+}
+
+fn changes(ir: &Value) -> Vec<(&str, u64)> {
+    ir["passes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|report| (report["pass"].as_str().unwrap(), report["changes"].as_u64().unwrap()))
+        .collect()
+}
+
+// $['a', 'b'].c, with a separate procedure for each name, a condition on the name "a" nested
+// in another, which is followed by a condition on the same name whose instructions are never
+// executed, and an unreachable procedure.
+fn unoptimized_ir() -> Value {
+    json!({
+        "procedures": [
+            {"name": "Selectors_0", "instructions": [{"ForEachMember": {"instructions": [
+                {"IfCurrentMemberNameEquals": {"name": "a", "instructions": [select_name("a", "Selectors_1")]}},
+                select_name("a", "Selectors_3"),
+                select_name("b", "Selectors_2"),
+            ]}}]},
+            {"name": "Selectors_1", "instructions": [save_member("c")]},
+            {"name": "Selectors_2", "instructions": [save_member("c")]},
+            {"name": "Selectors_3", "instructions": [save_member("d")]},
+            {"name": "Selectors_4", "instructions": [save_member("e")]},
+        ],
+        "filter_procedures": [],
+        "filter_subqueries": [],
+        "segments_count": 2
+    })
+}
+
+#[test]
+fn passes_simplify_ir_without_changing_results() {
+    let ir = unoptimized_ir();
+    let optimized_ir = optimize("all", &ir, StandaloneProgGeneratingCompiler::new());
+    assert_eq!(procedure_names(&optimized_ir), ["Selectors_0", "Selectors_1", "Selectors_3"]);
+    assert_eq!(changes(&optimized_ir), [
+        ("merge-identical-procedures", 1),
+        ("remove-unreachable-procedures", 1),
+        ("fold-member-name-conditions", 2),
+        ("remove-subqueries-state-updates", 0),
+    ]);
+    let name_conditions = &optimized_ir["procedures"][0]["instructions"][0]["ForEachMember"]["instructions"];
+    assert_eq!(name_conditions.as_array().unwrap().len(), 2);
+    let document = json!({"a": {"c": 1, "d": 2}, "b": {"c": 3, "d": 4}});
+    let expected_result = [&Value::from(1), &Value::from(3)];
+    assert_eq!(Interpreter::from_ir(&ir.to_string()).unwrap().run(&document), expected_result);
+    assert_eq!(Interpreter::from_ir(&optimized_ir.to_string()).unwrap().run(&document), expected_result);
+}
+
+#[test]
+fn disabled_passes_are_not_run() {
+    let optimized_ir = optimize(
+        "disabled",
+        &unoptimized_ir(),
+        StandaloneProgGeneratingCompiler::new()
+            .without_ir_pass(IRPass::MergeIdenticalProcedures)
+            .without_ir_pass(IRPass::FoldMemberNameConditions),
+    );
+    assert_eq!(procedure_names(&optimized_ir), ["Selectors_0", "Selectors_1", "Selectors_2", "Selectors_3"]);
+    assert_eq!(changes(&optimized_ir), [
+        ("remove-unreachable-procedures", 1),
+        ("remove-subqueries-state-updates", 0),
+    ]);
+}
+
+#[test]
+fn subqueries_states_are_updated_only_when_filters_can_be_executed() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/filters.json");
+    StandaloneProgGeneratingCompiler::new()
+        .write_ir_to_file(&ir_path)
+        .compile::<ValueCodeStandaloneProgGenerator>("$.a[?@.b].c", &format!("{execution_dir}/filters.rs"))
+        .unwrap();
+    let ir: Value = serde_json::from_str(&fs::read_to_string(&ir_path).unwrap()).unwrap();
+    let updating_procedures: Vec<&str> = ir["procedures"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|procedure| procedure["instructions"][0] == "UpdateSubqueriesState")
+        .map(|procedure| procedure["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(updating_procedures, ["Selectors_2"]);
+    assert_eq!(changes(&ir).last(), Some(&("remove-subqueries-state-updates", 2)));
+}
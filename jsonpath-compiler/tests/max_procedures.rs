@@ -0,0 +1,197 @@
+use std::fs;
+
+use paste::paste;
+
+use jsonpath_compiler::compiler::{
+    CompilationError, DEFAULT_MAX_PROCEDURES, LibGeneratingCompiler, QueriesSource,
+    StandaloneProgGeneratingCompiler,
+};
+use jsonpath_compiler::interpreter::Interpreter;
+use jsonpath_compiler::targets::rust::RustCodeStandaloneProgGenerator;
+use jsonpath_compiler::targets::rust::value::ValueCodeStandaloneProgGenerator;
+use jsonpath_compiler::targets::simdjson::ondemand::OnDemandCodeLibGenerator;
+use jsonpath_compiler::test_helper::{create_execution_dir, TestHelper, TestTarget};
+
+macro_rules! max_procedures {
+    ($target:ident) => {
+        paste! {
+            #[test]
+            fn [<$target:snake _wildcards_after_descendant_name>]() {
+                TestHelper::new(
+                    r#"$..a.*.*"#,
+                    r#"{"a": {"a": [1, {"a": {"b": [2]}}], "c": {"d": 3}}, "e": [{"a": {"f": {"g": 4}}}]}"#,
+                    r#"[1, {"a": {"b": [2]}}, {"b": [2]}, 2, 3, 4]"#,
+                    TestTarget::$target,
+                )
+                    .with_max_procedures(1)
+                    .run()
+            }
+
+            #[test]
+            fn [<$target:snake _slice_after_descendant_names>]() {
+                TestHelper::new(
+                    r#"$..a..b[1:]"#,
+                    r#"{"a": [{"b": [1, 2, {"b": [3, 4]}]}, {"x": {"b": [5, 6]}}], "b": [7, 8]}"#,
+                    r#"[2, {"b": [3, 4]}, 4, 6]"#,
+                    TestTarget::$target,
+                )
+                    .with_max_procedures(2)
+                    .run()
+            }
+
+            #[test]
+            fn [<$target:snake _indices_after_descendant_negative_index>]() {
+                TestHelper::new(
+                    r#"$[*]..[-1][0]"#,
+                    r#"[[[1, 2], [3, [4, 5]]], {"a": [[6]]}]"#,
+                    r#"[3, 4, 6]"#,
+                    TestTarget::$target,
+                )
+                    .with_max_procedures(1)
+                    .run()
+            }
+
+            #[test]
+            fn [<$target:snake _wildcard_after_descendant_names_selecting_nested_nodes>]() {
+                TestHelper::new(
+                    r#"$..['a','b'].*"#,
+                    r#"{"a": {"b": {"c": 1}, "d": [2]}, "b": [{"a": 3}]}"#,
+                    r#"[{"c": 1}, 1, [2], {"a": 3}]"#,
+                    TestTarget::$target,
+                )
+                    .with_max_procedures(1)
+                    .run()
+            }
+        }
+    }
+}
+
+max_procedures!(SimdjsonOndemand);
+max_procedures!(SimdjsonDom);
+max_procedures!(Rust);
+max_procedures!(RustSerdeJson);
+max_procedures!(Interpreter);
+
+#[test]
+fn procedures_count_is_limited() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/ir.json");
+    StandaloneProgGeneratingCompiler::new()
+        .with_max_procedures(16)
+        .write_ir_to_file(&ir_path)
+        .compile::<ValueCodeStandaloneProgGenerator>(
+            "$..a.*.*.*.*.*.*.*.*",
+            &format!("{execution_dir}/query.rs"),
+        )
+        .unwrap();
+    let ir: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&ir_path).unwrap()).unwrap();
+    let procedures = ir["procedures"].as_array().unwrap();
+    assert!(procedures.len() <= 17);
+    assert!(procedures.iter().any(|procedure| procedure["are_active_segments_passed"] == true));
+}
+
+#[test]
+fn procedures_limit_exceeded_by_query_with_filters_is_reported() {
+    let execution_dir = create_execution_dir();
+    let query = "$..a[?@.b].*";
+    let result = StandaloneProgGeneratingCompiler::new()
+        .with_max_procedures(1)
+        .compile::<ValueCodeStandaloneProgGenerator>(query, &format!("{execution_dir}/filters.rs"));
+    assert!(matches!(
+        result,
+        Err(CompilationError::ProceduresLimitExceeded { query_name, max_procedures: 1 }) if query_name == query
+    ));
+    assert!(Interpreter::with_max_procedures(query, 1).is_err());
+    assert!(Interpreter::with_max_procedures(query, 16).is_ok());
+}
+
+#[test]
+fn procedures_limit_exceeded_by_query_with_slice_with_negative_step_is_reported() {
+    let execution_dir = create_execution_dir();
+    let query = "$..a[::-1].*";
+    let result = StandaloneProgGeneratingCompiler::new()
+        .with_max_procedures(1)
+        .compile::<ValueCodeStandaloneProgGenerator>(query, &format!("{execution_dir}/negative_step.rs"));
+    assert!(matches!(
+        result,
+        Err(CompilationError::ProceduresLimitExceeded { query_name, max_procedures: 1 }) if query_name == query
+    ));
+    assert!(Interpreter::with_max_procedures(query, 16).is_ok());
+}
+
+#[test]
+fn procedures_limit_exceeded_by_queries_executed_in_single_pass_is_reported() {
+    let execution_dir = create_execution_dir();
+    let queries = vec![
+        ("first".to_string(), "$..a.*".to_string()),
+        ("second".to_string(), "$..b[0]".to_string()),
+    ];
+    let result = LibGeneratingCompiler::new()
+        .with_max_procedures(1)
+        .with_single_pass("all_queries")
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate { queries },
+            &format!("{execution_dir}/single_pass.hpp"),
+        );
+    let Err(CompilationError::MultipleErrors(errors)) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+    assert!(matches!(
+        &errors[..],
+        [(name, CompilationError::ProceduresLimitExceeded { query_name, max_procedures: 1 })]
+            if name == "all_queries" && query_name == "all_queries"
+    ));
+}
+
+#[test]
+fn procedures_limit_exceeded_by_every_query_of_library_is_reported() {
+    let execution_dir = create_execution_dir();
+    let queries = vec![
+        ("first".to_string(), "$..a[?@.b].*".to_string()),
+        ("second".to_string(), "$.a".to_string()),
+        ("third".to_string(), "$..c[?@.d].*".to_string()),
+    ];
+    let result = LibGeneratingCompiler::new()
+        .with_max_procedures(1)
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate { queries },
+            &format!("{execution_dir}/filters.hpp"),
+        );
+    let Err(CompilationError::MultipleErrors(errors)) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+    let names: Vec<&str> = errors.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["first", "third"]);
+    assert!(errors.iter().all(|(name, err)| matches!(
+        err,
+        CompilationError::ProceduresLimitExceeded { query_name, max_procedures: 1 } if query_name == name
+    )));
+}
+
+#[test]
+fn default_procedures_limit_is_not_enforced_for_query_with_filters() {
+    let query = format!("$[?@.a]{}", ".*".repeat(DEFAULT_MAX_PROCEDURES));
+    let document = serde_json::json!([{"a": {"b": 1}}, {"c": 2}]);
+    assert!(Interpreter::new(&query).unwrap().run(&document).is_empty());
+    assert!(matches!(
+        Interpreter::with_max_procedures(&query, DEFAULT_MAX_PROCEDURES),
+        Err(CompilationError::ProceduresLimitExceeded { max_procedures: DEFAULT_MAX_PROCEDURES, .. })
+    ));
+}
+
+#[test]
+fn overlapping_slices_are_tested_once_for_each_element() {
+    let execution_dir = create_execution_dir();
+    let ir_path = format!("{execution_dir}/slices.json");
+    let slices: Vec<String> = (0..16).map(|start| format!("{start}:{}", start + 2)).collect();
+    StandaloneProgGeneratingCompiler::new()
+        .write_ir_to_file(&ir_path)
+        .compile::<RustCodeStandaloneProgGenerator>(
+            &format!("$[{}]", slices.join(", ")),
+            &format!("{execution_dir}/slices.rs"),
+        )
+        .unwrap();
+    let ir = fs::read_to_string(&ir_path).unwrap();
+    assert_eq!(ir.matches("IfCurrentIndexInSlice").count(), 2 * slices.len());
+}
@@ -0,0 +1,240 @@
+use std::fs;
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+use jsonpath_compiler::compiler::{
+    CompilationError, LibGeneratingCompiler, QueriesSource, StandaloneProgGeneratingCompiler,
+};
+use jsonpath_compiler::targets::InputFormat;
+use jsonpath_compiler::targets::simdjson::dom::DomCodeStandaloneProgGenerator;
+use jsonpath_compiler::targets::simdjson::ondemand::{
+    OnDemandCodeLibGenerator, OnDemandCodeStandaloneProgGenerator,
+};
+use jsonpath_compiler::test_helper::{
+    compile_cpp, create_execution_dir, execute_lib, execute_standalone_prog, interpreter_results,
+    lib_compilers, lib_main_code,
+};
+
+fn parse_lines(output: &str) -> Vec<Value> {
+    output.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+fn execute_standalone(
+    compiler: StandaloneProgGeneratingCompiler,
+    query: &str,
+    input: &str,
+    program_args: &[&str],
+) -> Vec<Value> {
+    parse_lines(&execute_standalone_prog::<OnDemandCodeStandaloneProgGenerator>(
+        compiler,
+        query,
+        &[input],
+        program_args,
+    ))
+}
+
+/// Executes the query with every compiler configuration supporting NDJSON input, and checks
+/// that all of them produce the same output.
+fn execute(query: &str, input: &str, input_format: InputFormat) -> Vec<Value> {
+    let standalone_compilers = [
+        StandaloneProgGeneratingCompiler::new(),
+        StandaloneProgGeneratingCompiler::new().with_eager_filter_evaluation(),
+        StandaloneProgGeneratingCompiler::new().with_mmap(),
+    ];
+    let mut outputs = standalone_compilers
+        .into_iter()
+        .map(|compiler| execute_standalone(compiler.with_input_format(input_format), query, input, &[]))
+        .chain(lib_compilers(query).into_iter().map(|compiler| {
+            parse_lines(&execute_lib(
+                compiler.with_input_format(input_format),
+                &[("query", query)],
+                &lib_main_code("cout << query(input.data(), length);"),
+                &[input],
+            ))
+        }));
+    let output = outputs.next().unwrap();
+    for other_output in outputs {
+        assert_eq!(other_output, output);
+    }
+    output
+}
+
+fn expected_results(query: &str, line: &str) -> Value {
+    Value::Array(interpreter_results(query, &serde_json::from_str(line).unwrap()))
+}
+
+fn assert_results_returned_per_line(query: &str, input: &str) {
+    let expected_output: Vec<Value> = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| expected_results(query, line))
+        .collect();
+    let output = execute(query, input, InputFormat::Ndjson { line_numbers: false });
+    assert_eq!(output, expected_output);
+}
+
+#[test]
+fn results_are_returned_per_line() {
+    assert_results_returned_per_line(
+        "$..b",
+        "{\"a\": [{\"b\": 1}, {\"b\": {\"b\": [2]}}]}\n{\"b\": [4, {\"c\": 5}]}\n[]\n{\"c\": {\"b\": 6}}\n",
+    );
+}
+
+#[test]
+fn filter_results_are_returned_per_line() {
+    assert_results_returned_per_line(
+        "$.a[?@.b > $.min]",
+        "{\"min\": 1, \"a\": [{\"b\": 1}, {\"b\": 2}]}\n{\"min\": 3, \"a\": [{\"b\": 2}, {\"b\": 5}, {\"c\": 4}]}\n{\"a\": [{\"b\": 0}]}",
+    );
+}
+
+#[test]
+fn blank_lines_are_skipped() {
+    assert_results_returned_per_line("$[0]", "\n[1, 2]\n\n  \n[3]\r\n[]\n\n");
+}
+
+#[test]
+fn results_are_tagged_with_line_numbers() {
+    let input = "\n[1, 2]\n\n[3]\n[]\n";
+    let output = execute("$[0]", input, InputFormat::Ndjson { line_numbers: true });
+    assert_eq!(
+        output,
+        vec![
+            json!({"line": 2, "results": [1]}),
+            json!({"line": 4, "results": [3]}),
+            json!({"line": 5, "results": []}),
+        ]
+    );
+}
+
+#[test]
+fn malformed_lines_are_reported() {
+    let input = "{\"a\": [1]}\n{\"a\": }\n{\"a\": [2]}\n\n[1, 2\n{\"a\": [3]}\ntru\n\n{\"a\": [4]}\n";
+    let output = execute("$.a[0]", input, InputFormat::Ndjson { line_numbers: true });
+    let lines: Vec<(u64, bool)> = output
+        .iter()
+        .map(|line| (line["line"].as_u64().unwrap(), line.get("error").is_some()))
+        .collect();
+    assert_eq!(lines, vec![(1, false), (2, true), (3, false), (5, true), (6, false), (7, true), (9, false)]);
+    assert_eq!(output[0]["results"], json!([1]));
+    assert_eq!(output[2]["results"], json!([2]));
+    assert_eq!(output[4]["results"], json!([3]));
+    assert_eq!(output[6]["results"], json!([4]));
+}
+
+#[test]
+fn results_are_returned_in_input_order_by_multiple_threads() {
+    let query = "$.a[?@.b > $.min]";
+    let input: String = (0..2000)
+        .map(|i| match i % 100 {
+            17 => "{\"a\": [\n".to_string(),
+            42 => "\n".to_string(),
+            _ => format!("{{\"min\": {}, \"a\": [{{\"b\": {}}}, {{\"b\": {}}}]}}\n", i % 7, i % 5, i % 11),
+        })
+        .collect();
+    let expected_output: Vec<Value> = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| match serde_json::from_str::<Value>(line) {
+            Ok(_) => json!({"line": index + 1, "results": expected_results(query, line)}),
+            Err(_) => json!({"line": index + 1}),
+        })
+        .collect();
+    let compilers = [
+        StandaloneProgGeneratingCompiler::new(),
+        StandaloneProgGeneratingCompiler::new().with_mmap(),
+    ];
+    for compiler in compilers {
+        let mut output = execute_standalone(
+            compiler.with_input_format(InputFormat::Ndjson { line_numbers: true }),
+            query,
+            &input,
+            &["--threads", "4"],
+        );
+        for line in &mut output {
+            if let Some(line) = line.as_object_mut() {
+                line.remove("error");
+            }
+        }
+        assert_eq!(output, expected_output);
+    }
+}
+
+#[test]
+fn invalid_threads_arguments_are_rejected() {
+    let execution_dir = create_execution_dir();
+    let query_file_path = format!("{execution_dir}/query.cpp");
+    StandaloneProgGeneratingCompiler::new()
+        .with_input_format(InputFormat::Ndjson { line_numbers: false })
+        .compile::<OnDemandCodeStandaloneProgGenerator>("$.a", &query_file_path)
+        .unwrap();
+    let prog_path = compile_cpp(&query_file_path, None);
+    let input_path = format!("{execution_dir}/input.ndjson");
+    fs::write(&input_path, "{\"a\": 1}\n").unwrap();
+    let invalid_threads_args: [&[&str]; 5] = [
+        &["--threads"],
+        &["--threads", "0"],
+        &["--threads", "four"],
+        &["--threads", "4x"],
+        &["--threads", "-1"],
+    ];
+    for threads_args in invalid_threads_args {
+        let output = Command::new(&prog_path).arg(&input_path).args(threads_args).output().unwrap();
+        assert!(!output.status.success(), "{threads_args:?} accepted");
+        assert!(String::from_utf8(output.stderr).unwrap().starts_with("usage: "));
+    }
+}
+
+#[test]
+fn ndjson_input_is_not_supported_by_other_targets() {
+    let execution_dir = create_execution_dir();
+    let result = StandaloneProgGeneratingCompiler::new()
+        .with_input_format(InputFormat::Ndjson { line_numbers: false })
+        .compile::<DomCodeStandaloneProgGenerator>("$.a", &format!("{execution_dir}/dom_query.cpp"));
+    assert!(matches!(result, Err(CompilationError::NdjsonInputNotSupported)));
+}
+
+#[test]
+fn ndjson_input_is_not_supported_in_single_pass() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_input_format(InputFormat::Ndjson { line_numbers: false })
+        .with_single_pass("all_queries")
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate {
+                queries: vec![
+                    ("a".to_string(), "$.a".to_string()),
+                    ("b".to_string(), "$..b".to_string()),
+                ],
+            },
+            &format!("{execution_dir}/queries.hpp"),
+        );
+    assert!(matches!(result, Err(CompilationError::NdjsonInputInSinglePass)));
+}
+
+#[test]
+fn ndjson_input_is_not_supported_with_result_spans_or_callbacks() {
+    let execution_dir = create_execution_dir();
+    let compilers = [
+        LibGeneratingCompiler::new().with_result_spans(),
+        LibGeneratingCompiler::new().with_result_callbacks(),
+    ];
+    let results: Vec<_> = compilers
+        .into_iter()
+        .map(|compiler| {
+            compiler
+                .with_input_format(InputFormat::Ndjson { line_numbers: false })
+                .compile::<OnDemandCodeLibGenerator>(
+                    QueriesSource::Immediate {
+                        queries: vec![("query".to_string(), "$.a".to_string())],
+                    },
+                    &format!("{execution_dir}/query.hpp"),
+                )
+        })
+        .collect();
+    assert!(matches!(results[0], Err(CompilationError::NdjsonInputWithResultSpans)));
+    assert!(matches!(results[1], Err(CompilationError::NdjsonInputWithResultCallbacks)));
+}
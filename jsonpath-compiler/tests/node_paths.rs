@@ -0,0 +1,163 @@
+use serde_json::{json, Value};
+
+use jsonpath_compiler::compiler::{
+    CompilationError, LibGeneratingCompiler, QueriesSource, StandaloneProgGeneratingCompiler,
+};
+use jsonpath_compiler::interpreter::Interpreter;
+use jsonpath_compiler::NodePathFormat;
+use jsonpath_compiler::targets::simdjson::dom::DomCodeStandaloneProgGenerator;
+use jsonpath_compiler::targets::simdjson::ondemand::{
+    OnDemandCodeLibGenerator, OnDemandCodeStandaloneProgGenerator,
+};
+use jsonpath_compiler::test_helper::{
+    create_execution_dir, execute_lib, execute_standalone_prog, lib_compilers, lib_main_code,
+};
+
+const FORMATS: [NodePathFormat; 2] = [NodePathFormat::NormalizedPath, NodePathFormat::JsonPointer];
+
+fn execute_standalone(
+    compiler: StandaloneProgGeneratingCompiler,
+    query: &str,
+    document: &str,
+) -> Value {
+    let output = execute_standalone_prog::<OnDemandCodeStandaloneProgGenerator>(
+        compiler,
+        query,
+        &[document],
+        &[],
+    );
+    serde_json::from_str(&output).unwrap()
+}
+
+fn execute_lib_with_callback(query: &str, document: &str, format: NodePathFormat) -> Vec<Value> {
+    let compiler = LibGeneratingCompiler::new().with_result_callbacks().with_node_paths(format);
+    let main_code = lib_main_code("query_with_callback(input.data(), length, print_result, nullptr);");
+    execute_lib(compiler, &[("query", query)], &main_code, &[document])
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn expected_results(query: &str, document: &str, format: NodePathFormat) -> Vec<Value> {
+    Interpreter::new(query)
+        .unwrap()
+        .run_with_node_paths(&serde_json::from_str(document).unwrap(), format)
+        .into_iter()
+        .map(|(path, value)| json!({"path": path, "value": value}))
+        .collect()
+}
+
+fn assert_node_paths_returned(query: &str, document: &str) {
+    for format in FORMATS {
+        let expected_result = Value::Array(expected_results(query, document, format));
+        let standalone_compilers = [
+            StandaloneProgGeneratingCompiler::new(),
+            StandaloneProgGeneratingCompiler::new().with_eager_filter_evaluation(),
+        ];
+        for compiler in standalone_compilers {
+            let result = execute_standalone(compiler.with_node_paths(format), query, document);
+            assert_eq!(result, expected_result);
+        }
+        for compiler in lib_compilers(query) {
+            let output = execute_lib(
+                compiler.with_node_paths(format),
+                &[("query", query)],
+                &lib_main_code("cout << query(input.data(), length);"),
+                &[document],
+            );
+            assert_eq!(serde_json::from_str::<Value>(&output).unwrap(), expected_result);
+        }
+    }
+}
+
+#[test]
+fn paths_of_nested_nodes_are_returned() {
+    assert_node_paths_returned(
+        "$..b",
+        r#"{"a": [{"b": 1}, {"b": {"b": [2, {"b": 3}]}}], "b": [4, {"c": 5}], "c": {"b": 6}}"#,
+    );
+}
+
+#[test]
+fn path_of_root_is_returned() {
+    assert_node_paths_returned("$", r#"{"a": [1, 2], "b": {}}"#);
+}
+
+#[test]
+fn paths_of_indices_and_slices_are_returned() {
+    assert_node_paths_returned("$.a[0, -1]", r#"{"a": [true, null, [1, 2]]}"#);
+    assert_node_paths_returned("$..[1:]", r#"[[1, [2, 3]], {"a": [4, 5]}, 6]"#);
+    assert_node_paths_returned("$[::-1][0]", r#"[[1, [2, 3]], [{"a": 4}], 5]"#);
+}
+
+#[test]
+fn paths_of_filtered_nodes_are_returned() {
+    assert_node_paths_returned(
+        "$.a[?@.b > 1]",
+        r#"{"a": [{"b": 1}, {"b": 2, "c": [3]}, {"c": 4}, {"b": 5}]}"#,
+    );
+    assert_node_paths_returned(
+        "$..[?@.c]..d",
+        r#"[{"c": true, "d": {"d": 1}}, {"e": [{"c": 0, "d": "x"}]}, {"d": 2}]"#,
+    );
+}
+
+#[test]
+fn member_names_are_escaped_in_paths() {
+    assert_node_paths_returned(
+        "$.*[0]",
+        r#"{"a'b": [1], "c\"d": [2], "e/f~g": [3], "h\\i\nj\u0001": [4], "é": [5]}"#,
+    );
+}
+
+#[test]
+fn paths_are_passed_to_callback() {
+    let query = "$..[?@.c]..d";
+    let document = r#"[{"c": true, "d": {"d": 1}}, {"e": [{"c": 0, "d": "x"}]}, {"d": 2}]"#;
+    for format in FORMATS {
+        let callback_results = execute_lib_with_callback(query, document, format);
+        assert_eq!(callback_results, expected_results(query, document, format));
+    }
+}
+
+#[test]
+fn node_paths_are_not_supported_by_other_targets() {
+    let execution_dir = create_execution_dir();
+    let result = StandaloneProgGeneratingCompiler::new()
+        .with_node_paths(NodePathFormat::NormalizedPath)
+        .compile::<DomCodeStandaloneProgGenerator>("$.a", &format!("{execution_dir}/dom_query.cpp"));
+    assert!(matches!(result, Err(CompilationError::NodePathsNotSupported)));
+}
+
+#[test]
+fn node_paths_are_not_supported_in_single_pass() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_node_paths(NodePathFormat::JsonPointer)
+        .with_single_pass("all_queries")
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate {
+                queries: vec![
+                    ("a".to_string(), "$.a".to_string()),
+                    ("b".to_string(), "$..b".to_string()),
+                ],
+            },
+            &format!("{execution_dir}/queries.hpp"),
+        );
+    assert!(matches!(result, Err(CompilationError::NodePathsInSinglePass)));
+}
+
+#[test]
+fn node_paths_are_not_returned_with_result_spans() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_node_paths(NodePathFormat::NormalizedPath)
+        .with_result_spans()
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate {
+                queries: vec![("query".to_string(), "$.a".to_string())],
+            },
+            &format!("{execution_dir}/query.hpp"),
+        );
+    assert!(matches!(result, Err(CompilationError::NodePathsWithResultSpans)));
+}
@@ -0,0 +1,91 @@
+use serde_json::{Deserializer, Value};
+
+use jsonpath_compiler::compiler::{CompilationError, LibGeneratingCompiler, QueriesSource};
+use jsonpath_compiler::targets::rust::value::ValueCodeLibGenerator;
+use jsonpath_compiler::targets::simdjson::ondemand::OnDemandCodeLibGenerator;
+use jsonpath_compiler::test_helper::{
+    create_execution_dir, execute_lib, interpreter_results, lib_compilers, lib_main_code,
+};
+
+/// Prints the results passed to the callback, followed by the results returned as a string.
+const MAIN_STATEMENTS: &str = r#"
+    query_with_callback(input.data(), length, print_result, nullptr);
+    cout << query(input.data(), length);
+"#;
+
+/// Returns the results passed to the callback, and the results returned as a string.
+fn execute_with_result_callbacks(
+    compiler: LibGeneratingCompiler,
+    query: &str,
+    document: &str,
+) -> (Vec<Value>, Value) {
+    let output = execute_lib(
+        compiler.with_result_callbacks(),
+        &[("query", query)],
+        &lib_main_code(MAIN_STATEMENTS),
+        &[document],
+    );
+    let mut callback_results: Vec<Value> =
+        Deserializer::from_str(&output).into_iter().map(|value| value.unwrap()).collect();
+    let result = callback_results.pop().unwrap();
+    (callback_results, result)
+}
+
+fn assert_results_passed_to_callback(query: &str, document: &str) {
+    let expected_result = interpreter_results(query, &serde_json::from_str(document).unwrap());
+    let mut compilers = lib_compilers(query);
+    compilers.push(LibGeneratingCompiler::new().with_result_spans());
+    for compiler in compilers {
+        let (callback_results, result) = execute_with_result_callbacks(compiler, query, document);
+        assert_eq!(callback_results, expected_result);
+        assert_eq!(result, Value::Array(expected_result.clone()));
+    }
+}
+
+#[test]
+fn nested_nodes_are_passed_to_callback_in_document_order() {
+    assert_results_passed_to_callback(
+        "$..b",
+        r#"{"a": [{"b": 1}, {"b": {"b": [2, {"b": 3}]}}], "b": [4, {"c": 5}], "c": {"b": 6}}"#,
+    );
+}
+
+#[test]
+fn selected_nodes_are_passed_to_callback() {
+    let document = r#"{"a": [{"b": 1}, {"b": 2, "c": [3]}, {"c": 4}, {"b": 5}], "d": [[6, [7]], 8]}"#;
+    for query in ["$", "$.a[0, -1]", "$..[1:]", "$.a[::-1].b", "$.a[?@.b > 1]", "$..[?@.c]..b", "$.e"] {
+        assert_results_passed_to_callback(query, document);
+    }
+}
+
+#[test]
+fn result_callbacks_are_not_supported_by_other_targets() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_result_callbacks()
+        .compile::<ValueCodeLibGenerator>(
+            QueriesSource::Immediate {
+                queries: vec![("query".to_string(), "$.a".to_string())],
+            },
+            &format!("{execution_dir}/query.rs"),
+        );
+    assert!(matches!(result, Err(CompilationError::ResultCallbacksNotSupported)));
+}
+
+#[test]
+fn result_callbacks_are_not_supported_in_single_pass() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_result_callbacks()
+        .with_single_pass("all_queries")
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate {
+                queries: vec![
+                    ("a".to_string(), "$.a".to_string()),
+                    ("b".to_string(), "$..b".to_string()),
+                ],
+            },
+            &format!("{execution_dir}/queries.hpp"),
+        );
+    assert!(matches!(result, Err(CompilationError::ResultCallbacksInSinglePass)));
+}
@@ -0,0 +1,121 @@
+use serde_json::Value;
+
+use jsonpath_compiler::compiler::{CompilationError, LibGeneratingCompiler, QueriesSource};
+use jsonpath_compiler::targets::simdjson::dom::DomCodeLibGenerator;
+use jsonpath_compiler::targets::simdjson::ondemand::OnDemandCodeLibGenerator;
+use jsonpath_compiler::test_helper::{
+    create_execution_dir, execute_lib, interpreter_results, lib_compilers, lib_main_code,
+};
+
+/// Prints the spans of the results, followed by the results returned as a string.
+const MAIN_STATEMENTS: &str = r#"
+    cout << "[";
+    bool first = true;
+    for (const auto &[offset, span_length] : query_spans(input.data(), length))
+    {
+        if (!first)
+            cout << ", ";
+        cout << "[" << offset << ", " << span_length << "]";
+        first = false;
+    }
+    cout << "]\n";
+    cout << query(input.data(), length);
+"#;
+
+/// Returns the results of the query read from the spans, and the results returned as a string.
+fn execute_with_result_spans(
+    compiler: LibGeneratingCompiler,
+    query: &str,
+    document: &str,
+) -> (Vec<Value>, Value) {
+    let output = execute_lib(
+        compiler.with_result_spans(),
+        &[("query", query)],
+        &lib_main_code(MAIN_STATEMENTS),
+        &[document],
+    );
+    let (spans, result) = output.split_once('\n').unwrap();
+    let spans: Vec<(usize, usize)> = serde_json::from_str(spans).unwrap();
+    let results_from_spans = spans
+        .into_iter()
+        .map(|(offset, length)| {
+            let span = &document[offset..offset + length];
+            assert_eq!(span.trim(), span);
+            serde_json::from_str(span).unwrap()
+        })
+        .collect();
+    (results_from_spans, serde_json::from_str(result).unwrap())
+}
+
+fn assert_results_returned_as_spans(query: &str, document: &str) {
+    let expected_result = interpreter_results(query, &serde_json::from_str(document).unwrap());
+    for compiler in lib_compilers(query) {
+        let (results_from_spans, result) = execute_with_result_spans(compiler, query, document);
+        assert_eq!(results_from_spans, expected_result);
+        assert_eq!(result, Value::Array(expected_result.clone()));
+    }
+}
+
+#[test]
+fn nested_nodes_are_returned_as_spans() {
+    assert_results_returned_as_spans(
+        "$..b",
+        "{\"a\": [ {\"b\" : 1 }, {\"b\":\t{\"b\": [2, \"x y\" ]\n} }],\n \"b\" : [3, {\"c\": 4}]\r\n}",
+    );
+}
+
+#[test]
+fn root_is_returned_as_span() {
+    assert_results_returned_as_spans("$", "  {\"a\": [1, 2], \"b\": {}}\n\n");
+}
+
+#[test]
+fn indices_and_slices_are_returned_as_spans() {
+    assert_results_returned_as_spans("$.a[0, -1]", "{\"a\": [ true , null, [1, 2] ]}");
+    assert_results_returned_as_spans("$..[1:]", "[[1, [2, 3] ], {\"a\": [4, 5 ]}, 6 ]");
+    assert_results_returned_as_spans("$[::-1][0]", "[[1, [2, 3] ], [ {\"a\": 4} ], 5 ]");
+}
+
+#[test]
+fn filtered_nodes_are_returned_as_spans() {
+    assert_results_returned_as_spans(
+        "$.a[?@.b > 1]",
+        "{\"a\": [{\"b\": 1}, {\"b\": 2, \"c\": [ 3 ] } , {\"c\": 4}, {\"b\" : 5}]}",
+    );
+    assert_results_returned_as_spans(
+        "$..[?@.c]..d",
+        "[{\"c\": true, \"d\": {\"d\": 1 }}, {\"e\": [{\"c\": 0, \"d\": \"x\" }]}, {\"d\": 2}]",
+    );
+}
+
+#[test]
+fn result_spans_are_not_supported_by_other_targets() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_result_spans()
+        .compile::<DomCodeLibGenerator>(
+            QueriesSource::Immediate {
+                queries: vec![("query".to_string(), "$.a".to_string())],
+            },
+            &format!("{execution_dir}/dom_query.hpp"),
+        );
+    assert!(matches!(result, Err(CompilationError::ResultSpansNotSupported)));
+}
+
+#[test]
+fn result_spans_are_not_supported_in_single_pass() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_result_spans()
+        .with_single_pass("all_queries")
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate {
+                queries: vec![
+                    ("a".to_string(), "$.a".to_string()),
+                    ("b".to_string(), "$..b".to_string()),
+                ],
+            },
+            &format!("{execution_dir}/queries.hpp"),
+        );
+    assert!(matches!(result, Err(CompilationError::ResultSpansInSinglePass)));
+}
@@ -0,0 +1,158 @@
+use std::fs;
+
+use serde_json::{Map, Value};
+
+use jsonpath_compiler::compiler::{CompilationError, LibGeneratingCompiler, QueriesSource};
+use jsonpath_compiler::interpreter::Interpreter;
+use jsonpath_compiler::NodePathFormat;
+use jsonpath_compiler::targets::InputFormat;
+use jsonpath_compiler::targets::rust::RustCodeLibGenerator;
+use jsonpath_compiler::targets::simdjson::ondemand::OnDemandCodeLibGenerator;
+use jsonpath_compiler::test_helper::{create_execution_dir, execute_lib, lib_main_code};
+
+fn named_queries(queries: &[(&str, &str)]) -> Vec<(String, String)> {
+    queries
+        .iter()
+        .map(|(name, query)| (name.to_string(), query.to_string()))
+        .collect()
+}
+
+fn execute_in_single_pass(queries: &[(&str, &str)], document: &str) -> Value {
+    let compiler = LibGeneratingCompiler::new().with_single_pass("all_queries");
+    let main_code = lib_main_code("cout << all_queries(input.data(), length);");
+    let output = execute_lib(compiler, queries, &main_code, &[document]);
+    serde_json::from_str(&output).unwrap()
+}
+
+fn assert_results_grouped_by_query_name(queries: &[(&str, &str)], document: &str) {
+    let result = execute_in_single_pass(queries, document);
+    let document: Value = serde_json::from_str(document).unwrap();
+    let expected_result: Map<String, Value> = queries
+        .iter()
+        .map(|(name, query)| {
+            let query_result = Interpreter::new(query).unwrap().run(&document);
+            (name.to_string(), Value::Array(query_result.into_iter().cloned().collect()))
+        })
+        .collect();
+    assert_eq!(result, Value::Object(expected_result));
+}
+
+#[test]
+fn merged_queries_results_are_grouped_by_query_name() {
+    let queries = [
+        ("names", "$.a[*].b"),
+        ("descendants", "$..b"),
+        ("root", "$"),
+        ("wildcard", "$.*"),
+        ("indices", "$..[-1]"),
+        ("slice", "$.a[1:]..c"),
+    ];
+    let document = r#"{"a": [{"b": 1, "c": [2, 3]}, {"b": {"b": 4}}, [{"c": 5}]], "b": [6, {"c": 7}]}"#;
+    assert_results_grouped_by_query_name(&queries, document);
+}
+
+#[test]
+fn node_saved_for_one_merged_query_includes_children_not_selected_by_others() {
+    let queries = [("names", "$.a[*].b"), ("wildcard", "$.b[*]"), ("name", "$.a")];
+    let document = r#"{"a": [{"b": 1}, {"c": 2}, {"b": [3]}], "b": [4, 5]}"#;
+    assert_results_grouped_by_query_name(&queries, document);
+}
+
+#[test]
+fn merged_query_results_are_reversed_by_slice_with_negative_step() {
+    let queries = [("reversed", "$.a[::-1].b"), ("wildcard", "$.b[*]"), ("name", "$.a")];
+    let document = r#"{"a": [{"b": 1}, {"c": 2}, {"b": [3]}], "b": [4, 5]}"#;
+    assert_results_grouped_by_query_name(&queries, document);
+}
+
+#[test]
+fn merged_queries_ir_is_executed_by_interpreter() {
+    let execution_dir = create_execution_dir();
+    let queries = [("names", "$.a[*].b"), ("root", "$"), ("descendants", "$..c")];
+    let ir_path = format!("{execution_dir}/queries_ir.json");
+    LibGeneratingCompiler::new()
+        .with_single_pass("all_queries")
+        .write_ir_to_file(&ir_path)
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate { queries: named_queries(&queries) },
+            &format!("{execution_dir}/queries_ir.hpp"),
+        )
+        .unwrap();
+    let queries_irs: Value = serde_json::from_str(&fs::read_to_string(&ir_path).unwrap()).unwrap();
+    let interpreter = Interpreter::from_ir(&queries_irs[0]["query"].to_string()).unwrap();
+    let document: Value =
+        serde_json::from_str(r#"{"a": [{"b": 1, "c": 2}, {"b": {"c": 3}}], "c": 4}"#).unwrap();
+    let expected_result: Vec<(&str, Vec<&Value>)> = queries
+        .iter()
+        .map(|(name, query)| (*name, Interpreter::new(query).unwrap().run(&document)))
+        .collect();
+    assert_eq!(interpreter.run_merged(&document), expected_result);
+}
+
+fn compile_in_single_pass(compiler: LibGeneratingCompiler) -> Result<(), CompilationError> {
+    let execution_dir = create_execution_dir();
+    let queries = named_queries(&[("names", "$.a.b"), ("descendants", "$..c")]);
+    compiler.with_single_pass("all_queries").compile::<OnDemandCodeLibGenerator>(
+        QueriesSource::Immediate { queries },
+        &format!("{execution_dir}/rejected.hpp"),
+    )
+}
+
+#[test]
+fn merged_queries_results_are_not_returned_as_spans() {
+    let result = compile_in_single_pass(LibGeneratingCompiler::new().with_result_spans());
+    assert!(matches!(result, Err(CompilationError::ResultSpansInSinglePass)));
+}
+
+#[test]
+fn merged_queries_results_are_not_passed_to_callback() {
+    let result = compile_in_single_pass(LibGeneratingCompiler::new().with_result_callbacks());
+    assert!(matches!(result, Err(CompilationError::ResultCallbacksInSinglePass)));
+}
+
+#[test]
+fn merged_queries_node_paths_are_not_returned() {
+    let result =
+        compile_in_single_pass(LibGeneratingCompiler::new().with_node_paths(NodePathFormat::NormalizedPath));
+    assert!(matches!(result, Err(CompilationError::NodePathsInSinglePass)));
+}
+
+#[test]
+fn merged_queries_are_not_executed_on_ndjson_input() {
+    let input_format = InputFormat::Ndjson { line_numbers: false };
+    let result = compile_in_single_pass(LibGeneratingCompiler::new().with_input_format(input_format));
+    assert!(matches!(result, Err(CompilationError::NdjsonInputInSinglePass)));
+}
+
+#[test]
+fn queries_with_filters_are_not_merged() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_single_pass("all_queries")
+        .compile::<OnDemandCodeLibGenerator>(
+            QueriesSource::Immediate {
+                queries: named_queries(&[("names", "$.a.b"), ("filter", "$[?@.a]")]),
+            },
+            &format!("{execution_dir}/filters.hpp"),
+        );
+    match result {
+        Err(CompilationError::MultipleErrors(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].0, "filter");
+            assert!(matches!(errors[0].1, CompilationError::FiltersInSinglePass));
+        }
+        _ => panic!("expected an error for the query with a filter"),
+    }
+}
+
+#[test]
+fn single_pass_is_not_supported_by_other_targets() {
+    let execution_dir = create_execution_dir();
+    let result = LibGeneratingCompiler::new()
+        .with_single_pass("all_queries")
+        .compile::<RustCodeLibGenerator>(
+            QueriesSource::Immediate { queries: named_queries(&[("names", "$.a.b")]) },
+            &format!("{execution_dir}/queries.rs"),
+        );
+    assert!(matches!(result, Err(CompilationError::SinglePassNotSupported)));
+}
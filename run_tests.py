@@ -4,13 +4,15 @@ import subprocess
 cts_path = "jsonpath-compliance-test-suite/cts.json"
 simdjson_path = "/opt/homebrew/Cellar/simdjson/3.10.1/"
 helpers_path = "jsonpath-compiler/lib/"
+supported_functions = ["length", "count", "match", "search", "value"]
 
 with open(cts_path) as f:
     cts = json.load(f)
 
 tests = [test for test in cts["tests"]
          if "invalid_selector" not in test 
-         and "functions" not in test["name"]]
+         and ("functions" not in test["name"]
+              or any(f"functions, {function}," in test["name"] for function in supported_functions))]
 tests_count = len(tests)
 passed_tests = 0
 